@@ -0,0 +1,59 @@
+#ifndef INFECTIONS_H
+#define INFECTIONS_H
+
+/* Generated with cbindgen from src/ffi.rs, don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A simulation, created by `sim_new` and destroyed by `sim_free`.
+typedef struct SimHandle SimHandle;
+
+// The state of a simulation at `time` days.
+typedef struct SimStats {
+  double time;
+  uint64_t population;
+  uint64_t dead;
+  uint64_t infected;
+  uint64_t healed;
+  uint64_t vaccinated;
+  uint64_t vaccinated_infected;
+} SimStats;
+
+// Creates a simulation from the parameters in `config`, given in the format of config.toml,
+// or with the default parameters if `config` is null. The same seed gives the same run.
+//
+// Returns null if the parameters couldn't be read.
+//
+// # Safety
+//
+// `config` has to be null or point to a NUL-terminated string.
+SimHandle *sim_new(const char *config, uint64_t seed);
+
+// Advances the simulation by `days` of simulated time.
+//
+// Returns false without stepping if `days` is infinite or NaN, and false if the step
+// panicked, leaving the simulation as it was at the panic.
+//
+// # Safety
+//
+// `sim` has to come from `sim_new` and not be freed yet.
+bool sim_step(SimHandle *sim, double days);
+
+// The current state of the simulation.
+//
+// # Safety
+//
+// `sim` has to come from `sim_new` and not be freed yet.
+SimStats sim_stats(const SimHandle *sim);
+
+// Destroys the simulation. Does nothing if `sim` is null.
+//
+// # Safety
+//
+// `sim` has to be null or come from `sim_new`, and can't be used afterwards.
+void sim_free(SimHandle *sim);
+
+#endif /* INFECTIONS_H */
@@ -0,0 +1,520 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    ffi::OsStr,
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use toml::Value;
+
+use crate::stats_csv;
+
+// the rows of the table shown at first
+const DEFAULT_ROWS: usize = 20;
+
+// what each run ended with, worked out from its statistics
+pub const OUTCOMES: [&str; 6] = ["days", "peak", "peak_day", "dead", "healed", "vaccinated"];
+
+// A finished run, from the statistics in <name>.csv and the parameters in <name>.toml next
+// to them, like the ones in the done/ directory of a --worker queue.
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub name: String,
+    pub outcomes: [f64; OUTCOMES.len()],
+    // the numbers among the parameters, in the order of their names
+    pub params: Vec<(String, f64)>,
+}
+
+impl Run {
+    fn load(csv: &Path) -> io::Result<Run> {
+        let history = stats_csv::read(&csv.to_string_lossy())?;
+        let (last_t, last) = history.last().copied().unwrap_or_default();
+        let (peak_t, peak) = history
+            .iter()
+            .max_by_key(|(_, stats)| stats.infected)
+            .copied()
+            .unwrap_or_default();
+        let job = csv.with_extension("toml");
+        let params = match fs::read_to_string(&job) {
+            Ok(text) => {
+                let table: Value = text.parse().map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{}: {}", job.display(), err),
+                    )
+                })?;
+                numbers(&table)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err),
+        };
+        Ok(Run {
+            name: csv.file_stem().unwrap().to_string_lossy().into_owned(),
+            outcomes: [
+                last_t,
+                peak.infected as f64,
+                peak_t,
+                last.dead as f64,
+                last.healed as f64,
+                last.vaccinated as f64,
+            ],
+            params,
+        })
+    }
+
+    // an outcome or a parameter
+    pub fn value(&self, column: &str) -> Option<f64> {
+        match OUTCOMES.iter().position(|&outcome| outcome == column) {
+            Some(index) => Some(self.outcomes[index]),
+            None => self
+                .params
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|&(_, value)| value),
+        }
+    }
+}
+
+// the seed is left out, as it's different for every run of a sweep and would always be shown
+fn numbers(table: &Value) -> Vec<(String, f64)> {
+    let mut numbers: Vec<_> = table
+        .as_table()
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| *name != "seed")
+        .filter_map(|(name, value)| {
+            let number = match value {
+                Value::Integer(n) => *n as f64,
+                Value::Float(x) => *x,
+                Value::Boolean(b) => *b as u8 as f64,
+                _ => return None,
+            };
+            Some((name.clone(), number))
+        })
+        .collect();
+    numbers.sort_by(|a, b| a.0.cmp(&b.0));
+    numbers
+}
+
+// the runs in `dir`, or in its done/ directory if it's the root of a queue
+pub fn load_runs(dir: &str) -> io::Result<Vec<Run>> {
+    let mut dir = Path::new(dir).to_path_buf();
+    if dir.join("done").is_dir() {
+        dir = dir.join("done");
+    }
+    let mut paths: Vec<_> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension() == Some(OsStr::new("csv")))
+        .collect();
+    paths.sort();
+    paths.iter().map(|path| Run::load(path)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+// eg. "dead>100", keeping the runs for which it holds
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    column: String,
+    comparison: Comparison,
+    value: f64,
+}
+
+impl Filter {
+    fn parse(text: &str) -> Result<Filter, String> {
+        // the two-character ones first, so that "<=" isn't taken for "<"
+        let operators = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ];
+        for &(operator, comparison) in &operators {
+            if let Some(at) = text.find(operator) {
+                let value = text[at + operator.len()..].trim();
+                return Ok(Filter {
+                    column: text[..at].trim().to_owned(),
+                    comparison,
+                    value: value
+                        .parse()
+                        .map_err(|_| format!("not a number: {}", value))?,
+                });
+            }
+        }
+        Err(format!("invalid filter, eg. dead>100: {}", text))
+    }
+
+    fn keeps(&self, run: &Run) -> bool {
+        let value = match run.value(&self.column) {
+            Some(value) => value,
+            None => return false,
+        };
+        match self.comparison {
+            Comparison::Less => value < self.value,
+            Comparison::LessOrEqual => value <= self.value,
+            Comparison::Equal => value == self.value,
+            Comparison::GreaterOrEqual => value >= self.value,
+            Comparison::Greater => value > self.value,
+        }
+    }
+}
+
+fn format_value(value: Option<f64>) -> String {
+    match value {
+        Some(value) if value.fract() == 0.0 && value.abs() < 1e15 => format!("{}", value),
+        Some(value) => format!("{:.3}", value),
+        None => "-".to_owned(),
+    }
+}
+
+struct Dashboard {
+    runs: Vec<Run>,
+    // the parameters differing between the runs, which is what a sweep goes through
+    varied: Vec<String>,
+    filters: Vec<Filter>,
+    // descending if the flag is set
+    sort: Option<(String, bool)>,
+    rows: usize,
+}
+
+impl Dashboard {
+    fn new(runs: Vec<Run>) -> Dashboard {
+        let names: BTreeSet<_> = runs
+            .iter()
+            .flat_map(|run| run.params.iter().map(|(name, _)| name.clone()))
+            .collect();
+        let varied = names
+            .into_iter()
+            .filter(|name| {
+                let values: Vec<_> = runs.iter().map(|run| run.value(name)).collect();
+                values.iter().any(|value| *value != values[0])
+            })
+            .collect();
+        Dashboard {
+            runs,
+            varied,
+            filters: vec![],
+            sort: None,
+            rows: DEFAULT_ROWS,
+        }
+    }
+
+    fn columns(&self) -> Vec<&str> {
+        OUTCOMES
+            .iter()
+            .copied()
+            .chain(self.varied.iter().map(String::as_str))
+            .collect()
+    }
+
+    fn is_column(&self, column: &str) -> bool {
+        column == "run" || self.runs.iter().any(|run| run.value(column).is_some())
+    }
+
+    fn shown(&self) -> Vec<&Run> {
+        let mut shown: Vec<_> = self
+            .runs
+            .iter()
+            .filter(|run| self.filters.iter().all(|filter| filter.keeps(run)))
+            .collect();
+        if let Some((column, descending)) = &self.sort {
+            shown.sort_by(|a, b| {
+                let order = if column == "run" {
+                    a.name.cmp(&b.name)
+                } else {
+                    // the runs without the value go last either way
+                    match (a.value(column), b.value(column)) {
+                        (Some(a), Some(b)) => a.total_cmp(&b),
+                        (Some(_), None) => return Ordering::Less,
+                        (None, Some(_)) => return Ordering::Greater,
+                        (None, None) => Ordering::Equal,
+                    }
+                };
+                if *descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+        shown
+    }
+
+    fn print(&self) {
+        let shown = self.shown();
+        let columns = self.columns();
+        let mut table = vec![];
+        table.push(
+            std::iter::once("run".to_owned())
+                .chain(columns.iter().map(|&column| column.to_owned()))
+                .collect::<Vec<_>>(),
+        );
+        for run in shown.iter().take(self.rows) {
+            table.push(
+                std::iter::once(run.name.clone())
+                    .chain(
+                        columns
+                            .iter()
+                            .map(|&column| format_value(run.value(column))),
+                    )
+                    .collect(),
+            );
+        }
+        let widths: Vec<_> = (0..=columns.len())
+            .map(|column| table.iter().map(|row| row[column].len()).max().unwrap())
+            .collect();
+        // clear the screen, so that the table stays in place between the commands
+        print!("\x1b[2J\x1b[H");
+        for row in &table {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(column, (cell, &width))| {
+                    if column == 0 {
+                        format!("{:<width$}", cell, width = width)
+                    } else {
+                        format!("{:>width$}", cell, width = width)
+                    }
+                })
+                .collect();
+            println!("{}", cells.join("  "));
+        }
+        println!();
+        let filters: Vec<_> = self
+            .filters
+            .iter()
+            .map(|filter| format!("{} {}", filter.column, format_comparison(filter)))
+            .collect();
+        println!(
+            "{} of {} runs{}{}",
+            shown.len().min(self.rows),
+            shown.len(),
+            if filters.is_empty() {
+                String::new()
+            } else {
+                format!(", where {}", filters.join(" and "))
+            },
+            match &self.sort {
+                Some((column, true)) => format!(", by {} descending", column),
+                Some((column, false)) => format!(", by {}", column),
+                None => String::new(),
+            }
+        );
+    }
+
+    // returns whether to keep going
+    fn handle(&mut self, line: &str) -> Result<bool, String> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => (),
+            ["quit"] | ["q"] => return Ok(false),
+            ["sort", column] => {
+                let (column, descending) = match column.strip_prefix('-') {
+                    Some(column) => (column, true),
+                    None => (*column, false),
+                };
+                if !self.is_column(column) {
+                    return Err(format!("no such column: {}", column));
+                }
+                self.sort = Some((column.to_owned(), descending));
+            }
+            ["filter", ..] => {
+                let filter = Filter::parse(&words[1..].join(""))?;
+                if !self.is_column(&filter.column) {
+                    return Err(format!("no such column: {}", filter.column));
+                }
+                self.filters.push(filter);
+            }
+            ["clear"] => {
+                self.filters.clear();
+                self.sort = None;
+            }
+            ["rows", rows] => {
+                self.rows = rows
+                    .parse()
+                    .map_err(|_| format!("not a number: {}", rows))?;
+            }
+            ["show", name] => {
+                let run = self
+                    .runs
+                    .iter()
+                    .find(|run| run.name == *name)
+                    .ok_or_else(|| format!("no such run: {}", name))?;
+                println!("{}", run.name);
+                for &outcome in &OUTCOMES {
+                    println!("  {} = {}", outcome, format_value(run.value(outcome)));
+                }
+                for (param, value) in &run.params {
+                    println!("  {} = {}", param, format_value(Some(*value)));
+                }
+                return Ok(true);
+            }
+            _ => return Err(HELP.to_owned()),
+        }
+        self.print();
+        Ok(true)
+    }
+}
+
+fn format_comparison(filter: &Filter) -> String {
+    let operator = match filter.comparison {
+        Comparison::Less => "<",
+        Comparison::LessOrEqual => "<=",
+        Comparison::Equal => "=",
+        Comparison::GreaterOrEqual => ">=",
+        Comparison::Greater => ">",
+    };
+    format!("{} {}", operator, format_value(Some(filter.value)))
+}
+
+const HELP: &str = "commands:
+  sort <column>      by the column, or -<column> for descending
+  filter <condition> keep the runs for which eg. dead>100 or beta<=0.5 holds
+  clear              remove the filters and the sorting
+  rows <n>           show n rows of the table
+  show <run>         all the outcomes and parameters of a run
+  quit";
+
+// A table of the runs in `dir`, sorted and filtered by the commands typed in, for going
+// through the results of a sweep.
+pub fn analyze(dir: &str) {
+    let runs =
+        load_runs(dir).unwrap_or_else(|err| panic!("couldn't load the runs in {}: {}", dir, err));
+    if runs.is_empty() {
+        eprintln!(
+            "No runs in {}, looking for <name>.csv with <name>.toml next to them.",
+            dir
+        );
+        return;
+    }
+    let mut dashboard = Dashboard::new(runs);
+    dashboard.print();
+    println!("{}", HELP);
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        match dashboard.handle(&line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(message) => println!("{}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, dead: f64, params: &[(&str, f64)]) -> Run {
+        Run {
+            name: name.to_owned(),
+            outcomes: [100.0, 50.0, 20.0, dead, 10.0, 0.0],
+            params: params
+                .iter()
+                .map(|&(name, value)| (name.to_owned(), value))
+                .collect(),
+        }
+    }
+
+    fn names<'a>(runs: &[&'a Run]) -> Vec<&'a str> {
+        runs.iter().map(|run| run.name.as_str()).collect()
+    }
+
+    #[test]
+    fn the_two_character_operators_are_not_taken_for_one() {
+        let filter = |text| Filter::parse(text).unwrap();
+        assert_eq!(filter("dead<=5").comparison, Comparison::LessOrEqual);
+        assert_eq!(filter("dead >= 5").comparison, Comparison::GreaterOrEqual);
+        assert_eq!(filter("dead<5").comparison, Comparison::Less);
+        assert_eq!(filter("dead>5").comparison, Comparison::Greater);
+        assert_eq!(
+            filter(" death_rate = 0.5 "),
+            Filter {
+                column: "death_rate".to_owned(),
+                comparison: Comparison::Equal,
+                value: 0.5,
+            }
+        );
+        assert!(Filter::parse("dead>many").is_err());
+        assert!(Filter::parse("dead").is_err());
+    }
+
+    #[test]
+    fn filters_keep_the_runs_they_hold_for() {
+        let runs = [run("a", 4.0, &[]), run("b", 5.0, &[]), run("c", 6.0, &[])];
+        let kept = |text: &str| -> Vec<&str> {
+            let filter = Filter::parse(text).unwrap();
+            runs.iter()
+                .filter(|run| filter.keeps(run))
+                .map(|run| run.name.as_str())
+                .collect()
+        };
+        assert_eq!(kept("dead<5"), ["a"]);
+        assert_eq!(kept("dead<=5"), ["a", "b"]);
+        assert_eq!(kept("dead=5"), ["b"]);
+        assert_eq!(kept("dead>=5"), ["b", "c"]);
+        assert_eq!(kept("dead>5"), ["c"]);
+        // a run without the value isn't kept by any filter on it
+        assert!(kept("death_rate>=0").is_empty());
+    }
+
+    #[test]
+    fn the_runs_without_the_value_are_sorted_last_either_way() {
+        let mut dashboard = Dashboard::new(vec![
+            run("a", 0.0, &[("death_rate", 0.2)]),
+            run("b", 0.0, &[]),
+            run("c", 0.0, &[("death_rate", 0.1)]),
+            run("d", 0.0, &[("death_rate", 0.3)]),
+        ]);
+        dashboard.sort = Some(("death_rate".to_owned(), false));
+        assert_eq!(names(&dashboard.shown()), ["c", "a", "d", "b"]);
+        dashboard.sort = Some(("death_rate".to_owned(), true));
+        assert_eq!(names(&dashboard.shown()), ["d", "a", "c", "b"]);
+        dashboard.sort = Some(("run".to_owned(), true));
+        assert_eq!(names(&dashboard.shown()), ["d", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn only_the_runs_kept_by_every_filter_are_shown() {
+        let mut dashboard = Dashboard::new(vec![
+            run("a", 1.0, &[("death_rate", 0.2)]),
+            run("b", 2.0, &[("death_rate", 0.1)]),
+            run("c", 3.0, &[("death_rate", 0.3)]),
+        ]);
+        dashboard.filters = vec![
+            Filter::parse("dead>1").unwrap(),
+            Filter::parse("death_rate<0.3").unwrap(),
+        ];
+        assert_eq!(names(&dashboard.shown()), ["b"]);
+    }
+
+    #[test]
+    fn the_seed_is_not_among_the_parameters() {
+        let table: Value = "seed = 1234\ndeath_rate = 0.5\nquarantine = true\nname = \"x\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            numbers(&table),
+            [
+                ("death_rate".to_owned(), 0.5),
+                ("quarantine".to_owned(), 1.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use std::process::{Child, Command};
+
+use crate::simulation::{Simulation, Statistics};
+
+// Periodically prints the headline numbers as plain text lines, so that they are available
+// to screen readers and scripts, and optionally passes them to a notification command
+// (eg. `notify-send`).
+pub struct Announcer {
+    interval: f64,
+    next: f64,
+    command: Option<String>,
+    // the notifications still running, waited for once they're done so that they don't
+    // linger as zombies
+    running: Vec<Child>,
+}
+
+pub fn describe(day: f64, stats: &Statistics) -> String {
+    format!(
+        "day {:.0}, population {}, infected {}, healed {}, vaccinated {}, dead {}",
+        day, stats.population, stats.infected, stats.healed, stats.vaccinated, stats.dead
+    )
+}
+
+impl Announcer {
+    pub fn new(interval: f64, command: Option<String>) -> Announcer {
+        assert!(
+            interval > 0.0,
+            "the announcement interval should be positive"
+        );
+        Announcer {
+            interval,
+            next: 0.0,
+            command,
+            running: vec![],
+        }
+    }
+
+    pub fn update(&mut self, sim: &Simulation) {
+        self.running
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        if sim.time() < self.next {
+            return;
+        }
+        let line = describe(sim.time(), &sim.stats());
+        println!("{}", line);
+        if let Some(command) = &self.command {
+            match Command::new(command).arg(&line).spawn() {
+                Ok(child) => self.running.push(child),
+                Err(err) => eprintln!("Couldn't run the notification command: {}", err),
+            }
+        }
+        // the first announcement time after now, however far behind it was
+        self.next = ((sim.time() / self.interval).floor() + 1.0) * self.interval;
+    }
+}
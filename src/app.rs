@@ -0,0 +1,863 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use glium::{
+    glutin::event::{ElementState, ModifiersState, MouseButton, MouseScrollDelta, WindowEvent},
+    texture::RawImage2d,
+    Display,
+};
+use rand::{rngs::StdRng, thread_rng, Rng};
+
+#[cfg(feature = "audio")]
+use crate::audio::AudioCues;
+#[cfg(feature = "microdata")]
+use crate::microdata::MicrodataWriter;
+#[cfg(feature = "shared_memory")]
+use crate::shared_memory::SharedMemoryWriter;
+use crate::{
+    announce::{self, Announcer},
+    cli::Args,
+    config,
+    keybindings::{Action, Keybindings},
+    memory_audit::MemoryAudit,
+    outcomes, param_diff,
+    param_editor::ParamEditor,
+    renderer::{background::Background, view_state::ViewState, DisplayMode, Renderer},
+    session_script::{self, Command, Player, Recorder, Script},
+    simulation::{
+        census,
+        counting_rng::CountingRng,
+        event_log::LogHeader,
+        events::EventWriter,
+        intervention::Intervention,
+        log_rotation::RotatingLog,
+        memory::MemoryUsage,
+        policy::{Policy, PolicyRegistry},
+        rewind::{RewindBuffer, Travel},
+        snapshot::SnapshotBuffer,
+        stop_condition::StopCondition,
+        timeline,
+        tuning::ParamUpdate,
+        *,
+    },
+    stats_csv, transmission,
+};
+
+const EVENT_LOG_PATH: &str = "event_log.txt";
+const VIEW_STATE_PATH: &str = "view_state.toml";
+const GRAPH_DATA_PATH: &str = "graph_data.tsv";
+const SCREENSHOT_PREFIX: &str = "screenshot_";
+// time compression doubles every this many lines scrolled with Ctrl held
+const SCROLL_LINES_PER_DOUBLING: f64 = 4.0;
+const PIXELS_PER_SCROLL_LINE: f64 = 40.0;
+// how far from a person a right click can be to select them
+const INSPECT_RADIUS: f64 = 2.0;
+// --preview runs this fraction of the people, at the same density
+const PREVIEW_FACTOR: f64 = 0.1;
+
+#[cfg(feature = "audio")]
+fn start_audio(sim: &mut Simulation, infections_per_tick: usize) {
+    sim.subscribe(Arc::new(Mutex::new(AudioCues::start(infections_per_tick))));
+}
+
+#[cfg(not(feature = "audio"))]
+fn start_audio(_sim: &mut Simulation, _infections_per_tick: usize) {
+    panic!("--audio requires building with the `audio` feature");
+}
+
+#[cfg(feature = "microdata")]
+type SharedMicrodata = Arc<Mutex<Option<MicrodataWriter>>>;
+
+// A running simulation, along with its window state.
+pub struct App {
+    args: Args,
+    params: Params,
+    keybindings: Keybindings,
+    renderer: Renderer,
+    sim: Arc<RwLock<Simulation>>,
+    snapshots: Arc<SnapshotBuffer>,
+    // the alternative run, with `branch_intervention` applied when it was forked
+    branch: Arc<RwLock<Option<Simulation>>>,
+    forks: mpsc::Sender<Option<Intervention>>,
+    sim_params: Arc<RwLock<SimulationParameters>>,
+    interventions: mpsc::Sender<Intervention>,
+    travel: mpsc::Sender<Travel>,
+    param_updates: mpsc::Sender<ParamUpdate>,
+    editor: ParamEditor,
+    cursor: (f64, f64),
+    modifiers: ModifiersState,
+    brush_active: bool,
+    frame_interval: Duration,
+    next_frame: Instant,
+    event_writer: Option<Arc<Mutex<EventWriter>>>,
+    // the event log, when it doesn't all stay in memory
+    log_stream: Option<Arc<Mutex<RotatingLog>>>,
+    // in bytes, as last recorded by the simulation thread
+    rewind_size: Arc<AtomicUsize>,
+    memory_audit: Option<MemoryAudit>,
+    #[cfg(feature = "microdata")]
+    microdata: SharedMicrodata,
+}
+
+impl App {
+    pub fn start(display: &Display, args: Args, config_path: &str) -> App {
+        let script = args.replay.as_deref().map(Script::load);
+        // the replayed run starts from the same seed, unless another one is given
+        let seed = args
+            .seed
+            .or_else(|| script.as_ref().map(|script| script.seed))
+            .unwrap_or_else(|| thread_rng().gen());
+        let mut rng = CountingRng::<StdRng>::seed_from_u64(seed);
+
+        let mut setup = config::load_setup(config_path, args.preset.as_deref());
+        if args.preview {
+            if setup.has_layout() {
+                eprintln!("The config places things at fixed positions, so it can't be previewed smaller.");
+            } else {
+                setup = setup.scaled(PREVIEW_FACTOR);
+                println!("Previewing with {} people.", setup.params.num_people);
+            }
+        }
+        let params = setup.params;
+        if (args.record.is_some() || script.is_some()) && params.sim_hz <= 0.0 {
+            eprintln!(
+                "With sim_hz = 0 the steps follow the wall clock, so the replay won't be exact."
+            );
+        }
+        if params.event_log_window > 0 && (args.export_tree.is_some() || args.report.is_some()) {
+            eprintln!(
+                "With event_log_window > 0 only the latest events are kept, so the transmission \
+                 tree and the report won't be written."
+            );
+        }
+        let mut schedule = config::load_events(config_path);
+        if let Some(script) = script {
+            schedule.extend(script.events);
+        }
+        let policy = config::load_policy(config_path, &PolicyRegistry::with_builtins());
+        let keybindings = config::load_keybindings(config_path);
+        // the command line conditions replace the ones from the config
+        let run_until = if args.run_until.is_empty() {
+            config::load_run_until(config_path)
+        } else {
+            args.run_until.clone()
+        };
+
+        let mut sim = setup.create(&mut rng);
+        sim.set_log_header(LogHeader {
+            seed,
+            draws: rng.draws(),
+            params,
+        });
+        let event_writer = args.export_events.as_ref().map(|path| {
+            let writer = Arc::new(Mutex::new(EventWriter::create(path).unwrap()));
+            sim.subscribe(writer.clone());
+            writer
+        });
+        let log_stream = if params.event_log_window > 0 {
+            let log = RotatingLog::create(
+                EVENT_LOG_PATH,
+                params.event_log_max_size,
+                params.event_log_compression,
+                sim.log().header().copied(),
+            )
+            .unwrap();
+            let log = Arc::new(Mutex::new(log));
+            sim.subscribe(log.clone());
+            Some(log)
+        } else {
+            None
+        };
+        if args.audio {
+            start_audio(&mut sim, params.audio_tick_infections);
+        }
+        let sim = Arc::new(RwLock::new(sim));
+        let initial_sim_params = SimulationParameters {
+            time_compression: 1.0,
+            running: false,
+            fast: false,
+        };
+        let sim_params = Arc::new(RwLock::new(initial_sim_params));
+        let recorder = args.record.as_ref().map(|path| {
+            println!("Recording the session to {} (seed {}).", path, seed);
+            Recorder::new(path, seed, initial_sim_params)
+        });
+
+        println!("Simulation created.");
+
+        let mut renderer = Renderer::new(
+            display,
+            params.size_x / 2.0,
+            params.size_y / 2.0,
+            params.size_x,
+            params.language,
+        );
+        if let Some(density) = &setup.density {
+            renderer.set_density(display, density, (params.size_x, params.size_y));
+        }
+        if let Some(background) = config::load_background(config_path) {
+            renderer.set_background(Background::load(
+                display,
+                &background,
+                (params.size_x, params.size_y),
+            ));
+        }
+        if let Some(state) = ViewState::load(VIEW_STATE_PATH) {
+            renderer.restore_view_state(&state);
+        }
+        if let Some(path) = &args.compare {
+            renderer.set_comparison(stats_csv::read(path).unwrap());
+        }
+
+        #[cfg(feature = "microdata")]
+        let microdata = Arc::new(Mutex::new(
+            args.microdata
+                .as_ref()
+                .map(|path| MicrodataWriter::create(path).unwrap()),
+        ));
+        #[cfg(not(feature = "microdata"))]
+        if args.microdata.is_some() {
+            panic!("--microdata requires building with the `microdata` feature");
+        }
+        #[cfg(feature = "shared_memory")]
+        let shared_memory = args
+            .shared_memory
+            .as_ref()
+            .map(|path| SharedMemoryWriter::create(path, params.num_people).unwrap());
+        #[cfg(not(feature = "shared_memory"))]
+        if args.shared_memory.is_some() {
+            panic!("--shared-memory requires building with the `shared_memory` feature");
+        }
+
+        let (interventions, interventions_rx) = mpsc::channel();
+        let (travel, travel_rx) = mpsc::channel();
+        let (forks, forks_rx) = mpsc::channel();
+        let (param_updates, param_updates_rx) = mpsc::channel();
+        let branch = Arc::new(RwLock::new(None));
+        let snapshots = Arc::new(SnapshotBuffer::new());
+        let rewind_size = Arc::new(AtomicUsize::new(0));
+        let memory_audit = args.memory_audit.map(MemoryAudit::new);
+
+        // a notification command alone announces once a day
+        let announcer = match (args.announce, &args.notify_command) {
+            (None, None) => None,
+            (interval, command) => Some(Announcer::new(interval.unwrap_or(1.0), command.clone())),
+        };
+
+        let thread_sim = sim.clone();
+        let thread_branch = branch.clone();
+        let thread_snapshots = snapshots.clone();
+        let thread_sim_params = sim_params.clone();
+        let thread_rewind_size = rewind_size.clone();
+        #[cfg(feature = "microdata")]
+        let thread_microdata = microdata.clone();
+        thread::spawn(move || {
+            run_simulation(
+                thread_sim,
+                thread_branch,
+                thread_snapshots,
+                Controls {
+                    sim_params: thread_sim_params,
+                    interventions: interventions_rx,
+                    travel: travel_rx,
+                    forks: forks_rx,
+                    param_updates: param_updates_rx,
+                    rewind_size: thread_rewind_size,
+                },
+                Session {
+                    rng,
+                    schedule: Player::new(schedule),
+                    recorder,
+                    policy,
+                },
+                announcer,
+                run_until,
+                #[cfg(feature = "microdata")]
+                thread_microdata,
+                #[cfg(feature = "shared_memory")]
+                shared_memory,
+            )
+        });
+
+        App {
+            args,
+            params,
+            keybindings,
+            renderer,
+            sim,
+            snapshots,
+            branch,
+            forks,
+            sim_params,
+            interventions,
+            travel,
+            param_updates,
+            editor: ParamEditor::new(),
+            cursor: (0.0, 0.0),
+            modifiers: ModifiersState::empty(),
+            brush_active: false,
+            frame_interval: interval(params.render_fps),
+            next_frame: Instant::now(),
+            event_writer,
+            log_stream,
+            rewind_size,
+            memory_audit,
+            #[cfg(feature = "microdata")]
+            microdata,
+        }
+    }
+
+    fn send(&self, intervention: Intervention) {
+        let _ = self.interventions.send(intervention);
+    }
+
+    fn infect_at_cursor(&self, display: &Display) {
+        let pos = self
+            .renderer
+            .screen_to_world(display.get_framebuffer_dimensions(), self.cursor);
+        if let Some(pos) = pos {
+            self.send(Intervention::InfectArea {
+                x: pos.x,
+                y: pos.y,
+                radius: self.params.brush_radius,
+            });
+        }
+    }
+
+    // starts recording the timeline of the person under the cursor and shows it
+    fn inspect_at_cursor(&mut self, display: &Display) {
+        let pos = self
+            .renderer
+            .screen_to_world(display.get_framebuffer_dimensions(), self.cursor);
+        let id = pos.and_then(|pos| self.sim.read().unwrap().nearest(pos, INSPECT_RADIUS));
+        if let Some(id) = id {
+            self.send(Intervention::Track(id));
+        }
+        self.renderer.set_inspected(id);
+    }
+
+    // writes out everything that was requested on the command line
+    pub fn shutdown(&self) {
+        let sim = self.sim.read().unwrap();
+        println!(
+            "Final state: {}, {}",
+            announce::describe(sim.time(), &sim.stats()),
+            sim.costs().describe(sim.params())
+        );
+        if let Err(err) = self.renderer.view_state().save(VIEW_STATE_PATH) {
+            eprintln!("Couldn't save the view state: {}", err);
+        }
+        let log_written = match &self.log_stream {
+            Some(log) => log.lock().unwrap().finish(),
+            None => sim.log().write(EVENT_LOG_PATH),
+        };
+        if let Err(err) = log_written {
+            eprintln!("Couldn't write the event log: {}", err);
+        }
+        if let Some(writer) = &self.event_writer {
+            if let Err(err) = writer.lock().unwrap().flush() {
+                eprintln!("Couldn't write the events: {}", err);
+            }
+        }
+        if let Some(path) = &self.args.export_stats {
+            if let Err(err) = self.renderer.export_stats(path) {
+                eprintln!("Couldn't export the statistics: {}", err);
+            }
+        }
+        if let Some(path) = &self.args.export_svg {
+            if let Err(err) = self.renderer.export_svg(path) {
+                eprintln!("Couldn't export the animated chart: {}", err);
+            }
+        }
+        // they'd be missing the events before the window
+        let whole_log = sim.params().event_log_window == 0;
+        if let Some(path) = self.args.export_tree.as_ref().filter(|_| whole_log) {
+            if let Err(err) = transmission::write(path, sim.log()) {
+                eprintln!("Couldn't export the transmission tree: {}", err);
+            }
+        }
+        #[cfg(feature = "microdata")]
+        if let Some(mut writer) = self.microdata.lock().unwrap().take() {
+            if let Err(err) = writer.finish() {
+                eprintln!("Couldn't finish the microdata file: {}", err);
+            }
+        }
+        if let Some(path) = &self.args.outcome_matrix {
+            if let Err(err) = outcomes::write(path, &sim) {
+                eprintln!("Couldn't write the outcome matrix: {}", err);
+            }
+        }
+        if let Some(path) = &self.args.export_timelines {
+            if let Err(err) = timeline::write_json(path, sim.timelines()) {
+                eprintln!("Couldn't export the timelines: {}", err);
+            }
+        }
+        if let Some(path) = &self.args.export_census {
+            if let Err(err) = census::write(path, &sim) {
+                eprintln!("Couldn't export the census: {}", err);
+            }
+        }
+        if let Some(path) = self.args.report.as_ref().filter(|_| whole_log) {
+            if let Err(err) = transmission::write_report(path, sim.log()) {
+                eprintln!("Couldn't write the report: {}", err);
+            }
+        }
+    }
+
+    fn perform(&mut self, display: &Display, action: Action) {
+        match action {
+            Action::Pause => {
+                self.sim_params.write().unwrap().toggle_running();
+            }
+            Action::SpeedUp => {
+                self.sim_params.write().unwrap().increase_time_compression();
+            }
+            Action::FastForward => {
+                self.sim_params.write().unwrap().toggle_fast();
+            }
+            Action::SlowDown => {
+                self.sim_params.write().unwrap().decrease_time_compression();
+            }
+            Action::Vaccinate => {
+                self.send(Intervention::VaccinateFraction(0.01));
+            }
+            Action::Infect => {
+                self.send(Intervention::InfectRandom(1));
+            }
+            Action::ToggleQuarantine => {
+                self.send(Intervention::ToggleQuarantine);
+            }
+            Action::ToggleLockdown => {
+                self.send(Intervention::ToggleLockdown);
+            }
+            Action::CycleCommunity => {
+                let count = self.sim.read().unwrap().communities().len();
+                self.renderer.cycle_community(count);
+            }
+            Action::StepBack => {
+                let _ = self.travel.send(Travel::Back);
+            }
+            Action::StepForward => {
+                let _ = self.travel.send(Travel::Forward);
+            }
+            Action::ToggleHeatmap => {
+                self.renderer.toggle_heatmap();
+            }
+            Action::ToggleDebug => {
+                self.renderer.toggle_debug();
+            }
+            Action::ToggleDiagnostics => {
+                self.renderer.toggle_diagnostics();
+            }
+            Action::ToggleContamination => {
+                self.renderer.toggle_contamination();
+            }
+            Action::StatusColors => {
+                self.renderer.set_mode(DisplayMode::Status);
+            }
+            Action::ImmunityColors => {
+                self.renderer.set_mode(DisplayMode::Immunity);
+            }
+            Action::FlowView => {
+                self.renderer.set_mode(DisplayMode::Flow);
+            }
+            Action::CommunityColors => {
+                self.renderer.set_mode(DisplayMode::Community);
+            }
+            Action::DoseColors => {
+                self.renderer.set_mode(DisplayMode::Doses);
+            }
+            Action::LineageColors => {
+                self.renderer.set_mode(DisplayMode::Lineage);
+            }
+            Action::AgeColors => {
+                self.renderer.set_mode(DisplayMode::AgeGroup);
+            }
+            Action::Follow => {
+                self.renderer.toggle_follow();
+            }
+            Action::Transmissibility(preset) => {
+                self.send(Intervention::SetTransmissibility(
+                    self.params.transmissibility_presets[preset],
+                ));
+            }
+            Action::EditParams => {
+                self.editor.toggle(self.sim.read().unwrap().params());
+                self.renderer.set_edited(self.editor.selected());
+            }
+            Action::ToggleParamDiff => {
+                self.renderer.toggle_param_diff(self.params);
+            }
+            Action::ExportParams => {
+                let sim = self.sim.read().unwrap();
+                match param_diff::export(sim.params(), sim.time()) {
+                    Ok(path) => println!("Wrote the current parameters to {}", path),
+                    Err(err) => eprintln!("Couldn't export the parameters: {}", err),
+                }
+            }
+            Action::Screenshot => match screenshot(display) {
+                Ok(path) => println!("Saved the screenshot to {}", path),
+                Err(err) => eprintln!("Couldn't save the screenshot: {}", err),
+            },
+            Action::CopyGraphData => {
+                let params = self.sim.read().unwrap().params();
+                match self.renderer.export_graph_data(GRAPH_DATA_PATH, params) {
+                    Ok(()) => println!("Wrote the graph data to {}", GRAPH_DATA_PATH),
+                    Err(err) => eprintln!("Couldn't write the graph data: {}", err),
+                }
+            }
+            Action::Branch => {
+                let fork = if self.branch.read().unwrap().is_some() {
+                    None
+                } else {
+                    Some(self.params.branch_intervention)
+                };
+                let _ = self.forks.send(fork);
+            }
+        }
+    }
+
+    // returns true if the application should exit
+    pub fn handle_window_event(&mut self, display: &Display, event: &WindowEvent) -> bool {
+        match *event {
+            WindowEvent::CloseRequested => {
+                self.shutdown();
+                return true;
+            }
+            WindowEvent::KeyboardInput { input, .. } if input.state == ElementState::Pressed => {
+                match input.virtual_keycode {
+                    Some(key) if self.editor.captures(key) => {
+                        if let Some(update) = self.editor.handle_key(key) {
+                            let _ = self.param_updates.send(update);
+                        }
+                        self.renderer.set_edited(self.editor.selected());
+                    }
+                    key => {
+                        if let Some(action) = key.and_then(|key| self.keybindings.action(key)) {
+                            self.perform(display, action);
+                        }
+                    }
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+            }
+            WindowEvent::MouseWheel { delta, .. } if self.modifiers.ctrl() => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y as f64,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y / PIXELS_PER_SCROLL_LINE,
+                };
+                self.sim_params
+                    .write()
+                    .unwrap()
+                    .scale_time_compression(2.0f64.powf(lines / SCROLL_LINES_PER_DOUBLING));
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = (position.x, position.y);
+                let hover = self
+                    .renderer
+                    .screen_to_world(display.get_framebuffer_dimensions(), self.cursor);
+                self.renderer.set_hover(hover);
+                if self.brush_active {
+                    self.infect_at_cursor(display);
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.renderer.set_hover(None);
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.brush_active = state == ElementState::Pressed;
+                if self.brush_active {
+                    self.infect_at_cursor(display);
+                }
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Right,
+                ..
+            } => {
+                self.inspect_at_cursor(display);
+            }
+            _ => (),
+        }
+        false
+    }
+
+    pub fn next_frame(&self) -> Instant {
+        self.next_frame
+    }
+
+    // starts the simulation, as if by the keyboard
+    pub fn resume(&self, time_compression: f64) {
+        let mut sim_params = self.sim_params.write().unwrap();
+        sim_params.time_compression =
+            time_compression.clamp(MIN_TIME_COMPRESSION, MAX_TIME_COMPRESSION);
+        sim_params.running = true;
+    }
+
+    // after `days`, if given, or once nobody is infected any more
+    pub fn finished(&self, days: Option<f64>) -> bool {
+        let sim = self.sim.read().unwrap();
+        matches!(days, Some(days) if sim.time() >= days) || sim.stats().infected == 0
+    }
+
+    // does nothing if the next frame isn't due yet
+    pub fn draw(&mut self, display: &Display) {
+        let now = Instant::now();
+        if now < self.next_frame {
+            return;
+        }
+        // frames that were missed are skipped rather than drawn late
+        self.next_frame = (self.next_frame + self.frame_interval).max(now);
+
+        let sim = self.sim.read().unwrap().clone();
+        let snapshot = self.snapshots.latest();
+        let branch = self.branch.read().unwrap().clone();
+        let sim_params = *self.sim_params.read().unwrap();
+        self.renderer
+            .draw(display, &sim, &snapshot, branch.as_ref(), &sim_params);
+        if let Some(audit) = &mut self.memory_audit {
+            let usage = MemoryUsage {
+                rewind: self.rewind_size.load(Ordering::Relaxed),
+                charts: self.renderer.charts_size(),
+                ..sim.memory_usage()
+            };
+            audit.update(sim.time(), usage);
+        }
+    }
+}
+
+// Saves the last frame drawn to the first of screenshot_1.png, screenshot_2.png etc. that
+// doesn't exist yet. Returns the path written.
+fn screenshot(display: &Display) -> Result<String, String> {
+    let frame: RawImage2d<u8> = display.read_front_buffer().map_err(|err| err.to_string())?;
+    let path = (1..)
+        .map(|index| format!("{}{}.png", SCREENSHOT_PREFIX, index))
+        .find(|path| !Path::new(path).exists())
+        .unwrap();
+    // the rows come from the bottom up
+    let row = frame.width as usize * 4;
+    let pixels: Vec<u8> = frame.data.chunks(row).rev().flatten().copied().collect();
+    image::save_buffer(
+        &path,
+        &pixels,
+        frame.width,
+        frame.height,
+        image::ColorType::Rgba8,
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(path)
+}
+
+// zero for a rate of zero, meaning as often as possible
+fn interval(per_second: f64) -> Duration {
+    if per_second > 0.0 {
+        Duration::from_secs_f64(1.0 / per_second)
+    } else {
+        Duration::from_secs(0)
+    }
+}
+
+// what the window controls the simulation thread with
+struct Controls {
+    sim_params: Arc<RwLock<SimulationParameters>>,
+    interventions: mpsc::Receiver<Intervention>,
+    travel: mpsc::Receiver<Travel>,
+    // `None` closes the branch
+    forks: mpsc::Receiver<Option<Intervention>>,
+    param_updates: mpsc::Receiver<ParamUpdate>,
+    rewind_size: Arc<AtomicUsize>,
+}
+
+// What drives the run besides the window: the seeded generator, the policy, the scheduled and
+// replayed commands, and the recording of the ones given from the window. Forking a branch and travelling
+// back aren't recorded, so a session using them won't replay the same.
+struct Session {
+    rng: CountingRng<StdRng>,
+    schedule: Player,
+    recorder: Option<Recorder>,
+    policy: Option<Box<dyn Policy>>,
+}
+
+fn run_simulation(
+    sim_arc: Arc<RwLock<Simulation>>,
+    branch_arc: Arc<RwLock<Option<Simulation>>>,
+    snapshots: Arc<SnapshotBuffer>,
+    controls: Controls,
+    mut session: Session,
+    mut announcer: Option<Announcer>,
+    mut run_until: Vec<StopCondition>,
+    #[cfg(feature = "microdata")] microdata: SharedMicrodata,
+    #[cfg(feature = "shared_memory")] mut shared_memory: Option<SharedMemoryWriter>,
+) {
+    let mut now = Instant::now();
+    let tick = interval(sim_arc.read().unwrap().params().sim_hz);
+    let mut next_tick = now;
+    let rng = &mut session.rng;
+    let mut last_day = None;
+    let mut rewind = RewindBuffer::new(&sim_arc.read().unwrap().params());
+    #[cfg(feature = "gpu")]
+    let mut gpu = gpu::GpuPhysics::new();
+    #[cfg(feature = "gpu")]
+    if gpu.is_none() {
+        eprintln!("No suitable GPU adapter found, falling back to the CPU.");
+    }
+
+    loop {
+        // the window has gone on to another run
+        if Arc::strong_count(&sim_arc) == 1 {
+            return;
+        }
+        // with a fixed rate, every step is as long as the interval between them
+        let dt = if tick.as_secs_f64() > 0.0 {
+            tick.as_secs_f64()
+        } else {
+            now.elapsed().as_secs_f64()
+        };
+        now = Instant::now();
+
+        let mut sim = sim_arc.read().unwrap().clone();
+        let mut branch = branch_arc.read().unwrap().clone();
+        for fork in controls.forks.try_iter() {
+            branch = fork.map(|intervention| {
+                let mut branch = sim.fork();
+                branch.apply(intervention, rng);
+                branch
+            });
+        }
+        for direction in controls.travel.try_iter() {
+            if let Some(snapshot) = rewind.travel(direction, sim.time()) {
+                sim = snapshot;
+                branch = None;
+                last_day = Some(sim.time().floor() as u64);
+                // the run branches off from the snapshot when it is resumed
+                controls.sim_params.write().unwrap().running = false;
+            }
+        }
+        let mut params = *controls.sim_params.read().unwrap();
+        let (step, day) = (sim.steps(), sim.time());
+        // only the user's commands are recorded, the scheduled ones come again from the
+        // config or the script on a replay
+        let scheduled = session.schedule.due(step, day);
+        let from_window = controls
+            .interventions
+            .try_iter()
+            .map(Command::Intervene)
+            .chain(controls.param_updates.try_iter().map(Command::SetParam));
+        let commands: Vec<_> = scheduled
+            .into_iter()
+            .map(|command| (command, true))
+            .chain(from_window.map(|command| (command, false)))
+            .collect();
+        // the user's interventions apply to both runs, the policy only drives the main one
+        for (command, scheduled) in commands {
+            match command {
+                Command::Intervene(intervention) => {
+                    sim.apply(intervention, rng);
+                    if let Some(branch) = &mut branch {
+                        branch.apply(intervention, rng);
+                    }
+                }
+                Command::SetParam(update) => {
+                    sim.update_param(update);
+                    if let Some(branch) = &mut branch {
+                        branch.update_param(update);
+                    }
+                }
+                _ => {
+                    session_script::control(command, &mut params);
+                    session_script::control(command, &mut controls.sim_params.write().unwrap());
+                    if let (Some(recorder), true) = (&mut session.recorder, scheduled) {
+                        recorder.skip(command);
+                    }
+                    continue;
+                }
+            }
+            if let (Some(recorder), false) = (&mut session.recorder, scheduled) {
+                recorder.record(step, day, command);
+            }
+        }
+        // the speed controls, whether changed from the window or by the schedule
+        if let Some(recorder) = &mut session.recorder {
+            recorder.observe(step, day, &params);
+        }
+        // the snapshots it could travel forward to while paused are gone once it goes on
+        if params.running {
+            rewind.discard_after(sim.time());
+        }
+        #[cfg(feature = "gpu")]
+        match &mut gpu {
+            Some(gpu) => sim.step_gpu(dt, rng, &params, gpu),
+            None => sim.step(dt, rng, &params),
+        }
+        #[cfg(not(feature = "gpu"))]
+        sim.step(dt, rng, &params);
+        if let Some(branch) = &mut branch {
+            branch.step(dt, rng, &params);
+        }
+
+        let day = sim.time().floor() as u64;
+        if last_day != Some(day) {
+            last_day = Some(day);
+            // the policy gets to decide once per simulated day
+            if let Some(policy) = &mut session.policy {
+                for intervention in policy.decide(&sim.stats(), day) {
+                    sim.apply(intervention, rng);
+                }
+            }
+            #[cfg(feature = "microdata")]
+            if let Some(writer) = microdata.lock().unwrap().as_mut() {
+                writer.write_day(day, &sim).unwrap();
+            }
+        }
+        if let Some(announcer) = &mut announcer {
+            announcer.update(&sim);
+        }
+        // every condition is only checked until the first one is met, so that the user can
+        // resume the simulation afterwards
+        if params.running {
+            if let Some(condition) = run_until.iter().find(|condition| condition.is_met(&sim)) {
+                println!(
+                    "Stopped ({}): {}, {}",
+                    condition,
+                    announce::describe(sim.time(), &sim.stats()),
+                    sim.costs().describe(sim.params())
+                );
+                controls.sim_params.write().unwrap().running = false;
+                run_until.clear();
+            }
+        }
+        rewind.record(&sim);
+        controls.rewind_size.store(rewind.size(), Ordering::Relaxed);
+        let snapshot = sim.render_snapshot();
+        #[cfg(feature = "shared_memory")]
+        if let Some(writer) = &mut shared_memory {
+            if let Err(err) = writer.publish(&snapshot) {
+                eprintln!("Couldn't publish the snapshot: {}", err);
+            }
+        }
+        snapshots.publish(snapshot);
+        *sim_arc.write().unwrap() = sim;
+        *branch_arc.write().unwrap() = branch;
+
+        next_tick += tick;
+        let now = Instant::now();
+        if next_tick > now {
+            thread::sleep(next_tick - now);
+        } else {
+            // fell behind, don't try to catch up
+            next_tick = now;
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use rodio::{source::SineWave, OutputStream, OutputStreamHandle, Source};
+
+use crate::simulation::events::{SimEvent, Subscriber};
+
+const VOLUME: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy)]
+enum Cue {
+    Tick,
+    Chime,
+    Alarm,
+}
+
+fn tone(handle: &OutputStreamHandle, frequency: f32, delay_ms: u64, duration_ms: u64) {
+    let source = SineWave::new(frequency)
+        .take_duration(Duration::from_millis(duration_ms))
+        .amplify(VOLUME)
+        .delay(Duration::from_millis(delay_ms));
+    if let Err(err) = handle.play_raw(source) {
+        eprintln!("Couldn't play a sound: {}", err);
+    }
+}
+
+fn play(handle: &OutputStreamHandle, cue: Cue) {
+    match cue {
+        Cue::Tick => tone(handle, 1800.0, 0, 15),
+        // rising
+        Cue::Chime => {
+            tone(handle, 660.0, 0, 200);
+            tone(handle, 880.0, 200, 200);
+            tone(handle, 1320.0, 400, 400);
+        }
+        // two alternating tones, three times
+        Cue::Alarm => {
+            for i in 0..6 {
+                let frequency = if i % 2 == 0 { 900.0 } else { 600.0 };
+                tone(handle, frequency, i * 250, 250);
+            }
+        }
+    }
+}
+
+// Plays a tick every `infections_per_tick` new infections, a chime when the epidemic dies
+// out and an alarm when the hospitals run over capacity. The sounds are played on a thread
+// of their own, as the output stream can't be sent between threads.
+pub struct AudioCues {
+    cues: mpsc::Sender<Cue>,
+    infections_per_tick: usize,
+    until_tick: usize,
+}
+
+impl AudioCues {
+    pub fn start(infections_per_tick: usize) -> AudioCues {
+        let (cues, cues_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let (_stream, handle) = match OutputStream::try_default() {
+                Ok(output) => output,
+                Err(err) => {
+                    eprintln!("Couldn't open the audio output: {}", err);
+                    return;
+                }
+            };
+            for cue in cues_rx {
+                play(&handle, cue);
+            }
+        });
+        AudioCues {
+            cues,
+            infections_per_tick: infections_per_tick.max(1),
+            until_tick: infections_per_tick.max(1),
+        }
+    }
+
+    fn cue(&self, cue: Cue) {
+        // fails only if the audio output couldn't be opened
+        let _ = self.cues.send(cue);
+    }
+}
+
+impl Subscriber for AudioCues {
+    fn notify(&mut self, _time: f64, event: &SimEvent) {
+        match event {
+            SimEvent::Infected { .. } => {
+                self.until_tick -= 1;
+                if self.until_tick == 0 {
+                    self.until_tick = self.infections_per_tick;
+                    self.cue(Cue::Tick);
+                }
+            }
+            SimEvent::Extinct => self.cue(Cue::Chime),
+            SimEvent::HospitalCapacity { exceeded: true } => self.cue(Cue::Alarm),
+            _ => (),
+        }
+    }
+}
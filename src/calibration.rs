@@ -0,0 +1,64 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::simulation::{setup::Setup, ContactCalibration, HEADLESS};
+
+// the length of a trial run, the rate being measured over its last day
+const TRIAL_DAYS: f64 = 2.0;
+const MAX_TRIALS: usize = 8;
+// relative to the target
+const TOLERANCE: f64 = 0.02;
+// the same for all the trials, so that they differ only by the calibrated parameter
+const TRIAL_SEED: u64 = 0;
+
+fn measure(setup: &Setup) -> f64 {
+    let mut rng = StdRng::seed_from_u64(TRIAL_SEED);
+    let mut sim = setup.create(&mut rng);
+    while sim.time() < TRIAL_DAYS {
+        sim.step(0.0, &mut rng, &HEADLESS);
+    }
+    sim.contact_rate().unwrap_or(0.0)
+}
+
+// Adjusts the parameters of the setup in trial runs until the people meet about
+// `target_contact_rate` others a day, if it's set. The rate grows about linearly both with the
+// speeds and with the density, so each trial scales the parameter by the ratio of the target
+// to the rate measured in the previous one.
+pub fn calibrate(setup: &mut Setup) {
+    let target = setup.params.target_contact_rate;
+    if target <= 0.0 {
+        return;
+    }
+    let by = setup.params.calibrate_contacts_by;
+    if by == ContactCalibration::Density && setup.has_census() {
+        eprintln!("The census gives the number of people, calibrating the contact rate by speed.");
+        setup.params.calibrate_contacts_by = ContactCalibration::Speed;
+        return calibrate(setup);
+    }
+    let mut rate = measure(setup);
+    for _ in 0..MAX_TRIALS {
+        if (rate - target).abs() <= TOLERANCE * target {
+            break;
+        }
+        if rate == 0.0 {
+            eprintln!("Nobody met anyone in a trial run, the contact rate can't be calibrated.");
+            return;
+        }
+        let scale = target / rate;
+        match by {
+            ContactCalibration::Speed => setup.params.speed_stdev *= scale,
+            ContactCalibration::Density => {
+                let num_people = setup.params.num_people as f64 * scale;
+                setup.params.num_people = (num_people.round() as usize).max(2);
+            }
+        }
+        rate = measure(setup);
+    }
+    let calibrated = match by {
+        ContactCalibration::Speed => format!("speed_stdev = {:.3}", setup.params.speed_stdev),
+        ContactCalibration::Density => format!("num_people = {}", setup.params.num_people),
+    };
+    println!(
+        "Calibrated {} for {:.2} contacts per person per day (the target is {}).",
+        calibrated, rate, target
+    );
+}
@@ -0,0 +1,190 @@
+use std::env;
+
+use crate::simulation::stop_condition::StopCondition;
+
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    pub preset: Option<String>,
+    pub compare: Option<String>,
+    pub export_stats: Option<String>,
+    pub export_svg: Option<String>,
+    pub export_tree: Option<String>,
+    pub report: Option<String>,
+    pub microdata: Option<String>,
+    pub shared_memory: Option<String>,
+    pub outcome_matrix: Option<String>,
+    pub export_timelines: Option<String>,
+    pub export_events: Option<String>,
+    pub export_census: Option<String>,
+    pub announce: Option<f64>,
+    pub notify_command: Option<String>,
+    pub memory_audit: Option<f64>,
+    pub run_until: Vec<StopCondition>,
+    pub extinction_runs: Option<u64>,
+    pub extinction_threshold: Option<usize>,
+    pub seed_check: Option<u64>,
+    pub preview: bool,
+    pub analyze: Option<String>,
+    pub compare_runs: Option<(String, String)>,
+    pub audio: bool,
+    pub trace_output: Option<String>,
+    pub rpc: Option<String>,
+    pub worker: Option<String>,
+    pub kiosk: Option<String>,
+    pub seed: Option<u64>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+}
+
+impl Args {
+    pub fn parse() -> Args {
+        let mut result: Args = Default::default();
+        let mut args = env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preset" => {
+                    result.preset = Some(args.next().expect("--preset requires a name"));
+                }
+                "--compare" => {
+                    result.compare = Some(args.next().expect("--compare requires a file name"));
+                }
+                "--export-stats" => {
+                    result.export_stats =
+                        Some(args.next().expect("--export-stats requires a file name"));
+                }
+                "--export-svg" => {
+                    result.export_svg =
+                        Some(args.next().expect("--export-svg requires a file name"));
+                }
+                "--export-tree" => {
+                    result.export_tree =
+                        Some(args.next().expect("--export-tree requires a file name"));
+                }
+                "--report" => {
+                    result.report = Some(args.next().expect("--report requires a file name"));
+                }
+                "--microdata" => {
+                    result.microdata = Some(args.next().expect("--microdata requires a file name"));
+                }
+                "--shared-memory" => {
+                    result.shared_memory =
+                        Some(args.next().expect("--shared-memory requires a file name"));
+                }
+                "--outcome-matrix" => {
+                    result.outcome_matrix =
+                        Some(args.next().expect("--outcome-matrix requires a file name"));
+                }
+                "--export-timelines" => {
+                    result.export_timelines = Some(
+                        args.next()
+                            .expect("--export-timelines requires a file name"),
+                    );
+                }
+                "--export-events" => {
+                    result.export_events =
+                        Some(args.next().expect("--export-events requires a file name"));
+                }
+                "--export-census" => {
+                    result.export_census =
+                        Some(args.next().expect("--export-census requires a file name"));
+                }
+                "--announce" => {
+                    let interval = args
+                        .next()
+                        .expect("--announce requires an interval in days");
+                    let interval: f64 = interval.parse().expect("invalid --announce interval");
+                    assert!(interval > 0.0, "the --announce interval should be positive");
+                    result.announce = Some(interval);
+                }
+                "--notify-command" => {
+                    result.notify_command =
+                        Some(args.next().expect("--notify-command requires a command"));
+                }
+                "--memory-audit" => {
+                    let interval = args
+                        .next()
+                        .expect("--memory-audit requires an interval in seconds");
+                    let interval: f64 = interval.parse().expect("invalid --memory-audit interval");
+                    // as a duration, it has to be finite as well
+                    assert!(
+                        interval > 0.0 && interval.is_finite(),
+                        "the --memory-audit interval should be positive"
+                    );
+                    result.memory_audit = Some(interval);
+                }
+                "--run-until" => {
+                    let condition = args
+                        .next()
+                        .expect("--run-until requires a condition, eg. day=365");
+                    result.run_until.push(StopCondition::parse(&condition));
+                }
+                "--extinction-runs" => {
+                    let runs = args
+                        .next()
+                        .expect("--extinction-runs requires a number of runs");
+                    result.extinction_runs = Some(runs.parse().expect("invalid number of runs"));
+                }
+                "--extinction-threshold" => {
+                    let threshold = args
+                        .next()
+                        .expect("--extinction-threshold requires a number of cases");
+                    result.extinction_threshold =
+                        Some(threshold.parse().expect("invalid extinction threshold"));
+                }
+                "--seed-check" => {
+                    let runs = args.next().expect("--seed-check requires a number of runs");
+                    result.seed_check = Some(runs.parse().expect("invalid number of runs"));
+                }
+                "analyze" => {
+                    result.analyze =
+                        Some(args.next().expect("analyze requires a directory of runs"));
+                }
+                "compare" => {
+                    let usage = "compare requires two directories of runs";
+                    let dir_a = args.next().expect(usage);
+                    let dir_b = args.next().expect(usage);
+                    result.compare_runs = Some((dir_a, dir_b));
+                }
+                "--preview" => {
+                    result.preview = true;
+                }
+                "--audio" => {
+                    result.audio = true;
+                }
+                "--trace-output" => {
+                    result.trace_output =
+                        Some(args.next().expect("--trace-output requires a file name"));
+                }
+                "--rpc" => {
+                    result.rpc = Some(
+                        args.next()
+                            .expect("--rpc requires an address, eg. 127.0.0.1:7878"),
+                    );
+                }
+                "--worker" => {
+                    result.worker = Some(
+                        args.next()
+                            .expect("--worker requires the directory of the job queue"),
+                    );
+                }
+                "--kiosk" => {
+                    result.kiosk = Some(args.next().expect("--kiosk requires a playlist file"));
+                }
+                "--seed" => {
+                    let seed = args.next().expect("--seed requires a number");
+                    result.seed = Some(seed.parse().expect("invalid seed"));
+                }
+                "--record" => {
+                    result.record = Some(args.next().expect("--record requires a file name"));
+                }
+                "--replay" => {
+                    result.replay = Some(args.next().expect("--replay requires a script file"));
+                }
+                _ => panic!("unknown argument: {}", arg),
+            }
+        }
+
+        result
+    }
+}
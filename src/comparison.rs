@@ -0,0 +1,213 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::analyze::{self, Run, OUTCOMES};
+
+const COMPARISON_PATH: &str = "comparison.json";
+// resamples for the confidence intervals, and shuffles for the significance test
+const BOOTSTRAP_SAMPLES: usize = 2000;
+const PERMUTATIONS: usize = 2000;
+// the intervals cover this fraction of the bootstrap distribution
+const CONFIDENCE: f64 = 0.95;
+const SIGNIFICANCE: f64 = 0.05;
+// the same resamples for the same runs each time
+const SEED: u64 = 0;
+
+// How an outcome differs between the two sets, as the second minus the first.
+struct Difference {
+    outcome: &'static str,
+    mean_a: f64,
+    mean_b: f64,
+    difference: f64,
+    interval: (f64, f64),
+    // of a difference at least this large in either direction if the sets didn't differ,
+    // from the permutation test
+    p_value: f64,
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn resample<R: Rng>(values: &[f64], rng: &mut R) -> Vec<f64> {
+    (0..values.len())
+        .map(|_| values[rng.gen_range(0, values.len())])
+        .collect()
+}
+
+fn compare_outcome<R: Rng>(outcome: &'static str, a: &[f64], b: &[f64], rng: &mut R) -> Difference {
+    let observed = mean(b) - mean(a);
+
+    let mut bootstrap: Vec<_> = (0..BOOTSTRAP_SAMPLES)
+        .map(|_| mean(&resample(b, rng)) - mean(&resample(a, rng)))
+        .collect();
+    bootstrap.sort_by(f64::total_cmp);
+    let quantile = |q: f64| bootstrap[((bootstrap.len() - 1) as f64 * q).round() as usize];
+    let tail = (1.0 - CONFIDENCE) / 2.0;
+
+    // the runs are shuffled between the sets, as if they were all from the same one
+    let mut pooled: Vec<_> = a.iter().chain(b).copied().collect();
+    let mut as_large = 0;
+    for _ in 0..PERMUTATIONS {
+        pooled.shuffle(rng);
+        let (a, b) = pooled.split_at(a.len());
+        if (mean(b) - mean(a)).abs() >= observed.abs() {
+            as_large += 1;
+        }
+    }
+
+    Difference {
+        outcome,
+        mean_a: mean(a),
+        mean_b: mean(b),
+        difference: observed,
+        interval: (quantile(tail), quantile(1.0 - tail)),
+        // counting the observed arrangement, so that it's never 0
+        p_value: (as_large + 1) as f64 / (PERMUTATIONS + 1) as f64,
+    }
+}
+
+fn outcomes(runs: &[Run], index: usize) -> Vec<f64> {
+    runs.iter().map(|run| run.outcomes[index]).collect()
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// JSON has no NaN or infinity, eg. for the outcomes missing from all the runs
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_owned()
+    }
+}
+
+fn write_json(
+    path: &str,
+    dirs: (&str, &str),
+    runs: (usize, usize),
+    differences: &[Difference],
+) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "{{")?;
+    for (name, dir, runs) in &[("a", dirs.0, runs.0), ("b", dirs.1, runs.1)] {
+        writeln!(
+            file,
+            "  \"{}\": {{\"dir\": {}, \"runs\": {}}},",
+            name,
+            json_string(dir),
+            runs
+        )?;
+    }
+    writeln!(file, "  \"confidence\": {},", CONFIDENCE)?;
+    writeln!(file, "  \"outcomes\": {{")?;
+    for (index, difference) in differences.iter().enumerate() {
+        writeln!(
+            file,
+            "    \"{}\": {{\"mean_a\": {}, \"mean_b\": {}, \"difference\": {}, \
+             \"interval\": [{}, {}], \"p_value\": {}, \"significant\": {}}}{}",
+            difference.outcome,
+            json_number(difference.mean_a),
+            json_number(difference.mean_b),
+            json_number(difference.difference),
+            json_number(difference.interval.0),
+            json_number(difference.interval.1),
+            json_number(difference.p_value),
+            difference.p_value < SIGNIFICANCE,
+            if index + 1 < differences.len() {
+                ","
+            } else {
+                ""
+            }
+        )?;
+    }
+    writeln!(file, "  }}")?;
+    writeln!(file, "}}")?;
+    file.flush()
+}
+
+// Compares the outcomes of the replicate runs in the two directories, given as for the
+// analyze subcommand, printing the differences with their bootstrap confidence intervals and
+// the p-values of a permutation test, and writing them to comparison.json.
+pub fn compare(dir_a: &str, dir_b: &str) {
+    let load = |dir: &str| {
+        analyze::load_runs(dir)
+            .unwrap_or_else(|err| panic!("couldn't load the runs in {}: {}", dir, err))
+    };
+    let (runs_a, runs_b) = (load(dir_a), load(dir_b));
+    if runs_a.is_empty() || runs_b.is_empty() {
+        eprintln!("Both directories need at least one run to compare.");
+        return;
+    }
+    if runs_a.len() < 2 || runs_b.len() < 2 {
+        eprintln!("With a single run in a set, the intervals only show the spread of the other.");
+    }
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let differences: Vec<_> = OUTCOMES
+        .iter()
+        .enumerate()
+        .map(|(index, &outcome)| {
+            compare_outcome(
+                outcome,
+                &outcomes(&runs_a, index),
+                &outcomes(&runs_b, index),
+                &mut rng,
+            )
+        })
+        .collect();
+
+    println!("A: {} ({} runs)", dir_a, runs_a.len());
+    println!("B: {} ({} runs)", dir_b, runs_b.len());
+    println!(
+        "{:<10} {:>12} {:>12} {:>12} {:>25} {:>8}",
+        "outcome",
+        "mean A",
+        "mean B",
+        "B - A",
+        format!("{:.0}% interval", CONFIDENCE * 100.0),
+        "p"
+    );
+    for difference in &differences {
+        println!(
+            "{:<10} {:>12.2} {:>12.2} {:>12.2} {:>25} {:>8.4}{}",
+            difference.outcome,
+            difference.mean_a,
+            difference.mean_b,
+            difference.difference,
+            format!(
+                "{:.2} to {:.2}",
+                difference.interval.0, difference.interval.1
+            ),
+            difference.p_value,
+            if difference.p_value < SIGNIFICANCE {
+                " *"
+            } else {
+                ""
+            }
+        );
+    }
+    println!("* significant at p < {}", SIGNIFICANCE);
+
+    let runs = (runs_a.len(), runs_b.len());
+    match write_json(COMPARISON_PATH, (dir_a, dir_b), runs, &differences) {
+        Ok(()) => println!("Wrote the comparison to {}", COMPARISON_PATH),
+        Err(err) => eprintln!("Couldn't write the comparison: {}", err),
+    }
+}
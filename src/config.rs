@@ -0,0 +1,215 @@
+use std::{fs::File, io::Read};
+
+use toml::Value;
+
+use crate::{
+    calibration,
+    keybindings::Keybindings,
+    renderer::background::BackgroundConfig,
+    session_script::{self, Event},
+    simulation::{
+        census::{self, Census},
+        community::Community,
+        density::DensityMap,
+        hospital::Hospital,
+        obstacle::Obstacle,
+        policy::{Policy, PolicyRegistry},
+        presets,
+        setup::Setup,
+        stop_condition::StopCondition,
+        strain::Strains,
+        zone::Zone,
+        Params, SeedCluster,
+    },
+};
+
+fn read_config(path: &str) -> Value {
+    let mut conf_file = File::open(path).unwrap();
+    let mut conf_str = String::new();
+    conf_file.read_to_string(&mut conf_str).unwrap();
+    conf_str.parse().unwrap()
+}
+
+pub fn load_params(path: &str, preset: Option<&str>) -> Params {
+    let user = read_config(path);
+
+    // a preset given on the command line takes priority over the one in the config
+    let preset_name = preset.map(str::to_owned).or_else(|| {
+        user.get("preset")
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+    });
+
+    let preset_name = match preset_name {
+        Some(name) => name,
+        None => {
+            let params: Params = user.try_into().unwrap();
+            params.validate().unwrap();
+            return params;
+        }
+    };
+
+    let preset = presets::preset(&preset_name).unwrap_or_else(|| {
+        panic!(
+            "unknown preset: {} (available: {})",
+            preset_name,
+            presets::PRESET_NAMES.join(", ")
+        )
+    });
+
+    // fields set explicitly in the config override the preset
+    override_params(preset, &user).unwrap()
+}
+
+// `params` with the fields given in the `overrides` table replaced
+pub fn override_params(params: Params, overrides: &Value) -> Result<Params, String> {
+    let mut merged = Value::try_from(params).unwrap();
+    if let (Some(merged), Some(overrides)) = (merged.as_table_mut(), overrides.as_table()) {
+        for (key, value) in overrides {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    let params: Params = merged.try_into().map_err(|err| err.to_string())?;
+    params.validate()?;
+    Ok(params)
+}
+
+// the policy is configured in the `[policy]` table, with its name in the `kind` key
+pub fn load_policy(path: &str, registry: &PolicyRegistry) -> Option<Box<dyn Policy>> {
+    let config = read_config(path);
+    let policy = config.get("policy")?;
+    let kind = policy
+        .get("kind")
+        .and_then(Value::as_str)
+        .expect("policy kind not given");
+    let policy = registry.create(kind, policy).unwrap_or_else(|| {
+        panic!(
+            "unknown policy: {} (available: {})",
+            kind,
+            registry.names().join(", ")
+        )
+    });
+    Some(policy)
+}
+
+pub fn load_keybindings(path: &str) -> Keybindings {
+    Keybindings::new(read_config(path).get("keybindings"))
+}
+
+pub fn load_run_until(path: &str) -> Vec<StopCondition> {
+    read_config(path)
+        .get("run_until")
+        .map_or_else(Vec::new, StopCondition::from_table)
+}
+
+// the `[[events]]` schedule, applied on top of the policy
+pub fn load_events(path: &str) -> Vec<Event> {
+    read_config(path)
+        .get("events")
+        .map_or_else(Vec::new, session_script::parse_events)
+}
+
+pub fn load_seed_clusters(path: &str) -> Vec<SeedCluster> {
+    read_config(path)
+        .get("seed_clusters")
+        .map_or_else(Vec::new, |clusters| clusters.clone().try_into().unwrap())
+}
+
+// either a grid of densities in `density`, or a PGM image given in `density_map`
+pub fn load_density(path: &str) -> Option<DensityMap> {
+    let config = read_config(path);
+    match (config.get("density"), config.get("density_map")) {
+        (Some(_), Some(_)) => panic!("only one of density and density_map can be given"),
+        (Some(grid), None) => Some(DensityMap::new(grid.clone().try_into().unwrap())),
+        (None, Some(map)) => {
+            let map = map.as_str().expect("density_map should be a file name");
+            Some(
+                DensityMap::load_pgm(map)
+                    .unwrap_or_else(|err| panic!("couldn't load the density map {}: {}", map, err)),
+            )
+        }
+        (None, None) => None,
+    }
+}
+
+// the people given in the `population_csv` file, instead of random ones
+pub fn load_census(path: &str) -> Option<Census> {
+    let config = read_config(path);
+    let census = config.get("population_csv")?;
+    let census = census
+        .as_str()
+        .expect("population_csv should be a file name");
+    Some(
+        census::read(census)
+            .unwrap_or_else(|err| panic!("couldn't load the population {}: {}", census, err)),
+    )
+}
+
+pub fn load_background(path: &str) -> Option<BackgroundConfig> {
+    read_config(path)
+        .get("background")
+        .map(|background| background.clone().try_into().unwrap())
+}
+
+pub fn load_communities(path: &str) -> Vec<Community> {
+    read_config(path)
+        .get("communities")
+        .map_or_else(Vec::new, |communities| {
+            communities.clone().try_into().unwrap()
+        })
+}
+
+pub fn load_zones(path: &str) -> Vec<Zone> {
+    read_config(path)
+        .get("zones")
+        .map_or_else(Vec::new, |zones| zones.clone().try_into().unwrap())
+}
+
+pub fn load_hospital(path: &str) -> Option<Hospital> {
+    read_config(path)
+        .get("hospital")
+        .map(|hospital| hospital.clone().try_into().unwrap())
+}
+
+pub fn load_obstacles(path: &str) -> Vec<Obstacle> {
+    read_config(path)
+        .get("obstacles")
+        .map_or_else(Vec::new, |obstacles| obstacles.clone().try_into().unwrap())
+}
+
+// the matrix and the vaccine efficacies need to cover all the strains, so they're read along
+pub fn load_strains(path: &str) -> Strains {
+    let config = read_config(path);
+    let introduced = config
+        .get("strains")
+        .map_or_else(Vec::new, |strains| strains.clone().try_into().unwrap());
+    let cross_immunity = config
+        .get("cross_immunity")
+        .map(|matrix| matrix.clone().try_into().unwrap());
+    let vaccine_efficacy = config
+        .get("vaccine_efficacy")
+        .map(|efficacy| efficacy.clone().try_into().unwrap());
+    let vaccines = config
+        .get("vaccines")
+        .map_or_else(Vec::new, |vaccines| vaccines.clone().try_into().unwrap());
+    Strains::new(introduced, cross_immunity).with_vaccines(vaccine_efficacy, vaccines)
+}
+
+// everything in the config the initial state depends on, with the contact rate calibrated
+pub fn load_setup(path: &str, preset: Option<&str>) -> Setup {
+    let obstacles = load_obstacles(path);
+    let mut setup = Setup {
+        params: load_params(path, preset),
+        density: load_density(path),
+        communities: load_communities(path),
+        zones: load_zones(path),
+        walls: obstacles.iter().flat_map(Obstacle::segments).collect(),
+        obstacles,
+        hospital: load_hospital(path),
+        strains: load_strains(path),
+        seed_clusters: load_seed_clusters(path),
+        census: load_census(path),
+    };
+    calibration::calibrate(&mut setup);
+    setup
+}
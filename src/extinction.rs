@@ -0,0 +1,86 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    config,
+    simulation::{
+        policy::{Policy, PolicyRegistry},
+        setup::Setup,
+        Simulation,
+    },
+};
+
+// the early phase is cut short after this long even if neither outcome was reached
+const MAX_DAYS: f64 = 365.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EarlyOutcome {
+    Extinct,
+    TookOff,
+    Undecided,
+}
+
+fn run_early_phase(
+    setup: &Setup,
+    mut policy: Option<Box<dyn Policy>>,
+    threshold: usize,
+    seed: u64,
+) -> EarlyOutcome {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sim = setup.create(&mut rng);
+
+    let outcome = |sim: &Simulation| {
+        if sim.stats().infected == 0 {
+            EarlyOutcome::Extinct
+        } else if sim.totals().cases > threshold {
+            EarlyOutcome::TookOff
+        } else {
+            EarlyOutcome::Undecided
+        }
+    };
+    sim.run_headless(&mut rng, &mut policy, |sim| {
+        sim.time() >= MAX_DAYS || outcome(sim) != EarlyOutcome::Undecided
+    });
+    outcome(&sim)
+}
+
+// Runs the early phase of the epidemic with seeds 0..runs, each until the infection dies
+// out or the cumulative number of cases exceeds `threshold`, and prints the fraction of runs
+// in which it died out.
+pub fn estimate(config_path: &str, preset: Option<&str>, runs: u64, threshold: usize) {
+    let setup = config::load_setup(config_path, preset);
+    let registry = PolicyRegistry::with_builtins();
+
+    let mut extinct = 0;
+    let mut undecided = 0;
+    for seed in 0..runs {
+        let policy = config::load_policy(config_path, &registry);
+        match run_early_phase(&setup, policy, threshold, seed) {
+            EarlyOutcome::Extinct => extinct += 1,
+            EarlyOutcome::TookOff => (),
+            EarlyOutcome::Undecided => undecided += 1,
+        }
+    }
+
+    let decided = runs - undecided;
+    println!(
+        "Early extinction in {} of {} runs (threshold: {} cases)",
+        extinct, runs, threshold
+    );
+    if undecided > 0 {
+        println!(
+            "{} runs reached neither outcome in {} days and are left out",
+            undecided, MAX_DAYS
+        );
+    }
+    if decided > 0 {
+        let p = extinct as f64 / decided as f64;
+        // normal approximation of the binomial confidence interval
+        let half_width = 1.96 * (p * (1.0 - p) / decided as f64).sqrt();
+        println!(
+            "Probability of early extinction: {:.3} (95% CI {:.3} - {:.3})",
+            p,
+            (p - half_width).max(0.0),
+            (p + half_width).min(1.0)
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// The C API of the dynamic library. The header in include/infections.h is generated from this
+// file with `cbindgen --config cbindgen.toml --output include/infections.h`, so the comments
+// meant for the embedders are doc comments.
+
+use std::{
+    ffi::CStr,
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::simulation::{setup::Setup, Params, Simulation};
+
+/// A simulation, created by `sim_new` and destroyed by `sim_free`.
+pub struct SimHandle {
+    sim: Simulation,
+    rng: StdRng,
+}
+
+/// The state of a simulation at `time` days.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimStats {
+    pub time: f64,
+    pub population: u64,
+    pub dead: u64,
+    pub infected: u64,
+    pub healed: u64,
+    pub vaccinated: u64,
+    pub vaccinated_infected: u64,
+}
+
+/// Creates a simulation from the parameters in `config`, given in the format of config.toml,
+/// or with the default parameters if `config` is null. The same seed gives the same run.
+///
+/// Returns null if the parameters couldn't be read.
+///
+/// # Safety
+///
+/// `config` has to be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sim_new(config: *const c_char, seed: u64) -> *mut SimHandle {
+    let params: Params = if config.is_null() {
+        Default::default()
+    } else {
+        let parsed = CStr::from_ptr(config)
+            .to_str()
+            .ok()
+            .and_then(|config| toml::from_str::<Params>(config).ok());
+        match parsed {
+            Some(params) if params.validate().is_ok() => params,
+            _ => return ptr::null_mut(),
+        }
+    };
+    // unwinding into the caller is undefined behaviour
+    panic::catch_unwind(|| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let sim = Setup::new(params).create(&mut rng);
+        Box::into_raw(Box::new(SimHandle { sim, rng }))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Advances the simulation by `days` of simulated time.
+///
+/// Returns false without stepping if `days` is infinite or NaN, and false if the step
+/// panicked, leaving the simulation as it was at the panic.
+///
+/// # Safety
+///
+/// `sim` has to come from `sim_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sim_step(sim: *mut SimHandle, days: f64) -> bool {
+    if !days.is_finite() {
+        return false;
+    }
+    let handle = &mut *sim;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let end = handle.sim.time() + days;
+        let SimHandle { sim, rng } = handle;
+        sim.run_headless(rng, &mut None, |sim| sim.time() >= end);
+    }));
+    if result.is_err() {
+        eprintln!("sim_step panicked, the simulation is left as it was at the panic");
+    }
+    result.is_ok()
+}
+
+/// The current state of the simulation.
+///
+/// # Safety
+///
+/// `sim` has to come from `sim_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sim_stats(sim: *const SimHandle) -> SimStats {
+    let sim = &(*sim).sim;
+    let stats = sim.stats();
+    SimStats {
+        time: sim.time(),
+        population: stats.population as u64,
+        dead: stats.dead as u64,
+        infected: stats.infected as u64,
+        healed: stats.healed as u64,
+        vaccinated: stats.vaccinated as u64,
+        vaccinated_infected: stats.vaccinated_infected as u64,
+    }
+}
+
+/// Destroys the simulation. Does nothing if `sim` is null.
+///
+/// # Safety
+///
+/// `sim` has to be null or come from `sim_new`, and can't be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sim_free(sim: *mut SimHandle) {
+    if !sim.is_null() {
+        drop(Box::from_raw(sim));
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use glium::glutin::event::VirtualKeyCode;
+use toml::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Pause,
+    SpeedUp,
+    SlowDown,
+    FastForward,
+    Vaccinate,
+    Infect,
+    ToggleQuarantine,
+    ToggleLockdown,
+    CycleCommunity,
+    StepBack,
+    StepForward,
+    Branch,
+    ToggleHeatmap,
+    ToggleDebug,
+    ToggleDiagnostics,
+    ToggleContamination,
+    StatusColors,
+    ImmunityColors,
+    FlowView,
+    CommunityColors,
+    DoseColors,
+    LineageColors,
+    AgeColors,
+    Follow,
+    CopyGraphData,
+    EditParams,
+    ToggleParamDiff,
+    ExportParams,
+    Screenshot,
+    // the index into transmissibility_presets
+    Transmissibility(usize),
+}
+
+const ACTIONS: &[(&str, Action)] = &[
+    ("pause", Action::Pause),
+    ("speed_up", Action::SpeedUp),
+    ("slow_down", Action::SlowDown),
+    ("fast_forward", Action::FastForward),
+    ("vaccinate", Action::Vaccinate),
+    ("infect", Action::Infect),
+    ("toggle_quarantine", Action::ToggleQuarantine),
+    ("toggle_lockdown", Action::ToggleLockdown),
+    ("cycle_community", Action::CycleCommunity),
+    ("step_back", Action::StepBack),
+    ("step_forward", Action::StepForward),
+    ("branch", Action::Branch),
+    ("toggle_heatmap", Action::ToggleHeatmap),
+    ("toggle_debug", Action::ToggleDebug),
+    ("toggle_diagnostics", Action::ToggleDiagnostics),
+    ("toggle_contamination", Action::ToggleContamination),
+    ("status_colors", Action::StatusColors),
+    ("immunity_colors", Action::ImmunityColors),
+    ("flow_view", Action::FlowView),
+    ("community_colors", Action::CommunityColors),
+    ("dose_colors", Action::DoseColors),
+    ("lineage_colors", Action::LineageColors),
+    ("age_colors", Action::AgeColors),
+    ("follow", Action::Follow),
+    ("copy_graph_data", Action::CopyGraphData),
+    ("edit_params", Action::EditParams),
+    ("toggle_param_diff", Action::ToggleParamDiff),
+    ("export_params", Action::ExportParams),
+    ("screenshot", Action::Screenshot),
+    ("transmissibility_1", Action::Transmissibility(0)),
+    ("transmissibility_2", Action::Transmissibility(1)),
+    ("transmissibility_3", Action::Transmissibility(2)),
+    ("transmissibility_4", Action::Transmissibility(3)),
+    ("transmissibility_5", Action::Transmissibility(4)),
+];
+
+fn default_keys(action: Action) -> Vec<VirtualKeyCode> {
+    let key = match action {
+        Action::Pause => VirtualKeyCode::Space,
+        Action::SpeedUp => VirtualKeyCode::T,
+        Action::SlowDown => VirtualKeyCode::R,
+        Action::FastForward => VirtualKeyCode::F,
+        Action::Vaccinate => VirtualKeyCode::V,
+        Action::Infect => VirtualKeyCode::I,
+        Action::ToggleQuarantine => VirtualKeyCode::Q,
+        Action::ToggleLockdown => VirtualKeyCode::K,
+        Action::CycleCommunity => VirtualKeyCode::Tab,
+        Action::StepBack => VirtualKeyCode::Left,
+        Action::StepForward => VirtualKeyCode::Right,
+        Action::Branch => VirtualKeyCode::B,
+        Action::ToggleHeatmap => VirtualKeyCode::H,
+        Action::ToggleDebug => VirtualKeyCode::F3,
+        Action::ToggleDiagnostics => VirtualKeyCode::M,
+        Action::ToggleContamination => VirtualKeyCode::S,
+        Action::StatusColors => VirtualKeyCode::Key1,
+        Action::ImmunityColors => VirtualKeyCode::Key2,
+        Action::FlowView => VirtualKeyCode::Key3,
+        Action::CommunityColors => VirtualKeyCode::F8,
+        Action::DoseColors => VirtualKeyCode::F9,
+        Action::LineageColors => VirtualKeyCode::F10,
+        Action::AgeColors => VirtualKeyCode::F11,
+        Action::Follow => VirtualKeyCode::C,
+        Action::CopyGraphData => VirtualKeyCode::G,
+        Action::EditParams => VirtualKeyCode::E,
+        Action::ToggleParamDiff => VirtualKeyCode::D,
+        Action::ExportParams => VirtualKeyCode::P,
+        Action::Screenshot => VirtualKeyCode::F12,
+        // on the keypad, as the number keys above the letters switch the colors
+        Action::Transmissibility(0) => VirtualKeyCode::Numpad1,
+        Action::Transmissibility(1) => VirtualKeyCode::Numpad2,
+        Action::Transmissibility(2) => VirtualKeyCode::Numpad3,
+        Action::Transmissibility(3) => VirtualKeyCode::Numpad4,
+        Action::Transmissibility(_) => VirtualKeyCode::Numpad5,
+    };
+    vec![key]
+}
+
+fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+
+    const LETTERS: [VirtualKeyCode; 26] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+    const DIGITS: [VirtualKeyCode; 10] =
+        [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+    const FUNCTION_KEYS: [VirtualKeyCode; 12] = [F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12];
+    const NUMPAD: [VirtualKeyCode; 10] = [
+        Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    ];
+
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphabetic() {
+            return Some(LETTERS[(c.to_ascii_uppercase() as u8 - b'A') as usize]);
+        }
+        if let Some(digit) = c.to_digit(10) {
+            return Some(DIGITS[digit as usize]);
+        }
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(n.wrapping_sub(1)).copied();
+    }
+    if let Some(n) = name
+        .strip_prefix("Numpad")
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        return NUMPAD.get(n).copied();
+    }
+    let key = match name {
+        "Space" => Space,
+        "Return" | "Enter" => Return,
+        "Escape" => Escape,
+        "Tab" => Tab,
+        "Back" | "Backspace" => Back,
+        "Up" => Up,
+        "Down" => Down,
+        "Left" => Left,
+        "Right" => Right,
+        "Plus" => Plus,
+        "Minus" => Minus,
+        "Equals" => Equals,
+        "Comma" => Comma,
+        "Period" => Period,
+        "Slash" => Slash,
+        "LBracket" => LBracket,
+        "RBracket" => RBracket,
+        "Home" => Home,
+        "End" => End,
+        "PageUp" => PageUp,
+        "PageDown" => PageDown,
+        "Insert" => Insert,
+        "Delete" => Delete,
+        "Pause" => Pause,
+        "Snapshot" => Snapshot,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[derive(Debug, Clone)]
+pub struct Keybindings {
+    actions: HashMap<VirtualKeyCode, Action>,
+}
+
+impl Keybindings {
+    // `config` is the `[keybindings]` table, mapping action names to a key name or a list
+    // of them; actions missing in it keep their default keys
+    pub fn new(config: Option<&Value>) -> Keybindings {
+        let mut actions = HashMap::new();
+        for &(name, action) in ACTIONS {
+            let keys = match config.and_then(|config| config.get(name)) {
+                Some(value) => parse_keys(name, value),
+                None => default_keys(action),
+            };
+            for key in keys {
+                if let Some(other) = actions.insert(key, action) {
+                    panic!("key {:?} bound to both {:?} and {:?}", key, other, action);
+                }
+            }
+        }
+        if let Some(table) = config.and_then(Value::as_table) {
+            for name in table.keys() {
+                if !ACTIONS.iter().any(|(action, _)| action == name) {
+                    panic!("unknown action in keybindings: {}", name);
+                }
+            }
+        }
+        Keybindings { actions }
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.actions.get(&key).copied()
+    }
+}
+
+fn parse_keys(action: &str, value: &Value) -> Vec<VirtualKeyCode> {
+    let names: Vec<&str> = match value {
+        Value::String(name) => vec![name],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => panic!("invalid keybinding for {}: {}", action, value),
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            key_from_name(name).unwrap_or_else(|| panic!("unknown key for {}: {}", action, name))
+        })
+        .collect()
+}
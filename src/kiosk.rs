@@ -0,0 +1,163 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use glium::{glutin::event::WindowEvent, Display};
+use serde_derive::Deserialize;
+
+use crate::{app::App, cli::Args, config, renderer::title_card::TitleCard, simulation::Params};
+
+// how often the title card is redrawn while it's shown
+const TITLE_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+// A scenario in the playlist given to --kiosk, eg.
+//   [[scenarios]]
+//   config = "scenarios/ebola.toml"
+//   title = "Ebola"
+//   description = "Few infections, but many of them deadly"
+//   days = 200
+//   time_compression = 4
+// Without `days`, the scenario runs until nobody is infected.
+#[derive(Debug, Clone, Deserialize)]
+struct Entry {
+    config: String,
+    title: Option<String>,
+    description: Option<String>,
+    days: Option<f64>,
+    #[serde(default = "default_time_compression")]
+    time_compression: f64,
+}
+
+fn default_time_compression() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Playlist {
+    // how long the title card is shown before each scenario
+    #[serde(default = "default_title_seconds")]
+    title_seconds: f64,
+    scenarios: Vec<Entry>,
+}
+
+fn default_title_seconds() -> f64 {
+    5.0
+}
+
+impl Playlist {
+    fn load(path: &str) -> Playlist {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("couldn't read the playlist {}: {}", path, err));
+        let playlist: Playlist = toml::from_str(&text)
+            .unwrap_or_else(|err| panic!("invalid playlist {}: {}", path, err));
+        if playlist.scenarios.is_empty() {
+            panic!("the playlist {} has no scenarios", path);
+        }
+        assert!(
+            playlist.title_seconds >= 0.0 && playlist.title_seconds.is_finite(),
+            "title_seconds in the playlist {} should be 0 or more",
+            path
+        );
+        playlist
+    }
+}
+
+enum Stage {
+    // the title card until then
+    Title(Instant),
+    Running(Box<App>),
+}
+
+// Goes through the scenarios of a playlist forever, showing the title card of each before
+// running it, for leaving a display unattended. The keyboard and the mouse are ignored.
+pub struct Kiosk {
+    playlist: Playlist,
+    args: Args,
+    current: usize,
+    // of the current scenario, for the title card
+    params: Params,
+    stage: Stage,
+    title_card: TitleCard,
+}
+
+impl Kiosk {
+    pub fn start(display: &Display, playlist_path: &str, args: Args) -> Kiosk {
+        let playlist = Playlist::load(playlist_path);
+        let params = config::load_params(&playlist.scenarios[0].config, args.preset.as_deref());
+        let mut kiosk = Kiosk {
+            playlist,
+            title_card: TitleCard::new(display, params.language),
+            args,
+            current: 0,
+            params,
+            stage: Stage::Title(Instant::now()),
+        };
+        kiosk.show_title(0);
+        kiosk
+    }
+
+    fn show_title(&mut self, index: usize) {
+        let entry = &self.playlist.scenarios[index];
+        self.current = index;
+        self.params = config::load_params(&entry.config, self.args.preset.as_deref());
+        self.stage =
+            Stage::Title(Instant::now() + Duration::from_secs_f64(self.playlist.title_seconds));
+    }
+
+    pub fn next_frame(&self) -> Instant {
+        match &self.stage {
+            Stage::Title(until) => (Instant::now() + TITLE_FRAME_INTERVAL).min(*until),
+            Stage::Running(app) => app.next_frame(),
+        }
+    }
+
+    // returns whether the window should be closed
+    pub fn handle_window_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CloseRequested => {
+                if let Stage::Running(app) = &self.stage {
+                    app.shutdown();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn draw(&mut self, display: &Display) {
+        let entry = &self.playlist.scenarios[self.current];
+        match &mut self.stage {
+            Stage::Title(until) => {
+                if Instant::now() < *until {
+                    let title = match &entry.title {
+                        Some(title) => title.clone(),
+                        None => Path::new(&entry.config)
+                            .file_stem()
+                            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+                    };
+                    self.title_card.draw(
+                        display,
+                        &title,
+                        entry.description.as_deref(),
+                        entry.days,
+                        self.params,
+                    );
+                    return;
+                }
+                let app = App::start(display, self.args.clone(), &entry.config);
+                app.resume(entry.time_compression);
+                self.stage = Stage::Running(Box::new(app));
+            }
+            Stage::Running(app) => {
+                app.draw(display);
+                if app.finished(entry.days) {
+                    println!("Finished {}, starting the next scenario.", entry.config);
+                    let next = (self.current + 1) % self.playlist.scenarios.len();
+                    self.show_title(next);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+// The simulation core, for embedding it without the window; the binary in main.rs uses it too.
+pub mod ffi;
+mod profiling;
+pub mod simulation;
+
+#[cfg(feature = "pyo3")]
+mod python;
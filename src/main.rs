@@ -1,115 +1,202 @@
+mod analyze;
+mod announce;
+mod app;
+#[cfg(feature = "audio")]
+mod audio;
+mod calibration;
+mod cli;
+mod comparison;
+mod config;
+mod extinction;
+mod keybindings;
+mod kiosk;
+mod memory_audit;
+#[cfg(feature = "microdata")]
+mod microdata;
+mod outcomes;
+mod param_diff;
+mod param_editor;
 mod renderer;
-mod simulation;
-
-use std::{
-    fs::File,
-    io::Read,
-    sync::{Arc, RwLock},
-    thread,
-    time::Instant,
-};
+#[cfg(feature = "rpc")]
+mod rpc;
+mod seed_check;
+mod session_script;
+#[cfg(feature = "shared_memory")]
+mod shared_memory;
+mod stats_csv;
+mod transmission;
+mod worker;
 
 use glium::{
     glutin::{
-        event::{ElementState, Event, VirtualKeyCode, WindowEvent},
+        event::{ElementState, Event, WindowEvent},
         event_loop::{ControlFlow, EventLoop},
         window::WindowBuilder,
         ContextBuilder,
     },
     Display,
 };
-use rand::thread_rng;
+#[cfg(feature = "profiling")]
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+#[cfg(feature = "profiling")]
+use tracing_subscriber::prelude::*;
 
-use renderer::*;
-use simulation::*;
+use infections::simulation;
 
-fn main() {
-    let mut rng = thread_rng();
+use app::App;
+use cli::Args;
+use kiosk::Kiosk;
+use renderer::menu::ScenarioMenu;
 
-    let mut conf_file = File::open("config.toml").unwrap();
-    let mut conf_str = String::new();
-    conf_file.read_to_string(&mut conf_str).unwrap();
-    let params = toml::from_str(&conf_str).unwrap();
+const CONFIG_PATH: &str = "config.toml";
+const SCENARIOS_DIR: &str = "scenarios";
+const DEFAULT_EXTINCTION_THRESHOLD: usize = 100;
 
-    let mut sim = Simulation::new(&mut rng, params);
-    sim.infect(params.init_infected, &mut rng);
-    sim.vaccinate(params.init_vaccinated, &mut rng);
-    let sim_arc = Arc::new(RwLock::new(sim));
-    let sim_params_arc = Arc::new(RwLock::new(SimulationParameters {
-        time_compression: 1.0,
-        running: false,
-    }));
+enum State {
+    Menu(Box<ScenarioMenu>),
+    Running(Box<App>),
+    Kiosk(Box<Kiosk>),
+}
 
-    println!("Simulation created.");
+// Records the spans in the chrome://tracing format. The file is complete once the guard is
+// dropped.
+#[cfg(feature = "profiling")]
+fn start_tracing(path: &str) -> FlushGuard {
+    let (layer, guard) = ChromeLayerBuilder::new().file(path).build();
+    tracing_subscriber::registry().with(layer).init();
+    guard
+}
 
-    let event_loop = EventLoop::new();
+#[cfg(not(feature = "profiling"))]
+fn start_tracing(_path: &str) {
+    panic!("--trace-output requires building with the `profiling` feature");
+}
 
-    let wb = WindowBuilder::new().with_title("Pandemic sim");
-    let cb = ContextBuilder::new();
-    let display = Display::new(wb, cb, &event_loop).unwrap();
+#[cfg(feature = "rpc")]
+fn serve_rpc(address: &str, preset: Option<&str>) {
+    rpc::serve(address, CONFIG_PATH, preset);
+}
 
-    let mut renderer = Renderer::new(
-        &display,
-        params.size_x / 2.0,
-        params.size_y / 2.0,
-        params.size_x,
-    );
+#[cfg(not(feature = "rpc"))]
+fn serve_rpc(_address: &str, _preset: Option<&str>) {
+    panic!("--rpc requires building with the `rpc` feature");
+}
 
-    let sim_clone = sim_arc.clone();
-    let sim_params_clone = sim_params_arc.clone();
+fn main() {
+    let args = Args::parse();
+    let mut trace_guard = args.trace_output.as_deref().map(start_tracing);
 
-    // simulation thread
-    thread::spawn(move || {
-        let mut now = Instant::now();
-        let mut rng = thread_rng();
+    // the estimator doesn't need a window
+    if let Some(runs) = args.extinction_runs {
+        extinction::estimate(
+            CONFIG_PATH,
+            args.preset.as_deref(),
+            runs,
+            args.extinction_threshold
+                .unwrap_or(DEFAULT_EXTINCTION_THRESHOLD),
+        );
+        return;
+    }
+    // and neither does the seed check
+    if let Some(runs) = args.seed_check {
+        seed_check::check(CONFIG_PATH, args.preset.as_deref(), runs);
+        return;
+    }
+    // nor the dashboard of the finished runs, which is in the terminal
+    if let Some(dir) = &args.analyze {
+        analyze::analyze(dir);
+        return;
+    }
+    if let Some((dir_a, dir_b)) = &args.compare_runs {
+        comparison::compare(dir_a, dir_b);
+        return;
+    }
+    // so does the remote control interface
+    if let Some(address) = &args.rpc {
+        serve_rpc(address, args.preset.as_deref());
+        return;
+    }
+    if let Some(queue) = &args.worker {
+        worker::run(queue, CONFIG_PATH, args.preset.as_deref());
+        return;
+    }
 
-        loop {
-            let dt = now.elapsed().as_secs_f64();
-            now = Instant::now();
+    let event_loop = EventLoop::new();
 
-            let mut sim = sim_arc.read().unwrap().clone();
-            let params = *sim_params_arc.read().unwrap();
-            sim.step(dt, &mut rng, &params);
-            *sim_arc.write().unwrap() = sim;
+    let wb = WindowBuilder::new().with_title("Pandemic sim");
+    let cb = ContextBuilder::new();
+    let display = Display::new(wb, cb, &event_loop).unwrap();
+
+    // without any scenarios to choose from, start right away with the default config
+    let mut state = if let Some(playlist) = &args.kiosk {
+        State::Kiosk(Box::new(Kiosk::start(&display, playlist, args.clone())))
+    } else {
+        match ScenarioMenu::find(&display, CONFIG_PATH, SCENARIOS_DIR, args.preset.as_deref()) {
+            Some(menu) => State::Menu(Box::new(menu)),
+            None => State::Running(Box::new(App::start(&display, args.clone(), CONFIG_PATH))),
         }
-    });
+    };
 
     // event handling loop (in main thread)
     event_loop.run(move |ev, _, control_flow| {
-        match ev {
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => {
-                    *control_flow = ControlFlow::Exit;
-                    return;
-                }
-                WindowEvent::KeyboardInput { input, .. } => {
-                    match (input.state, input.virtual_keycode) {
-                        (ElementState::Pressed, Some(VirtualKeyCode::Space)) => {
-                            sim_params_clone.write().unwrap().toggle_running();
+        let mut chosen = None;
+        match &mut state {
+            State::Menu(menu) => {
+                *control_flow = ControlFlow::Poll;
+                match ev {
+                    Event::WindowEvent { event, .. } => match event {
+                        WindowEvent::CloseRequested => {
+                            *control_flow = ControlFlow::Exit;
                         }
-                        (ElementState::Pressed, Some(VirtualKeyCode::T)) => {
-                            sim_params_clone
-                                .write()
-                                .unwrap()
-                                .increase_time_compression();
-                        }
-                        (ElementState::Pressed, Some(VirtualKeyCode::R)) => {
-                            sim_params_clone
-                                .write()
-                                .unwrap()
-                                .decrease_time_compression();
+                        WindowEvent::KeyboardInput { input, .. }
+                            if input.state == ElementState::Pressed =>
+                        {
+                            chosen = input.virtual_keycode.and_then(|key| menu.handle_key(key));
                         }
                         _ => (),
+                    },
+                    Event::MainEventsCleared => {
+                        menu.draw(&display);
                     }
+                    _ => (),
                 }
-                _ => return,
-            },
-            Event::MainEventsCleared => {
-                let sim = sim_clone.read().unwrap().clone();
-                renderer.draw(&display, &sim);
             }
-            _ => (),
+            State::Running(app) => {
+                // woken up by the events, or else when the next frame is due
+                *control_flow = ControlFlow::WaitUntil(app.next_frame());
+                match ev {
+                    Event::WindowEvent { event, .. }
+                        if app.handle_window_event(&display, &event) =>
+                    {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    Event::MainEventsCleared => {
+                        app.draw(&display);
+                        *control_flow = ControlFlow::WaitUntil(app.next_frame());
+                    }
+                    _ => (),
+                }
+            }
+            State::Kiosk(kiosk) => {
+                *control_flow = ControlFlow::WaitUntil(kiosk.next_frame());
+                match ev {
+                    Event::WindowEvent { event, .. } if kiosk.handle_window_event(&event) => {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    Event::MainEventsCleared => {
+                        kiosk.draw(&display);
+                        *control_flow = ControlFlow::WaitUntil(kiosk.next_frame());
+                    }
+                    _ => (),
+                }
+            }
+        }
+        if *control_flow == ControlFlow::Exit {
+            // the event loop exits the process without dropping anything
+            trace_guard.take();
+        }
+        if let Some(path) = chosen {
+            state = State::Running(Box::new(App::start(&display, args.clone(), &path)));
         }
-        *control_flow = ControlFlow::Poll;
     });
 }
@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+use crate::simulation::memory::MemoryUsage;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+// Prints where the memory goes every `interval` of wall clock time, along with the most seen
+// so far, for checking that a run left going for days stays within its caps.
+pub struct MemoryAudit {
+    interval: Duration,
+    next: Instant,
+    peak: usize,
+}
+
+impl MemoryAudit {
+    pub fn new(interval: f64) -> MemoryAudit {
+        MemoryAudit {
+            interval: Duration::from_secs_f64(interval),
+            next: Instant::now(),
+            peak: 0,
+        }
+    }
+
+    pub fn update(&mut self, day: f64, usage: MemoryUsage) {
+        self.peak = self.peak.max(usage.total());
+        let now = Instant::now();
+        if now < self.next {
+            return;
+        }
+        println!(
+            "Memory on day {:.0}: {} (peak {:.1} MB)",
+            day,
+            usage,
+            self.peak as f64 / BYTES_PER_MB
+        );
+        self.next = now + self.interval;
+    }
+}
@@ -0,0 +1,67 @@
+use std::{fs::File, io::BufWriter, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, BooleanArray, Float32Array, UInt32Array, UInt64Array},
+    datatypes::{DataType, Field, Schema},
+    error::ArrowError,
+    ipc::writer::FileWriter,
+    record_batch::RecordBatch,
+};
+
+use crate::simulation::{person::Status, Simulation};
+
+// Writes one record per person per day into an Arrow IPC file, one batch per day.
+pub struct MicrodataWriter {
+    schema: Arc<Schema>,
+    writer: FileWriter<BufWriter<File>>,
+}
+
+impl MicrodataWriter {
+    pub fn create(path: &str) -> Result<MicrodataWriter, ArrowError> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("day", DataType::UInt32, false),
+            Field::new("id", DataType::UInt64, false),
+            Field::new("x", DataType::Float32, false),
+            Field::new("y", DataType::Float32, false),
+            Field::new("infected", DataType::Boolean, false),
+            Field::new("past_infected", DataType::Boolean, false),
+            Field::new("vaccinated", DataType::Boolean, false),
+            Field::new("dead", DataType::Boolean, false),
+        ]));
+        let file = BufWriter::new(File::create(path)?);
+        let writer = FileWriter::try_new(file, &schema)?;
+        Ok(MicrodataWriter { schema, writer })
+    }
+
+    pub fn write_day(&mut self, day: u64, sim: &Simulation) -> Result<(), ArrowError> {
+        let people: Vec<_> = sim.people().collect();
+        let flag = |f: &dyn Fn(&Status) -> bool| -> ArrayRef {
+            Arc::new(BooleanArray::from(
+                people.iter().map(|p| f(p.status())).collect::<Vec<_>>(),
+            ))
+        };
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt32Array::from(vec![day as u32; people.len()])),
+            Arc::new(UInt64Array::from(
+                sim.ids().iter().map(|&id| id as u64).collect::<Vec<_>>(),
+            )),
+            Arc::new(Float32Array::from(
+                people.iter().map(|p| p.pos().x as f32).collect::<Vec<_>>(),
+            )),
+            Arc::new(Float32Array::from(
+                people.iter().map(|p| p.pos().y as f32).collect::<Vec<_>>(),
+            )),
+            flag(&|status| status.infected().is_some()),
+            flag(&|status| status.past_infected()),
+            flag(&|status| status.vaccinated()),
+            flag(&|status| status.dead().is_some()),
+        ];
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+        self.writer.write(&batch)
+    }
+
+    // writes the file footer, the file is unreadable without it
+    pub fn finish(&mut self) -> Result<(), ArrowError> {
+        self.writer.finish()
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::simulation::{
+    population::{age_group, AGE_GROUPS},
+    Simulation,
+};
+
+// the columns, in the order of the courses
+const OUTCOMES: [&str; 3] = ["never_infected", "infected", "died"];
+
+// eg. "18-39", or "unknown" for the people without an age
+fn age_group_name(group: Option<usize>) -> String {
+    match group {
+        Some(group) => match AGE_GROUPS.get(group + 1) {
+            Some(&next) => format!("{}-{}", AGE_GROUPS[group], next - 1.0),
+            None => format!("{}+", AGE_GROUPS[group]),
+        },
+        None => "unknown".to_owned(),
+    }
+}
+
+// Final outcomes cross-tabulated by the number of vaccine doses, as CSV, for everyone and, if
+// any of the ages are known, for each of the age groups.
+pub fn write(path: &str, sim: &Simulation) -> io::Result<()> {
+    let max_doses = if sim.params().two_dose { 2 } else { 1 };
+    let mut counts = BTreeMap::new();
+    for (status, age) in sim.statuses().iter().zip(sim.ages()) {
+        let group = counts
+            .entry(age.map(age_group))
+            .or_insert_with(|| vec![[0usize; 3]; max_doses + 1]);
+        group[status.doses()][status.course() as usize] += 1;
+    }
+    for (&(doses, age_group, course), &removed) in sim.removed() {
+        let group = counts
+            .entry(age_group)
+            .or_insert_with(|| vec![[0usize; 3]; max_doses + 1]);
+        group[doses][course as usize] += removed;
+    }
+
+    let mut all = vec![[0usize; 3]; max_doses + 1];
+    for group in counts.values() {
+        for (total, row) in all.iter_mut().zip(group) {
+            for (total, count) in total.iter_mut().zip(row) {
+                *total += count;
+            }
+        }
+    }
+    let mut tables = vec![("all".to_owned(), &all)];
+    if counts.keys().any(Option::is_some) {
+        // the ones of unknown age last
+        let groups = (0..AGE_GROUPS.len()).map(Some).chain(Some(None));
+        for group in groups {
+            if let Some(rows) = counts.get(&group) {
+                tables.push((age_group_name(group), rows));
+            }
+        }
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "age_group,doses,{},total", OUTCOMES.join(","))?;
+    for (name, rows) in tables {
+        for (doses, row) in rows.iter().enumerate() {
+            writeln!(
+                file,
+                "{},{},{},{},{},{}",
+                name,
+                doses,
+                row[0],
+                row[1],
+                row[2],
+                row.iter().sum::<usize>()
+            )?;
+        }
+    }
+    file.flush()
+}
@@ -0,0 +1,75 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+};
+
+use toml::{value::Table, Value};
+
+use crate::simulation::Params;
+
+const EXPORT_PREFIX: &str = "exported_params_";
+
+// A parameter which differs from its default or from the loaded config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub name: String,
+    pub value: Value,
+    pub config: Value,
+    pub default: Value,
+}
+
+impl Difference {
+    // changed while running
+    pub fn edited(&self) -> bool {
+        self.value != self.config
+    }
+}
+
+fn table(params: Params) -> Table {
+    match Value::try_from(params).unwrap() {
+        Value::Table(table) => table,
+        _ => unreachable!(),
+    }
+}
+
+// sorted by name
+pub fn differences(current: Params, config: Params) -> Vec<Difference> {
+    let config = table(config);
+    let default = table(Default::default());
+    table(current)
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let config = config[&name].clone();
+            let default = default[&name].clone();
+            if value == config && value == default {
+                None
+            } else {
+                Some(Difference {
+                    name,
+                    value,
+                    config,
+                    default,
+                })
+            }
+        })
+        .collect()
+}
+
+// Writes all the parameters to the first of exported_params_1.toml, exported_params_2.toml etc.
+// that doesn't exist yet, in the format of config.toml. Returns the path written.
+pub fn export(params: Params, time: f64) -> io::Result<String> {
+    let mut index = 1;
+    let (path, mut file) = loop {
+        let path = format!("{}{}.toml", EXPORT_PREFIX, index);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => break (path, file),
+            // taken, possibly by another instance in the meantime
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => index += 1,
+            Err(err) => return Err(err),
+        }
+    };
+    writeln!(file, "# the parameters on day {:.1}", time)?;
+    write!(file, "{}", toml::to_string(&params).unwrap())?;
+    file.flush()?;
+    Ok(path)
+}
@@ -0,0 +1,75 @@
+use glium::glutin::event::VirtualKeyCode;
+
+use crate::simulation::{
+    tuning::{ParamUpdate, TUNABLES},
+    Params,
+};
+
+// While active, Left/Right choose one of the TUNABLES and Up/Down change it. The values are
+// kept here, so that quick presses add up before the simulation thread gets the updates.
+pub struct ParamEditor {
+    params: Option<Params>,
+    selected: usize,
+}
+
+impl ParamEditor {
+    pub fn new() -> ParamEditor {
+        ParamEditor {
+            params: None,
+            selected: 0,
+        }
+    }
+
+    // starts from the current parameters of the simulation
+    pub fn toggle(&mut self, current: Params) {
+        self.params = match self.params {
+            Some(_) => None,
+            None => Some(current),
+        };
+    }
+
+    // whether the key is used by the editor instead of its usual action
+    pub fn captures(&self, key: VirtualKeyCode) -> bool {
+        self.params.is_some()
+            && matches!(
+                key,
+                VirtualKeyCode::Left
+                    | VirtualKeyCode::Right
+                    | VirtualKeyCode::Up
+                    | VirtualKeyCode::Down
+            )
+    }
+
+    pub fn handle_key(&mut self, key: VirtualKeyCode) -> Option<ParamUpdate> {
+        let params = self.params.as_mut()?;
+        let steps = match key {
+            VirtualKeyCode::Left => {
+                self.selected = (self.selected + TUNABLES.len() - 1) % TUNABLES.len();
+                return None;
+            }
+            VirtualKeyCode::Right => {
+                self.selected = (self.selected + 1) % TUNABLES.len();
+                return None;
+            }
+            VirtualKeyCode::Up => 1.0,
+            VirtualKeyCode::Down => -1.0,
+            _ => return None,
+        };
+        let tunable = &TUNABLES[self.selected];
+        let value = tunable.nudge((tunable.get)(params), steps);
+        (tunable.set)(params, value);
+        Some(ParamUpdate {
+            index: self.selected,
+            value,
+        })
+    }
+
+    // the name and value of the selected parameter, with the decimal places to show, while
+    // editing
+    pub fn selected(&self) -> Option<(&'static str, f64, usize)> {
+        let tunable = &TUNABLES[self.selected];
+        self.params
+            .as_ref()
+            .map(|params| (tunable.name, (tunable.get)(params), tunable.precision()))
+    }
+}
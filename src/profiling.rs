@@ -0,0 +1,15 @@
+// Spans for --trace-output, lasting until the end of the enclosing block. Without the
+// `profiling` feature they compile to nothing.
+#[cfg(feature = "profiling")]
+#[macro_export]
+macro_rules! trace_span {
+    ($name:expr) => {
+        let _span = tracing::info_span!($name).entered();
+    };
+}
+
+#[cfg(not(feature = "profiling"))]
+#[macro_export]
+macro_rules! trace_span {
+    ($name:expr) => {};
+}
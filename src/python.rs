@@ -0,0 +1,236 @@
+use pyo3::{
+    exceptions::{PyAttributeError, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyDict, PyList},
+};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use toml::Value;
+
+use crate::simulation::{presets, setup::Setup, Params, Simulation, Statistics};
+
+fn to_python(py: Python, value: &Value) -> PyObject {
+    match value {
+        Value::String(text) => text.to_object(py),
+        Value::Integer(number) => number.to_object(py),
+        Value::Float(number) => number.to_object(py),
+        Value::Boolean(flag) => flag.to_object(py),
+        Value::Datetime(datetime) => datetime.to_string().to_object(py),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| to_python(py, item))
+            .collect::<Vec<_>>()
+            .to_object(py),
+        Value::Table(table) => {
+            let dict = PyDict::new(py);
+            for (key, value) in table {
+                dict.set_item(key, to_python(py, value)).unwrap();
+            }
+            dict.to_object(py)
+        }
+    }
+}
+
+fn from_python(value: &PyAny) -> PyResult<Value> {
+    // Python's bools are ints too
+    if let Ok(flag) = value.downcast::<PyBool>() {
+        return Ok(Value::Boolean(flag.is_true()));
+    }
+    if let Ok(number) = value.extract::<i64>() {
+        return Ok(Value::Integer(number));
+    }
+    if let Ok(number) = value.extract::<f64>() {
+        return Ok(Value::Float(number));
+    }
+    if let Ok(text) = value.extract::<String>() {
+        return Ok(Value::String(text));
+    }
+    if let Ok(list) = value.downcast::<PyList>() {
+        return list
+            .iter()
+            .map(from_python)
+            .collect::<PyResult<_>>()
+            .map(Value::Array);
+    }
+    if let Ok(dict) = value.downcast::<PyDict>() {
+        let mut table = toml::value::Table::new();
+        for (key, value) in dict {
+            table.insert(key.extract()?, from_python(value)?);
+        }
+        return Ok(Value::Table(table));
+    }
+    Err(PyTypeError::new_err(format!(
+        "unsupported parameter value: {}",
+        value
+    )))
+}
+
+fn stats_dict(py: Python, stats: &Statistics) -> PyObject {
+    let dict = PyDict::new(py);
+    let fields = [
+        ("population", stats.population),
+        ("dead", stats.dead),
+        ("infected", stats.infected),
+        ("healed", stats.healed),
+        ("vaccinated", stats.vaccinated),
+        ("vaccinated_infected", stats.vaccinated_infected),
+        ("one_dose", stats.one_dose),
+        ("two_doses", stats.two_doses),
+        ("hybrid_immune", stats.hybrid_immune),
+        ("severe", stats.severe),
+        ("critical", stats.critical),
+        ("hospitalized", stats.hospitalized),
+    ];
+    for &(name, count) in &fields {
+        dict.set_item(name, count).unwrap();
+    }
+    dict.to_object(py)
+}
+
+// The parameters as in config.toml, read and written as attributes, eg. `params.num_people`.
+#[pyclass(name = "Params")]
+#[derive(Clone)]
+struct PyParams {
+    params: Params,
+}
+
+impl PyParams {
+    fn table(&self) -> toml::value::Table {
+        match Value::try_from(self.params).unwrap() {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyParams {
+    #[new]
+    fn new() -> PyParams {
+        PyParams {
+            params: Default::default(),
+        }
+    }
+
+    #[staticmethod]
+    fn from_toml(text: &str) -> PyResult<PyParams> {
+        let params: Params =
+            toml::from_str(text).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        params.validate().map_err(PyValueError::new_err)?;
+        Ok(PyParams { params })
+    }
+
+    #[staticmethod]
+    fn preset(name: &str) -> PyResult<PyParams> {
+        presets::preset(name)
+            .map(|params| PyParams { params })
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "unknown preset: {} (available: {})",
+                    name,
+                    presets::PRESET_NAMES.join(", ")
+                ))
+            })
+    }
+
+    fn to_toml(&self) -> String {
+        toml::to_string(&self.params).unwrap()
+    }
+
+    fn __getattr__(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        self.table()
+            .get(name)
+            .map(|value| to_python(py, value))
+            .ok_or_else(|| PyAttributeError::new_err(format!("unknown parameter: {}", name)))
+    }
+
+    fn __setattr__(&mut self, name: &str, value: &PyAny) -> PyResult<()> {
+        let mut table = self.table();
+        if !table.contains_key(name) {
+            return Err(PyAttributeError::new_err(format!(
+                "unknown parameter: {}",
+                name
+            )));
+        }
+        table.insert(name.to_owned(), from_python(value)?);
+        let params: Params = Value::Table(table)
+            .try_into()
+            .map_err(|err| PyValueError::new_err(format!("invalid {}: {}", name, err)))?;
+        params.validate().map_err(PyValueError::new_err)?;
+        self.params = params;
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Params({:?})", self.to_toml())
+    }
+}
+
+// A simulation stepped in the fast mode, without the window. The policies and the other extras
+// loaded by the binary from config.toml are left out.
+#[pyclass(name = "Simulation")]
+struct PySimulation {
+    sim: Simulation,
+    rng: StdRng,
+}
+
+#[pymethods]
+impl PySimulation {
+    #[new]
+    fn new(params: PyParams, seed: Option<u64>) -> PyResult<PySimulation> {
+        let params = params.params;
+        params.validate().map_err(PyValueError::new_err)?;
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let sim = Setup::new(params).create(&mut rng);
+        Ok(PySimulation { sim, rng })
+    }
+
+    // advances by `days` of simulated time, 1 by default
+    fn step(&mut self, py: Python, days: Option<f64>) -> PyResult<()> {
+        let days = days.unwrap_or(1.0);
+        if !days.is_finite() {
+            return Err(PyValueError::new_err(format!(
+                "days should be finite, not {}",
+                days
+            )));
+        }
+        let PySimulation { sim, rng } = self;
+        py.allow_threads(|| {
+            let end = sim.time() + days;
+            sim.run_headless(rng, &mut None, |sim| sim.time() >= end);
+        });
+        Ok(())
+    }
+
+    #[getter]
+    fn time(&self) -> f64 {
+        self.sim.time()
+    }
+
+    #[getter]
+    fn params(&self) -> PyParams {
+        PyParams {
+            params: self.sim.params(),
+        }
+    }
+
+    fn stats(&self, py: Python) -> PyObject {
+        stats_dict(py, &self.sim.stats())
+    }
+
+    // (time, stats) sampled every stats_sample_interval days
+    fn history(&self, py: Python) -> Vec<(f64, PyObject)> {
+        self.sim
+            .history()
+            .iter()
+            .map(|(time, stats)| (*time, stats_dict(py, stats)))
+            .collect()
+    }
+}
+
+#[pymodule]
+fn infections(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyParams>()?;
+    module.add_class::<PySimulation>()?;
+    Ok(())
+}
@@ -1,24 +1,72 @@
+pub mod background;
+mod frame_counter;
+mod locale;
 mod matrix;
+pub mod menu;
+mod palette;
 mod stats_buf;
+mod svg;
+mod text;
+pub mod title_card;
+pub mod view_state;
 
-use std::fs::File;
+use std::{f64::consts::PI, io, time::Duration};
 
 use glium::{
-    draw_parameters::DrawParameters, implement_vertex, index, uniform, Display, Frame, Program,
-    Rect, Surface, VertexBuffer,
+    draw_parameters::DrawParameters, implement_vertex, index, uniform, Blend, Display, Frame,
+    Program, Rect, Surface, VertexBuffer,
 };
-use glium_text::{FontTexture, TextDisplay, TextSystem};
 use nalgebra::Vector2;
 
-use crate::simulation::{
-    person::{Status, RADIUS},
-    Simulation,
+use crate::{
+    param_diff,
+    simulation::{
+        density::DensityMap,
+        obstacle::{hour_of_day, HOURS_PER_DAY},
+        person::{Person, Status, RADIUS},
+        population::{age_group, AGE_GROUPS},
+        snapshot::{self, RenderSnapshot, SNAPSHOT_VERSION},
+        timeline::Phase,
+        Language, Params, Simulation, SimulationParameters, Statistics, MAX_TIME_COMPRESSION,
+        MIN_TIME_COMPRESSION,
+    },
+    stats_csv,
 };
 
+use background::Background;
+use frame_counter::FrameCounter;
+use locale::Locale;
 use matrix::Matrix;
 use stats_buf::StatsBuf;
-
-const VERTEX_SHADER_SRC: &'static str = r#"
+use text::TextRenderer;
+use view_state::ViewState;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+// where the numbers in the stats panel end, in the units of its text
+const STATS_VALUE_COLUMN: f32 = 20.0;
+
+// a line of the stats panel: the key, the value, its color and whether a rise is good news
+type StatRow = (&'static str, fn(&Statistics) -> usize, [f32; 3], bool);
+// the panel of changed parameters is cut off after this many
+const MAX_PARAM_DIFF_LINES: usize = 20;
+// how long the contacts of the followed person stay highlighted, in days
+const CONTACT_HIGHLIGHT_TIME: f64 = 1.0;
+// the minimap is shown once less than this fraction of the box fits in the view
+const MINIMAP_ZOOM: f64 = 0.8;
+// along the longer side of the box, in pixels
+const MINIMAP_SIZE: u32 = 150;
+// in the flow view, the length of the line of someone moving at speed_stdev
+const FLOW_LINE_LENGTH: f64 = 2.0;
+// the diameter of the vectors relative to the people
+const VECTOR_SIZE: f64 = 0.5;
+const SPEED_HISTOGRAM_BINS: usize = 20;
+// the size of the diagnostics panel and its parts, in pixels
+const DIAGNOSTICS_WIDTH: u32 = 320;
+const DIAGNOSTICS_HISTOGRAM_HEIGHT: f32 = 80.0;
+const DIAGNOSTICS_LABEL_WIDTH: f32 = 100.0;
+const DIAGNOSTICS_MAX_CELL: f32 = 24.0;
+
+const VERTEX_SHADER_SRC: &str = r#"
     #version 140
 
     in vec2 position;
@@ -33,14 +81,15 @@ const VERTEX_SHADER_SRC: &'static str = r#"
     }
 "#;
 
-const FRAGMENT_SHADER_SRC: &'static str = r#"
+const FRAGMENT_SHADER_SRC: &str = r#"
     #version 140
 
     in vec3 in_color;
+    uniform float alpha;
     out vec4 color;
 
     void main() {
-        color = vec4(in_color, 1.0);
+        color = vec4(in_color, alpha);
     }
 "#;
 
@@ -51,38 +100,244 @@ struct Vertex {
 
 implement_vertex!(Vertex, position);
 
+// vertices grouped by their color, to draw each color at once
+#[derive(Default)]
+struct ColorBatches {
+    batches: Vec<([f32; 3], Vec<Vertex>)>,
+}
+
+impl ColorBatches {
+    fn get(&mut self, color: [f32; 3]) -> &mut Vec<Vertex> {
+        let index = match self.batches.iter().position(|(c, _)| *c == color) {
+            Some(index) => index,
+            None => {
+                self.batches.push((color, vec![]));
+                self.batches.len() - 1
+            }
+        };
+        &mut self.batches[index].1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayMode {
+    Status,
+    // how long ago the people became immune
+    Immunity,
+    // the velocities as lines, instead of the people as dots
+    Flow,
+    Community,
+    // the number of vaccine doses
+    Doses,
+    // the strain of the current infection
+    Lineage,
+    AgeGroup,
+}
+
 pub struct Renderer {
     center: Vector2<f64>,
     size_smaller: f64,
     program: Program,
-    text_system: TextSystem,
-    font: FontTexture,
+    text: TextRenderer,
     stats_buf: StatsBuf,
-    last_t: f64,
+    hover: Option<Vector2<f64>>,
+    locale: Locale,
+    // the shades of the density map and the cells with them
+    density: Vec<(f32, VertexBuffer<Vertex>)>,
+    background: Option<Background>,
+    selected_community: Option<usize>,
+    community_buf: StatsBuf,
+    inspected: Option<usize>,
+    // whether the simulation view is split between the main run and a branch
+    split: bool,
+    heatmap: bool,
+    debug: bool,
+    // the speed histogram and the mixing matrix
+    diagnostics: bool,
+    // the contamination of the surfaces, with `fomites`
+    contamination: bool,
+    frame_counter: FrameCounter,
+    mode: DisplayMode,
+    // whether the view is centered on the inspected person
+    follow: bool,
+    // the parameter being changed from the keyboard, its new value and decimal places
+    edited: Option<(&'static str, f64, usize)>,
+    // the loaded config to compare the current parameters to, while the panel is shown
+    param_diff: Option<Params>,
 }
 
-impl Renderer {
-    pub fn new(display: &Display, center_x: f64, center_y: f64, size_smaller: f64) -> Self {
-        let text_system = TextSystem::new(display);
-        let font = FontTexture::new(display, File::open("DejaVuSans.ttf").unwrap(), 24).unwrap();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+}
 
+impl Renderer {
+    pub fn new(
+        display: &Display,
+        center_x: f64,
+        center_y: f64,
+        size_smaller: f64,
+        language: Language,
+    ) -> Self {
         Renderer {
             center: Vector2::new(center_x, center_y),
             size_smaller,
             program: Program::from_source(display, VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC, None)
                 .unwrap(),
-            text_system,
-            font,
+            text: TextRenderer::new(display, "DejaVuSans.ttf"),
             stats_buf: StatsBuf::new(),
-            last_t: -0.01,
+            hover: None,
+            locale: Locale::new(language),
+            density: vec![],
+            background: None,
+            selected_community: None,
+            community_buf: StatsBuf::without_hospital_load(),
+            inspected: None,
+            split: false,
+            heatmap: false,
+            debug: false,
+            diagnostics: false,
+            contamination: false,
+            frame_counter: FrameCounter::new(),
+            mode: DisplayMode::Status,
+            follow: false,
+            edited: None,
+            param_diff: None,
         }
     }
 
+    pub fn set_hover(&mut self, pos: Option<Vector2<f64>>) {
+        self.hover = pos;
+    }
+
+    // the map doesn't change, so the shaded cells are built once, grouped by their shade
+    pub fn set_density(&mut self, display: &Display, density: &DensityMap, box_size: (f64, f64)) {
+        let (width, height) = density.size();
+        let cell_w = box_size.0 / width as f64;
+        let cell_h = box_size.1 / height as f64;
+        let mut shades: Vec<(f32, Vec<Vertex>)> = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                let alpha = 0.15 * density.value(x, y) as f32;
+                let (x0, y1) = (x as f64 * cell_w, box_size.1 - y as f64 * cell_h);
+                let (x1, y0) = (x0 + cell_w, y1 - cell_h);
+                let quad = [[x0, y0], [x1, y0], [x1, y1], [x0, y0], [x1, y1], [x0, y1]];
+                let index = match shades.iter().position(|(a, _)| *a == alpha) {
+                    Some(index) => index,
+                    None => {
+                        shades.push((alpha, vec![]));
+                        shades.len() - 1
+                    }
+                };
+                shades[index]
+                    .1
+                    .extend(quad.iter().map(|&position| Vertex { position }));
+            }
+        }
+        self.density = shades
+            .into_iter()
+            .map(|(alpha, vertices)| (alpha, VertexBuffer::new(display, &vertices).unwrap()))
+            .collect();
+    }
+
+    pub fn set_background(&mut self, background: Background) {
+        self.background = Some(background);
+    }
+
+    // goes through the communities, and then back to none selected
+    pub fn cycle_community(&mut self, count: usize) {
+        self.selected_community = match self.selected_community {
+            None if count > 0 => Some(0),
+            Some(index) if index + 1 < count => Some(index + 1),
+            _ => None,
+        };
+        self.community_buf = StatsBuf::without_hospital_load();
+    }
+
+    pub fn toggle_heatmap(&mut self) {
+        self.heatmap = !self.heatmap;
+    }
+
+    pub fn set_mode(&mut self, mode: DisplayMode) {
+        self.mode = mode;
+    }
+
+    pub fn set_edited(&mut self, edited: Option<(&'static str, f64, usize)>) {
+        self.edited = edited;
+    }
+
+    pub fn toggle_param_diff(&mut self, config: Params) {
+        self.param_diff = match self.param_diff {
+            Some(_) => None,
+            None => Some(config),
+        };
+    }
+
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+    }
+
+    pub fn toggle_debug(&mut self) {
+        self.debug = !self.debug;
+    }
+
+    pub fn toggle_diagnostics(&mut self) {
+        self.diagnostics = !self.diagnostics;
+    }
+
+    pub fn toggle_contamination(&mut self) {
+        self.contamination = !self.contamination;
+    }
+
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            center_x: self.center.x,
+            center_y: self.center.y,
+            size: self.size_smaller,
+            heatmap: self.heatmap,
+            selected_community: self.selected_community,
+        }
+    }
+
+    pub fn restore_view_state(&mut self, state: &ViewState) {
+        self.center = Vector2::new(state.center_x, state.center_y);
+        self.size_smaller = state.size;
+        self.heatmap = state.heatmap;
+        self.selected_community = state.selected_community;
+    }
+
+    pub fn set_inspected(&mut self, id: Option<usize>) {
+        self.inspected = id;
+    }
+
+    pub fn set_comparison(&mut self, comparison: Vec<(f64, Statistics)>) {
+        self.stats_buf.set_comparison(comparison);
+    }
+
+    // the series copied from the simulation for the charts
+    pub fn charts_size(&self) -> usize {
+        self.stats_buf.approx_size() + self.community_buf.approx_size()
+    }
+
+    pub fn export_stats(&self, path: &str) -> io::Result<()> {
+        stats_csv::write(path, self.stats_buf.data())
+    }
+
+    pub fn export_svg(&self, path: &str) -> io::Result<()> {
+        svg::write(path, self.stats_buf.data())
+    }
+
+    pub fn export_graph_data(&self, path: &str, params: Params) -> io::Result<()> {
+        self.stats_buf.write_tsv(path, params)
+    }
+
     fn circle(display: &Display) -> VertexBuffer<Vertex> {
         let mut shape = vec![];
         let n_sides = 20;
         for i in 0..n_sides {
-            let ang = 2.0 * 3.14159 * (i as f64) / n_sides as f64;
+            let ang = 2.0 * std::f64::consts::PI * (i as f64) / n_sides as f64;
             shape.push(Vertex {
                 position: [RADIUS * ang.cos(), RADIUS * ang.sin()],
             });
@@ -90,8 +345,31 @@ impl Renderer {
         VertexBuffer::new(display, &shape).unwrap()
     }
 
-    fn draw_sim(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
-        let (size_x, size_y) = target.get_dimensions();
+    // the left half of the square when split
+    fn sim_viewport(&self, dimensions: (u32, u32)) -> Rect {
+        let full = Self::full_sim_viewport(dimensions);
+        if !self.split {
+            return full;
+        }
+        let half = full.width / 2 - 5;
+        Rect {
+            left: full.left,
+            bottom: full.bottom + full.height / 4,
+            width: half,
+            height: half,
+        }
+    }
+
+    fn branch_viewport(&self, dimensions: (u32, u32)) -> Rect {
+        let viewport = self.sim_viewport(dimensions);
+        Rect {
+            left: viewport.left + viewport.width + 10,
+            ..viewport
+        }
+    }
+
+    fn full_sim_viewport(dimensions: (u32, u32)) -> Rect {
+        let (size_x, size_y) = dimensions;
 
         let (box_size, horizontal) = if size_x < size_y {
             (size_x - 20, false)
@@ -99,6 +377,47 @@ impl Renderer {
             (size_y - 20, true)
         };
 
+        Rect {
+            left: 10,
+            bottom: if horizontal {
+                10
+            } else {
+                size_y - box_size - 10
+            },
+            width: box_size,
+            height: box_size,
+        }
+    }
+
+    // cursor position is in pixels, relative to the top left corner of the window
+    pub fn screen_to_world(
+        &self,
+        dimensions: (u32, u32),
+        cursor: (f64, f64),
+    ) -> Option<Vector2<f64>> {
+        let viewport = self.sim_viewport(dimensions);
+        let x = (cursor.0 - viewport.left as f64) / viewport.width as f64;
+        let y = (dimensions.1 as f64 - cursor.1 - viewport.bottom as f64) / viewport.height as f64;
+        if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+            return None;
+        }
+        let ndc = Vector2::new(x * 2.0 - 1.0, y * 2.0 - 1.0);
+        Some(self.center + ndc * self.size_smaller / 2.0)
+    }
+
+    fn draw_sim(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        sim: &Simulation,
+        snapshot: Option<&RenderSnapshot>,
+        viewport: Rect,
+    ) {
+        infections::trace_span!("draw_sim");
+        if self.mode == DisplayMode::Flow {
+            self.draw_flow(display, target, sim, viewport);
+            return;
+        }
         let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
             * Matrix::scale(
                 2.0 / self.size_smaller as f32,
@@ -108,31 +427,298 @@ impl Renderer {
         let vertex_buffer = Self::circle(display);
         let indices = index::NoIndices(index::PrimitiveType::TriangleFan);
         let draw_parameters = DrawParameters {
-            viewport: Some(Rect {
-                left: 10,
-                bottom: if horizontal {
-                    10
-                } else {
-                    size_y - box_size - 10
-                },
-                width: box_size,
-                height: box_size,
-            }),
+            viewport: Some(viewport),
+            blend: Blend::alpha_blending(),
             ..Default::default()
         };
 
-        for person in sim.people() {
+        let visible = sim
+            .people()
+            .filter(|person| self.visible(person.pos()))
+            .count();
+        if visible > sim.params().lod_threshold {
+            self.draw_points(display, target, sim, snapshot, viewport, matrix);
+            self.draw_vectors(display, target, sim, viewport, matrix);
+            return;
+        }
+
+        let corpse_display_time = sim.params().corpse_display_time;
+
+        for (index, person) in sim.people().enumerate() {
             let matrix2 =
                 Matrix::translation(person.pos().x as f32, person.pos().y as f32) * matrix;
             let uniforms = uniform! {
                 matrix: matrix2.inner(),
-                color: color(person.status()),
+                color: self.person_color(index, &person, sim),
+                alpha: alpha(&person, sim.time(), corpse_display_time),
+            };
+
+            target
+                .draw(
+                    &vertex_buffer,
+                    indices,
+                    &self.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+        self.draw_vectors(display, target, sim, viewport, matrix);
+    }
+
+    // the second species as points smaller than the people, in the infected color while they
+    // can pass on the infection
+    fn draw_vectors(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        sim: &Simulation,
+        viewport: Rect,
+        matrix: Matrix,
+    ) {
+        let diameter = 2.0 * RADIUS * VECTOR_SIZE / self.size_smaller * viewport.width as f64;
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            point_size: Some(diameter.max(1.0) as f32),
+            ..Default::default()
+        };
+        let mut batches = ColorBatches::default();
+        for vector in sim.vectors() {
+            let color = if vector.status().infected().is_some() {
+                COLOR_INFECTED
+            } else {
+                COLOR_VECTOR
+            };
+            let pos = vector.pos();
+            batches.get(color).push(Vertex {
+                position: [pos.x, pos.y],
+            });
+        }
+        self.draw_batches(
+            display,
+            target,
+            &batches,
+            index::PrimitiveType::Points,
+            matrix,
+            &draw_parameters,
+        );
+    }
+
+    // a line from every living person along their current velocity, in their status color
+    fn draw_flow(&self, display: &Display, target: &mut Frame, sim: &Simulation, viewport: Rect) {
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            ..Default::default()
+        };
+        let scale = FLOW_LINE_LENGTH / sim.params().speed_stdev.max(1e-9);
+
+        let mut batches = ColorBatches::default();
+        for (person, mobility) in sim.people().zip(sim.mobilities()) {
+            if person.status().dead().is_some() {
+                continue;
+            }
+            let start = person.pos();
+            let end = start + person.vel() * mobility * scale;
+            let lines = batches.get(color(person.status()));
+            lines.push(Vertex {
+                position: [start.x, start.y],
+            });
+            lines.push(Vertex {
+                position: [end.x, end.y],
+            });
+        }
+        self.draw_batches(
+            display,
+            target,
+            &batches,
+            index::PrimitiveType::LinesList,
+            matrix,
+            &draw_parameters,
+        );
+    }
+
+    // every person as a single point, for when too many are visible to draw them as circles
+    // the colors by status come from the snapshot, computed here if there isn't one
+    fn draw_points(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        sim: &Simulation,
+        snapshot: Option<&RenderSnapshot>,
+        viewport: Rect,
+        matrix: Matrix,
+    ) {
+        // the size of a person on the screen, but at least a pixel
+        let diameter = 2.0 * RADIUS / self.size_smaller * viewport.width as f64;
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            point_size: Some(diameter.max(1.0) as f32),
+            ..Default::default()
+        };
+        let corpse_display_time = sim.params().corpse_display_time;
+
+        let mut batches = ColorBatches::default();
+        if self.mode == DisplayMode::Status {
+            let computed;
+            let snapshot = match snapshot {
+                Some(snapshot) => snapshot,
+                None => {
+                    computed = sim.render_snapshot();
+                    &computed
+                }
+            };
+            // the faded out corpses are left out of the snapshot, and the ones still shown
+            // can't fade with the colors batched
+            for &(x, y, code) in &snapshot.people {
+                batches.get(code_color(code)).push(Vertex {
+                    position: [x as f64, y as f64],
+                });
+            }
+        } else {
+            for (index, person) in sim.people().enumerate() {
+                // corpses can't fade out with the colors batched, they disappear at once instead
+                if alpha(&person, sim.time(), corpse_display_time) <= 0.0 {
+                    continue;
+                }
+                let pos = person.pos();
+                batches
+                    .get(self.person_color(index, &person, sim))
+                    .push(Vertex {
+                        position: [pos.x, pos.y],
+                    });
+            }
+        }
+        self.draw_batches(
+            display,
+            target,
+            &batches,
+            index::PrimitiveType::Points,
+            matrix,
+            &draw_parameters,
+        );
+    }
+
+    fn draw_batches(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        batches: &ColorBatches,
+        primitive: index::PrimitiveType,
+        matrix: Matrix,
+        draw_parameters: &DrawParameters,
+    ) {
+        for (color, vertices) in &batches.batches {
+            let vertex_buffer = VertexBuffer::new(display, vertices).unwrap();
+            let uniforms = uniform! {
+                matrix: matrix.inner(),
+                color: *color,
+                alpha: 1.0f32,
             };
+            target
+                .draw(
+                    &vertex_buffer,
+                    index::NoIndices(primitive),
+                    &self.program,
+                    &uniforms,
+                    draw_parameters,
+                )
+                .unwrap();
+        }
+    }
+
+    // `index` is the person's position in `sim.people()`
+    fn person_color(&self, index: usize, person: &Person, sim: &Simulation) -> [f32; 3] {
+        let status = person.status();
+        match self.mode {
+            DisplayMode::Status | DisplayMode::Flow => return color(status),
+            DisplayMode::Immunity => {
+                return immunity_color(status, sim.time(), sim.params().immunity_display_time)
+            }
+            _ => (),
+        }
+        if status.dead().is_some() {
+            return COLOR_DEAD;
+        }
+        match self.mode {
+            DisplayMode::Community => {
+                let community = sim.person_communities()[index];
+                community.map_or(palette::UNKNOWN, palette::categorical)
+            }
+            DisplayMode::Doses => palette::sequential(status.doses(), 3),
+            DisplayMode::Lineage => status
+                .strain()
+                .map_or(palette::UNKNOWN, palette::categorical),
+            _ => match sim.ages()[index] {
+                Some(age) => palette::sequential(age_group(age), AGE_GROUPS.len()),
+                None => palette::UNKNOWN,
+            },
+        }
+    }
 
+    fn visible(&self, pos: Vector2<f64>) -> bool {
+        let half = self.size_smaller / 2.0;
+        (pos.x - self.center.x).abs() <= half && (pos.y - self.center.y).abs() <= half
+    }
+
+    fn draw_background(&self, target: &mut Frame, viewport: Rect) {
+        let background = match &self.background {
+            Some(background) => background,
+            None => return,
+        };
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            ..Default::default()
+        };
+        background.draw(target, matrix, &draw_parameters);
+    }
+
+    // semi-transparent, so that a background image shows through
+    fn draw_zones(&self, display: &Display, target: &mut Frame, sim: &Simulation, viewport: Rect) {
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        let indices = index::NoIndices(index::PrimitiveType::TriangleFan);
+
+        let zones = sim.zones().iter().map(|zone| (zone.rect(), zone.tint));
+        let hospital = sim
+            .hospital()
+            .map(|hospital| (hospital.rect(), hospital.tint));
+        for (rect, tint) in zones.chain(hospital) {
+            let (x0, y0) = (rect.x, rect.y);
+            let (x1, y1) = (x0 + rect.width, y0 + rect.height);
+            let quad = [
+                Vertex { position: [x0, y0] },
+                Vertex { position: [x1, y0] },
+                Vertex { position: [x1, y1] },
+                Vertex { position: [x0, y1] },
+            ];
+            let vertex_buffer = VertexBuffer::new(display, &quad).unwrap();
+            let uniforms = uniform! {
+                matrix: matrix.inner(),
+                color: tint,
+                alpha: 0.7f32,
+            };
             target
                 .draw(
                     &vertex_buffer,
-                    &indices,
+                    indices,
                     &self.program,
                     &uniforms,
                     &draw_parameters,
@@ -141,6 +727,470 @@ impl Renderer {
         }
     }
 
+    // With daily schedules, the view darkens towards midnight and brightens towards noon, so
+    // that it's clear which part of the day they're in.
+    fn draw_night(&self, display: &Display, target: &mut Frame, sim: &Simulation, viewport: Rect) {
+        if !sim.has_daily_schedules() {
+            return;
+        }
+        let hour = hour_of_day(sim.time());
+        let darkness = (1.0 + (2.0 * PI * hour / HOURS_PER_DAY).cos()) / 2.0;
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        // the whole viewport
+        let quad = [
+            Vertex {
+                position: [-1.0, -1.0],
+            },
+            Vertex {
+                position: [1.0, -1.0],
+            },
+            Vertex {
+                position: [1.0, 1.0],
+            },
+            Vertex {
+                position: [-1.0, 1.0],
+            },
+        ];
+        let vertex_buffer = VertexBuffer::new(display, &quad).unwrap();
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: COLOR_NIGHT,
+            alpha: (NIGHT_ALPHA * darkness) as f32,
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                index::NoIndices(index::PrimitiveType::TriangleFan),
+                &self.program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+    }
+
+    fn draw_walls(&self, display: &Display, target: &mut Frame, sim: &Simulation, viewport: Rect) {
+        if sim.obstacles().is_empty() {
+            return;
+        }
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            line_width: Some(2.0),
+            ..Default::default()
+        };
+        // the open doors are left out as gaps
+        let closed_doors = sim.closed_doors();
+        let lines: Vec<_> = sim
+            .walls()
+            .iter()
+            .chain(&closed_doors)
+            .flat_map(|wall| {
+                vec![
+                    Vertex {
+                        position: [wall.a.x, wall.a.y],
+                    },
+                    Vertex {
+                        position: [wall.b.x, wall.b.y],
+                    },
+                ]
+            })
+            .collect();
+        let vertex_buffer = VertexBuffer::new(display, &lines).unwrap();
+        let indices = index::NoIndices(index::PrimitiveType::LinesList);
+        let uniforms = uniform! {
+            matrix: matrix.inner(),
+            color: [0.2f32, 0.2, 0.2],
+            alpha: 1.0f32,
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                indices,
+                &self.program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+    }
+
+    // shades the cells of the density map, darker meaning denser
+    fn draw_density(&self, target: &mut Frame, viewport: Rect) {
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        let indices = index::NoIndices(index::PrimitiveType::TrianglesList);
+
+        for (alpha, vertex_buffer) in &self.density {
+            let uniforms = uniform! {
+                matrix: matrix.inner(),
+                color: [0.0f32, 0.0, 0.0],
+                alpha: *alpha,
+            };
+            target
+                .draw(
+                    vertex_buffer,
+                    indices,
+                    &self.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+    }
+
+    // tints the regions where crowding makes the infection more likely to pass on, more
+    // strongly the riskier they are
+    fn draw_heatmap(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        sim: &Simulation,
+        viewport: Rect,
+    ) {
+        if !self.heatmap {
+            return;
+        }
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        let indices = index::NoIndices(index::PrimitiveType::TriangleFan);
+
+        let regions = sim.regions();
+        let (cells_x, cells_y) = regions.size();
+        for y in 0..cells_y {
+            for x in 0..cells_x {
+                let crowding = sim.region_crowding((x, y));
+                if crowding <= 1.0 {
+                    continue;
+                }
+                let (min, max) = regions.bounds((x, y));
+                let quad = [
+                    Vertex {
+                        position: [min.x, min.y],
+                    },
+                    Vertex {
+                        position: [max.x, min.y],
+                    },
+                    Vertex {
+                        position: [max.x, max.y],
+                    },
+                    Vertex {
+                        position: [min.x, max.y],
+                    },
+                ];
+                let vertex_buffer = VertexBuffer::new(display, &quad).unwrap();
+                let uniforms = uniform! {
+                    matrix: matrix.inner(),
+                    color: COLOR_INFECTED,
+                    alpha: 0.4 * (1.0 - 1.0 / crowding) as f32,
+                };
+                target
+                    .draw(
+                        &vertex_buffer,
+                        indices,
+                        &self.program,
+                        &uniforms,
+                        &draw_parameters,
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    // tints the cells of the contamination grid relative to the most contaminated one
+    fn draw_contamination(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        sim: &Simulation,
+        viewport: Rect,
+    ) {
+        if !self.contamination || !sim.params().fomites {
+            return;
+        }
+        let grid = sim.contamination();
+        let max_level = grid.max_level();
+        if max_level <= 0.0 {
+            return;
+        }
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        let indices = index::NoIndices(index::PrimitiveType::TriangleFan);
+
+        let (cells_x, cells_y) = grid.size();
+        for y in 0..cells_y {
+            for x in 0..cells_x {
+                let level = grid.level((x, y));
+                if level <= 0.0 {
+                    continue;
+                }
+                let (min, max) = grid.bounds((x, y));
+                let quad = [
+                    Vertex {
+                        position: [min.x, min.y],
+                    },
+                    Vertex {
+                        position: [max.x, min.y],
+                    },
+                    Vertex {
+                        position: [max.x, max.y],
+                    },
+                    Vertex {
+                        position: [min.x, max.y],
+                    },
+                ];
+                let vertex_buffer = VertexBuffer::new(display, &quad).unwrap();
+                let uniforms = uniform! {
+                    matrix: matrix.inner(),
+                    color: COLOR_CONTAMINATION,
+                    alpha: 0.5 * (level / max_level) as f32,
+                };
+                target
+                    .draw(
+                        &vertex_buffer,
+                        indices,
+                        &self.program,
+                        &uniforms,
+                        &draw_parameters,
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    // the counts in the community under the cursor, or in the cell of the region grid where
+    // there's none
+    fn draw_region_overlay(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        let pos = match self.hover {
+            Some(pos) => pos,
+            None => return,
+        };
+        let communities = sim.communities();
+        let regions = sim.regions();
+        let ((min, max), lines) = match communities.iter().position(|c| c.contains(pos)) {
+            Some(index) => {
+                let community = &communities[index];
+                let stats = sim.community_stats(index);
+                let min = Vector2::new(community.x, community.y);
+                let size = Vector2::new(community.width, community.height);
+                (
+                    (min, min + size),
+                    [
+                        self.locale.fill("community", &[&community.name]),
+                        self.locale.fill("infected", &[&stats.infected]),
+                        self.locale.fill("healed", &[&stats.healed]),
+                        self.locale.fill("vaccinated", &[&stats.vaccinated]),
+                    ],
+                )
+            }
+            None => {
+                let cell = match regions.cell_at(pos) {
+                    Some(cell) => cell,
+                    None => return,
+                };
+                let stats = regions.stats(cell);
+                (
+                    regions.bounds(cell),
+                    [
+                        self.locale.fill("region", &[&cell.0, &cell.1]),
+                        self.locale.fill("infected", &[&stats.infected]),
+                        self.locale.fill("susceptible", &[&stats.susceptible]),
+                        self.locale.fill("vaccinated", &[&stats.vaccinated]),
+                    ],
+                )
+            }
+        };
+
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(self.sim_viewport(target.get_dimensions())),
+            ..Default::default()
+        };
+
+        let outline = [
+            Vertex {
+                position: [min.x, min.y],
+            },
+            Vertex {
+                position: [max.x, min.y],
+            },
+            Vertex {
+                position: [max.x, max.y],
+            },
+            Vertex {
+                position: [min.x, max.y],
+            },
+        ];
+        let vertex_buffer = VertexBuffer::new(display, &outline).unwrap();
+        let indices = index::NoIndices(index::PrimitiveType::LineLoop);
+        let uniforms = uniform! {
+            matrix: matrix.inner(),
+            color: [0.0f32, 0.0, 0.0],
+            alpha: 1.0f32,
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                indices,
+                &self.program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+
+        let text_matrix = Matrix::scale(1.0 / 30.0, 1.0 / 30.0)
+            * Matrix::translation(-0.5, 0.5)
+            * Matrix::scale(2.0, 2.0);
+        for (i, line) in lines.iter().enumerate() {
+            self.draw_text(
+                target,
+                line,
+                Matrix::translation(0.3, -1.0 - i as f32 * 1.2) * text_matrix,
+                draw_parameters.clone(),
+            );
+        }
+    }
+
+    fn draw_quad(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        (x0, y0, x1, y1): (f64, f64, f64, f64),
+        color: [f32; 3],
+        draw_parameters: &DrawParameters,
+    ) {
+        let quad = [
+            Vertex { position: [x0, y0] },
+            Vertex { position: [x1, y0] },
+            Vertex { position: [x1, y1] },
+            Vertex { position: [x0, y1] },
+        ];
+        let vertex_buffer = VertexBuffer::new(display, &quad).unwrap();
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: color,
+            alpha: 1.0f32,
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                index::NoIndices(index::PrimitiveType::TriangleFan),
+                &self.program,
+                &uniforms,
+                draw_parameters,
+            )
+            .unwrap();
+    }
+
+    // a Gantt-style strip with the phases of the inspected person since the start, along
+    // the top of the simulation view
+    fn draw_inspector(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        let timeline = match self.inspected.and_then(|id| sim.timeline(id)) {
+            Some(timeline) => timeline,
+            None => return,
+        };
+        let sim_viewport = self.sim_viewport(target.get_dimensions());
+        let viewport = Rect {
+            left: sim_viewport.left + 10,
+            bottom: sim_viewport.bottom + sim_viewport.height.saturating_sub(50),
+            width: sim_viewport.width.saturating_sub(20),
+            height: 40,
+        };
+        let w = viewport.width as f32;
+        let h = viewport.height as f32;
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            ..Default::default()
+        };
+
+        self.draw_quad(
+            display,
+            target,
+            (-1.0, -1.0, 1.0, 1.0),
+            [1.0, 1.0, 1.0],
+            &draw_parameters,
+        );
+
+        let x = |t: f64| t / sim.time().max(1e-9) * 2.0 - 1.0;
+        for (i, &(start, phase)) in timeline.segments.iter().enumerate() {
+            let end = timeline
+                .segments
+                .get(i + 1)
+                .map_or(sim.time(), |&(end, _)| end);
+            let color = match phase {
+                Phase::Susceptible => COLOR_HEALTHY,
+                Phase::Infected(_) => COLOR_INFECTED,
+                Phase::Isolated(_) => COLOR_ISOLATED,
+                Phase::Recovered => COLOR_HEALED,
+                Phase::Dead => COLOR_DEAD,
+            };
+            self.draw_quad(
+                display,
+                target,
+                (x(start), -0.9, x(end), -0.1),
+                color,
+                &draw_parameters,
+            );
+        }
+        let tick_width = 2.0 / w as f64;
+        for &dose in &timeline.doses {
+            self.draw_quad(
+                display,
+                target,
+                (x(dose) - tick_width, -1.0, x(dose) + tick_width, 0.0),
+                COLOR_VACCINATED,
+                &draw_parameters,
+            );
+        }
+
+        // text 14 pixels high
+        let matrix = Matrix::scale(28.0 / w, 28.0 / h) * Matrix::translation(-0.98, 0.2);
+        let phase = timeline
+            .segments
+            .last()
+            .map_or("", |(_, phase)| phase.name());
+        self.draw_text(
+            target,
+            &self.locale.fill("inspected", &[&timeline.id, &phase]),
+            matrix,
+            draw_parameters,
+        );
+    }
+
     fn draw_text(
         &self,
         target: &mut Frame,
@@ -148,19 +1198,28 @@ impl Renderer {
         matrix: Matrix,
         draw_parameters: DrawParameters,
     ) {
-        let text = TextDisplay::new(&self.text_system, &self.font, text);
+        self.draw_colored_text(target, text, matrix, [0.0, 0.0, 0.0], draw_parameters);
+    }
 
-        glium_text::draw(
-            &text,
-            &self.text_system,
+    fn draw_colored_text(
+        &self,
+        target: &mut Frame,
+        text: &str,
+        matrix: Matrix,
+        color: [f32; 3],
+        draw_parameters: DrawParameters,
+    ) {
+        let [r, g, b] = color;
+        self.text.draw(
             target,
+            text,
             matrix.inner(),
-            (0.0, 0.0, 0.0, 1.0),
-            draw_parameters.clone(),
+            [r, g, b, 1.0],
+            draw_parameters,
         );
     }
 
-    fn draw_numbers(&self, target: &mut Frame, sim: &Simulation) {
+    fn draw_numbers(&self, target: &mut Frame, sim: &Simulation, stats: Statistics) {
         let (size_x, size_y) = target.get_dimensions();
 
         let (box_size, horizontal) = if size_x < size_y {
@@ -197,52 +1256,229 @@ impl Renderer {
             ..Default::default()
         };
 
-        let stats = sim.stats();
+        let params = sim.params();
+        let count = |value: usize| self.locale.integer(value as u64);
+        let black = [0.0, 0.0, 0.0];
+
+        let yesterday = sim.stats_at(sim.time() - 1.0);
+        let trend = |field: fn(&Statistics) -> usize| {
+            yesterday.map(|yesterday| field(&stats) as i64 - field(&yesterday) as i64)
+        };
+
+        let rows: [StatRow; 6] = [
+            ("population", |stats| stats.population, black, true),
+            ("infected", |stats| stats.infected, COLOR_INFECTED, false),
+            (
+                "infected_vaccinated",
+                |stats| stats.vaccinated_infected,
+                COLOR_VACCINATED_INFECTED,
+                false,
+            ),
+            ("healed", |stats| stats.healed, COLOR_HEALED, true),
+            (
+                "vaccinated",
+                |stats| stats.vaccinated,
+                COLOR_VACCINATED,
+                true,
+            ),
+            ("dead", |stats| stats.dead, COLOR_DEAD, false),
+        ];
+        for (i, (key, field, color, rising_is_good)) in rows.iter().enumerate() {
+            let row = Matrix::translation(0.0, -1.0 - 1.5 * i as f32) * matrix;
+            self.draw_stat(
+                target,
+                key,
+                &count(field(&stats)),
+                *color,
+                row,
+                &draw_parameters,
+            );
+            if let Some(change) = trend(*field) {
+                self.draw_trend(target, change, *rising_is_good, row, &draw_parameters);
+            }
+        }
+
+        let restrictions = sim.restrictions();
+        let active: Vec<_> = [
+            (restrictions.quarantine, self.locale.get("quarantine")),
+            (restrictions.lockdown, self.locale.get("lockdown")),
+        ]
+        .iter()
+        .filter(|(active, _)| *active)
+        .map(|(_, name)| *name)
+        .collect();
 
         self.draw_text(
             target,
-            &format!("Population: {}", stats.population),
-            Matrix::translation(0.1, -1.0) * matrix,
+            &self.locale.fill(
+                "restrictions",
+                &[&if active.is_empty() {
+                    self.locale.get("restrictions_none").to_owned()
+                } else {
+                    active.join(", ")
+                }],
+            ),
+            Matrix::translation(0.1, -10.0) * matrix,
             draw_parameters.clone(),
         );
 
         self.draw_text(
             target,
-            &format!("Infected: {}", stats.infected),
-            Matrix::translation(0.1, -2.5) * matrix,
+            &self.locale.fill(
+                "hospital_load",
+                &[
+                    &self.locale.decimal(stats.hospital_load(params), 1),
+                    &self.locale.decimal(params.hospital_capacity, 1),
+                ],
+            ),
+            Matrix::translation(0.1, -11.5) * matrix,
             draw_parameters.clone(),
         );
 
-        self.draw_text(
+        self.draw_stat(
             target,
-            &format!("   of these, vaccinated: {}", stats.vaccinated_infected),
-            Matrix::translation(0.1, -4.0) * matrix,
-            draw_parameters.clone(),
+            "cost",
+            &self.locale.decimal(sim.costs().total(params), 1),
+            black,
+            Matrix::translation(0.0, -13.0) * matrix,
+            &draw_parameters,
         );
 
-        self.draw_text(
+        let row = Matrix::translation(0.0, -14.5) * matrix;
+        self.draw_stat(
+            target,
+            "hybrid_immune",
+            &count(stats.hybrid_immune),
+            black,
+            row,
+            &draw_parameters,
+        );
+        if let Some(change) = trend(|stats| stats.hybrid_immune) {
+            self.draw_trend(target, change, true, row, &draw_parameters);
+        }
+
+        if params.two_dose {
+            self.draw_text(
+                target,
+                &self
+                    .locale
+                    .fill("doses", &[&count(stats.one_dose), &count(stats.two_doses)]),
+                Matrix::translation(0.1, -16.0) * matrix,
+                draw_parameters.clone(),
+            );
+        }
+
+        // highlighted while changed from the configuration
+        let transmissibility = sim.transmissibility();
+        self.draw_stat(
+            target,
+            "transmissibility",
+            &format!("×{}", self.locale.decimal(transmissibility, 1)),
+            if transmissibility == 1.0 {
+                black
+            } else {
+                COLOR_TRANSMISSIBILITY
+            },
+            Matrix::translation(0.0, -17.5) * matrix,
+            &draw_parameters,
+        );
+
+        // for the last full day with the reports in, so that both are about the same people
+        let delay = if params.reporting {
+            params.case_reporting_delay.round()
+        } else {
+            0.0
+        };
+        let day = sim.time() - 1.0 - delay;
+        if day >= 0.0 {
+            let day = day.floor() as usize;
+            let rt = |rt: Option<f64>| rt.map_or("-".to_owned(), |rt| self.locale.decimal(rt, 2));
+            let text = if params.reporting {
+                self.locale.fill(
+                    "rt_estimated",
+                    &[&day, &rt(sim.true_rt(day)), &rt(sim.estimated_rt(day))],
+                )
+            } else {
+                self.locale.fill("rt", &[&day, &rt(sim.true_rt(day))])
+            };
+            self.draw_text(
+                target,
+                &text,
+                Matrix::translation(0.1, -19.0) * matrix,
+                draw_parameters.clone(),
+            );
+        }
+
+        if sim.has_daily_schedules() {
+            let minutes = (hour_of_day(sim.time()) * 60.0).floor() as u64;
+            let clock = format!("{:02}:{:02}", minutes / 60, minutes % 60);
+            self.draw_text(
+                target,
+                &self
+                    .locale
+                    .fill("clock", &[&(sim.time().floor() as u64), &clock]),
+                Matrix::translation(0.1, -20.5) * matrix,
+                draw_parameters.clone(),
+            );
+        }
+    }
+
+    // a line of the stats panel, with its value right-aligned in a column
+    fn draw_stat(
+        &self,
+        target: &mut Frame,
+        key: &str,
+        value: &str,
+        color: [f32; 3],
+        row: Matrix,
+        draw_parameters: &DrawParameters,
+    ) {
+        let label = self.locale.fill(key, &[&""]);
+        self.draw_colored_text(
             target,
-            &format!("Healed: {}", stats.healed),
-            Matrix::translation(0.1, -5.5) * matrix,
+            label.trim_end(),
+            Matrix::translation(0.1, 0.0) * row,
+            color,
             draw_parameters.clone(),
         );
-
-        self.draw_text(
+        let x = STATS_VALUE_COLUMN - self.text.width(value);
+        self.draw_colored_text(
             target,
-            &format!("Vaccinated: {}", stats.vaccinated),
-            Matrix::translation(0.1, -7.0) * matrix,
+            value,
+            Matrix::translation(x, 0.0) * row,
+            color,
             draw_parameters.clone(),
         );
+    }
 
-        self.draw_text(
+    // the change since a day ago, after the value of a stat
+    fn draw_trend(
+        &self,
+        target: &mut Frame,
+        change: i64,
+        rising_is_good: bool,
+        row: Matrix,
+        draw_parameters: &DrawParameters,
+    ) {
+        if change == 0 {
+            return;
+        }
+        let arrow = if change > 0 { "▲" } else { "▼" };
+        let color = if (change > 0) == rising_is_good {
+            COLOR_TREND_GOOD
+        } else {
+            COLOR_TREND_BAD
+        };
+        self.draw_colored_text(
             target,
-            &format!("Dead: {}", stats.dead),
-            Matrix::translation(0.1, -8.5) * matrix,
-            draw_parameters,
+            &format!("{} {}", arrow, self.locale.integer(change.unsigned_abs())),
+            Matrix::translation(STATS_VALUE_COLUMN + 0.5, 0.0) * row,
+            color,
+            draw_parameters.clone(),
         );
     }
 
-    fn draw_key(&self, display: &Display, target: &mut Frame) {
+    fn draw_key(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
         let (size_x, size_y) = target.get_dimensions();
 
         let (box_size, horizontal) = if size_x < size_y {
@@ -279,18 +1515,77 @@ impl Renderer {
             ..Default::default()
         };
 
-        let elements = [
-            (COLOR_HEALTHY, "Healthy"),
-            (COLOR_INFECTED, "Infected"),
-            (COLOR_HEALED, "Healed"),
-            (COLOR_VACCINATED, "Vaccinated"),
-            (COLOR_VACCINATED_INFECTED, "Vaccinated and infected"),
-            (COLOR_DEAD, "Dead (graph only)"),
-        ];
+        let params = sim.params();
+        let key = |name| self.locale.get(name).to_owned();
+        let mut elements = match self.mode {
+            DisplayMode::Status | DisplayMode::Flow => vec![
+                (COLOR_HEALTHY, key("key_healthy")),
+                (COLOR_INFECTED, key("key_infected")),
+                (COLOR_HEALED, key("key_healed")),
+                (COLOR_VACCINATED, key("key_vaccinated")),
+                (COLOR_VACCINATED_INFECTED, key("key_vaccinated_infected")),
+                (COLOR_DEAD, key("key_dead")),
+            ],
+            DisplayMode::Immunity => vec![
+                (COLOR_NOT_IMMUNE, key("key_not_immune")),
+                (COLOR_IMMUNE_RECENT, key("key_immune_recent")),
+                (
+                    COLOR_IMMUNE_OLD,
+                    self.locale
+                        .fill("key_immune_old", &[&params.immunity_display_time]),
+                ),
+                (COLOR_INFECTED, key("key_infected")),
+                (COLOR_DEAD, key("key_dead")),
+            ],
+            DisplayMode::Community => sim
+                .communities()
+                .iter()
+                .enumerate()
+                .map(|(index, community)| (palette::categorical(index), community.name.clone()))
+                .chain(Some((palette::UNKNOWN, key("key_outside"))))
+                .collect(),
+            DisplayMode::Doses => (0..3)
+                .map(|doses| {
+                    let name = self.locale.fill("key_doses", &[&doses]);
+                    (palette::sequential(doses, 3), name)
+                })
+                .collect(),
+            DisplayMode::Lineage => {
+                let introduced = sim
+                    .strains()
+                    .introduced()
+                    .map(|(index, strain)| (palette::categorical(index), strain.name.clone()));
+                Some((palette::categorical(0), key("key_original_strain")))
+                    .into_iter()
+                    .chain(introduced)
+                    .chain(Some((palette::UNKNOWN, key("key_not_infected"))))
+                    .collect()
+            }
+            DisplayMode::AgeGroup => {
+                let groups = AGE_GROUPS.iter().enumerate().map(|(group, &youngest)| {
+                    let name = match AGE_GROUPS.get(group + 1) {
+                        Some(&next) => self
+                            .locale
+                            .fill("key_age_group", &[&youngest, &(next - 1.0)]),
+                        None => self.locale.fill("key_age_oldest", &[&youngest]),
+                    };
+                    (palette::sequential(group, AGE_GROUPS.len()), name)
+                });
+                groups
+                    .chain(Some((palette::UNKNOWN, key("key_age_unknown"))))
+                    .collect()
+            }
+        };
+        if !matches!(
+            self.mode,
+            DisplayMode::Status | DisplayMode::Flow | DisplayMode::Immunity
+        ) {
+            elements.push((COLOR_DEAD, key("key_dead")));
+        }
 
         self.draw_text(
             target,
-            "Color key:",
+            self.locale.get("key_title"),
             Matrix::translation(0.1, -1.0) * matrix,
             draw_parameters.clone(),
         );
@@ -303,12 +1598,13 @@ impl Renderer {
             let uniforms = uniform! {
                 matrix: matrix2.inner(),
                 color: *color,
+                alpha: 1.0f32,
             };
 
             target
                 .draw(
                     &vertex_buffer,
-                    &indices,
+                    indices,
                     &self.program,
                     &uniforms,
                     &draw_parameters,
@@ -324,6 +1620,96 @@ impl Renderer {
         }
     }
 
+    // the numbers go under the color key, the graph above the time slider
+    fn draw_community(&mut self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        let community = match self.selected_community {
+            Some(community) if community < sim.communities().len() => community,
+            _ => return,
+        };
+        let (size_x, size_y) = target.get_dimensions();
+
+        let (box_size, horizontal) = if size_x < size_y {
+            (size_x, false)
+        } else {
+            (size_y, true)
+        };
+
+        let (numbers_viewport, graph_viewport) = if horizontal {
+            (
+                Rect {
+                    left: box_size + (size_x - box_size) / 2 + 10,
+                    bottom: 0,
+                    width: (size_x - box_size) / 2 - 20,
+                    height: size_y - 20,
+                },
+                Rect {
+                    left: box_size + 10,
+                    bottom: 60,
+                    width: size_x - box_size - 20,
+                    height: (size_y / 3).saturating_sub(70),
+                },
+            )
+        } else {
+            let free_height = size_y - box_size;
+            (
+                Rect {
+                    left: 10 + size_x / 2,
+                    bottom: 0,
+                    width: size_x / 2 - 20,
+                    height: free_height - 20,
+                },
+                Rect {
+                    left: 10,
+                    bottom: 60,
+                    width: size_x - 20,
+                    height: (free_height / 3).saturating_sub(70),
+                },
+            )
+        };
+
+        let w = numbers_viewport.width as f32;
+        let h = numbers_viewport.height as f32;
+
+        let matrix = Matrix::scale(1.0 / 30.0, 1.0 / 30.0)
+            * Matrix::translation(-0.5, 0.5 * h / w)
+            * Matrix::scale(2.0, 2.0 * w / h);
+
+        let draw_parameters = DrawParameters {
+            viewport: Some(numbers_viewport),
+            ..Default::default()
+        };
+
+        let stats = sim.community_stats(community);
+        let lines = [
+            self.locale
+                .fill("community", &[&sim.communities()[community].name]),
+            self.locale.fill("population", &[&stats.population]),
+            self.locale.fill("infected", &[&stats.infected]),
+            self.locale.fill("healed", &[&stats.healed]),
+            self.locale.fill("vaccinated", &[&stats.vaccinated]),
+            self.locale.fill("dead", &[&stats.dead]),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            self.draw_text(
+                target,
+                line,
+                Matrix::translation(0.1, -13.0 - i as f32 * 1.5) * matrix,
+                draw_parameters.clone(),
+            );
+        }
+
+        if graph_viewport.height < 20 {
+            return;
+        }
+        self.community_buf.update(sim.community_history(community));
+        let draw_parameters = DrawParameters {
+            viewport: Some(graph_viewport),
+            ..Default::default()
+        };
+        self.community_buf
+            .draw(display, target, self, &draw_parameters, sim.params());
+    }
+
     fn graph_viewport(&self, target: &Frame) -> Rect {
         let (size_x, size_y) = target.get_dimensions();
 
@@ -351,30 +1737,668 @@ impl Renderer {
         }
     }
 
-    pub fn draw(&mut self, display: &Display, sim: &Simulation) {
+    fn slider_viewport(&self, target: &Frame) -> Rect {
+        let (size_x, size_y) = target.get_dimensions();
+
+        if size_x < size_y {
+            Rect {
+                left: 10,
+                bottom: 10,
+                width: size_x - 20,
+                height: 40,
+            }
+        } else {
+            Rect {
+                left: size_y + 10,
+                bottom: 10,
+                width: size_x - size_y - 20,
+                height: 40,
+            }
+        }
+    }
+
+    // the slider is logarithmic, with 1x in the middle
+    fn draw_time_slider(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        sim_params: &SimulationParameters,
+    ) {
+        let time_compression = sim_params.time_compression;
+        let viewport = self.slider_viewport(target);
+        let w = viewport.width as f32;
+        let h = viewport.height as f32;
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            ..Default::default()
+        };
+
+        let (left, right) = (0.0, 0.95);
+        let min = MIN_TIME_COMPRESSION.log2();
+        let max = MAX_TIME_COMPRESSION.log2();
+        let x = |value: f64| left + (value.log2() - min) / (max - min) * (right - left);
+
+        let track = [
+            Vertex {
+                position: [left, 0.0],
+            },
+            Vertex {
+                position: [right, 0.0],
+            },
+            Vertex {
+                position: [x(1.0), -0.4],
+            },
+            Vertex {
+                position: [x(1.0), 0.4],
+            },
+        ];
+        let vertex_buffer = VertexBuffer::new(display, &track).unwrap();
+        let indices = index::NoIndices(index::PrimitiveType::LinesList);
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: [0.0f32, 0.0, 0.0],
+            alpha: 1.0f32,
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                indices,
+                &self.program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+
+        let pos = x(time_compression.clamp(MIN_TIME_COMPRESSION, MAX_TIME_COMPRESSION));
+        let half_width = 4.0 / w as f64;
+        let marker = [
+            Vertex {
+                position: [pos - half_width, -0.6],
+            },
+            Vertex {
+                position: [pos + half_width, -0.6],
+            },
+            Vertex {
+                position: [pos + half_width, 0.6],
+            },
+            Vertex {
+                position: [pos - half_width, 0.6],
+            },
+        ];
+        let vertex_buffer = VertexBuffer::new(display, &marker).unwrap();
+        let indices = index::NoIndices(index::PrimitiveType::TriangleFan);
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: COLOR_INFECTED,
+            alpha: 1.0f32,
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                indices,
+                &self.program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+
+        // text 20 pixels high
+        let matrix = Matrix::scale(40.0 / w, 40.0 / h) * Matrix::translation(-0.95, -0.3);
+        self.draw_text(
+            target,
+            &if sim_params.fast {
+                self.locale.get("fast_forward").to_owned()
+            } else {
+                self.locale
+                    .fill("time_compression", &[&format!("{:.2}", time_compression)])
+            },
+            matrix,
+            draw_parameters,
+        );
+    }
+
+    // rings around the followed person and the people they met recently, fading with time
+    fn draw_contacts(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        let timeline = match self.inspected.and_then(|id| sim.timeline(id)) {
+            Some(timeline) if self.follow => timeline,
+            _ => return,
+        };
+        let matrix = Matrix::translation(-self.center.x as f32, -self.center.y as f32)
+            * Matrix::scale(
+                2.0 / self.size_smaller as f32,
+                2.0 / self.size_smaller as f32,
+            );
+        let draw_parameters = DrawParameters {
+            viewport: Some(self.sim_viewport(target.get_dimensions())),
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        let ring = RADIUS * 1.8;
+        let ring: Vec<_> = (0..20)
+            .map(|i| {
+                let ang = 2.0 * PI * i as f64 / 20.0;
+                Vertex {
+                    position: [ring * ang.cos(), ring * ang.sin()],
+                }
+            })
+            .collect();
+        let vertex_buffer = VertexBuffer::new(display, &ring).unwrap();
+
+        let recent = timeline
+            .contacts
+            .iter()
+            .rev()
+            .take_while(|contact| sim.time() - contact.end < CONTACT_HIGHLIGHT_TIME)
+            .map(|contact| {
+                let fade = 1.0 - (sim.time() - contact.end) / CONTACT_HIGHLIGHT_TIME;
+                (contact.other, COLOR_CONTACT, fade as f32)
+            });
+        let rings = std::iter::once((timeline.id, [0.0, 0.0, 0.0], 1.0)).chain(recent);
+        for (id, color, alpha) in rings {
+            let person = match sim.person(id) {
+                Some(person) => person,
+                None => continue,
+            };
+            let matrix = Matrix::translation(person.pos().x as f32, person.pos().y as f32) * matrix;
+            let uniforms = uniform! {
+                matrix: matrix.inner(),
+                color: color,
+                alpha: alpha,
+            };
+            target
+                .draw(
+                    &vertex_buffer,
+                    index::NoIndices(index::PrimitiveType::LineLoop),
+                    &self.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+    }
+
+    // the whole box in the bottom right corner of the simulation view, shaded by the number of
+    // the infected, with the visible part outlined
+    fn draw_minimap(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        let (box_x, box_y) = (sim.params().size_x, sim.params().size_y);
+        if self.size_smaller >= box_x.max(box_y) * MINIMAP_ZOOM {
+            return;
+        }
+        let sim_viewport = self.sim_viewport(target.get_dimensions());
+        let scale = MINIMAP_SIZE as f64 / box_x.max(box_y);
+        let (width, height) = ((box_x * scale) as u32, (box_y * scale) as u32);
+        let viewport = Rect {
+            left: sim_viewport.left + sim_viewport.width.saturating_sub(width + 10),
+            bottom: sim_viewport.bottom + 10,
+            width,
+            height,
+        };
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        self.draw_quad(
+            display,
+            target,
+            (-1.0, -1.0, 1.0, 1.0),
+            [1.0, 1.0, 1.0],
+            &draw_parameters,
+        );
+
+        let matrix = Matrix::translation(-box_x as f32 / 2.0, -box_y as f32 / 2.0)
+            * Matrix::scale(2.0 / box_x as f32, 2.0 / box_y as f32);
+        let rect = |min: Vector2<f64>, max: Vector2<f64>| {
+            [
+                Vertex {
+                    position: [min.x, min.y],
+                },
+                Vertex {
+                    position: [max.x, min.y],
+                },
+                Vertex {
+                    position: [max.x, max.y],
+                },
+                Vertex {
+                    position: [min.x, max.y],
+                },
+            ]
+        };
+        let mut draw = |vertices: &[Vertex], primitive, matrix: Matrix, color: [f32; 3], alpha| {
+            let vertex_buffer = VertexBuffer::new(display, vertices).unwrap();
+            let uniforms = uniform! {
+                matrix: matrix.inner(),
+                color: color,
+                alpha: alpha,
+            };
+            target
+                .draw(
+                    &vertex_buffer,
+                    index::NoIndices(primitive),
+                    &self.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        };
+
+        let regions = sim.regions();
+        let (cells_x, cells_y) = regions.size();
+        let cells = (0..cells_y).flat_map(|y| (0..cells_x).map(move |x| (x, y)));
+        let max_infected = cells
+            .clone()
+            .map(|cell| regions.stats(cell).infected)
+            .max()
+            .unwrap_or(0);
+        for cell in cells {
+            let infected = regions.stats(cell).infected;
+            if infected == 0 {
+                continue;
+            }
+            let (min, max) = regions.bounds(cell);
+            let alpha = 0.8 * infected as f32 / max_infected as f32;
+            draw(
+                &rect(min, max),
+                index::PrimitiveType::TriangleFan,
+                matrix,
+                COLOR_INFECTED,
+                alpha,
+            );
+        }
+
+        let half = self.size_smaller / 2.0;
+        let offset = Vector2::new(half, half);
+        draw(
+            &rect(self.center - offset, self.center + offset),
+            index::PrimitiveType::LineLoop,
+            matrix,
+            [0.0, 0.0, 0.0],
+            1.0,
+        );
+        draw(
+            &rect(Vector2::new(-1.0, -1.0), Vector2::new(1.0, 1.0)),
+            index::PrimitiveType::LineLoop,
+            Matrix::identity(),
+            [0.5, 0.5, 0.5],
+            1.0,
+        );
+    }
+
+    // frame pacing and the cost of the last step, in the top left corner of the simulation view
+    fn draw_debug_overlay(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        if !self.debug {
+            return;
+        }
+        let timings = sim.timings();
+        let ms = |duration: Duration| format!("{:.2}", duration.as_secs_f64() * 1000.0);
+        let lines = [
+            self.locale
+                .fill("debug_fps", &[&format!("{:.1}", self.frame_counter.fps)]),
+            self.locale.fill(
+                "debug_steps",
+                &[&format!("{:.1}", self.frame_counter.steps_per_sec)],
+            ),
+            self.locale.fill("debug_people", &[&sim.ids().len()]),
+            self.locale.fill("debug_pairs", &[&timings.pairs]),
+            match sim.contact_rate() {
+                Some(rate) => self
+                    .locale
+                    .fill("debug_contact_rate", &[&format!("{:.2}", rate)]),
+                None => self.locale.get("debug_contact_rate_pending").to_owned(),
+            },
+            self.locale.fill(
+                "debug_timings",
+                &[
+                    &ms(timings.physics),
+                    &ms(timings.collisions),
+                    &ms(timings.statuses),
+                ],
+            ),
+            self.locale.fill(
+                "debug_memory",
+                &[&format!("{:.1}", sim.approx_size() as f64 / BYTES_PER_MB)],
+            ),
+        ];
+
+        self.draw_text_box(display, target, &lines, Corner::TopLeft);
+    }
+
+    // The speeds of the living and the contacts between the groups, in the bottom left
+    // corner of the simulation view, above the parameter editor.
+    fn draw_diagnostics(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        if !self.diagnostics {
+            return;
+        }
+        let (speeds, max_speed) = sim.speed_histogram(SPEED_HISTOGRAM_BINS);
+        let mixing = sim.mixing();
+        let groups = sim.communities().len() + 1;
+        let sim_viewport = self.sim_viewport(target.get_dimensions());
+        let width = DIAGNOSTICS_WIDTH.min(sim_viewport.width.saturating_sub(20));
+        let cell = ((width as f32 - DIAGNOSTICS_LABEL_WIDTH - 10.0) / groups as f32)
+            .clamp(1.0, DIAGNOSTICS_MAX_CELL);
+        let matrix_top = 40.0 + DIAGNOSTICS_HISTOGRAM_HEIGHT;
+        let matrix_height = if mixing.is_some() {
+            cell * groups as f32
+        } else {
+            0.0
+        };
+        let viewport = Rect {
+            left: sim_viewport.left + 10,
+            bottom: sim_viewport.bottom + 70,
+            width,
+            height: (matrix_top + matrix_height + 10.0) as u32,
+        };
+        let (w, h) = (viewport.width as f32, viewport.height as f32);
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            ..Default::default()
+        };
+        // from pixels within the panel, counted from its top left corner
+        let rect = |x0: f32, y0: f32, x1: f32, y1: f32| {
+            (
+                (x0 / w * 2.0 - 1.0) as f64,
+                (1.0 - y1 / h * 2.0) as f64,
+                (x1 / w * 2.0 - 1.0) as f64,
+                (1.0 - y0 / h * 2.0) as f64,
+            )
+        };
+        // text 14 pixels high, with the baseline at `y`
+        let text_at = |x: f32, y: f32| {
+            Matrix::scale(28.0 / w, 28.0 / h)
+                * Matrix::translation(x / w * 2.0 - 1.0, 1.0 - y / h * 2.0)
+        };
+
+        self.draw_quad(
+            display,
+            target,
+            (-1.0, -1.0, 1.0, 1.0),
+            [1.0, 1.0, 1.0],
+            &draw_parameters,
+        );
+
+        self.draw_text(
+            target,
+            &self
+                .locale
+                .fill("diagnostics_speeds", &[&self.locale.decimal(max_speed, 2)]),
+            text_at(5.0, 18.0),
+            draw_parameters.clone(),
+        );
+        let tallest = speeds.iter().cloned().max().unwrap_or(0).max(1);
+        let bar_width = (w - 10.0) / speeds.len() as f32;
+        for (i, &count) in speeds.iter().enumerate() {
+            let height = DIAGNOSTICS_HISTOGRAM_HEIGHT * count as f32 / tallest as f32;
+            let x = 5.0 + bar_width * i as f32;
+            let bottom = 25.0 + DIAGNOSTICS_HISTOGRAM_HEIGHT;
+            self.draw_quad(
+                display,
+                target,
+                rect(x, bottom - height, x + bar_width - 1.0, bottom),
+                COLOR_DEAD,
+                &draw_parameters,
+            );
+        }
+
+        let mixing = match mixing {
+            Some(mixing) => mixing,
+            None => {
+                self.draw_text(
+                    target,
+                    self.locale.get("diagnostics_mixing_pending"),
+                    text_at(5.0, matrix_top - 2.0),
+                    draw_parameters,
+                );
+                return;
+            }
+        };
+        let most = mixing.iter().flatten().cloned().max().unwrap_or(0);
+        self.draw_text(
+            target,
+            &self.locale.fill("diagnostics_mixing", &[&most]),
+            text_at(5.0, matrix_top - 2.0),
+            draw_parameters.clone(),
+        );
+        let names = sim
+            .communities()
+            .iter()
+            .map(|community| community.name.as_str())
+            .chain(Some(self.locale.get("diagnostics_outside")));
+        for (row, name) in names.enumerate() {
+            let y = matrix_top + cell * row as f32;
+            self.draw_text(
+                target,
+                name,
+                text_at(5.0, y + cell.min(18.0)),
+                draw_parameters.clone(),
+            );
+            for (column, &count) in mixing[row].iter().enumerate() {
+                let share = count as f32 / most.max(1) as f32;
+                let color = [
+                    1.0 + (COLOR_CONTACT[0] - 1.0) * share,
+                    1.0 + (COLOR_CONTACT[1] - 1.0) * share,
+                    1.0 + (COLOR_CONTACT[2] - 1.0) * share,
+                ];
+                let x = DIAGNOSTICS_LABEL_WIDTH + cell * column as f32;
+                self.draw_quad(
+                    display,
+                    target,
+                    rect(x, y, x + cell - 1.0, y + cell - 1.0),
+                    color,
+                    &draw_parameters,
+                );
+            }
+        }
+    }
+
+    fn draw_param_editor(&self, display: &Display, target: &mut Frame) {
+        if let Some((name, value, precision)) = self.edited {
+            let lines = [
+                self.locale.fill(
+                    "edit_param",
+                    &[&name, &self.locale.decimal(value, precision)],
+                ),
+                self.locale.get("edit_help").to_owned(),
+            ];
+            self.draw_text_box(display, target, &lines, Corner::BottomLeft);
+        }
+    }
+
+    // the parameters differing from the defaults, or changed since the config was loaded
+    fn draw_param_diff(&self, display: &Display, target: &mut Frame, sim: &Simulation) {
+        let config = match self.param_diff {
+            Some(config) => config,
+            None => return,
+        };
+        let differences = param_diff::differences(sim.params(), config);
+        let mut lines = vec![self.locale.get("param_diff_title").to_owned()];
+        if differences.is_empty() {
+            lines.push(self.locale.get("param_diff_none").to_owned());
+        }
+        for difference in differences.iter().take(MAX_PARAM_DIFF_LINES) {
+            let (key, previous) = if difference.edited() {
+                ("param_diff_edited", &difference.config)
+            } else {
+                ("param_diff_config", &difference.default)
+            };
+            lines.push(
+                self.locale
+                    .fill(key, &[&difference.name, &difference.value, previous]),
+            );
+        }
+        if differences.len() > MAX_PARAM_DIFF_LINES {
+            lines.push(self.locale.fill(
+                "param_diff_more",
+                &[&(differences.len() - MAX_PARAM_DIFF_LINES)],
+            ));
+        }
+        self.draw_text_box(display, target, &lines, Corner::TopRight);
+    }
+
+    // in a corner of the simulation view
+    fn draw_text_box(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        lines: &[String],
+        corner: Corner,
+    ) {
+        let sim_viewport = self.sim_viewport(target.get_dimensions());
+        let height = 20 * lines.len() as u32 + 10;
+        let width = 420.min(sim_viewport.width.saturating_sub(20));
+        let bottom = match corner {
+            Corner::TopLeft | Corner::TopRight => {
+                sim_viewport.bottom + sim_viewport.height.saturating_sub(height + 10)
+            }
+            Corner::BottomLeft => sim_viewport.bottom + 10,
+        };
+        let left = match corner {
+            Corner::TopRight => sim_viewport.left + sim_viewport.width.saturating_sub(width + 10),
+            Corner::TopLeft | Corner::BottomLeft => sim_viewport.left + 10,
+        };
+        let viewport = Rect {
+            left,
+            bottom,
+            width,
+            height,
+        };
+        let w = viewport.width as f32;
+        let h = viewport.height as f32;
+        let draw_parameters = DrawParameters {
+            viewport: Some(viewport),
+            ..Default::default()
+        };
+        self.draw_quad(
+            display,
+            target,
+            (-1.0, -1.0, 1.0, 1.0),
+            [1.0, 1.0, 1.0],
+            &draw_parameters,
+        );
+        for (i, line) in lines.iter().enumerate() {
+            // text 14 pixels high, on lines 20 pixels apart
+            let y = 1.0 - (20.0 * (i as f32 + 1.0)) * 2.0 / h;
+            let matrix = Matrix::scale(28.0 / w, 28.0 / h) * Matrix::translation(-0.98, y);
+            self.draw_text(target, line, matrix, draw_parameters.clone());
+        }
+    }
+
+    // labels the two halves of a split view
+    fn draw_branch_labels(&self, target: &mut Frame, branch: &Simulation) {
+        let dimensions = target.get_dimensions();
+        let labels = [
+            (
+                self.sim_viewport(dimensions),
+                self.locale.get("branch_main").to_owned(),
+            ),
+            (
+                self.branch_viewport(dimensions),
+                self.locale.fill(
+                    "branch_alternative",
+                    &[&branch.params().branch_intervention],
+                ),
+            ),
+        ];
+        for (viewport, label) in &labels {
+            let viewport = Rect {
+                bottom: viewport.bottom + viewport.height,
+                height: 30,
+                ..*viewport
+            };
+            let w = viewport.width as f32;
+            let h = viewport.height as f32;
+            // text 16 pixels high
+            let matrix = Matrix::scale(32.0 / w, 32.0 / h) * Matrix::translation(-0.98, -0.5);
+            let draw_parameters = DrawParameters {
+                viewport: Some(viewport),
+                ..Default::default()
+            };
+            self.draw_text(target, label, matrix, draw_parameters);
+        }
+    }
+
+    // `snapshot` is the latest one published for the main run, which may be a step ahead of
+    // `sim`; the branch doesn't have one
+    pub fn draw(
+        &mut self,
+        display: &Display,
+        sim: &Simulation,
+        snapshot: &RenderSnapshot,
+        branch: Option<&Simulation>,
+        sim_params: &SimulationParameters,
+    ) {
         let mut target = display.draw();
 
         target.clear_color(1.0, 1.0, 1.0, 1.0);
+        self.frame_counter.frame(sim.steps());
+
+        self.split = branch.is_some();
+        if self.follow {
+            if let Some(person) = self.inspected.and_then(|id| sim.person(id)) {
+                self.center = person.pos();
+            }
+        }
+        // only when it is from the same step
+        let snapshot = Some(snapshot)
+            .filter(|snapshot| snapshot.version == SNAPSHOT_VERSION && snapshot.time == sim.time());
+        let viewports = [
+            Some((sim, snapshot, self.sim_viewport(target.get_dimensions()))),
+            branch.map(|branch| (branch, None, self.branch_viewport(target.get_dimensions()))),
+        ];
+        for &(sim, snapshot, viewport) in viewports.iter().flatten() {
+            self.draw_background(&mut target, viewport);
+            self.draw_zones(display, &mut target, sim, viewport);
+            self.draw_night(display, &mut target, sim, viewport);
+            self.draw_walls(display, &mut target, sim, viewport);
+            self.draw_density(&mut target, viewport);
+            self.draw_sim(display, &mut target, sim, snapshot, viewport);
+        }
+        let viewport = self.sim_viewport(target.get_dimensions());
+        self.draw_heatmap(display, &mut target, sim, viewport);
+        self.draw_contamination(display, &mut target, sim, viewport);
+        if let Some(branch) = branch {
+            self.draw_branch_labels(&mut target, branch);
+        }
 
-        self.draw_sim(display, &mut target, sim);
+        self.draw_region_overlay(display, &mut target, sim);
 
-        self.draw_numbers(&mut target, sim);
+        self.draw_contacts(display, &mut target, sim);
 
-        self.draw_key(display, &mut target);
+        self.draw_minimap(display, &mut target, sim);
 
-        if self.last_t < sim.time().floor() {
-            self.stats_buf.record(sim.time().floor(), sim.stats());
-        }
+        self.draw_inspector(display, &mut target, sim);
+
+        let stats = snapshot.map_or_else(|| sim.stats(), |snapshot| snapshot.stats);
+        self.draw_numbers(&mut target, sim, stats);
+
+        self.draw_key(display, &mut target, sim);
+
+        self.draw_time_slider(display, &mut target, sim_params);
+
+        self.draw_community(display, &mut target, sim);
+
+        self.draw_debug_overlay(display, &mut target, sim);
+        self.draw_diagnostics(display, &mut target, sim);
+        self.draw_param_editor(display, &mut target);
+        self.draw_param_diff(display, &mut target, sim);
 
-        self.last_t = sim.time();
+        self.stats_buf.update(sim.history());
+        self.stats_buf.update_weather(sim.weather_history());
+        self.stats_buf.update_reported(sim.reported_history());
+        self.stats_buf.set_interventions(sim.interventions());
+        self.stats_buf.update_in_force(sim.in_force_history());
+        self.stats_buf
+            .set_branch(branch.map_or(&[][..], |branch| branch.history()));
 
         let graph_viewport = self.graph_viewport(&target);
         let draw_parameters = DrawParameters {
             viewport: Some(graph_viewport),
             ..Default::default()
         };
-        self.stats_buf
-            .draw(display, &mut target, &self, &draw_parameters);
+        {
+            infections::trace_span!("draw_graph");
+            self.stats_buf
+                .draw(display, &mut target, self, &draw_parameters, sim.params());
+        }
 
         target.finish().unwrap();
     }
@@ -386,21 +2410,70 @@ const COLOR_HEALED: [f32; 3] = [0.5, 0.5, 0.0];
 const COLOR_VACCINATED: [f32; 3] = [0.0, 0.0, 1.0];
 const COLOR_VACCINATED_INFECTED: [f32; 3] = [0.7, 0.0, 0.7];
 const COLOR_DEAD: [f32; 3] = [0.2, 0.2, 0.2];
+const COLOR_VECTOR: [f32; 3] = [0.6, 0.45, 0.25];
+const COLOR_CONTAMINATION: [f32; 3] = [0.5, 0.3, 0.0];
+const COLOR_ISOLATED: [f32; 3] = [0.5, 0.0, 0.0];
+const COLOR_NIGHT: [f32; 3] = [0.05, 0.05, 0.3];
+// how dark the view gets at midnight
+const NIGHT_ALPHA: f64 = 0.25;
+const COLOR_BRANCH: [f32; 3] = [0.0, 0.4, 0.8];
+const COLOR_WEATHER: [f32; 3] = [0.4, 0.4, 0.6];
+const COLOR_HOSPITAL_LOAD: [f32; 3] = [1.0, 0.5, 0.0];
+const COLOR_REPORTED: [f32; 3] = [1.0, 1.0, 1.0];
+const COLOR_CONTACT: [f32; 3] = [1.0, 0.5, 0.0];
+const COLOR_NOT_IMMUNE: [f32; 3] = [0.75, 0.75, 0.75];
+const COLOR_IMMUNE_RECENT: [f32; 3] = [0.0, 0.2, 1.0];
+const COLOR_IMMUNE_OLD: [f32; 3] = [0.7, 0.8, 1.0];
+// the steps between the two, so that the people can be drawn in a few batches of a color
+const IMMUNITY_SHADES: f64 = 16.0;
+const COLOR_INTERVENTION: [f32; 3] = [0.4, 0.4, 0.4];
+const COLOR_LOCKDOWN: [f32; 3] = [0.3, 0.3, 0.6];
+const COLOR_TRANSMISSIBILITY: [f32; 3] = [0.9, 0.4, 0.0];
+const COLOR_TREND_GOOD: [f32; 3] = [0.0, 0.6, 0.0];
+const COLOR_TREND_BAD: [f32; 3] = [0.8, 0.0, 0.0];
 
 fn color(status: &Status) -> [f32; 3] {
+    code_color(snapshot::status_code(status))
+}
+
+fn code_color(code: u8) -> [f32; 3] {
+    match code {
+        snapshot::STATUS_INFECTED => COLOR_INFECTED,
+        snapshot::STATUS_VACCINATED_INFECTED => COLOR_VACCINATED_INFECTED,
+        snapshot::STATUS_HEALED => COLOR_HEALED,
+        snapshot::STATUS_VACCINATED => COLOR_VACCINATED,
+        snapshot::STATUS_DEAD => COLOR_DEAD,
+        _ => COLOR_HEALTHY,
+    }
+}
+
+// fading from the recent to the old color over `display_time` days since the last recovery or
+// dose
+fn immunity_color(status: &Status, time: f64, display_time: f64) -> [f32; 3] {
+    if status.dead().is_some() {
+        return COLOR_DEAD;
+    }
     if status.infected().is_some() {
-        if status.vaccinated() {
-            COLOR_VACCINATED_INFECTED
-        } else {
-            COLOR_INFECTED
-        }
-    } else {
-        if status.vaccinated() {
-            COLOR_VACCINATED
-        } else if status.past_infected() {
-            COLOR_HEALED
-        } else {
-            COLOR_HEALTHY
+        return COLOR_INFECTED;
+    }
+    let since = match status.immune_since() {
+        Some(since) => since,
+        None => return COLOR_NOT_IMMUNE,
+    };
+    let age = ((time - since) / display_time.max(1e-9)).min(1.0);
+    let age = ((age * IMMUNITY_SHADES).round() / IMMUNITY_SHADES) as f32;
+    let mut color = COLOR_IMMUNE_RECENT;
+    for (c, old) in color.iter_mut().zip(&COLOR_IMMUNE_OLD) {
+        *c += (old - *c) * age;
+    }
+    color
+}
+
+fn alpha(person: &Person, time: f64, corpse_display_time: f64) -> f32 {
+    match person.status().dead() {
+        Some(died) if corpse_display_time > 0.0 => {
+            (1.0 - (time - died) / corpse_display_time).clamp(0.0, 1.0) as f32
         }
+        _ => 1.0,
     }
 }
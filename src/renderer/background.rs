@@ -0,0 +1,119 @@
+use glium::{
+    implement_vertex, index,
+    texture::{RawImage2d, SrgbTexture2d},
+    uniform, Display, DrawParameters, Frame, Program, Surface, VertexBuffer,
+};
+use serde_derive::Deserialize;
+
+use super::matrix::Matrix;
+
+const VERTEX_SHADER_SRC: &str = r#"
+    #version 140
+
+    in vec2 position;
+    in vec2 tex_coords;
+
+    uniform mat4 matrix;
+    out vec2 v_tex_coords;
+
+    void main() {
+        gl_Position = matrix * vec4(position, 0.0, 1.0);
+        v_tex_coords = tex_coords;
+    }
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+    #version 140
+
+    in vec2 v_tex_coords;
+    uniform sampler2D tex;
+    out vec4 color;
+
+    void main() {
+        color = texture(tex, v_tex_coords);
+    }
+"#;
+
+#[derive(Debug, Clone, Copy)]
+struct TexturedVertex {
+    position: [f64; 2],
+    tex_coords: [f32; 2],
+}
+
+implement_vertex!(TexturedVertex, position, tex_coords);
+
+// The `[background]` table: an image and the world coordinates of its corners. It covers
+// the whole box by default.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackgroundConfig {
+    pub path: String,
+    #[serde(default)]
+    pub x: f64,
+    #[serde(default)]
+    pub y: f64,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+}
+
+pub struct Background {
+    texture: SrgbTexture2d,
+    program: Program,
+    vertex_buffer: VertexBuffer<TexturedVertex>,
+}
+
+impl Background {
+    pub fn load(display: &Display, config: &BackgroundConfig, box_size: (f64, f64)) -> Background {
+        let image = image::open(&config.path)
+            .unwrap_or_else(|err| panic!("couldn't load the background {}: {}", config.path, err))
+            .to_rgba8();
+        let dimensions = image.dimensions();
+        let image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+        let texture = SrgbTexture2d::new(display, image).unwrap();
+
+        let (x0, y0) = (config.x, config.y);
+        let x1 = x0 + config.width.unwrap_or(box_size.0);
+        let y1 = y0 + config.height.unwrap_or(box_size.1);
+        let quad = [
+            TexturedVertex {
+                position: [x0, y0],
+                tex_coords: [0.0, 0.0],
+            },
+            TexturedVertex {
+                position: [x1, y0],
+                tex_coords: [1.0, 0.0],
+            },
+            TexturedVertex {
+                position: [x1, y1],
+                tex_coords: [1.0, 1.0],
+            },
+            TexturedVertex {
+                position: [x0, y1],
+                tex_coords: [0.0, 1.0],
+            },
+        ];
+
+        Background {
+            texture,
+            program: Program::from_source(display, VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC, None)
+                .unwrap(),
+            vertex_buffer: VertexBuffer::new(display, &quad).unwrap(),
+        }
+    }
+
+    // `matrix` maps the world coordinates to the simulation viewport
+    pub fn draw(&self, target: &mut Frame, matrix: Matrix, draw_parameters: &DrawParameters) {
+        let uniforms = uniform! {
+            matrix: matrix.inner(),
+            tex: &self.texture,
+        };
+        target
+            .draw(
+                &self.vertex_buffer,
+                index::NoIndices(index::PrimitiveType::TriangleFan),
+                &self.program,
+                &uniforms,
+                draw_parameters,
+            )
+            .unwrap();
+    }
+}
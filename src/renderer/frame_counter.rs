@@ -0,0 +1,41 @@
+use std::time::{Duration, Instant};
+
+const SAMPLE_PERIOD: Duration = Duration::from_secs(1);
+
+// Frames drawn and simulation steps taken per second, averaged over the last full second.
+#[derive(Debug, Clone)]
+pub struct FrameCounter {
+    sample_start: Instant,
+    frames: u32,
+    steps: u64,
+    pub fps: f64,
+    pub steps_per_sec: f64,
+}
+
+impl FrameCounter {
+    pub fn new() -> FrameCounter {
+        FrameCounter {
+            sample_start: Instant::now(),
+            frames: 0,
+            steps: 0,
+            fps: 0.0,
+            steps_per_sec: 0.0,
+        }
+    }
+
+    // `steps` is the total number of steps the simulation has taken so far
+    pub fn frame(&mut self, steps: u64) {
+        self.frames += 1;
+        let elapsed = self.sample_start.elapsed();
+        if elapsed < SAMPLE_PERIOD {
+            return;
+        }
+        let secs = elapsed.as_secs_f64();
+        self.fps = self.frames as f64 / secs;
+        // the simulation is replaced when travelling in time, so the count can go back
+        self.steps_per_sec = steps.saturating_sub(self.steps) as f64 / secs;
+        self.sample_start = Instant::now();
+        self.frames = 0;
+        self.steps = steps;
+    }
+}
@@ -0,0 +1,122 @@
+use std::{collections::HashMap, fmt};
+
+use toml::Value;
+
+use crate::simulation::Language;
+
+const ENGLISH: &str = include_str!("../../locales/en.toml");
+const POLISH: &str = include_str!("../../locales/pl.toml");
+const GERMAN: &str = include_str!("../../locales/de.toml");
+// SI prefixes for the compact numbers, for every power of 1000
+const SI_PREFIXES: [&str; 4] = ["", "k", "M", "G"];
+
+fn parse(source: &str) -> HashMap<String, String> {
+    let table: Value = source.parse().unwrap();
+    table
+        .as_table()
+        .unwrap()
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+        .collect()
+}
+
+// UI strings; the ones missing in a translation fall back to English.
+pub struct Locale {
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Locale {
+        let source = match language {
+            Language::En => ENGLISH,
+            Language::Pl => POLISH,
+            Language::De => GERMAN,
+        };
+        Locale {
+            strings: parse(source),
+            fallback: parse(ENGLISH),
+        }
+    }
+
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    // replaces the `{}` placeholders with `args`, in order
+    pub fn fill(&self, key: &str, args: &[&dyn fmt::Display]) -> String {
+        let mut parts = self.get(key).split("{}");
+        let mut result = parts.next().unwrap_or("").to_owned();
+        for (i, part) in parts.enumerate() {
+            if let Some(arg) = args.get(i) {
+                result += &arg.to_string();
+            }
+            result += part;
+        }
+        result
+    }
+
+    // eg. 12,345 in English
+    pub fn integer(&self, value: u64) -> String {
+        group_digits(&value.to_string(), self.get("thousands_separator"))
+    }
+
+    pub fn decimal(&self, value: f64, precision: usize) -> String {
+        let text = format!("{:.*}", precision, value.abs());
+        let (integer, fraction) = match text.find('.') {
+            Some(index) => (&text[..index], &text[index + 1..]),
+            None => (text.as_str(), ""),
+        };
+        let mut result = if value < 0.0 && text.chars().any(|c| c != '0' && c != '.') {
+            "-".to_owned()
+        } else {
+            String::new()
+        };
+        result += &group_digits(integer, self.get("thousands_separator"));
+        if !fraction.is_empty() {
+            result += self.get("decimal_separator");
+            result += fraction;
+        }
+        result
+    }
+
+    // eg. 1.2k or 45M, with up to 3 significant digits
+    pub fn compact(&self, value: f64) -> String {
+        let mut scaled = value;
+        let mut prefix = 0;
+        while scaled.abs() >= 999.5 && prefix < SI_PREFIXES.len() - 1 {
+            scaled /= 1000.0;
+            prefix += 1;
+        }
+        let precision = if prefix == 0 || scaled.abs() >= 99.95 {
+            0
+        } else if scaled.abs() >= 9.995 {
+            1
+        } else {
+            2
+        };
+        let text = self.decimal(scaled, precision);
+        let separator = self.get("decimal_separator");
+        // 1.50k reads better as 1.5k
+        let text = if precision > 0 {
+            text.trim_end_matches('0').trim_end_matches(separator)
+        } else {
+            &text
+        };
+        format!("{}{}", text, SI_PREFIXES[prefix])
+    }
+}
+
+// `digits` without a sign, split into groups of three
+fn group_digits(digits: &str, separator: &str) -> String {
+    let groups: Vec<_> = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|group| std::str::from_utf8(group).unwrap())
+        .collect();
+    groups.join(separator)
+}
@@ -71,11 +71,9 @@ impl ops::Mul<Matrix> for Matrix {
 
     fn mul(self, other: Matrix) -> Matrix {
         let mut new_coords = [[0.0; 4]; 4];
-        for i in 0..4 {
-            for j in 0..4 {
-                for k in 0..4 {
-                    new_coords[i][j] += self.coords[i][k] * other.coords[k][j];
-                }
+        for (i, row) in new_coords.iter_mut().enumerate() {
+            for (j, coord) in row.iter_mut().enumerate() {
+                *coord = (0..4).map(|k| self.coords[i][k] * other.coords[k][j]).sum();
             }
         }
         Matrix { coords: new_coords }
@@ -0,0 +1,145 @@
+use std::{fs, path::PathBuf};
+
+use glium::{glutin::event::VirtualKeyCode, Display, Surface};
+
+use super::{locale::Locale, matrix::Matrix, text::TextRenderer};
+
+use crate::{config, simulation::Params};
+
+struct Scenario {
+    path: String,
+    name: String,
+    params: Params,
+}
+
+// Start screen listing the config files in a directory.
+pub struct ScenarioMenu {
+    scenarios: Vec<Scenario>,
+    selected: usize,
+    text: TextRenderer,
+    locale: Locale,
+}
+
+impl ScenarioMenu {
+    // The default config is listed first. Returns None if there are no scenarios in `dir`
+    // to choose from.
+    pub fn find(
+        display: &Display,
+        default_path: &str,
+        dir: &str,
+        preset: Option<&str>,
+    ) -> Option<ScenarioMenu> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        if paths.is_empty() {
+            return None;
+        }
+        paths.sort();
+        paths.insert(0, default_path.into());
+
+        let scenarios: Vec<Scenario> = paths
+            .into_iter()
+            .map(|path| {
+                let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+                let path = path.to_string_lossy().into_owned();
+                let params = config::load_params(&path, preset);
+                Scenario { path, name, params }
+            })
+            .collect();
+
+        // the menu is shown in the language of the default config
+        let locale = Locale::new(scenarios[0].params.language);
+        Some(ScenarioMenu {
+            scenarios,
+            selected: 0,
+            text: TextRenderer::new(display, "DejaVuSans.ttf"),
+            locale,
+        })
+    }
+
+    // returns the path of the chosen config file once the user confirms the selection
+    pub fn handle_key(&mut self, key: VirtualKeyCode) -> Option<String> {
+        match key {
+            VirtualKeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            VirtualKeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.scenarios.len() - 1);
+            }
+            VirtualKeyCode::Return => {
+                return Some(self.scenarios[self.selected].path.clone());
+            }
+            _ => (),
+        }
+        None
+    }
+
+    fn draw_text<S: Surface>(&self, target: &mut S, text: &str, x: f32, y: f32, aspect: f32) {
+        let matrix = Matrix::scale(0.04, 0.04 * aspect) * Matrix::translation(x, y);
+        self.text.draw(
+            target,
+            text,
+            matrix.inner(),
+            [0.0, 0.0, 0.0, 1.0],
+            Default::default(),
+        );
+    }
+
+    pub fn draw(&self, display: &Display) {
+        let mut target = display.draw();
+        target.clear_color(1.0, 1.0, 1.0, 1.0);
+
+        let (width, height) = target.get_dimensions();
+        let aspect = width as f32 / height as f32;
+
+        self.draw_text(
+            &mut target,
+            self.locale.get("menu_title"),
+            -0.9,
+            0.85,
+            aspect,
+        );
+        for (i, scenario) in self.scenarios.iter().enumerate() {
+            let marker = if i == self.selected { ">" } else { " " };
+            self.draw_text(
+                &mut target,
+                &format!("{} {}", marker, scenario.name),
+                -0.9,
+                0.7 - 0.065 * aspect * i as f32,
+                aspect,
+            );
+        }
+
+        let params = self.scenarios[self.selected].params;
+        let locale = &self.locale;
+        let preview = [
+            locale.fill("menu_people", &[&params.num_people]),
+            locale.fill("menu_area", &[&params.size_x, &params.size_y]),
+            locale.fill(
+                "menu_initial",
+                &[&params.init_infected, &params.init_vaccinated],
+            ),
+            locale.fill(
+                "menu_infection_prob",
+                &[&params.infection_prob_infected_to_general],
+            ),
+            locale.fill("menu_duration", &[&params.infection_avg_duration]),
+            locale.fill("menu_death_rate", &[&params.death_rate]),
+        ];
+        for (i, line) in preview.iter().enumerate() {
+            self.draw_text(
+                &mut target,
+                line,
+                0.0,
+                0.7 - 0.065 * aspect * i as f32,
+                aspect,
+            );
+        }
+
+        target.finish().unwrap();
+    }
+}
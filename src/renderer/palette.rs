@@ -0,0 +1,37 @@
+// Colors for the attributes the people can be colored by, other than the status.
+
+// for the people whose value isn't known, eg. without an age in the census
+pub const UNKNOWN: [f32; 3] = [0.75, 0.75, 0.75];
+
+// easy to tell apart, for values without an order of their own like the communities
+const CATEGORICAL: [[f32; 3]; 8] = [
+    [0.12, 0.47, 0.71],
+    [1.0, 0.5, 0.05],
+    [0.17, 0.63, 0.17],
+    [0.84, 0.15, 0.16],
+    [0.58, 0.4, 0.74],
+    [0.55, 0.34, 0.29],
+    [0.89, 0.47, 0.76],
+    [0.74, 0.74, 0.13],
+];
+
+// the colors repeat after the first eight
+pub fn categorical(index: usize) -> [f32; 3] {
+    CATEGORICAL[index % CATEGORICAL.len()]
+}
+
+// from light to dark blue, for the `value`-th of `count` ordered values like the dose counts
+pub fn sequential(value: usize, count: usize) -> [f32; 3] {
+    let light = [0.78, 0.86, 0.94];
+    let dark = [0.03, 0.19, 0.42];
+    let t = if count > 1 {
+        value.min(count - 1) as f32 / (count - 1) as f32
+    } else {
+        1.0
+    };
+    let mut color = light;
+    for (c, dark) in color.iter_mut().zip(&dark) {
+        *c += (dark - *c) * t;
+    }
+    color
+}
@@ -1,19 +1,83 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    mem,
+};
+
 use glium::{index, uniform, Display, DrawParameters, Frame, IndexBuffer, Surface, VertexBuffer};
 
 use super::{
-    matrix::Matrix, Renderer, Vertex, COLOR_DEAD, COLOR_HEALED, COLOR_HEALTHY, COLOR_INFECTED,
-    COLOR_VACCINATED, COLOR_VACCINATED_INFECTED,
+    matrix::Matrix, Renderer, Vertex, COLOR_BRANCH, COLOR_DEAD, COLOR_HEALED, COLOR_HEALTHY,
+    COLOR_HOSPITAL_LOAD, COLOR_INFECTED, COLOR_INTERVENTION, COLOR_ISOLATED, COLOR_LOCKDOWN,
+    COLOR_REPORTED, COLOR_TRANSMISSIBILITY, COLOR_VACCINATED, COLOR_VACCINATED_INFECTED,
+    COLOR_WEATHER,
 };
 
-use crate::simulation::Statistics;
+use crate::simulation::{
+    intervention::{InForce, Intervention},
+    reporting::Reported,
+    Params, Statistics,
+};
+
+// a peak of the infected curve is the highest point within this many days on both sides
+const PEAK_WINDOW: f64 = 7.0;
+// smaller peaks are noise, as a fraction of the population
+const PEAK_MIN_FRACTION: f64 = 0.01;
+// the lanes of the interventions go below the labels of the time axis
+const LANES_TOP: f64 = -0.86;
+const LANE_HEIGHT: f64 = 0.04;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lane {
+    Quarantine,
+    Lockdown,
+    // changed from the configured value
+    Transmissibility,
+}
+
+const LANES: [Lane; 3] = [Lane::Quarantine, Lane::Lockdown, Lane::Transmissibility];
+
+impl Lane {
+    fn color(self) -> [f32; 3] {
+        match self {
+            Lane::Quarantine => COLOR_ISOLATED,
+            Lane::Lockdown => COLOR_LOCKDOWN,
+            Lane::Transmissibility => COLOR_TRANSMISSIBILITY,
+        }
+    }
+}
 
 pub struct StatsBuf {
     data: Vec<(f64, Statistics)>,
+    comparison: Vec<(f64, Statistics)>,
+    branch: Vec<(f64, Statistics)>,
+    weather: Vec<(f64, f64)>,
+    reported: Vec<(f64, Reported)>,
+    interventions: Vec<(f64, Intervention)>,
+    in_force: Vec<(f64, InForce)>,
+    hospital_load: bool,
 }
 
 impl StatsBuf {
     pub fn new() -> StatsBuf {
-        StatsBuf { data: vec![] }
+        StatsBuf {
+            data: vec![],
+            comparison: vec![],
+            branch: vec![],
+            weather: vec![],
+            reported: vec![],
+            interventions: vec![],
+            in_force: vec![],
+            hospital_load: true,
+        }
+    }
+
+    // the hospital capacity only applies to the whole population
+    pub fn without_hospital_load() -> StatsBuf {
+        StatsBuf {
+            hospital_load: false,
+            ..StatsBuf::new()
+        }
     }
 
     pub fn min_t(&self) -> f64 {
@@ -21,17 +85,125 @@ impl StatsBuf {
     }
 
     pub fn max_t(&self) -> f64 {
-        self.data.last().map_or(1.0, |(t, _)| *t)
+        let max_t = self.data.last().map_or(1.0, |(t, _)| *t);
+        self.comparison.last().map_or(max_t, |(t, _)| max_t.max(*t))
+    }
+
+    pub fn update(&mut self, history: &[(f64, Statistics)]) {
+        follow(&mut self.data, history);
+    }
+
+    pub fn data(&self) -> &[(f64, Statistics)] {
+        &self.data
     }
 
-    pub fn record(&mut self, t: f64, stats: Statistics) {
-        self.data.push((t, stats));
+    pub fn set_comparison(&mut self, comparison: Vec<(f64, Statistics)>) {
+        self.comparison = comparison;
+    }
+
+    pub fn update_weather(&mut self, weather: &[(f64, f64)]) {
+        follow(&mut self.weather, weather);
+    }
+
+    pub fn update_reported(&mut self, reported: &[(f64, Reported)]) {
+        follow(&mut self.reported, reported);
+    }
+
+    pub fn set_interventions(&mut self, interventions: &[(f64, Intervention)]) {
+        self.interventions.clear();
+        self.interventions.extend_from_slice(interventions);
+    }
+
+    pub fn update_in_force(&mut self, in_force: &[(f64, InForce)]) {
+        follow(&mut self.in_force, in_force);
+    }
+
+    pub fn set_branch(&mut self, branch: &[(f64, Statistics)]) {
+        self.branch.clear();
+        self.branch.extend_from_slice(branch);
+    }
+
+    pub fn approx_size(&self) -> usize {
+        mem::size_of_val(&self.data[..])
+            + mem::size_of_val(&self.comparison[..])
+            + mem::size_of_val(&self.branch[..])
+            + mem::size_of_val(&self.weather[..])
+            + mem::size_of_val(&self.reported[..])
+            + mem::size_of_val(&self.interventions[..])
+            + mem::size_of_val(&self.in_force[..])
+    }
+
+    // The series as drawn, tab-separated for pasting into a spreadsheet, after the parameters
+    // as TOML comments.
+    pub fn write_tsv(&self, path: &str, params: Params) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        // through a Value, which puts the tables after the plain values
+        let settings = toml::Value::try_from(params)
+            .and_then(|settings| toml::to_string(&settings))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        for line in settings.lines() {
+            writeln!(file, "# {}", line)?;
+        }
+
+        // the weather is sampled along with the stats
+        let weather = self.weather.len() == self.data.len() && !self.weather.is_empty();
+        let reported = self.reported.len() == self.data.len() && !self.reported.is_empty();
+        write!(
+            file,
+            "t\tpopulation\tdead\tinfected\thealed\tvaccinated\tvaccinated_infected"
+        )?;
+        if self.hospital_load {
+            write!(file, "\thospital_load")?;
+        }
+        if weather {
+            write!(file, "\tweather")?;
+        }
+        if reported {
+            write!(
+                file,
+                "\ttotal_cases\treported_cases\ttotal_deaths\treported_deaths"
+            )?;
+        }
+        writeln!(file)?;
+        for (i, (t, stats)) in self.data.iter().enumerate() {
+            write!(
+                file,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                t,
+                stats.population,
+                stats.dead,
+                stats.infected,
+                stats.healed,
+                stats.vaccinated,
+                stats.vaccinated_infected
+            )?;
+            if self.hospital_load {
+                write!(file, "\t{}", stats.hospital_load(params))?;
+            }
+            if weather {
+                write!(file, "\t{}", self.weather[i].1)?;
+            }
+            if reported {
+                let counts = self.reported[i].1;
+                write!(
+                    file,
+                    "\t{}\t{}\t{}\t{}",
+                    counts.true_cases, counts.cases, counts.true_deaths, counts.deaths
+                )?;
+            }
+            writeln!(file)?;
+        }
+        file.flush()
     }
 
     fn data_to_vertex(&self, t: f64, num: usize, max_num: usize) -> Vertex {
+        self.value_to_vertex(t, num as f64, max_num)
+    }
+
+    fn value_to_vertex(&self, t: f64, value: f64, max_num: usize) -> Vertex {
         let (min_t, max_t) = (self.min_t(), self.max_t());
         let x = (t - min_t) / (max_t - min_t) * 1.8 - 0.8;
-        let y = (num as f64) / (max_num as f64) * 1.7 - 0.7;
+        let y = (value / max_num as f64).min(1.0) * 1.7 - 0.7;
         Vertex { position: [x, y] }
     }
 
@@ -41,6 +213,7 @@ impl StatsBuf {
         target: &mut Frame,
         renderer: &Renderer,
         draw_parameters: &DrawParameters,
+        params: Params,
     ) {
         let aspect = draw_parameters
             .viewport
@@ -85,6 +258,7 @@ impl StatsBuf {
             let uniforms = uniform! {
                 matrix: matrix.inner(),
                 color: colors[i],
+                alpha: 1.0f32,
             };
 
             target
@@ -93,24 +267,55 @@ impl StatsBuf {
                     &index_buffer,
                     &renderer.program,
                     &uniforms,
-                    &draw_parameters,
+                    draw_parameters,
                 )
                 .unwrap();
         }
 
+        // a loaded run is dashed, a branch running alongside is solid
+        self.draw_overlay(
+            display,
+            target,
+            renderer,
+            draw_parameters,
+            (&self.comparison, index::PrimitiveType::LinesList),
+            [0.0, 0.0, 0.0],
+        );
+        self.draw_overlay(
+            display,
+            target,
+            renderer,
+            draw_parameters,
+            (&self.branch, index::PrimitiveType::LineStrip),
+            COLOR_BRANCH,
+        );
+
+        if self.hospital_load {
+            self.draw_hospital_load(display, target, renderer, draw_parameters, params);
+        }
+
+        self.draw_weather(display, target, renderer, draw_parameters, aspect);
+        self.draw_reported(display, target, renderer, draw_parameters, aspect);
+        if params.deaths_axis {
+            self.draw_deaths(display, target, renderer, draw_parameters, aspect);
+        }
+        self.draw_annotations(display, target, renderer, draw_parameters, aspect);
+        self.draw_lanes(display, target, renderer, draw_parameters);
+
         // draw axes
         let uniforms = uniform! {
             matrix: matrix.inner(),
             color: [0.0f32, 0.0, 0.0],
+            alpha: 1.0f32,
         };
 
         target
             .draw(
                 &vertex_buffer_axes,
-                &index_buffer_axes,
+                index_buffer_axes,
                 &renderer.program,
                 &uniforms,
-                &draw_parameters,
+                draw_parameters,
             )
             .unwrap();
 
@@ -125,11 +330,10 @@ impl StatsBuf {
             draw_parameters.clone(),
         );
 
-        let text = format!(
-            "{}",
+        let text = renderer.locale.compact(
             self.data
                 .first()
-                .map_or(1, |(_, stats)| stats.population + stats.dead),
+                .map_or(1, |(_, stats)| stats.population + stats.dead) as f64,
         );
         renderer.draw_text(
             target,
@@ -149,6 +353,537 @@ impl StatsBuf {
         );
     }
 
+    // With `LinesList`, every other segment is drawn, making a dashed line.
+    fn draw_overlay(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        renderer: &Renderer,
+        draw_parameters: &DrawParameters,
+        (series, primitive): (&[(f64, Statistics)], index::PrimitiveType),
+        color: [f32; 3],
+    ) {
+        if series.len() < 2 {
+            return;
+        }
+
+        // infected are measured from the bottom of the graph, the dead from the top - the same
+        // way as the corresponding strips of the live graph
+        let vertex = |(t, stats): &(f64, Statistics)| {
+            let total = stats.population + stats.dead;
+            (
+                self.data_to_vertex(*t, stats.infected, total),
+                self.data_to_vertex(*t, total - stats.dead, total),
+            )
+        };
+        let (infected, dead): (Vec<Vertex>, Vec<Vertex>) = match primitive {
+            index::PrimitiveType::LinesList => {
+                series.windows(2).step_by(2).flatten().map(vertex).unzip()
+            }
+            _ => series.iter().map(vertex).unzip(),
+        };
+
+        let indices = index::NoIndices(primitive);
+        let draw_parameters = DrawParameters {
+            line_width: Some(2.0),
+            ..draw_parameters.clone()
+        };
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: color,
+            alpha: 1.0f32,
+        };
+
+        for vertices in &[infected, dead] {
+            let vertex_buffer = VertexBuffer::new(display, vertices).unwrap();
+            target
+                .draw(
+                    &vertex_buffer,
+                    indices,
+                    &renderer.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+    }
+
+    fn draw_hospital_load(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        renderer: &Renderer,
+        draw_parameters: &DrawParameters,
+        params: Params,
+    ) {
+        if self.data.len() < 2 {
+            return;
+        }
+
+        let load: Vec<Vertex> = self
+            .data
+            .iter()
+            .map(|(t, stats)| {
+                let total = stats.population + stats.dead;
+                self.value_to_vertex(*t, stats.hospital_load(params), total)
+            })
+            .collect();
+
+        // capacity is marked with a dashed horizontal line
+        let total = self
+            .data
+            .first()
+            .map_or(1, |(_, stats)| stats.population + stats.dead);
+        let (min_t, max_t) = (self.min_t(), self.max_t());
+        let capacity: Vec<Vertex> = (0..=40)
+            .map(|i| {
+                let t = min_t + (max_t - min_t) * i as f64 / 40.0;
+                self.value_to_vertex(t, params.hospital_capacity, total)
+            })
+            .collect();
+
+        let draw_parameters = DrawParameters {
+            line_width: Some(2.0),
+            ..draw_parameters.clone()
+        };
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: COLOR_HOSPITAL_LOAD,
+            alpha: 1.0f32,
+        };
+
+        for (vertices, primitive) in &[
+            (load, index::PrimitiveType::LineStrip),
+            (capacity, index::PrimitiveType::LinesList),
+        ] {
+            let vertex_buffer = VertexBuffer::new(display, vertices).unwrap();
+            target
+                .draw(
+                    &vertex_buffer,
+                    index::NoIndices(*primitive),
+                    &renderer.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+    }
+
+    // The cumulative cases from the bottom and the deaths from the top, the true ones solid and
+    // the reported ones dashed.
+    fn draw_reported(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        renderer: &Renderer,
+        draw_parameters: &DrawParameters,
+        aspect: f32,
+    ) {
+        if self.reported.len() < 2 {
+            return;
+        }
+        let total = self
+            .data
+            .first()
+            .map_or(1, |(_, stats)| stats.population + stats.dead);
+        let line = |count: &dyn Fn(&Reported) -> usize, from_top: bool| -> Vec<Vertex> {
+            self.reported
+                .iter()
+                .map(|(t, counts)| {
+                    let count = count(counts).min(total);
+                    let num = if from_top { total - count } else { count };
+                    self.data_to_vertex(*t, num, total)
+                })
+                .collect()
+        };
+        let dashed = |line: Vec<Vertex>| -> Vec<Vertex> {
+            line.windows(2).step_by(2).flatten().copied().collect()
+        };
+        let draw_parameters = DrawParameters {
+            line_width: Some(2.0),
+            ..draw_parameters.clone()
+        };
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: COLOR_REPORTED,
+            alpha: 1.0f32,
+        };
+        let lines = [
+            (
+                line(&|counts| counts.true_cases, false),
+                index::PrimitiveType::LineStrip,
+            ),
+            (
+                dashed(line(&|counts| counts.cases, false)),
+                index::PrimitiveType::LinesList,
+            ),
+            (
+                line(&|counts| counts.true_deaths, true),
+                index::PrimitiveType::LineStrip,
+            ),
+            (
+                dashed(line(&|counts| counts.deaths, true)),
+                index::PrimitiveType::LinesList,
+            ),
+        ];
+        for (vertices, primitive) in &lines {
+            let vertex_buffer = VertexBuffer::new(display, vertices).unwrap();
+            target
+                .draw(
+                    &vertex_buffer,
+                    index::NoIndices(*primitive),
+                    &renderer.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+
+        // inside the graph, next to the top of the population axis
+        let text_scale = Matrix::scale(0.03, 0.03 * aspect);
+        let digit_height = 0.03 * aspect;
+        renderer.draw_text(
+            target,
+            renderer.locale.get("reported_legend"),
+            text_scale * Matrix::translation(-0.78, 0.99 - digit_height),
+            draw_parameters.clone(),
+        );
+    }
+
+    // on an axis of its own along the right edge, from fine weather at the bottom to bad at
+    // the top
+    fn draw_weather(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        renderer: &Renderer,
+        draw_parameters: &DrawParameters,
+        aspect: f32,
+    ) {
+        if self.weather.len() < 2 {
+            return;
+        }
+        let line: Vec<Vertex> = self
+            .weather
+            .iter()
+            .map(|&(t, badness)| self.value_to_vertex(t, badness, 1))
+            .collect();
+        let axis = vec![
+            Vertex {
+                position: [1.0, -0.7],
+            },
+            Vertex {
+                position: [1.0, 1.0],
+            },
+        ];
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: COLOR_WEATHER,
+            alpha: 1.0f32,
+        };
+        for vertices in &[line, axis] {
+            let vertex_buffer = VertexBuffer::new(display, vertices).unwrap();
+            target
+                .draw(
+                    &vertex_buffer,
+                    index::NoIndices(index::PrimitiveType::LineStrip),
+                    &renderer.program,
+                    &uniforms,
+                    draw_parameters,
+                )
+                .unwrap();
+        }
+
+        let text_scale = Matrix::scale(0.03, 0.03 * aspect);
+        let label = renderer.locale.get("weather");
+        let digit_width = 0.025;
+        let digit_height = 0.03 * aspect;
+        renderer.draw_text(
+            target,
+            label,
+            text_scale
+                * Matrix::translation(
+                    0.98 - label.chars().count() as f32 * digit_width,
+                    0.99 - digit_height,
+                ),
+            draw_parameters.clone(),
+        );
+    }
+
+    // A line against its own scale, up to a round number above the most deaths, as they would be
+    // hardly visible on the scale of the population.
+    fn draw_deaths(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        renderer: &Renderer,
+        draw_parameters: &DrawParameters,
+        aspect: f32,
+    ) {
+        if self.data.len() < 2 {
+            return;
+        }
+        let most = self
+            .data
+            .iter()
+            .map(|(_, stats)| stats.dead)
+            .max()
+            .unwrap_or(0);
+        let scale = round_up(most.max(1));
+
+        let line: Vec<Vertex> = self
+            .data
+            .iter()
+            .map(|(t, stats)| self.data_to_vertex(*t, stats.dead, scale))
+            .collect();
+        let axis = vec![
+            Vertex {
+                position: [1.0, -0.7],
+            },
+            Vertex {
+                position: [1.0, 1.0],
+            },
+        ];
+        let draw_parameters = DrawParameters {
+            line_width: Some(2.0),
+            ..draw_parameters.clone()
+        };
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: COLOR_DEAD,
+            alpha: 1.0f32,
+        };
+        for vertices in &[line, axis] {
+            let vertex_buffer = VertexBuffer::new(display, vertices).unwrap();
+            target
+                .draw(
+                    &vertex_buffer,
+                    index::NoIndices(index::PrimitiveType::LineStrip),
+                    &renderer.program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+
+        // below the weather label
+        let text_scale = Matrix::scale(0.03, 0.03 * aspect);
+        let digit_width = 0.025;
+        let digit_height = 0.03 * aspect;
+        let labels = [
+            (
+                renderer
+                    .locale
+                    .fill("deaths_axis", &[&renderer.locale.integer(scale as u64)]),
+                0.99 - 2.0 * digit_height,
+            ),
+            ("0".to_owned(), -0.69),
+        ];
+        for (label, y) in &labels {
+            renderer.draw_text(
+                target,
+                label,
+                text_scale
+                    * Matrix::translation(0.98 - label.chars().count() as f32 * digit_width, *y),
+                draw_parameters.clone(),
+            );
+        }
+    }
+
+    // the indices of the samples at the peaks of the infected curve, once the PEAK_WINDOW after
+    // them has passed
+    fn peaks(&self) -> Vec<usize> {
+        let max_t = self.data.last().map_or(0.0, |(t, _)| *t);
+        let mut peaks = vec![];
+        let mut start = 0;
+        for (i, (t, stats)) in self.data.iter().enumerate() {
+            if t + PEAK_WINDOW > max_t {
+                break;
+            }
+            while self.data[start].0 < t - PEAK_WINDOW {
+                start += 1;
+            }
+            let total = stats.population + stats.dead;
+            if (stats.infected as f64) < PEAK_MIN_FRACTION * total as f64 {
+                continue;
+            }
+            // the first of equal samples is the peak
+            let highest = self.data[start..i]
+                .iter()
+                .all(|(_, other)| other.infected < stats.infected)
+                && self.data[i + 1..]
+                    .iter()
+                    .take_while(|(other_t, _)| *other_t <= t + PEAK_WINDOW)
+                    .all(|(_, other)| other.infected <= stats.infected);
+            if highest {
+                peaks.push(i);
+            }
+        }
+        peaks
+    }
+
+    // when each of the lanes was turned on or off, in order, as sampled with the stats
+    fn changes(&self) -> Vec<(f64, Lane, bool)> {
+        let mut active = [false; 3];
+        let mut changes = vec![];
+        for &(t, in_force) in &self.in_force {
+            let now = [
+                in_force.restrictions.quarantine,
+                in_force.restrictions.lockdown,
+                in_force.transmissibility != 1.0,
+            ];
+            for lane in LANES {
+                if active[lane as usize] != now[lane as usize] {
+                    active[lane as usize] = now[lane as usize];
+                    changes.push((t, lane, now[lane as usize]));
+                }
+            }
+        }
+        changes
+    }
+
+    fn vaccinations(&self) -> impl Iterator<Item = f64> + '_ {
+        self.interventions
+            .iter()
+            .filter(|(_, intervention)| matches!(intervention, Intervention::VaccinateFraction(_)))
+            .map(|(t, _)| *t)
+    }
+
+    // A row of bars under the time axis for each of the lanes, while they are active, with the
+    // vaccinations marked across all of them.
+    fn draw_lanes(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        renderer: &Renderer,
+        draw_parameters: &DrawParameters,
+    ) {
+        let x = |t: f64| self.value_to_vertex(t, 0.0, 1).position[0];
+        let row = |lane: Lane| LANES_TOP - LANE_HEIGHT * (lane as usize + 1) as f64;
+        let mut starts = [None; 3];
+        for (t, lane, on) in self.changes() {
+            if on {
+                starts[lane as usize] = Some(t);
+            } else if let Some(start) = starts[lane as usize].take() {
+                let y = row(lane);
+                let bar = (x(start), y, x(t), y + LANE_HEIGHT * 0.8);
+                renderer.draw_quad(display, target, bar, lane.color(), draw_parameters);
+            }
+        }
+        for (i, start) in starts.iter().enumerate() {
+            if let Some(start) = start {
+                let lane = LANES[i];
+                let y = row(lane);
+                let bar = (x(*start), y, x(self.max_t()), y + LANE_HEIGHT * 0.8);
+                renderer.draw_quad(display, target, bar, lane.color(), draw_parameters);
+            }
+        }
+        for t in self.vaccinations() {
+            let tick = (
+                x(t) - 0.002,
+                row(Lane::Transmissibility),
+                x(t) + 0.002,
+                LANES_TOP,
+            );
+            renderer.draw_quad(display, target, tick, COLOR_VACCINATED, draw_parameters);
+        }
+    }
+
+    // the peaks of the infected curve, and the restrictions and vaccinations as vertical lines
+    fn draw_annotations(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        renderer: &Renderer,
+        draw_parameters: &DrawParameters,
+        aspect: f32,
+    ) {
+        let text_scale = Matrix::scale(0.03, 0.03 * aspect);
+        let digit_width = 0.025;
+        let digit_height = 0.03 * aspect;
+
+        for i in self.peaks() {
+            let (t, stats) = &self.data[i];
+            let total = stats.population + stats.dead;
+            let [x, y] = self.data_to_vertex(*t, stats.infected, total).position;
+            let label = renderer.locale.fill(
+                "peak",
+                &[
+                    &renderer.locale.decimal(*t, 0),
+                    &renderer.locale.integer(stats.infected as u64),
+                ],
+            );
+            let x = (x as f32 - 0.5 * label.chars().count() as f32 * digit_width).max(-0.8);
+            renderer.draw_text(
+                target,
+                &label,
+                text_scale * Matrix::translation(x, (y as f32 + 0.01).min(0.99 - digit_height)),
+                draw_parameters.clone(),
+            );
+        }
+
+        let mut marks: Vec<_> = self
+            .changes()
+            .into_iter()
+            .filter_map(|(t, lane, on)| {
+                let key = match (lane, on) {
+                    (Lane::Quarantine, true) => "mark_quarantine_start",
+                    (Lane::Quarantine, false) => "mark_quarantine_end",
+                    (Lane::Lockdown, true) => "mark_lockdown_start",
+                    (Lane::Lockdown, false) => "mark_lockdown_end",
+                    (Lane::Transmissibility, _) => return None,
+                };
+                Some((t, renderer.locale.get(key)))
+            })
+            .collect();
+        marks.extend(
+            self.vaccinations()
+                .map(|t| (t, renderer.locale.get("mark_vaccination"))),
+        );
+        marks.sort_by(|(t1, _), (t2, _)| t1.partial_cmp(t2).unwrap());
+        if marks.is_empty() {
+            return;
+        }
+
+        // dashed, from the bottom of the graph to the top
+        let lines: Vec<Vertex> = marks
+            .iter()
+            .flat_map(|&(t, _)| {
+                let x = self.value_to_vertex(t, 0.0, 1).position[0];
+                (0..20).map(move |i| Vertex {
+                    position: [x, -0.7 + 1.7 * i as f64 / 19.0],
+                })
+            })
+            .collect();
+        let uniforms = uniform! {
+            matrix: Matrix::identity().inner(),
+            color: COLOR_INTERVENTION,
+            alpha: 1.0f32,
+        };
+        let vertex_buffer = VertexBuffer::new(display, &lines).unwrap();
+        target
+            .draw(
+                &vertex_buffer,
+                index::NoIndices(index::PrimitiveType::LinesList),
+                &renderer.program,
+                &uniforms,
+                draw_parameters,
+            )
+            .unwrap();
+
+        // the labels are staggered, so that the ones close together don't overlap
+        for (i, (t, label)) in marks.iter().enumerate() {
+            let x = self.value_to_vertex(*t, 0.0, 1).position[0] as f32;
+            let y = 0.99 - digit_height * (1.0 + (i % 3) as f32);
+            renderer.draw_text(
+                target,
+                label,
+                text_scale * Matrix::translation(x + 0.01, y),
+                draw_parameters.clone(),
+            );
+        }
+    }
+
     fn draw_time_ticks(
         &self,
         target: &mut Frame,
@@ -180,11 +915,8 @@ impl StatsBuf {
         };
 
         let mut print_t = |t: f32| {
-            let text = if step < 1.0 {
-                format!("{:.1}", t)
-            } else {
-                format!("{:.0}", t)
-            };
+            let precision = if step < 1.0 { 1 } else { 0 };
+            let text = renderer.locale.decimal(t as f64, precision);
             let x = self.data_to_vertex(t as f64, 0, 1).position[0] as f32;
             renderer.draw_text(
                 target,
@@ -247,12 +979,38 @@ impl StatsBuf {
         let mut indices = [vec![], vec![], vec![], vec![], vec![], vec![]];
 
         for i in 0..self.data.len() as u16 {
-            for j in 0..6 {
-                indices[j].push(7 * i + j as u16);
-                indices[j].push(7 * i + j as u16 + 1);
+            for (j, indices) in indices.iter_mut().enumerate() {
+                indices.push(7 * i + j as u16);
+                indices.push(7 * i + j as u16 + 1);
             }
         }
 
         indices
     }
 }
+
+// the smallest of 1, 2, 5, 10, 20, 50... that is at least `value`
+// The series mostly only grow, so only the new samples need to be copied. Everything is
+// copied again after the simulation has been rewound or the older samples evicted, which is
+// noticed by the last sample kept not matching any more.
+fn follow<T: Clone>(copy: &mut Vec<(f64, T)>, series: &[(f64, T)]) {
+    copy.truncate(series.len());
+    if let (Some(first), Some(last)) = (copy.first(), copy.last()) {
+        if first.0 != series[0].0 || last.0 != series[copy.len() - 1].0 {
+            copy.clear();
+        }
+    }
+    copy.extend_from_slice(&series[copy.len()..]);
+}
+
+fn round_up(value: usize) -> usize {
+    let mut step = 1;
+    loop {
+        for &multiple in &[1, 2, 5] {
+            if step * multiple >= value {
+                return step * multiple;
+            }
+        }
+        step *= 10;
+    }
+}
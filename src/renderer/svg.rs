@@ -0,0 +1,146 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use super::{
+    COLOR_DEAD, COLOR_HEALED, COLOR_HEALTHY, COLOR_INFECTED, COLOR_VACCINATED,
+    COLOR_VACCINATED_INFECTED,
+};
+
+use crate::simulation::Statistics;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 400.0;
+// room for the labels of the axes
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_BOTTOM: f64 = 30.0;
+const MARGIN: f64 = 10.0;
+// how long the chart takes to grow to the end of the run
+const ANIMATION_SECONDS: f64 = 10.0;
+
+fn hex(color: [f32; 3]) -> String {
+    let channel = |value: f32| (value * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2])
+    )
+}
+
+// The boundaries between the strips, from the bottom, as in the graph of the window.
+fn boundaries(stats: &Statistics) -> [usize; 7] {
+    let total = stats.population + stats.dead;
+    let vaccinated = stats.vaccinated - stats.vaccinated_infected;
+    let infected = stats.vaccinated + stats.infected - stats.vaccinated_infected;
+    [
+        0,
+        vaccinated,
+        stats.vaccinated,
+        infected,
+        infected + stats.healed,
+        total - stats.dead,
+        total,
+    ]
+}
+
+// The stacked area chart of the run, revealed from left to right over ANIMATION_SECONDS when
+// opened in a browser - an SVG animation, so no script or video is needed to embed it.
+pub fn write(path: &str, data: &[(f64, Statistics)]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let (min_t, max_t) = match (data.first(), data.last()) {
+        (Some((first, _)), Some((last, _))) if last > first => (*first, *last),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not enough statistics to draw",
+            ))
+        }
+    };
+    let (left, right) = (MARGIN_LEFT, WIDTH - MARGIN);
+    let (top, bottom) = (MARGIN, HEIGHT - MARGIN_BOTTOM);
+    let x = |t: f64| left + (t - min_t) / (max_t - min_t) * (right - left);
+    let y = |num: usize, total: usize| bottom - num as f64 / total.max(1) as f64 * (bottom - top);
+    let total = data[0].1.population + data[0].1.dead;
+
+    writeln!(
+        file,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        WIDTH, HEIGHT
+    )?;
+    writeln!(file, "  <defs>")?;
+    writeln!(file, r#"    <clipPath id="progress">"#)?;
+    writeln!(
+        file,
+        r#"      <rect x="{}" y="0" width="0" height="{}">"#,
+        left, HEIGHT
+    )?;
+    writeln!(
+        file,
+        r#"        <animate attributeName="width" from="0" to="{}" dur="{}s" fill="freeze"/>"#,
+        right - left,
+        ANIMATION_SECONDS
+    )?;
+    writeln!(file, "      </rect>")?;
+    writeln!(file, "    </clipPath>")?;
+    writeln!(file, "  </defs>")?;
+    writeln!(
+        file,
+        r#"  <rect width="{}" height="{}" fill="white"/>"#,
+        WIDTH, HEIGHT
+    )?;
+
+    let colors = [
+        COLOR_VACCINATED,
+        COLOR_VACCINATED_INFECTED,
+        COLOR_INFECTED,
+        COLOR_HEALED,
+        COLOR_HEALTHY,
+        COLOR_DEAD,
+    ];
+    writeln!(file, r#"  <g clip-path="url(#progress)">"#)?;
+    for (strip, color) in colors.iter().enumerate() {
+        // along the upper boundary of the strip and back along the lower one
+        let upper = data.iter().map(|(t, stats)| {
+            let total = stats.population + stats.dead;
+            (x(*t), y(boundaries(stats)[strip + 1], total))
+        });
+        let lower = data.iter().rev().map(|(t, stats)| {
+            let total = stats.population + stats.dead;
+            (x(*t), y(boundaries(stats)[strip], total))
+        });
+        let points: Vec<_> = upper
+            .chain(lower)
+            .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+            .collect();
+        writeln!(
+            file,
+            r#"    <polygon points="{}" fill="{}"/>"#,
+            points.join(" "),
+            hex(*color)
+        )?;
+    }
+    writeln!(file, "  </g>")?;
+
+    writeln!(
+        file,
+        r#"  <polyline points="{0},{1} {0},{2} {3},{2}" fill="none" stroke="black"/>"#,
+        left, top, bottom, right
+    )?;
+    let labels = [
+        (left - 5.0, bottom, "end", "0".to_owned()),
+        (left - 5.0, top + 10.0, "end", total.to_string()),
+        (left, bottom + 20.0, "start", format!("{:.0}", min_t)),
+        (right, bottom + 20.0, "end", format!("{:.0}", max_t)),
+    ];
+    for (x, y, anchor, text) in &labels {
+        writeln!(
+            file,
+            r#"  <text x="{}" y="{}" text-anchor="{}" font-family="sans-serif" font-size="12">{}</text>"#,
+            x, y, anchor, text
+        )?;
+    }
+    writeln!(file, "</svg>")?;
+    file.flush()
+}
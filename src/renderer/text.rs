@@ -0,0 +1,198 @@
+use std::{borrow::Cow, cell::RefCell, fs};
+
+use glium::{
+    draw_parameters::DrawParameters,
+    implement_vertex, index,
+    texture::{ClientFormat, MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat},
+    uniform,
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter},
+    Blend, Display, Program, Surface, VertexBuffer,
+};
+use rusttype::{gpu_cache::Cache, point, Font, PositionedGlyph, Scale};
+
+// the glyphs are rasterized at this height in pixels, and scaled from there
+const GLYPH_SIZE: f32 = 24.0;
+const ATLAS_SIZE: u32 = 1024;
+
+const VERTEX_SHADER_SRC: &str = r#"
+    #version 140
+
+    in vec2 position;
+    in vec2 tex_coords;
+
+    uniform mat4 matrix;
+    out vec2 v_tex_coords;
+
+    void main() {
+        gl_Position = matrix * vec4(position, 0.0, 1.0);
+        v_tex_coords = tex_coords;
+    }
+"#;
+
+const FRAGMENT_SHADER_SRC: &str = r#"
+    #version 140
+
+    in vec2 v_tex_coords;
+    uniform sampler2D atlas;
+    uniform vec4 color;
+    out vec4 f_color;
+
+    void main() {
+        f_color = vec4(color.rgb, color.a * texture(atlas, v_tex_coords).r);
+    }
+"#;
+
+#[derive(Debug, Clone, Copy)]
+struct GlyphVertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+}
+
+implement_vertex!(GlyphVertex, position, tex_coords);
+
+// Draws single lines of text from glyphs cached in a texture atlas. Like glium_text did, the
+// text is laid out in units of the line height, from the origin up to y = 1.
+pub struct TextRenderer {
+    display: Display,
+    font: Font<'static>,
+    // in pixels at GLYPH_SIZE
+    line_height: f32,
+    descent: f32,
+    program: Program,
+    cache: RefCell<Cache<'static>>,
+    atlas: Texture2d,
+}
+
+impl TextRenderer {
+    pub fn new(display: &Display, font_path: &str) -> TextRenderer {
+        let data = fs::read(font_path)
+            .unwrap_or_else(|err| panic!("couldn't read the font {}: {}", font_path, err));
+        let font =
+            Font::try_from_vec(data).unwrap_or_else(|| panic!("{} is not a valid font", font_path));
+        let atlas = Texture2d::with_format(
+            display,
+            RawImage2d {
+                data: Cow::Owned(vec![0u8; (ATLAS_SIZE * ATLAS_SIZE) as usize]),
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+                format: ClientFormat::U8,
+            },
+            UncompressedFloatFormat::U8,
+            MipmapsOption::NoMipmap,
+        )
+        .unwrap();
+        let v_metrics = font.v_metrics(Scale::uniform(GLYPH_SIZE));
+
+        TextRenderer {
+            display: display.clone(),
+            font,
+            line_height: v_metrics.ascent - v_metrics.descent,
+            descent: v_metrics.descent,
+            program: Program::from_source(display, VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC, None)
+                .unwrap(),
+            cache: RefCell::new(Cache::builder().dimensions(ATLAS_SIZE, ATLAS_SIZE).build()),
+            atlas,
+        }
+    }
+
+    // kerned by the layout
+    fn layout(&self, text: &str) -> Vec<PositionedGlyph<'static>> {
+        self.font
+            .layout(text, Scale::uniform(GLYPH_SIZE), point(0.0, 0.0))
+            .collect()
+    }
+
+    // in the units the text is drawn in
+    pub fn width(&self, text: &str) -> f32 {
+        self.layout(text).last().map_or(0.0, |glyph| {
+            (glyph.position().x + glyph.unpositioned().h_metrics().advance_width) / self.line_height
+        })
+    }
+
+    pub fn draw<S: Surface>(
+        &self,
+        target: &mut S,
+        text: &str,
+        matrix: [[f32; 4]; 4],
+        color: [f32; 4],
+        draw_parameters: DrawParameters,
+    ) {
+        let glyphs = self.layout(text);
+        let mut cache = self.cache.borrow_mut();
+        for glyph in &glyphs {
+            cache.queue_glyph(0, glyph.clone());
+        }
+        let atlas = &self.atlas;
+        cache
+            .cache_queued(|rect, data| {
+                atlas.main_level().write(
+                    glium::Rect {
+                        left: rect.min.x,
+                        bottom: rect.min.y,
+                        width: rect.width(),
+                        height: rect.height(),
+                    },
+                    RawImage2d {
+                        data: Cow::Borrowed(data),
+                        width: rect.width(),
+                        height: rect.height(),
+                        format: ClientFormat::U8,
+                    },
+                );
+            })
+            .unwrap();
+
+        let mut vertices = vec![];
+        for glyph in &glyphs {
+            let (uv, rect) = match cache.rect_for(0, glyph) {
+                Ok(Some(rects)) => rects,
+                // spaces and the glyphs missing from the font
+                _ => continue,
+            };
+            // the pixel rectangle has y pointing down, from the baseline
+            let x = |x: i32| x as f32 / self.line_height;
+            let y = |y: i32| (-y as f32 - self.descent) / self.line_height;
+            let (left, right) = (x(rect.min.x), x(rect.max.x));
+            let (top, bottom) = (y(rect.min.y), y(rect.max.y));
+            let vertex = |x, y, u, v| GlyphVertex {
+                position: [x, y],
+                tex_coords: [u, v],
+            };
+            vertices.extend_from_slice(&[
+                vertex(left, top, uv.min.x, uv.min.y),
+                vertex(right, top, uv.max.x, uv.min.y),
+                vertex(right, bottom, uv.max.x, uv.max.y),
+                vertex(left, top, uv.min.x, uv.min.y),
+                vertex(right, bottom, uv.max.x, uv.max.y),
+                vertex(left, bottom, uv.min.x, uv.max.y),
+            ]);
+        }
+        if vertices.is_empty() {
+            return;
+        }
+
+        let vertex_buffer = VertexBuffer::new(&self.display, &vertices).unwrap();
+        let uniforms = uniform! {
+            matrix: matrix,
+            color: color,
+            atlas: self
+                .atlas
+                .sampled()
+                .magnify_filter(MagnifySamplerFilter::Linear)
+                .minify_filter(MinifySamplerFilter::Linear),
+        };
+        let draw_parameters = DrawParameters {
+            blend: Blend::alpha_blending(),
+            ..draw_parameters
+        };
+        target
+            .draw(
+                &vertex_buffer,
+                index::NoIndices(index::PrimitiveType::TrianglesList),
+                &self.program,
+                &uniforms,
+                &draw_parameters,
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use glium::{Display, Surface};
+
+use super::{locale::Locale, matrix::Matrix, text::TextRenderer};
+
+use crate::simulation::{Language, Params};
+
+// The screen shown before each run in the kiosk mode: the name of the scenario, what it's
+// about and how long it runs.
+pub struct TitleCard {
+    text: TextRenderer,
+    locale: Locale,
+}
+
+impl TitleCard {
+    pub fn new(display: &Display, language: Language) -> TitleCard {
+        TitleCard {
+            text: TextRenderer::new(display, "DejaVuSans.ttf"),
+            locale: Locale::new(language),
+        }
+    }
+
+    fn draw_text<S: Surface>(&self, target: &mut S, text: &str, pos: (f32, f32), size: f32) {
+        let (width, height) = target.get_dimensions();
+        let aspect = width as f32 / height as f32;
+        let matrix = Matrix::scale(size, size * aspect) * Matrix::translation(pos.0, pos.1);
+        self.text.draw(
+            target,
+            text,
+            matrix.inner(),
+            [0.0, 0.0, 0.0, 1.0],
+            Default::default(),
+        );
+    }
+
+    // `days` is None for a run that goes on until the epidemic dies out
+    pub fn draw(
+        &self,
+        display: &Display,
+        title: &str,
+        description: Option<&str>,
+        days: Option<f64>,
+        params: Params,
+    ) {
+        let mut target = display.draw();
+        target.clear_color(1.0, 1.0, 1.0, 1.0);
+
+        let locale = &self.locale;
+        self.draw_text(&mut target, title, (-0.8, 0.3), 0.08);
+        let mut lines = vec![];
+        if let Some(description) = description {
+            lines.push(description.to_owned());
+        }
+        lines.push(match days {
+            Some(days) => locale.fill("kiosk_days", &[&days]),
+            None => locale.get("kiosk_until_extinction").to_owned(),
+        });
+        lines.push(locale.fill("menu_people", &[&params.num_people]));
+        lines.push(locale.fill(
+            "menu_initial",
+            &[&params.init_infected, &params.init_vaccinated],
+        ));
+        for (i, line) in lines.iter().enumerate() {
+            self.draw_text(&mut target, line, (-0.8, 0.05 - 0.12 * i as f32), 0.04);
+        }
+
+        target.finish().unwrap();
+    }
+}
@@ -0,0 +1,37 @@
+use std::{fs, io};
+
+use serde_derive::{Deserialize, Serialize};
+
+// The parts of the view that persist between runs, kept in a small TOML file apart from the
+// simulation config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewState {
+    pub center_x: f64,
+    pub center_y: f64,
+    // the visible part of the box, along the shorter side of the view
+    pub size: f64,
+    #[serde(default)]
+    pub heatmap: bool,
+    #[serde(default)]
+    pub selected_community: Option<usize>,
+}
+
+impl ViewState {
+    // `None` if there is no saved state yet or it can't be read
+    pub fn load(path: &str) -> Option<ViewState> {
+        let contents = fs::read_to_string(path).ok()?;
+        match toml::from_str(&contents) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                eprintln!("Ignoring the saved view state {}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let contents = toml::to_string(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(path, contents)
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use serde_json::{json, Value};
+
+use crate::{
+    config,
+    simulation::{
+        intervention::Intervention,
+        policy::{Policy, PolicyRegistry},
+        setup::Setup,
+        Simulation,
+    },
+};
+
+// the JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+type RpcResult = Result<Value, (i64, String)>;
+
+// a request passed from a connection to the simulation thread
+struct Call {
+    method: String,
+    params: Value,
+    reply: Sender<RpcResult>,
+}
+
+// A headless simulation driven over the network.
+struct Instance {
+    config_path: String,
+    setup: Setup,
+    sim: Simulation,
+    rng: StdRng,
+    policy: Option<Box<dyn Policy>>,
+    running: bool,
+}
+
+impl Instance {
+    fn new(config_path: &str, preset: Option<&str>, seed: u64) -> Instance {
+        let setup = config::load_setup(config_path, preset);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let sim = setup.create(&mut rng);
+        Instance {
+            config_path: config_path.to_owned(),
+            setup,
+            sim,
+            rng,
+            policy: config::load_policy(config_path, &PolicyRegistry::with_builtins()),
+            running: false,
+        }
+    }
+
+    fn reset(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.sim = self.setup.create(&mut self.rng);
+        self.policy = config::load_policy(&self.config_path, &PolicyRegistry::with_builtins());
+    }
+
+    fn stats(&self) -> Value {
+        let stats = self.sim.stats();
+        json!({
+            "time": self.sim.time(),
+            "running": self.running,
+            "population": stats.population,
+            "dead": stats.dead,
+            "infected": stats.infected,
+            "healed": stats.healed,
+            "vaccinated": stats.vaccinated,
+            "vaccinated_infected": stats.vaccinated_infected,
+            "severe": stats.severe,
+            "critical": stats.critical,
+            "hospitalized": stats.hospitalized,
+        })
+    }
+
+    fn call(&mut self, method: &str, params: &Value) -> RpcResult {
+        let invalid = |reason: &str| Err((INVALID_PARAMS, reason.to_owned()));
+        match method {
+            "reset" => {
+                let seed = match params.get("seed") {
+                    None => thread_rng().gen(),
+                    Some(seed) => match seed.as_u64() {
+                        Some(seed) => seed,
+                        None => return invalid("the seed has to be a non-negative integer"),
+                    },
+                };
+                self.reset(seed);
+                Ok(self.stats())
+            }
+            "start" => {
+                self.running = true;
+                Ok(self.stats())
+            }
+            "pause" => {
+                self.running = false;
+                Ok(self.stats())
+            }
+            "step" => {
+                let days = match params.get("days").map(Value::as_f64) {
+                    None => 1.0,
+                    Some(Some(days)) if days >= 0.0 => days,
+                    Some(_) => return invalid("days has to be a non-negative number"),
+                };
+                let end = self.sim.time() + days;
+                self.sim
+                    .run_headless(&mut self.rng, &mut self.policy, |sim| sim.time() >= end);
+                Ok(self.stats())
+            }
+            "apply_intervention" => {
+                let intervention = match params.get("intervention").and_then(Value::as_str) {
+                    Some(text) => text.parse::<Intervention>(),
+                    None => return invalid("expected an intervention, eg. \"lockdown on\""),
+                };
+                match intervention {
+                    Ok(intervention) => {
+                        self.sim.apply(intervention, &mut self.rng);
+                        Ok(self.stats())
+                    }
+                    Err(err) => invalid(&err),
+                }
+            }
+            "get_stats" => Ok(self.stats()),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method: {}", method))),
+        }
+    }
+}
+
+// The calls are handled between the steps, so that the simulation never changes while one is
+// being answered.
+fn run(mut instance: Instance, calls: Receiver<Call>) {
+    loop {
+        // a paused simulation only waits for the next call
+        let call = if instance.running {
+            match calls.try_recv() {
+                Ok(call) => Some(call),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        } else {
+            match calls.recv() {
+                Ok(call) => Some(call),
+                Err(_) => return,
+            }
+        };
+        match call {
+            Some(call) => {
+                let _ = call.reply.send(instance.call(&call.method, &call.params));
+            }
+            None => instance
+                .sim
+                .step_headless(&mut instance.rng, &mut instance.policy),
+        }
+    }
+}
+
+// The response to a request line, or None for notifications.
+fn respond(calls: &Sender<Call>, line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(err) => return Some(error_response(Value::Null, PARSE_ERROR, &err.to_string())),
+    };
+    let id = request.get("id").cloned();
+    let method = match request.get("method").and_then(Value::as_str) {
+        Some(method) => method,
+        None => {
+            let id = id.unwrap_or(Value::Null);
+            return Some(error_response(id, INVALID_REQUEST, "missing method"));
+        }
+    };
+    // only named parameters, the positional ones would be silently ignored
+    let params = request.get("params").cloned().unwrap_or(json!({}));
+    if !params.is_object() {
+        let message = "the params have to be an object of named parameters";
+        return id.map(|id| error_response(id, INVALID_PARAMS, message));
+    }
+    let (reply, result) = mpsc::channel();
+    let call = Call {
+        method: method.to_owned(),
+        params,
+        reply,
+    };
+    // the simulation thread is gone if it panicked
+    let result = match calls.send(call).ok().and_then(|()| result.recv().ok()) {
+        Some(result) => result,
+        None => Err((INTERNAL_ERROR, "the simulation has stopped".to_owned())),
+    };
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn handle_connection(calls: &Sender<Call>, stream: TcpStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = respond(calls, &line) {
+            writeln!(writer, "{}", response)?;
+        }
+    }
+    Ok(())
+}
+
+// Serves JSON-RPC 2.0 on `address`, one request per line, eg.
+// {"jsonrpc": "2.0", "id": 1, "method": "step", "params": {"days": 30}}
+// The methods are reset (with an optional seed), start, pause, step, apply_intervention and
+// get_stats; each returns the statistics afterwards. A started simulation runs in the fast mode
+// until paused.
+pub fn serve(address: &str, config_path: &str, preset: Option<&str>) {
+    let listener = TcpListener::bind(address)
+        .unwrap_or_else(|err| panic!("couldn't listen on {}: {}", address, err));
+    println!("Listening for JSON-RPC requests on {}.", address);
+    let instance = Instance::new(config_path, preset, thread_rng().gen());
+    let (calls, calls_rx) = mpsc::channel();
+    thread::spawn(move || run(instance, calls_rx));
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Couldn't accept a connection: {}", err);
+                continue;
+            }
+        };
+        let calls = calls.clone();
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(&calls, stream) {
+                eprintln!("JSON-RPC connection closed: {}", err);
+            }
+        });
+    }
+}
@@ -0,0 +1,97 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    config,
+    simulation::{policy::PolicyRegistry, setup::Setup},
+};
+
+// the runs are scaled down to about this many people
+const SEED_CHECK_PEOPLE: usize = 500;
+// a run ends once nobody is infected, or after this long
+const MAX_DAYS: f64 = 730.0;
+// a run in which the infection died out before reaching this fraction of the people counts
+// as an early extinction
+const EXTINCTION_FRACTION: f64 = 0.02;
+// the share of the runs ending in an early extinction from which the user is warned
+const WARN_EXTINCT_RUNS: f64 = 0.2;
+
+struct Outcome {
+    // as fractions of the population
+    peak: f64,
+    total: f64,
+    died_out: bool,
+}
+
+fn run(setup: &Setup, config_path: &str, seed: u64) -> Outcome {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sim = setup.create(&mut rng);
+    let mut policy = config::load_policy(config_path, &PolicyRegistry::with_builtins());
+    let people = sim.params().num_people as f64;
+
+    let mut peak = 0;
+    sim.run_headless(&mut rng, &mut policy, |sim| {
+        let infected = sim.stats().infected;
+        peak = peak.max(infected);
+        sim.time() >= MAX_DAYS || infected == 0
+    });
+    let cases = sim.totals().cases;
+    Outcome {
+        peak: peak as f64 / people,
+        total: cases as f64 / people,
+        died_out: sim.stats().infected == 0 && (cases as f64) < EXTINCTION_FRACTION * people,
+    }
+}
+
+// eg. "median 31.2%, 10-90% 25.0-36.8%, range 0.4-40.1%"
+fn describe_spread(mut values: Vec<f64>) -> String {
+    values.sort_by(f64::total_cmp);
+    let quantile = |q: f64| values[((values.len() - 1) as f64 * q).round() as usize] * 100.0;
+    format!(
+        "median {:.1}%, 10-90% {:.1}-{:.1}%, range {:.1}-{:.1}%",
+        quantile(0.5),
+        quantile(0.1),
+        quantile(0.9),
+        quantile(0.0),
+        quantile(1.0)
+    )
+}
+
+// Runs the config with the seeds 0..runs in small, headless runs, and prints how much the
+// peak and the total number of cases vary between them. A spread dominated by the runs in
+// which the infection died out early means that a single long run says little.
+pub fn check(config_path: &str, preset: Option<&str>, runs: u64) {
+    let mut setup = config::load_setup(config_path, preset);
+    let runs = runs.max(1);
+    if setup.has_layout() {
+        println!("The config places things at fixed positions, so the runs are at full size.");
+    } else {
+        let full_size = setup.params.num_people;
+        let factor = (SEED_CHECK_PEOPLE as f64 / full_size as f64).min(1.0);
+        setup = setup.scaled(factor);
+        println!(
+            "Scaled down from {} to {} people, at the same density.",
+            full_size, setup.params.num_people
+        );
+    }
+    println!("Running {} seeds...", runs);
+
+    let outcomes: Vec<_> = (0..runs)
+        .map(|seed| run(&setup, config_path, seed))
+        .collect();
+    let died_out = outcomes.iter().filter(|outcome| outcome.died_out).count();
+    println!(
+        "Peak infected: {}",
+        describe_spread(outcomes.iter().map(|outcome| outcome.peak).collect())
+    );
+    println!(
+        "Total cases: {}",
+        describe_spread(outcomes.iter().map(|outcome| outcome.total).collect())
+    );
+    println!("Died out early in {} of {} runs", died_out, outcomes.len());
+    if died_out as f64 >= WARN_EXTINCT_RUNS * outcomes.len() as f64 {
+        println!(
+            "Warning: the outcomes are dominated by stochastic extinction, a single run may \
+             end early or not at all by chance; consider more initial infections."
+        );
+    }
+}
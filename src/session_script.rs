@@ -0,0 +1,332 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::Write,
+    str::FromStr,
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::simulation::{
+    intervention::Intervention,
+    tuning::{ParamUpdate, TUNABLES},
+    SimulationParameters,
+};
+
+// Something done to a run from the window, in the text form used by the scripts:
+//   any intervention, eg. "lockdown on"
+//   "set death_rate 0.05" for one of the tunable parameters
+//   "pause" and "resume"
+//   "time_compression 4"
+//   "fast on" and "fast off"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Intervene(Intervention),
+    SetParam(ParamUpdate),
+    Running(bool),
+    TimeCompression(f64),
+    Fast(bool),
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Intervene(intervention) => write!(f, "{}", intervention),
+            Command::SetParam(update) => {
+                write!(f, "set {} {}", TUNABLES[update.index].name, update.value)
+            }
+            Command::Running(true) => write!(f, "resume"),
+            Command::Running(false) => write!(f, "pause"),
+            Command::TimeCompression(factor) => write!(f, "time_compression {}", factor),
+            Command::Fast(true) => write!(f, "fast on"),
+            Command::Fast(false) => write!(f, "fast off"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Command, String> {
+        let invalid = || format!("invalid command: {}", text);
+        let number = |word: &str| word.parse::<f64>().map_err(|_| invalid());
+        let words: Vec<_> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["set", name, value] => {
+                let index = TUNABLES
+                    .iter()
+                    .position(|tunable| tunable.name == *name)
+                    .ok_or_else(|| format!("not a tunable parameter: {}", name))?;
+                Ok(Command::SetParam(ParamUpdate {
+                    index,
+                    value: number(value)?,
+                }))
+            }
+            ["pause"] => Ok(Command::Running(false)),
+            ["resume"] => Ok(Command::Running(true)),
+            ["time_compression", factor] => Ok(Command::TimeCompression(number(factor)?)),
+            ["fast", "on"] => Ok(Command::Fast(true)),
+            ["fast", "off"] => Ok(Command::Fast(false)),
+            _ => text.parse().map(Command::Intervene).map_err(|_| invalid()),
+        }
+    }
+}
+
+// A command at the time it was given. The recorded ones know the step too, which is what
+// makes the replay exact; the `[[events]]` schedules in config.toml can give just the day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub step: Option<u64>,
+    pub day: f64,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EventTable {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    step: Option<u64>,
+    day: f64,
+    action: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ScriptFile {
+    seed: u64,
+    #[serde(default)]
+    events: Vec<EventTable>,
+}
+
+impl Event {
+    fn from_table(table: EventTable) -> Result<Event, String> {
+        Ok(Event {
+            step: table.step,
+            day: table.day,
+            command: table.action.parse()?,
+        })
+    }
+
+    fn to_table(&self) -> EventTable {
+        EventTable {
+            step: self.step,
+            day: self.day,
+            action: self.command.to_string(),
+        }
+    }
+}
+
+// the `[[events]]` tables, with `day` and `action` keys and an optional `step`
+pub fn parse_events(value: &toml::Value) -> Vec<Event> {
+    let tables: Vec<EventTable> = value
+        .clone()
+        .try_into()
+        .unwrap_or_else(|err| panic!("invalid events: {}", err));
+    tables
+        .into_iter()
+        .map(|table| Event::from_table(table).unwrap_or_else(|err| panic!("{}", err)))
+        .collect()
+}
+
+// A session recorded with --record, eg.
+//   seed = 1234
+//   [[events]]
+//   step = 0
+//   day = 0.0
+//   action = "resume"
+// The events can be copied into config.toml as they are, as a schedule for other runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    pub seed: u64,
+    pub events: Vec<Event>,
+}
+
+impl Script {
+    pub fn load(path: &str) -> Script {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("couldn't read the script {}: {}", path, err));
+        let file: ScriptFile =
+            toml::from_str(&text).unwrap_or_else(|err| panic!("invalid script {}: {}", path, err));
+        let events = file
+            .events
+            .into_iter()
+            .map(|table| {
+                Event::from_table(table)
+                    .unwrap_or_else(|err| panic!("invalid script {}: {}", path, err))
+            })
+            .collect();
+        Script {
+            seed: file.seed,
+            events,
+        }
+    }
+}
+
+// Appends the commands to the script as they come, so that it's complete however the
+// window gets closed.
+pub struct Recorder {
+    path: String,
+    file: Option<File>,
+    // the speed controls aren't sent as commands, so they're recorded as they change
+    last_params: SimulationParameters,
+}
+
+impl Recorder {
+    pub fn new(path: &str, seed: u64, params: SimulationParameters) -> Recorder {
+        let file = File::create(path).and_then(|mut file| {
+            writeln!(file, "seed = {}", seed)?;
+            Ok(file)
+        });
+        let file = match file {
+            Ok(file) => Some(file),
+            Err(err) => {
+                eprintln!("Couldn't write the script {}: {}", path, err);
+                None
+            }
+        };
+        Recorder {
+            path: path.to_owned(),
+            file,
+            last_params: params,
+        }
+    }
+
+    pub fn record(&mut self, step: u64, day: f64, command: Command) {
+        let event = Event {
+            step: Some(step),
+            day,
+            command,
+        };
+        let table = toml::to_string(&event.to_table()).unwrap();
+        if let Some(file) = &mut self.file {
+            if let Err(err) = write!(file, "\n[[events]]\n{}", table) {
+                eprintln!("Couldn't write the script {}: {}", self.path, err);
+            }
+        }
+    }
+
+    // a speed control from the schedule, which is played again along with the script rather
+    // than recorded
+    pub fn skip(&mut self, command: Command) {
+        control(command, &mut self.last_params);
+    }
+
+    pub fn observe(&mut self, step: u64, day: f64, params: &SimulationParameters) {
+        let last = self.last_params;
+        self.last_params = *params;
+        if params.time_compression != last.time_compression {
+            self.record(step, day, Command::TimeCompression(params.time_compression));
+        }
+        if params.fast != last.fast {
+            self.record(step, day, Command::Fast(params.fast));
+        }
+        if params.running != last.running {
+            self.record(step, day, Command::Running(params.running));
+        }
+    }
+}
+
+// Gives out the events of a script or a schedule once their step, or their day if they
+// don't have one, is reached.
+pub struct Player {
+    events: Vec<Event>,
+}
+
+impl Player {
+    pub fn new(events: Vec<Event>) -> Player {
+        Player { events }
+    }
+
+    pub fn due(&mut self, step: u64, day: f64) -> Vec<Command> {
+        let is_due = |event: &Event| match event.step {
+            Some(at) => at <= step,
+            None => event.day <= day,
+        };
+        let due = self
+            .events
+            .iter()
+            .filter(|event| is_due(event))
+            .map(|event| event.command)
+            .collect();
+        self.events.retain(|event| !is_due(event));
+        due
+    }
+}
+
+// the speed controls changed by `command`, returning false for the other commands
+pub fn control(command: Command, params: &mut SimulationParameters) -> bool {
+    match command {
+        Command::Running(running) => params.running = running,
+        Command::TimeCompression(factor) => params.time_compression = factor,
+        Command::Fast(fast) => params.fast = fast,
+        Command::Intervene(_) | Command::SetParam(_) => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_command_reads_back_as_it_was_written() {
+        let commands = [
+            Command::Intervene(Intervention::VaccinateFraction(0.25)),
+            Command::Intervene(Intervention::InfectRandom(3)),
+            Command::Intervene(Intervention::InfectArea {
+                x: 10.5,
+                y: -2.0,
+                radius: 4.0,
+            }),
+            Command::Intervene(Intervention::ToggleQuarantine),
+            Command::Intervene(Intervention::ToggleLockdown),
+            Command::Intervene(Intervention::SetQuarantine(true)),
+            Command::Intervene(Intervention::SetQuarantine(false)),
+            Command::Intervene(Intervention::SetLockdown(true)),
+            Command::Intervene(Intervention::SetLockdown(false)),
+            Command::Intervene(Intervention::Track(17)),
+            Command::Intervene(Intervention::SetTransmissibility(1.5)),
+            Command::Intervene(Intervention::RollOutVaccine(1)),
+            Command::SetParam(ParamUpdate {
+                index: TUNABLES.len() - 1,
+                value: 0.125,
+            }),
+            Command::Running(true),
+            Command::Running(false),
+            Command::TimeCompression(4.0),
+            Command::Fast(true),
+            Command::Fast(false),
+        ];
+        for command in &commands {
+            let text = command.to_string();
+            assert_eq!(text.parse::<Command>(), Ok(*command), "{}", text);
+        }
+    }
+
+    #[test]
+    fn invalid_commands_are_rejected() {
+        assert!("set not_a_parameter 1".parse::<Command>().is_err());
+        assert!("time_compression fast".parse::<Command>().is_err());
+        assert!("fast maybe".parse::<Command>().is_err());
+        assert!("".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn events_are_due_at_their_step_or_else_their_day() {
+        let event = |step, day, running| Event {
+            step,
+            day,
+            command: Command::Running(running),
+        };
+        let mut player = Player::new(vec![
+            // the step decides, even though the day has passed already
+            event(Some(10), 0.0, true),
+            event(None, 1.0, false),
+            event(Some(20), 0.5, false),
+        ]);
+        assert_eq!(player.due(5, 0.5), vec![]);
+        assert_eq!(player.due(10, 0.9), vec![Command::Running(true)]);
+        assert_eq!(player.due(15, 1.0), vec![Command::Running(false)]);
+        // an event is only given out once
+        assert_eq!(player.due(15, 2.0), vec![]);
+        assert_eq!(player.due(25, 2.0), vec![Command::Running(false)]);
+    }
+}
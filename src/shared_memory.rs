@@ -0,0 +1,163 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    sync::atomic::{fence, AtomicU32, AtomicU64, Ordering},
+};
+
+use memmap2::MmapMut;
+
+use crate::simulation::snapshot::{RenderSnapshot, SNAPSHOT_VERSION};
+
+// Publishes the render snapshots into a memory-mapped file, so that another process can draw
+// them without copying them through a pipe. Everything is little-endian:
+//
+// header, 64 bytes:
+//   0  [u8; 8]  magic, "EPISNAP\0"
+//   8  u32      SNAPSHOT_VERSION
+//   12 u32      number of slots
+//   16 u64      size of a slot in bytes
+//   24 u64      capacity of a slot in people
+//   32 u64      sequence number of the latest complete slot, 0 before the first one
+//   40          reserved
+//
+// followed by the slots, snapshot `n` going into slot `n % slots`:
+//   0  u64      sequence number, 0 while the slot is being written
+//   8  f64      simulation time in days
+//   16 u64      number of people in the slot
+//   24 u64 x 6  population, dead, infected, healed, vaccinated, vaccinated_infected
+//   72          the people, 12 bytes each: f32 x, f32 y, u32 status code
+//
+// A reader takes the latest sequence number from the header, copies its slot and then checks
+// that the slot still has the same sequence number; otherwise it was overwritten in the
+// meantime and the copy has to be retried. Everything is written with atomic stores of whole
+// words, so the reader should load them the same way.
+//
+// When the population outgrows the slots, eg. with births, the file is made larger: the latest
+// sequence number is set to 0 first, then the header gets the new sizes, and the reader has to
+// map the file again once it sees a different slot size.
+const MAGIC: &[u8; 8] = b"EPISNAP\0";
+const HEADER_SIZE: usize = 64;
+const SLOT_COUNT: usize = 3;
+const SLOT_HEADER_SIZE: usize = 72;
+const PERSON_SIZE: usize = 12;
+const LATEST_OFFSET: usize = 32;
+
+pub struct SharedMemoryWriter {
+    file: File,
+    map: MmapMut,
+    capacity: usize,
+    sequence: u64,
+}
+
+// the slots start at multiples of 8, so that their sequence numbers can be stored atomically
+fn slot_size(capacity: usize) -> usize {
+    (SLOT_HEADER_SIZE + capacity * PERSON_SIZE).div_ceil(8) * 8
+}
+
+fn word64(map: &mut MmapMut, offset: usize) -> &AtomicU64 {
+    let bytes = &mut map[offset..offset + 8];
+    assert_eq!(bytes.as_ptr() as usize % 8, 0);
+    // in bounds and aligned, and the map is only written through atomics
+    unsafe { &*(bytes.as_mut_ptr() as *const AtomicU64) }
+}
+
+fn word32(map: &mut MmapMut, offset: usize) -> &AtomicU32 {
+    let bytes = &mut map[offset..offset + 4];
+    assert_eq!(bytes.as_ptr() as usize % 4, 0);
+    unsafe { &*(bytes.as_mut_ptr() as *const AtomicU32) }
+}
+
+fn store64(map: &mut MmapMut, offset: usize, value: u64, order: Ordering) {
+    word64(map, offset).store(value.to_le(), order);
+}
+
+fn store32(map: &mut MmapMut, offset: usize, value: u32) {
+    word32(map, offset).store(value.to_le(), Ordering::Relaxed);
+}
+
+impl SharedMemoryWriter {
+    // The slots start with room for `capacity` people, and grow when there are more.
+    pub fn create(path: &str, capacity: usize) -> io::Result<SharedMemoryWriter> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let map = SharedMemoryWriter::map(&file, capacity)?;
+        let mut writer = SharedMemoryWriter {
+            file,
+            map,
+            capacity,
+            sequence: 0,
+        };
+        writer.write_header();
+        Ok(writer)
+    }
+
+    fn map(file: &File, capacity: usize) -> io::Result<MmapMut> {
+        file.set_len((HEADER_SIZE + SLOT_COUNT * slot_size(capacity)) as u64)?;
+        // the file is only ever resized by us
+        unsafe { MmapMut::map_mut(file) }
+    }
+
+    fn write_header(&mut self) {
+        let map = &mut self.map;
+        store64(map, 0, u64::from_le_bytes(*MAGIC), Ordering::Relaxed);
+        store32(map, 8, SNAPSHOT_VERSION);
+        store32(map, 12, SLOT_COUNT as u32);
+        store64(map, 16, slot_size(self.capacity) as u64, Ordering::Relaxed);
+        store64(map, 24, self.capacity as u64, Ordering::Relaxed);
+        for slot in 0..SLOT_COUNT {
+            let start = HEADER_SIZE + slot * slot_size(self.capacity);
+            store64(map, start, 0, Ordering::Relaxed);
+        }
+        store64(map, LATEST_OFFSET, 0, Ordering::Release);
+    }
+
+    fn grow(&mut self, people: usize) -> io::Result<()> {
+        store64(&mut self.map, LATEST_OFFSET, 0, Ordering::Release);
+        self.capacity = people.max(2 * self.capacity);
+        self.map = SharedMemoryWriter::map(&self.file, self.capacity)?;
+        self.write_header();
+        Ok(())
+    }
+
+    pub fn publish(&mut self, snapshot: &RenderSnapshot) -> io::Result<()> {
+        if snapshot.people.len() > self.capacity {
+            self.grow(snapshot.people.len())?;
+        }
+        self.sequence += 1;
+        let start = HEADER_SIZE + (self.sequence as usize % SLOT_COUNT) * slot_size(self.capacity);
+        let map = &mut self.map;
+
+        store64(map, start, 0, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        let people = &snapshot.people;
+        let stats = &snapshot.stats;
+        store64(map, start + 8, snapshot.time.to_bits(), Ordering::Relaxed);
+        store64(map, start + 16, people.len() as u64, Ordering::Relaxed);
+        let counts = [
+            stats.population,
+            stats.dead,
+            stats.infected,
+            stats.healed,
+            stats.vaccinated,
+            stats.vaccinated_infected,
+        ];
+        for (index, &count) in counts.iter().enumerate() {
+            store64(map, start + 24 + index * 8, count as u64, Ordering::Relaxed);
+        }
+        for (index, &(x, y, status)) in people.iter().enumerate() {
+            let offset = start + SLOT_HEADER_SIZE + index * PERSON_SIZE;
+            store32(map, offset, x.to_bits());
+            store32(map, offset + 4, y.to_bits());
+            store32(map, offset + 8, status as u32);
+        }
+
+        store64(map, start, self.sequence, Ordering::Release);
+        store64(map, LATEST_OFFSET, self.sequence, Ordering::Release);
+        Ok(())
+    }
+}
@@ -1,13 +1,89 @@
+mod broad_phase;
+pub mod census;
+pub mod community;
+pub mod contact_rate;
+pub mod contamination;
+pub mod costs;
+pub mod counting_rng;
+pub mod density;
+mod event_driven;
+pub mod event_log;
+pub mod events;
+pub mod geometry;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod hospital;
+pub mod intervention;
+pub mod log_rotation;
+pub mod memory;
+pub mod mixing;
+pub mod navigation;
+pub mod obstacle;
 mod params;
 pub mod person;
+pub mod placement;
+pub mod policy;
+pub mod population;
+pub mod presets;
+mod pressure;
+pub mod regions;
+pub mod reporting;
+pub mod reproduction;
+pub mod rewind;
+#[cfg(feature = "scripting")]
+mod script_policy;
+pub mod setup;
+pub mod snapshot;
+mod spatial;
+pub mod stop_condition;
+pub mod strain;
+pub mod timeline;
+pub mod tuning;
+pub mod vital;
+mod weather;
+pub mod zone;
 
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    f64::consts::PI,
+    iter, mem,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use nalgebra::Vector2;
 use rand::{seq::SliceRandom, Rng};
 
-use params::Params;
+use community::Community;
+use contact_rate::ContactRate;
+use contamination::ContaminationGrid;
+use costs::Costs;
+use density::DensityMap;
+use event_log::{EventLog, LogHeader, Setting, Source};
+use events::{EventBus, SharedSubscriber, SimEvent, Subscriber, Totals};
+use geometry::Segment;
+use hospital::{needs_hospital, Hospital};
+use intervention::{InForce, Intervention, Restrictions};
+use memory::MemoryUsage;
+use mixing::Mixing;
+use navigation::{Destination, Navigation};
+use obstacle::{Door, Obstacle};
+pub use params::{ContactCalibration, Language, Params, SeedCluster};
 use person::*;
+use placement::Constraints;
+use policy::Policy;
+use population::{age_group, Population};
+use regions::RegionGrid;
+use reporting::{Reported, Reporting};
+use reproduction::Reproduction;
+use snapshot::{RenderSnapshot, SNAPSHOT_VERSION};
+use spatial::SpatialGrid;
+use strain::Strains;
+use timeline::{Phase, Timeline};
+use tuning::{ParamUpdate, TUNABLES};
+use vital::Turnover;
+use weather::Weather;
+use zone::Zone;
 
 fn clamp_f64(x: f64, limit: f64) -> f64 {
     if x > limit {
@@ -33,12 +109,25 @@ fn clamp_vec2(v: Vector2<f64>, limit: (f64, f64)) -> Vector2<f64> {
     Vector2::new(clamp_f64_half(v.x, limit.0), clamp_f64_half(v.y, limit.1))
 }
 
+// the range covered by the time compression slider
+pub const MIN_TIME_COMPRESSION: f64 = 1.0 / 64.0;
+pub const MAX_TIME_COMPRESSION: f64 = 64.0;
+
 #[derive(Debug, Clone, Copy)]
 pub struct SimulationParameters {
     pub time_compression: f64,
     pub running: bool,
+    // step with the maximum step duration as fast as possible, ignoring the wall clock
+    pub fast: bool,
 }
 
+// for stepping without a window, in the fast mode from the start
+pub const HEADLESS: SimulationParameters = SimulationParameters {
+    time_compression: 1.0,
+    running: true,
+    fast: true,
+};
+
 impl SimulationParameters {
     pub fn increase_time_compression(&mut self) {
         self.time_compression *= 2.0;
@@ -48,210 +137,1832 @@ impl SimulationParameters {
         self.time_compression /= 2.0;
     }
 
+    pub fn scale_time_compression(&mut self, factor: f64) {
+        self.time_compression =
+            (self.time_compression * factor).clamp(MIN_TIME_COMPRESSION, MAX_TIME_COMPRESSION);
+    }
+
     pub fn toggle_running(&mut self) {
         self.running = !self.running;
     }
+
+    pub fn toggle_fast(&mut self) {
+        self.fast = !self.fast;
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Simulation {
     box_size: (f64, f64),
     time: f64,
-    people: Vec<Person>,
+    people: Population,
     params: Params,
+    restrictions: Restrictions,
+    // the multiplier of the infection probabilities
+    transmissibility: f64,
+    costs: Costs,
+    totals: Totals,
+    log: EventLog,
+    bus: EventBus,
+    regions: RegionGrid,
+    spatial: SpatialGrid,
+    history: Vec<(f64, Statistics)>,
+    weather: Weather,
+    weather_history: Vec<(f64, f64)>,
+    reporting: Reporting,
+    reproduction: Reproduction,
+    reported_history: Vec<(f64, Reported)>,
+    in_force_history: Vec<(f64, InForce)>,
+    // when each of the interventions was applied, in order
+    interventions: Vec<(f64, Intervention)>,
+    // the people no longer displayed, the dead after the `corpse_display_time` and those who
+    // died of other causes, counted by the number of vaccine doses they had, their age group
+    // where known, and how the epidemic went for them
+    removed: BTreeMap<(usize, Option<usize>, Course), usize>,
+    timelines: Vec<Timeline>,
+    // how long the pairs of ids (the smaller first) have been in contact, weighted by the
+    // crowding and the zone, with `contact_duration`; ordered, so that the contacts ending
+    // in a step are rolled for in the same order in every run with the same seed
+    contacts: BTreeMap<(usize, usize), f64>,
+    // the pairs of ids (the smaller first) that met in the last step
+    touching: HashSet<(usize, usize)>,
+    contact_rate: ContactRate,
+    mixing: Mixing,
+    communities: Vec<Community>,
+    zones: Vec<Zone>,
+    walls: Vec<Segment>,
+    // the walls are made of, along with their doors
+    obstacles: Vec<Obstacle>,
+    navigation: Navigation,
+    hospital: Option<Hospital>,
+    // the identifiers of the people working in the hospital
+    hospital_staff: HashSet<usize>,
+    strains: Strains,
+    // the version of the vaccine given with the new doses
+    vaccine: usize,
+    // the number of people placed in each community, including the dead
+    community_sizes: Vec<usize>,
+    community_history: Vec<Vec<(f64, Statistics)>>,
+    next_sample: f64,
+    over_capacity: bool,
+    steps: u64,
+    timings: StepTimings,
+    turnover: Turnover,
+    // the second species, only meeting the people
+    vectors: Population,
+    contamination: ContaminationGrid,
+    // the events before the first step, for the subscribers added after the setup
+    setup_events: Vec<(f64, SimEvent)>,
+    // where the newborns can be placed
+    density: Option<Arc<DensityMap>>,
+}
+
+// where the time of the last step went
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StepTimings {
+    // moving the people and finding the collisions
+    pub physics: Duration,
+    pub collisions: Duration,
+    pub statuses: Duration,
+    pub pairs: usize,
 }
 
 const MAX_STEP_DURATION: f64 = 0.05;
+// how quickly the people with a destination turn towards the way there, a day
+const DESTINATION_STEERING: f64 = 10.0;
+// the people in a room head out this long before its last open door closes
+const DOOR_LEAVE_HOURS: f64 = 2.0;
 
 impl Simulation {
-    pub fn new<R: Rng>(rng: &mut R, params: Params) -> Simulation {
-        let mut people = vec![];
-        let box_size = (params.size_x, params.size_y);
+    // people are placed uniformly, unless a density map is given
+    pub fn new<R: Rng>(rng: &mut R, params: Params, density: Option<&DensityMap>) -> Simulation {
+        let constraints = Constraints {
+            box_size: (params.size_x, params.size_y),
+            density,
+            communities: &[],
+            walls: &[],
+        };
+        Simulation::placed(rng, params, &constraints)
+    }
+
+    // everyone in a random spot respecting the constraints
+    pub fn placed<R: Rng>(rng: &mut R, params: Params, constraints: &Constraints) -> Simulation {
+        let mut positions = vec![];
+        let mut population = Population::new();
         for _ in 0..params.num_people {
-            loop {
-                let new_person = Person::random(rng, box_size, params.speed_stdev);
-                let can_add = people
-                    .iter()
-                    .all(|other: &Person| !other.overlaps(&new_person, box_size));
-                if can_add {
-                    people.push(new_person);
-                    break;
+            let position = constraints.random_spot(rng, &positions);
+            positions.push(position);
+            population.push(Person::random_at(rng, position, params.speed_stdev));
+        }
+        let mut sim = Simulation::with_population(rng, params, population);
+        sim.set_density(constraints.density);
+        sim
+    }
+
+    pub fn with_population<R: Rng>(
+        rng: &mut R,
+        params: Params,
+        population: Population,
+    ) -> Simulation {
+        let box_size = (params.size_x, params.size_y);
+        let mut regions = RegionGrid::new(box_size, params.region_grid_size);
+        regions.rebuild(&population);
+        let mut spatial = SpatialGrid::new(box_size);
+        spatial.rebuild(population.positions());
+
+        let mut population = population;
+        if params.vital_dynamics {
+            for age in population.ages_mut() {
+                if age.is_none() {
+                    *age = Some(rng.gen::<f64>() * vital::MAX_INITIAL_AGE);
                 }
             }
         }
 
-        Simulation {
+        let mut vectors = Population::new();
+        for _ in 0..params.vectors {
+            let position = Vector2::new(
+                rng.gen::<f64>() * params.size_x,
+                rng.gen::<f64>() * params.size_y,
+            );
+            vectors.push(Person::random_at(rng, position, params.vector_speed_stdev));
+        }
+
+        let mut sample: Vec<usize> = population.ids().to_vec();
+        sample.shuffle(rng);
+        sample.truncate(params.timeline_sample);
+        sample.sort_unstable();
+
+        // drawn whether it's used or not, so that the reporting doesn't change the run
+        let reporting = Reporting::new(rng.gen());
+
+        let mut sim = Simulation {
             box_size,
             time: 0.0,
-            people,
+            people: population,
             params,
+            restrictions: Default::default(),
+            transmissibility: 1.0,
+            costs: Default::default(),
+            totals: Default::default(),
+            log: if params.event_log_window > 0 {
+                EventLog::with_window(params.event_log_window)
+            } else {
+                EventLog::new()
+            },
+            bus: Default::default(),
+            regions,
+            spatial,
+            history: vec![],
+            weather: Default::default(),
+            weather_history: vec![],
+            reporting,
+            reproduction: Default::default(),
+            reported_history: vec![],
+            in_force_history: vec![],
+            interventions: vec![],
+            removed: BTreeMap::new(),
+            density: None,
+            timelines: vec![],
+            contacts: BTreeMap::new(),
+            touching: HashSet::new(),
+            contact_rate: Default::default(),
+            mixing: Default::default(),
+            communities: vec![],
+            zones: vec![],
+            walls: vec![],
+            obstacles: vec![],
+            navigation: Default::default(),
+            hospital: None,
+            hospital_staff: HashSet::new(),
+            strains: Default::default(),
+            vaccine: 0,
+            community_sizes: vec![],
+            community_history: vec![],
+            next_sample: 0.0,
+            over_capacity: false,
+            steps: 0,
+            timings: Default::default(),
+            turnover: Default::default(),
+            vectors,
+            contamination: ContaminationGrid::new(box_size, params.fomite_grid_size),
+            setup_events: vec![],
+        };
+        for id in sample {
+            sim.track(id);
+        }
+        sim
+    }
+
+    // assigns everyone to the first community containing their current position
+    pub fn set_communities(&mut self, communities: Vec<Community>) {
+        let positions = self.people.positions().to_vec();
+        let assigned = self.people.communities_mut();
+        let mut sizes = vec![0; communities.len()];
+        for (community, pos) in assigned.iter_mut().zip(positions) {
+            *community = communities.iter().position(|c| c.contains(pos));
+            if let Some(index) = *community {
+                sizes[index] += 1;
+            }
+        }
+        self.community_history = vec![vec![]; communities.len()];
+        self.community_sizes = sizes;
+        self.communities = communities;
+    }
+
+    // the ones subscribing before the first step get the events of the setup too
+    pub fn subscribe(&mut self, subscriber: SharedSubscriber) {
+        {
+            let mut subscriber = subscriber.lock().unwrap();
+            for (time, event) in &self.setup_events {
+                subscriber.notify(*time, event);
+            }
+        }
+        self.bus.subscribe(subscriber);
+    }
+
+    // a copy to run alongside this one, without the subscribers
+    pub fn fork(&self) -> Simulation {
+        Simulation {
+            bus: Default::default(),
+            ..self.clone()
+        }
+    }
+
+    // the event log, the costs and the rest kept by the simulation itself get the events
+    // through the bus like everyone else
+    fn emit(&mut self, event: SimEvent) {
+        if let SimEvent::Infected { .. } = event {
+            if self.restrictions.quarantine {
+                self.emit(SimEvent::Tested { people: 1 });
+            }
+        }
+        if self.steps == 0 {
+            self.setup_events.push((self.time, event));
+        }
+        let reporting = if self.params.reporting {
+            Some(&mut self.reporting as &mut dyn Subscriber)
+        } else {
+            None
+        };
+        let local = iter::once(&mut self.log as &mut dyn Subscriber)
+            .chain(iter::once(&mut self.costs as &mut dyn Subscriber))
+            .chain(iter::once(&mut self.totals as &mut dyn Subscriber))
+            .chain(iter::once(&mut self.reproduction as &mut dyn Subscriber))
+            .chain(reporting)
+            .chain(
+                self.timelines
+                    .iter_mut()
+                    .map(|timeline| timeline as &mut dyn Subscriber),
+            );
+        self.bus.publish(self.time, &event, local);
+    }
+
+    pub fn set_zones(&mut self, zones: Vec<Zone>) {
+        self.zones = zones;
+    }
+
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    // where the newborns are placed, as at the start
+    pub fn set_density(&mut self, density: Option<&DensityMap>) {
+        self.density = density.cloned().map(Arc::new);
+    }
+
+    pub fn set_obstacles(&mut self, obstacles: Vec<Obstacle>) {
+        self.walls = obstacles.iter().flat_map(Obstacle::segments).collect();
+        self.obstacles = obstacles;
+    }
+
+    // without the doors
+    pub fn walls(&self) -> &[Segment] {
+        &self.walls
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    // whether anything follows the time of the day, like the doors with opening hours
+    pub fn has_daily_schedules(&self) -> bool {
+        self.obstacles
+            .iter()
+            .any(|obstacle| obstacle.doors.iter().any(Door::has_hours))
+    }
+
+    // the doors outside of their hours, which are walls until they open
+    pub fn closed_doors(&self) -> Vec<Segment> {
+        self.obstacles
+            .iter()
+            .flat_map(|obstacle| {
+                obstacle
+                    .doors
+                    .iter()
+                    .filter(|door| !door.is_open(self.time))
+                    .filter_map(move |door| obstacle.door_segment(door))
+            })
+            .collect()
+    }
+
+    // with `staff` of the people chosen at random to work there
+    pub fn set_hospital<R: Rng>(&mut self, hospital: Hospital, rng: &mut R) {
+        let mut ids = self.people.ids().to_vec();
+        ids.shuffle(rng);
+        self.hospital_staff = ids.into_iter().take(hospital.staff).collect();
+        self.hospital = Some(hospital);
+    }
+
+    pub fn hospital(&self) -> Option<&Hospital> {
+        self.hospital.as_ref()
+    }
+
+    // The severe and critical cases in the hospital, who stay there until discharged - as
+    // many as `hospital_capacity` has room for, in the order the people are kept in.
+    fn admitted(&self) -> Vec<bool> {
+        let mut admitted = vec![false; self.people.len()];
+        if let Some(hospital) = &self.hospital {
+            let mut load = 0.0;
+            let people = self.people.statuses().iter().zip(self.people.positions());
+            for (index, (status, &pos)) in people.enumerate() {
+                let cost = hospital::cost(&self.params, status.severity());
+                if needs_hospital(status)
+                    && hospital.contains(pos)
+                    && load + cost <= self.params.hospital_capacity
+                {
+                    load += cost;
+                    admitted[index] = true;
+                }
+            }
+        }
+        admitted
+    }
+
+    // Where the people with somewhere to go are headed: the visitors of the open rooms into
+    // them, and the people in a room about to close out of it, then the severe cases the
+    // hospital has room for, isolated or not, and its staff to the hospital.
+    fn destinations(&self) -> Vec<(usize, Destination)> {
+        let mut destinations = vec![];
+        for (index, room) in self.obstacles.iter().enumerate() {
+            if !room.is_room() {
+                continue;
+            }
+            let hours_left: Vec<_> = room
+                .doors
+                .iter()
+                .map(|door| door.hours_until_closed(self.time))
+                .filter(|&hours| hours > 0.0)
+                .collect();
+            if hours_left.is_empty() {
+                continue;
+            }
+            let closing = hours_left.iter().all(|&hours| hours <= DOOR_LEAVE_HOURS);
+            let people = self.people.positions().iter().zip(self.people.ids());
+            for (person, (&pos, &id)) in people.enumerate() {
+                let inside = room.contains(pos);
+                if inside && closing {
+                    destinations.push((person, Destination::OutOf(index)));
+                } else if !inside && !closing && room.is_visited_by(index, id) {
+                    destinations.push((person, Destination::Room(index)));
+                }
+            }
+        }
+        if let Some(hospital) = &self.hospital {
+            let statuses = self.people.statuses();
+            let admitted = self.admitted();
+            let mut load: f64 = (0..self.people.len())
+                .filter(|&index| admitted[index])
+                .map(|index| hospital::cost(&self.params, statuses[index].severity()))
+                .sum();
+            let people = self.people.positions().iter().zip(self.people.ids());
+            for (person, (&pos, id)) in people.enumerate() {
+                let status = &statuses[person];
+                if admitted[person] || status.dead().is_some() {
+                    continue;
+                }
+                let cost = hospital::cost(&self.params, status.severity());
+                if needs_hospital(status)
+                    && !hospital.contains(pos)
+                    && load + cost <= self.params.hospital_capacity
+                {
+                    load += cost;
+                    destinations.push((person, Destination::Hospital));
+                } else if self.hospital_staff.contains(id)
+                    && !hospital.contains(pos)
+                    && !self.isolated(status)
+                {
+                    destinations.push((person, Destination::Hospital));
+                }
+            }
+        }
+        destinations
+    }
+
+    fn is_at(&self, destination: Destination, pos: Vector2<f64>) -> bool {
+        match destination {
+            Destination::Room(index) => self.obstacles[index].contains(pos),
+            Destination::OutOf(index) => !self.obstacles[index].contains(pos),
+            Destination::Hospital => match &self.hospital {
+                Some(hospital) => hospital.contains(pos),
+                None => false,
+            },
+        }
+    }
+
+    // The people with a destination turn towards the way there, found on a coarse grid
+    // around the walls and the closed doors.
+    fn steer_to_destinations(&mut self, dt: f64) {
+        let destinations = self.destinations();
+        if destinations.is_empty() {
+            return;
+        }
+        let walls: Vec<_> = self
+            .walls
+            .iter()
+            .copied()
+            .chain(self.closed_doors())
+            .collect();
+        let turn = (DESTINATION_STEERING * dt).min(1.0);
+        let mut navigation = mem::take(&mut self.navigation);
+        for (person, destination) in destinations {
+            let field = navigation.field(
+                destination,
+                self.box_size,
+                self.params.navigation_grid_size,
+                &walls,
+                |pos| self.is_at(destination, pos),
+            );
+            let direction = match field.direction(self.people.positions()[person]) {
+                Some(direction) => direction,
+                None => continue,
+            };
+            let velocity = self.people.velocities()[person];
+            let speed = velocity.norm();
+            if speed == 0.0 {
+                continue;
+            }
+            let turned = velocity / speed * (1.0 - turn) + direction * turn;
+            if turned.norm() > 0.0 {
+                self.people.parts_mut().1[person] = turned.normalize() * speed;
+            }
+        }
+        self.navigation = navigation;
+    }
+
+    pub fn set_strains(&mut self, strains: Strains) {
+        self.strains = strains;
+    }
+
+    pub fn strains(&self) -> &Strains {
+        &self.strains
+    }
+
+    // Whoever ran into a wall since `previous` bounces off it. A step through the edge of the
+    // box is checked as if the box didn't wrap.
+    fn bounce_off_walls(&mut self, previous: &[Vector2<f64>]) {
+        let box_size = self.box_size;
+        let closed_doors = self.closed_doors();
+        let (positions, velocities, _) = self.people.parts_mut();
+        for ((pos, vel), &from) in positions.iter_mut().zip(velocities).zip(previous) {
+            let start = *pos - clamp_vec2(*pos - from, box_size);
+            let mut end = *pos;
+            let mut hit = false;
+            for wall in self.walls.iter().chain(&closed_doors) {
+                hit |= geometry::collide(start, &mut end, vel, RADIUS, wall);
+            }
+            if hit {
+                *pos = Vector2::new(clamp_f64(end.x, box_size.0), clamp_f64(end.y, box_size.1));
+            }
+        }
+    }
+
+    pub fn vaccine(&self) -> usize {
+        self.vaccine
+    }
+
+    // the updated vaccines scheduled for the step ending at `self.time`
+    fn roll_out_vaccines<R: Rng>(&mut self, dt: f64, rng: &mut R) {
+        let start = self.time - dt;
+        let due: Vec<_> = self
+            .strains
+            .updated_vaccines()
+            .filter(|(_, vaccine)| {
+                vaccine
+                    .day
+                    .is_some_and(|day| day >= start && day < self.time)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        for vaccine in due {
+            self.apply(Intervention::RollOutVaccine(vaccine), rng);
         }
     }
 
+    // the new strains appear in random people who aren't infected at the time, in the step
+    // ending at `self.time`
+    fn introduce_strains<R: Rng>(&mut self, dt: f64, rng: &mut R) {
+        let start = self.time - dt;
+        let due: Vec<_> = self
+            .strains
+            .introduced()
+            .filter(|(_, strain)| strain.day >= start && strain.day < self.time)
+            .map(|(index, strain)| (index, strain.cases))
+            .collect();
+        for (strain, cases) in due {
+            let statuses = self.people.statuses();
+            let mut candidates: Vec<_> = (0..self.people.len())
+                .filter(|&index| {
+                    statuses[index].dead().is_none() && statuses[index].infected().is_none()
+                })
+                .collect();
+            candidates.shuffle(rng);
+            for index in candidates.into_iter().take(cases) {
+                let time = self.time;
+                self.people.statuses_mut()[index].infect_with(strain, time, self.params, rng);
+                self.emit(SimEvent::Infected {
+                    person: self.people.ids()[index],
+                    infector: None,
+                    source: Source::EXTERNAL,
+                });
+            }
+        }
+    }
+
+    // where zones overlap, the first one listed applies
+    fn zone_factor(&self, pos: Vector2<f64>) -> f64 {
+        self.zones
+            .iter()
+            .find(|zone| zone.contains(pos))
+            .map_or(1.0, |zone| zone.factor)
+    }
+
     pub fn infect<R: Rng>(&mut self, n: usize, rng: &mut R) {
         let mut indices: Vec<_> = (0..self.people.len()).collect();
         indices.shuffle(rng);
         for index in indices.into_iter().take(n) {
-            self.people[index].infect(self.time);
+            self.infect_person(index, rng);
+        }
+    }
+
+    pub fn infect_ids<R: Rng>(&mut self, ids: &[usize], rng: &mut R) {
+        for &id in ids {
+            let index = self.index_of(id).unwrap();
+            self.infect_person(index, rng);
         }
     }
 
+    // infects the people nearest to the center of the cluster, up to its radius
+    pub fn seed_cluster<R: Rng>(&mut self, cluster: SeedCluster, rng: &mut R) {
+        let center = Vector2::new(cluster.x, cluster.y);
+        let positions = self.people.positions();
+        let statuses = self.people.statuses();
+        let mut candidates: Vec<(f64, usize)> = self
+            .neighbors_within(center, cluster.radius)
+            .into_iter()
+            .filter(|&index| {
+                statuses[index].dead().is_none() && statuses[index].infected().is_none()
+            })
+            .map(|index| {
+                let offset = clamp_vec2(positions[index] - center, self.box_size);
+                (offset.norm(), index)
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        for (_, index) in candidates.into_iter().take(cluster.count) {
+            self.infect_person(index, rng);
+        }
+    }
+
+    // infection from outside of the simulated population
+    fn infect_person<R: Rng>(&mut self, index: usize, rng: &mut R) {
+        self.people.statuses_mut()[index].infect(self.time, self.params, rng);
+        self.emit(SimEvent::Infected {
+            person: self.people.ids()[index],
+            infector: None,
+            source: Source::EXTERNAL,
+        });
+    }
+
+    fn vaccinate_person(&mut self, index: usize) {
+        let status = &mut self.people.statuses_mut()[index];
+        status.vaccinate(self.time, self.vaccine);
+        let dose = status.doses();
+        self.emit(SimEvent::Vaccinated {
+            person: self.people.ids()[index],
+            dose,
+        });
+    }
+
     pub fn vaccinate<R: Rng>(&mut self, n: usize, rng: &mut R) {
         let mut indices: Vec<_> = (0..self.people.len()).collect();
         indices.shuffle(rng);
         for index in indices.into_iter().take(n) {
-            self.people[index].vaccinate();
+            self.vaccinate_person(index);
         }
     }
 
+    // Marks a `fraction` of the people who aren't infected as recovered, up to `max_days`
+    // before the current time.
+    pub fn past_infect<R: Rng>(&mut self, fraction: f64, max_days: f64, rng: &mut R) {
+        let time = self.time;
+        let statuses = self.people.statuses_mut();
+        let mut indices: Vec<_> = (0..statuses.len())
+            .filter(|&index| statuses[index].infected().is_none())
+            .collect();
+        let n = ((indices.len() as f64 * fraction).round() as usize).min(indices.len());
+        indices.shuffle(rng);
+        for index in indices.into_iter().take(n) {
+            let recovered = time - rng.gen::<f64>() * max_days;
+            statuses[index].mark_recovered(recovered);
+        }
+    }
+
+    // the ids with the times they recovered at
+    pub fn mark_recovered_ids(&mut self, recovered: &[(usize, f64)]) {
+        for &(id, time) in recovered {
+            let index = self.index_of(id).unwrap();
+            self.people.statuses_mut()[index].mark_recovered(time);
+        }
+    }
+
+    pub fn vaccinate_ids(&mut self, ids: &[usize]) {
+        for &id in ids {
+            let index = self.index_of(id).unwrap();
+            self.vaccinate_person(index);
+        }
+    }
+
+    pub fn apply<R: Rng>(&mut self, intervention: Intervention, rng: &mut R) {
+        match intervention {
+            Intervention::VaccinateFraction(fraction) => {
+                let statuses = self.people.statuses();
+                let mut indices: Vec<_> = (0..statuses.len())
+                    .filter(|&index| statuses[index].susceptible())
+                    .collect();
+                let n = ((indices.len() as f64 * fraction).ceil() as usize).min(indices.len());
+                indices.shuffle(rng);
+                for index in indices.into_iter().take(n) {
+                    self.vaccinate_person(index);
+                }
+            }
+            Intervention::InfectRandom(n) => {
+                let statuses = self.people.statuses_mut();
+                let mut indices: Vec<_> = (0..statuses.len())
+                    .filter(|&index| {
+                        let status = &statuses[index];
+                        status.dead().is_none() && status.infected().is_none()
+                    })
+                    .collect();
+                indices.shuffle(rng);
+                for index in indices.into_iter().take(n) {
+                    self.infect_person(index, rng);
+                }
+            }
+            Intervention::InfectArea { x, y, radius } => {
+                for index in self.neighbors_within(Vector2::new(x, y), radius) {
+                    let status = self.people.statuses()[index];
+                    if status.dead().is_none() && status.infected().is_none() {
+                        self.infect_person(index, rng);
+                    }
+                }
+            }
+            Intervention::ToggleQuarantine => {
+                self.set_quarantine(!self.restrictions.quarantine);
+            }
+            Intervention::ToggleLockdown => {
+                self.restrictions.lockdown = !self.restrictions.lockdown;
+            }
+            Intervention::SetQuarantine(on) => {
+                self.set_quarantine(on);
+            }
+            Intervention::SetLockdown(on) => {
+                self.restrictions.lockdown = on;
+            }
+            Intervention::Track(id) => {
+                self.track(id);
+            }
+            Intervention::SetTransmissibility(factor) => {
+                self.transmissibility = factor;
+            }
+            Intervention::RollOutVaccine(vaccine) => {
+                if vaccine >= self.strains.vaccines() {
+                    eprintln!("There is no vaccine {} to roll out.", vaccine);
+                    return;
+                }
+                self.vaccine = vaccine;
+            }
+        }
+        self.interventions.push((self.time, intervention));
+        // the dropped ones are replaced by the ones setting what they left in force
+        memory::evict_folding(
+            &mut self.interventions,
+            self.params.history_cap,
+            intervention::fold,
+        );
+        self.emit(SimEvent::Intervention(intervention));
+    }
+
+    // everyone infected at the moment gets tested when the quarantine starts
+    fn set_quarantine(&mut self, on: bool) {
+        if on && !self.restrictions.quarantine {
+            let people = self.stats().infected;
+            self.emit(SimEvent::Tested { people });
+        }
+        self.restrictions.quarantine = on;
+    }
+
+    pub fn costs(&self) -> Costs {
+        self.costs
+    }
+
+    pub fn totals(&self) -> Totals {
+        self.totals
+    }
+
+    pub fn restrictions(&self) -> Restrictions {
+        self.restrictions
+    }
+
+    pub fn update_param(&mut self, update: ParamUpdate) {
+        (TUNABLES[update.index].set)(&mut self.params, update.value);
+    }
+
+    pub fn transmissibility(&self) -> f64 {
+        self.transmissibility
+    }
+
+    // the parameters with the infection probabilities currently in effect
+    fn infection_params(&self) -> Params {
+        self.params.with_transmissibility(self.transmissibility)
+    }
+
+    pub fn log(&self) -> &EventLog {
+        &self.log
+    }
+
+    pub fn set_log_header(&mut self, header: LogHeader) {
+        self.log.set_header(header);
+    }
+
+    pub fn regions(&self) -> &RegionGrid {
+        &self.regions
+    }
+
+    // indices of the people whose centers are within `r` of `pos`
+    pub fn neighbors_within(&self, pos: Vector2<f64>, r: f64) -> Vec<usize> {
+        self.spatial.query(self.people.positions(), pos, r)
+    }
+
+    fn isolated(&self, status: &Status) -> bool {
+        self.restrictions.quarantine && status.infected().is_some() && status.dead().is_none()
+    }
+
     pub fn time(&self) -> f64 {
         self.time
     }
 
-    pub fn people(&self) -> &[Person] {
-        &self.people
+    pub fn people(&self) -> impl Iterator<Item = Person> + '_ {
+        self.people.iter()
+    }
+
+    pub fn params(&self) -> Params {
+        self.params
+    }
+
+    // the people to draw, leaving out the corpses which have faded out
+    pub fn render_snapshot(&self) -> RenderSnapshot {
+        let corpse_display_time = self.params.corpse_display_time;
+        let people = self
+            .people
+            .positions()
+            .iter()
+            .zip(self.people.statuses())
+            .filter(|(_, status)| match status.dead() {
+                Some(died) => corpse_display_time <= 0.0 || self.time - died < corpse_display_time,
+                None => true,
+            })
+            .map(|(pos, status)| (pos.x as f32, pos.y as f32, snapshot::status_code(status)))
+            .collect();
+        RenderSnapshot {
+            version: SNAPSHOT_VERSION,
+            time: self.time,
+            people,
+            stats: self.stats(),
+        }
+    }
+
+    // stable identifiers of the people, in the same order as `people()`
+    #[cfg_attr(not(feature = "microdata"), allow(dead_code))]
+    pub fn ids(&self) -> &[usize] {
+        self.people.ids()
+    }
+
+    // in years, where known, in the same order as the people
+    pub fn ages(&self) -> &[Option<f64>] {
+        self.people.ages()
+    }
+
+    // the community each person was placed in, in the same order as the people
+    pub fn person_communities(&self) -> &[Option<usize>] {
+        self.people.communities()
+    }
+
+    // statistics sampled every `stats_sample_interval` days
+    fn index_of(&self, id: usize) -> Option<usize> {
+        // the ids are assigned in increasing order, and removing people keeps the order
+        self.people.ids().binary_search(&id).ok()
+    }
+
+    // the id of the living person closest to `pos`, if there is one within `r`
+    pub fn person(&self, id: usize) -> Option<Person> {
+        self.index_of(id).map(|index| self.people.get(index))
+    }
+
+    pub fn nearest(&self, pos: Vector2<f64>, r: f64) -> Option<usize> {
+        let positions = self.people.positions();
+        let statuses = self.people.statuses();
+        self.neighbors_within(pos, r)
+            .into_iter()
+            .filter(|&index| statuses[index].dead().is_none())
+            .map(|index| {
+                let offset = clamp_vec2(positions[index] - pos, self.box_size);
+                (offset.norm(), index)
+            })
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .map(|(_, index)| self.people.ids()[index])
+    }
+
+    pub fn track(&mut self, id: usize) {
+        if self.timelines.iter().any(|timeline| timeline.id == id) {
+            return;
+        }
+        if self.index_of(id).is_some() {
+            self.timelines.push(Timeline::new(id));
+            self.update_timelines();
+        }
+    }
+
+    fn update_timelines(&mut self) {
+        for i in 0..self.timelines.len() {
+            let index = match self.index_of(self.timelines[i].id) {
+                Some(index) => index,
+                None => continue,
+            };
+            let status = self.people.statuses()[index];
+            let phase = Phase::of(&status, self.isolated(&status));
+            self.timelines[i].record(self.time, phase, status.doses());
+            self.timelines[i].limit(self.params.timeline_cap);
+        }
+    }
+
+    pub fn timelines(&self) -> &[Timeline] {
+        &self.timelines
+    }
+
+    pub fn timeline(&self, id: usize) -> Option<&Timeline> {
+        self.timelines.iter().find(|timeline| timeline.id == id)
+    }
+
+    // of the people displayed, including the dead for the `corpse_display_time`
+    pub fn statuses(&self) -> &[Status] {
+        self.people.statuses()
+    }
+
+    // the people no longer displayed, by the number of vaccine doses they had, their age
+    // group where known, and how the epidemic went for them
+    pub fn removed(&self) -> &BTreeMap<(usize, Option<usize>, Course), usize> {
+        &self.removed
+    }
+
+    fn count_removed(&mut self, removed: &[(Status, Option<f64>)]) {
+        for (status, age) in removed {
+            let key = (status.doses(), age.map(age_group), status.course());
+            *self.removed.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    pub fn communities(&self) -> &[Community] {
+        &self.communities
+    }
+
+    pub fn community_history(&self, community: usize) -> &[(f64, Statistics)] {
+        &self.community_history[community]
+    }
+
+    // roughly how much memory a copy of the simulation takes
+    pub fn approx_size(&self) -> usize {
+        let usage = self.memory_usage();
+        mem::size_of::<Simulation>()
+            + self.people.approx_size()
+            + usage.history
+            + usage.event_log
+            + usage.timelines
+            + usage.counts
+    }
+
+    // the parts that grow as it runs, without the rewind buffer and the charts kept outside of it
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let communities: usize = self
+            .community_history
+            .iter()
+            .map(|history| mem::size_of_val(&history[..]))
+            .sum();
+        MemoryUsage {
+            history: mem::size_of_val(&self.history[..])
+                + communities
+                + mem::size_of_val(&self.weather_history[..])
+                + mem::size_of_val(&self.reported_history[..])
+                + mem::size_of_val(&self.in_force_history[..])
+                + mem::size_of_val(&self.interventions[..]),
+            event_log: mem::size_of_val(self.log.entries()),
+            timelines: self.timelines.iter().map(Timeline::approx_size).sum(),
+            counts: self.reproduction.approx_size()
+                + self.reporting.approx_size()
+                + self.removed.len() * mem::size_of::<((usize, Option<usize>, Course), usize)>(),
+            ..Default::default()
+        }
+    }
+
+    // for a run continued from a census taken at `time`, before anything happens in it
+    pub fn start_at(&mut self, time: f64) {
+        self.time = time;
+        self.next_sample = time;
+    }
+
+    // the number of steps taken while running
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn timings(&self) -> StepTimings {
+        self.timings
+    }
+
+    // the others met by a living person per day, over the last full day
+    pub fn contact_rate(&self) -> Option<f64> {
+        self.contact_rate.per_person_day
+    }
+
+    // the contacts between the groups over the last full day, see Mixing
+    pub fn mixing(&self) -> Option<&[Vec<u64>]> {
+        self.mixing.matrix.as_deref()
+    }
+
+    // the speeds of the living, in `bins` bins up to the largest of them, which is returned too
+    pub fn speed_histogram(&self, bins: usize) -> (Vec<usize>, f64) {
+        let speeds: Vec<f64> = self
+            .people
+            .velocities()
+            .iter()
+            .zip(self.people.statuses())
+            .filter(|(_, status)| status.dead().is_none())
+            .map(|(velocity, _)| velocity.norm())
+            .collect();
+        let max = speeds.iter().cloned().fold(0.0, f64::max);
+        (mixing::histogram(speeds.into_iter(), bins, max), max)
+    }
+
+    pub fn history(&self) -> &[(f64, Statistics)] {
+        &self.history
+    }
+
+    // the last sample taken at or before `time`
+    pub fn stats_at(&self, time: f64) -> Option<Statistics> {
+        let index = self.history.partition_point(|(t, _)| *t <= time);
+        index.checked_sub(1).map(|index| self.history[index].1)
+    }
+
+    // from 0 for fine weather to 1 for bad weather; always 0 without `weather`
+    pub fn weather(&self) -> f64 {
+        if self.params.weather {
+            self.weather.badness()
+        } else {
+            0.0
+        }
+    }
+
+    pub fn weather_history(&self) -> &[(f64, f64)] {
+        &self.weather_history
+    }
+
+    // of the people infected over the week up to `day`
+    pub fn true_rt(&self, day: usize) -> Option<f64> {
+        self.reproduction.true_rt(day)
+    }
+
+    // for the people infected over the week up to `day`, from the cases reported
+    // `case_reporting_delay` days later, with `reporting`
+    pub fn estimated_rt(&self, day: usize) -> Option<f64> {
+        if !self.params.reporting {
+            return None;
+        }
+        let weights = reproduction::generation_interval(
+            self.params.generation_interval_mean,
+            self.params.generation_interval_stdev,
+        );
+        let reported_day = day + self.params.case_reporting_delay.round() as usize;
+        reproduction::estimate_rt(self.reporting.daily_cases(), &weights, reported_day)
+    }
+
+    // sampled along with the stats, with `reporting`
+    pub fn reported_history(&self) -> &[(f64, Reported)] {
+        &self.reported_history
+    }
+
+    pub fn interventions(&self) -> &[(f64, Intervention)] {
+        &self.interventions
+    }
+
+    pub fn in_force_history(&self) -> &[(f64, InForce)] {
+        &self.in_force_history
+    }
+
+    fn sample_stats(&mut self) {
+        if self.time < self.next_sample {
+            return;
+        }
+        self.history.push((self.time, self.stats()));
+        if self.params.weather {
+            self.weather_history.push((self.time, self.weather()));
+        }
+        if self.params.reporting {
+            self.reported_history
+                .push((self.time, self.reporting.counts()));
+        }
+        let in_force = InForce {
+            restrictions: self.restrictions,
+            transmissibility: self.transmissibility,
+        };
+        self.in_force_history.push((self.time, in_force));
+        for community in 0..self.communities.len() {
+            let stats = self.community_stats(community);
+            self.community_history[community].push((self.time, stats));
+        }
+        let (cap, eviction) = (self.params.history_cap, self.params.history_eviction);
+        memory::evict(&mut self.history, cap, eviction);
+        memory::evict(&mut self.weather_history, cap, eviction);
+        memory::evict(&mut self.reported_history, cap, eviction);
+        memory::evict(&mut self.in_force_history, cap, eviction);
+        for history in &mut self.community_history {
+            memory::evict(history, cap, eviction);
+        }
+        // the first sampling time after now, however far behind it was
+        let interval = self.params.stats_sample_interval;
+        self.next_sample = ((self.time / interval).floor() + 1.0) * interval;
     }
 
     pub fn step<R: Rng>(&mut self, dt: f64, rng: &mut R, params: &SimulationParameters) {
+        if self.params.event_driven {
+            self.step_with(dt, rng, params, event_driven::advance);
+        } else {
+            self.step_with(dt, rng, params, |sim, dt| {
+                sim.move_people(dt);
+                sim.find_collisions()
+            });
+        }
+    }
+
+    // a step in the fast mode, with the policy deciding once per simulated day like in the
+    // window, from the day the run starts on
+    pub fn step_headless<R: Rng>(&mut self, rng: &mut R, policy: &mut Option<Box<dyn Policy>>) {
+        let (first, last_day) = (self.steps == 0, self.time.floor() as u64);
+        self.step(0.0, rng, &HEADLESS);
+        let day = self.time.floor() as u64;
+        if first || day != last_day {
+            if let Some(policy) = policy {
+                for intervention in policy.decide(&self.stats(), day) {
+                    self.apply(intervention, rng);
+                }
+            }
+        }
+    }
+
+    // steps without a window until `done` holds, checked before each step
+    pub fn run_headless<R, F>(
+        &mut self,
+        rng: &mut R,
+        policy: &mut Option<Box<dyn Policy>>,
+        mut done: F,
+    ) where
+        R: Rng,
+        F: FnMut(&Simulation) -> bool,
+    {
+        while !done(self) {
+            self.step_headless(rng, policy);
+        }
+    }
+
+    // `physics` moves the people and returns the colliding pairs
+    fn step_with<R, F>(&mut self, dt: f64, rng: &mut R, params: &SimulationParameters, physics: F)
+    where
+        R: Rng,
+        F: FnOnce(&mut Simulation, f64) -> BTreeSet<(usize, usize)>,
+    {
+        self.sample_stats();
+
         if !params.running {
             return;
         }
+        crate::trace_span!("step");
 
-        let dt = dt.min(MAX_STEP_DURATION) * params.time_compression;
+        let dt = if params.fast {
+            MAX_STEP_DURATION
+        } else {
+            dt.min(MAX_STEP_DURATION) * params.time_compression
+        };
 
-        self.move_people(dt);
-        let collisions = self.find_collisions();
-        self.apply_collisions(collisions, rng);
+        let start = Instant::now();
+        if !self.obstacles.is_empty() || self.hospital.is_some() {
+            self.steer_to_destinations(dt);
+        }
+        let previous = if self.obstacles.is_empty() {
+            vec![]
+        } else {
+            self.people.positions().to_vec()
+        };
+        let collisions = physics(self, dt);
+        if self.params.pressure_iterations > 0 {
+            self.relieve_pressure();
+        }
+        if !self.obstacles.is_empty() {
+            self.bounce_off_walls(&previous);
+        }
+        let physics_done = Instant::now();
+        let pairs = collisions.len();
+        let new_contacts = {
+            crate::trace_span!("narrow_phase");
+            self.apply_collisions(collisions, dt, rng)
+        };
+        let collisions_done = Instant::now();
 
         self.time += dt;
+        self.introduce_strains(dt, rng);
+        self.roll_out_vaccines(dt, rng);
+        let living = self
+            .people
+            .statuses()
+            .iter()
+            .filter(|status| status.dead().is_none())
+            .count();
+        self.contact_rate
+            .record(self.time, new_contacts, living as f64 * dt);
+        if self.params.weather {
+            self.weather.step(dt, self.params.weather_timescale, rng);
+        }
+        self.accrue_costs(dt);
+        self.give_second_doses();
 
-        let mut dead = vec![];
-        for (i, person) in self.people.iter_mut().enumerate() {
-            if person.update_status(self.time, self.params, dt, rng) {
-                dead.push(i);
+        let mut outcomes = false;
+        {
+            crate::trace_span!("status_update");
+            for index in 0..self.people.len() {
+                let id = self.people.ids()[index];
+                let (_, velocities, statuses) = self.people.parts_mut();
+                let outcome = match statuses[index].update(self.time, self.params, dt, rng) {
+                    Some(outcome) => outcome,
+                    None => continue,
+                };
+                if outcome == Outcome::Died {
+                    statuses[index].die(self.time);
+                    velocities[index] = Vector2::new(0.0, 0.0);
+                }
+                self.emit(match outcome {
+                    Outcome::Healed => SimEvent::Recovered { person: id },
+                    Outcome::Died => SimEvent::Died { person: id },
+                });
+                outcomes = true;
             }
         }
-        dead.sort();
-        for index in dead.into_iter().rev() {
-            self.people.remove(index);
+        if self.params.fomites {
+            self.update_contamination(dt, rng);
         }
-    }
+        if self.params.vital_dynamics {
+            outcomes |= self.update_vital_dynamics(dt, rng);
+        }
+        if self.params.reporting {
+            self.reporting.update(self.time, self.params);
+        }
+        self.timings = StepTimings {
+            physics: physics_done - start,
+            collisions: collisions_done - physics_done,
+            statuses: collisions_done.elapsed(),
+            pairs,
+        };
+        self.steps += 1;
+        self.setup_events = vec![];
 
-    fn move_people(&mut self, dt: f64) {
-        for person in &mut self.people {
-            person.shift(dt, self.box_size);
+        let infected = self
+            .people
+            .statuses()
+            .iter()
+            .any(|s| s.infected().is_some());
+        if outcomes && !infected {
+            self.emit(SimEvent::Extinct);
         }
-    }
 
-    fn find_collisions(&self) -> HashSet<(usize, usize)> {
-        let mut sorted_x: Vec<usize> = (0..self.people.len()).collect();
-        let mut sorted_y = sorted_x.clone();
+        self.update_timelines();
 
-        sorted_x.sort_by(|index1, index2| {
-            self.people[*index1]
-                .pos()
-                .x
-                .partial_cmp(&self.people[*index2].pos().x)
-                .unwrap()
-        });
-        sorted_y.sort_by(|index1, index2| {
-            self.people[*index1]
-                .pos()
-                .y
-                .partial_cmp(&self.people[*index2].pos().y)
-                .unwrap()
+        // corpses stay in place for a while before being removed
+        let time = self.time;
+        let corpse_display_time = self.params.corpse_display_time;
+        let removed = self.people.retain(|status| {
+            status
+                .dead()
+                .is_none_or(|died| time - died < corpse_display_time)
         });
+        self.count_removed(&removed);
 
-        let mut pairs = HashSet::new();
+        self.regions.rebuild(&self.people);
+        self.spatial.rebuild(self.people.positions());
 
-        let len = sorted_x.len();
-        for (i, person_index) in sorted_x.iter().enumerate() {
-            for j in i + 1..i + 1 + len {
-                let person1 = &self.people[*person_index];
-                let person2 = &self.people[sorted_x[j % len]];
-                if person1.overlaps(person2, self.box_size) {
-                    if *person_index < sorted_x[j % len] {
-                        pairs.insert((*person_index, sorted_x[j % len]));
-                    } else {
-                        pairs.insert((sorted_x[j % len], *person_index));
+        if !self.vectors.is_empty() {
+            self.step_vectors(dt, rng);
+        }
+
+        self.check_capacity(rng);
+    }
+
+    pub fn contamination(&self) -> &ContaminationGrid {
+        &self.contamination
+    }
+
+    // The infected contaminate their cells, and everyone else can catch the infection from
+    // them. The quarantined are kept away from the surfaces, like from the other people.
+    fn update_contamination<R: Rng>(&mut self, dt: f64, rng: &mut R) {
+        let (time, params) = (self.time, self.infection_params());
+        self.contamination.decay(dt, params.fomite_half_life);
+        if params.wind_x != 0.0 || params.wind_y != 0.0 || params.fomite_diffusion > 0.0 {
+            let wind = Vector2::new(params.wind_x, params.wind_y);
+            self.contamination.advect(dt, wind, params.fomite_diffusion);
+        }
+        let mut events = vec![];
+        for index in 0..self.people.len() {
+            let status = self.people.statuses()[index];
+            if status.dead().is_some() || self.isolated(&status) {
+                continue;
+            }
+            let pos = self.people.positions()[index];
+            if let Some(strain) = status.strain() {
+                self.contamination
+                    .deposit(pos, params.fomite_shedding * dt, strain);
+                continue;
+            }
+            let (level, strain) = self.contamination.at(pos);
+            if level <= 0.0 {
+                continue;
+            }
+            let source = self.people.statuses_mut()[index].contact(
+                time,
+                params,
+                &self.strains,
+                &Status::carrier(strain, time),
+                Setting::Surface,
+                params.fomite_exposure * level * dt,
+                rng,
+            );
+            if let Some(source) = source {
+                events.push(SimEvent::Infected {
+                    person: self.people.ids()[index],
+                    infector: None,
+                    source,
+                });
+            }
+        }
+        for event in events {
+            self.emit(event);
+        }
+    }
+
+    pub fn vectors(&self) -> impl Iterator<Item = Person> + '_ {
+        self.vectors.iter()
+    }
+
+    // Moves the vectors, ends their infections once they've lasted long enough, and passes the
+    // infection between them and the people nearby. Any number of people can meet a vector at
+    // once, and the quarantined and the dead don't meet them at all.
+    fn step_vectors<R: Rng>(&mut self, dt: f64, rng: &mut R) {
+        let mobility = vec![1.0; self.vectors.len()];
+        self.vectors.shift(dt, &mobility, self.box_size);
+        let (time, params) = (self.time, self.infection_params());
+        for status in self.vectors.statuses_mut() {
+            if matches!(status.infected(), Some(infected) if time - infected >= params.vector_infection_duration)
+            {
+                status.clear_infection();
+            }
+        }
+
+        let mut events = vec![];
+        for vector in 0..self.vectors.len() {
+            let pos = self.vectors.positions()[vector];
+            let nearby =
+                self.spatial
+                    .query(self.people.positions(), pos, params.vector_contact_radius);
+            for index in nearby {
+                let status = self.people.statuses()[index];
+                if status.dead().is_some() || self.isolated(&status) {
+                    continue;
+                }
+                let vector_status = self.vectors.statuses()[vector];
+                if vector_status.infected().is_none() {
+                    self.vectors.statuses_mut()[vector].contact(
+                        time,
+                        params,
+                        &self.strains,
+                        &status,
+                        Setting::Vector,
+                        params.exposure_to_vector,
+                        rng,
+                    );
+                } else if status.infected().is_none() {
+                    let source = self.people.statuses_mut()[index].contact(
+                        time,
+                        params,
+                        &self.strains,
+                        &vector_status,
+                        Setting::Vector,
+                        params.exposure_from_vector,
+                        rng,
+                    );
+                    if let Some(source) = source {
+                        events.push(SimEvent::Infected {
+                            person: self.people.ids()[index],
+                            infector: None,
+                            source,
+                        });
                     }
-                } else if clamp_f64_half(person2.pos().x - person1.pos().x, self.box_size.0)
-                    > RADIUS
-                {
-                    break;
                 }
             }
         }
+        for event in events {
+            self.emit(event);
+        }
+    }
 
-        let len = sorted_y.len();
-        for (i, person_index) in sorted_y.iter().enumerate() {
-            for j in i + 1..i + 1 + len {
-                let person1 = &self.people[*person_index];
-                let person2 = &self.people[sorted_y[j % len]];
-                if person1.overlaps(person2, self.box_size) {
-                    if *person_index < sorted_y[j % len] {
-                        pairs.insert((*person_index, sorted_y[j % len]));
-                    } else {
-                        pairs.insert((sorted_y[j % len], *person_index));
-                    }
-                } else if clamp_f64_half(person2.pos().y - person1.pos().y, self.box_size.1)
-                    > RADIUS
-                {
-                    break;
+    // Ages everyone, removes the people dying of other causes and adds the newborns, placed
+    // like everyone at the start. Returns whether any of the infected died.
+    fn update_vital_dynamics<R: Rng>(&mut self, dt: f64, rng: &mut R) -> bool {
+        for age in self.people.ages_mut().iter_mut().flatten() {
+            *age += dt / vital::DAYS_PER_YEAR;
+        }
+
+        let keep: Vec<bool> = (0..self.people.len())
+            .map(|index| {
+                let status = self.people.statuses()[index];
+                let age = self.people.ages()[index].unwrap_or(0.0);
+                status.dead().is_some()
+                    || rng.gen::<f64>() >= vital::death_prob(&self.params, age, dt)
+            })
+            .collect();
+        let mut died = vec![];
+        for (index, _) in keep.iter().enumerate().filter(|(_, &keep)| !keep) {
+            if let Some(community) = self.people.communities()[index] {
+                self.community_sizes[community] -= 1;
+            }
+            died.push(self.people.ids()[index]);
+        }
+        let mut index = 0;
+        let removed = self.people.retain(|_| {
+            index += 1;
+            keep[index - 1]
+        });
+        self.turnover.natural_deaths += removed.len();
+        self.count_removed(&removed);
+        for person in died {
+            self.emit(SimEvent::DiedNaturally { person });
+        }
+        let infected_died = removed
+            .iter()
+            .any(|(status, _)| status.infected().is_some());
+
+        let living = self
+            .people
+            .statuses()
+            .iter()
+            .filter(|status| status.dead().is_none())
+            .count();
+        let births = vital::births(&self.params, living, dt, rng);
+        // joining the community they're born in
+        let density = self.density.clone();
+        let constraints = Constraints {
+            box_size: self.box_size,
+            density: density.as_deref(),
+            communities: &self.communities,
+            walls: &self.walls,
+        };
+        let mut placed = self.people.positions().to_vec();
+        let mut born = vec![];
+        for _ in 0..births {
+            match constraints.try_random_spot(rng, &placed, vital::BIRTH_PLACEMENT_ATTEMPTS) {
+                Some(position) => {
+                    placed.push(position);
+                    born.push(position);
                 }
+                None => break,
             }
         }
+        for position in born {
+            self.people
+                .push(Person::random_at(rng, position, self.params.speed_stdev));
+            let index = self.people.len() - 1;
+            let community = self
+                .communities
+                .iter()
+                .position(|community| community.contains(position));
+            self.people.communities_mut()[index] = community;
+            self.people.ages_mut()[index] = Some(0.0);
+            if let Some(community) = community {
+                self.community_sizes[community] += 1;
+            }
+            self.turnover.births += 1;
+        }
+        infected_died
+    }
 
-        pairs
+    pub fn turnover(&self) -> Turnover {
+        self.turnover
     }
 
-    fn apply_collisions<R: Rng>(&mut self, collisions: HashSet<(usize, usize)>, rng: &mut R) {
-        for (index1, index2) in collisions {
-            let (new_vel1, new_vel2) = {
-                let person1 = &self.people[index1];
-                let person2 = &self.people[index2];
-                let normal = clamp_vec2(person2.pos() - person1.pos(), self.box_size).normalize();
-                let relative_vel = person1.vel() - person2.vel();
-                let vel_norm = relative_vel.dot(&normal);
-                let vel1 = person1.vel();
-                let vel2 = person2.vel();
-                if vel_norm > 0.0 {
-                    (vel1 - vel_norm * normal, vel2 + vel_norm * normal)
+    fn give_second_doses(&mut self) {
+        for index in 0..self.people.len() {
+            if self.people.statuses()[index].second_dose_due(self.time, self.params) {
+                self.vaccinate_person(index);
+            }
+        }
+    }
+
+    fn accrue_costs(&mut self, dt: f64) {
+        let statuses = self.people.statuses();
+        if self.restrictions.lockdown {
+            let living = statuses.iter().filter(|s| s.dead().is_none()).count();
+            self.costs.lockdown_person_days += living as f64 * dt;
+        }
+        let isolated = statuses.iter().filter(|s| self.isolated(s)).count();
+        self.costs.isolation_days += isolated as f64 * dt;
+    }
+
+    // With `auto_lockdown`, this is a closed-loop policy: lock down when the hospitals run
+    // over capacity, lift the lockdown when they are back under it.
+    fn check_capacity<R: Rng>(&mut self, rng: &mut R) {
+        let over_capacity = self.stats().hospital_load(self.params) > self.params.hospital_capacity;
+        if over_capacity != self.over_capacity {
+            self.over_capacity = over_capacity;
+            self.emit(SimEvent::HospitalCapacity {
+                exceeded: over_capacity,
+            });
+            if self.params.auto_lockdown && self.restrictions.lockdown != over_capacity {
+                self.apply(Intervention::ToggleLockdown, rng);
+            }
+        }
+    }
+
+    // fraction of its velocity with which a person actually moves
+    fn mobility(&self, status: &Status) -> f64 {
+        if status.dead().is_some() || self.isolated(status) {
+            0.0
+        } else {
+            let restricted = if self.restrictions.lockdown {
+                self.params.lockdown_mobility
+            } else {
+                1.0
+            };
+            restricted * (1.0 - self.params.weather_mobility_effect * self.weather())
+        }
+    }
+
+    // The fraction of their velocity the people actually move with. The isolated cases on
+    // their way to the hospital move as if nothing held them back.
+    pub fn mobilities(&self) -> Vec<f64> {
+        let admitted = self.admitted();
+        let mut to_hospital = vec![false; self.people.len()];
+        if self.hospital.is_some() {
+            for (person, destination) in self.destinations() {
+                to_hospital[person] |= destination == Destination::Hospital;
+            }
+        }
+        self.people
+            .statuses()
+            .iter()
+            .enumerate()
+            .map(|(index, status)| {
+                if admitted[index] {
+                    0.0
+                } else if to_hospital[index] && self.isolated(status) {
+                    1.0
                 } else {
-                    (vel1, vel2)
+                    self.mobility(status)
                 }
+            })
+            .collect()
+    }
+
+    fn move_people(&mut self, dt: f64) {
+        let mobility = self.mobilities();
+        self.people.shift(dt, &mobility, self.box_size);
+    }
+
+    // The pairs found by the physics still meet in this step, the relaxation only keeps them
+    // from piling up into the next ones.
+    fn relieve_pressure(&mut self) {
+        let solid: Vec<_> = self
+            .people
+            .statuses()
+            .iter()
+            .map(|status| status.dead().is_none() && !self.isolated(status))
+            .collect();
+        let box_size = self.box_size;
+        let iterations = self.params.pressure_iterations;
+        let (positions, _, _) = self.people.parts_mut();
+        pressure::relax(positions, &solid, box_size, iterations);
+    }
+
+    fn find_collisions(&self) -> BTreeSet<(usize, usize)> {
+        broad_phase::find_pairs(self.people.positions(), self.box_size)
+    }
+
+    fn apply_collisions<R: Rng>(
+        &mut self,
+        collisions: BTreeSet<(usize, usize)>,
+        dt: f64,
+        rng: &mut R,
+    ) -> usize {
+        // emitted after all the pairs are processed, the statuses are borrowed until then
+        let mut events = vec![];
+        let mut ongoing = HashSet::new();
+        let mut touching = HashSet::new();
+        // the groups of the people in the contacts that started in this step
+        let mut started = vec![];
+        let admitted = self.admitted();
+        for (index1, index2) in collisions {
+            let status1 = self.people.statuses()[index1];
+            let status2 = self.people.statuses()[index2];
+            // quarantined people don't meet anyone, other than the hospital staff tending to
+            // them
+            let tended = |patient: usize, other: usize| {
+                admitted[patient] && self.hospital_staff.contains(&self.people.ids()[other])
             };
-            self.people[index1].set_vel(new_vel1);
-            self.people[index2].set_vel(new_vel2);
-            let copy1 = self.people[index1].clone();
-            let copy2 = self.people[index2].clone();
-            self.people[index1].contact(self.time, self.params, copy2, rng);
-            self.people[index2].contact(self.time, self.params, copy1, rng);
+            if (self.isolated(&status1) || self.isolated(&status2))
+                && !tended(index1, index2)
+                && !tended(index2, index1)
+            {
+                continue;
+            }
+            let vel1 = self.people.velocities()[index1];
+            let vel2 = self.people.velocities()[index2];
+            let id1 = self.people.ids()[index1];
+            let id2 = self.people.ids()[index2];
+            let (time, params) = (self.time, self.infection_params());
+            let (pos1, pos2) = (
+                self.people.positions()[index1],
+                self.people.positions()[index2],
+            );
+            let groups = (self.group(index1), self.group(index2));
+            let midpoint = pos1 + clamp_vec2(pos2 - pos1, self.box_size) / 2.0;
+            let exposure = self.crowding(midpoint)
+                * self.zone_factor(midpoint)
+                * (1.0 + params.weather_transmission_effect * self.weather());
+            let (positions, velocities, statuses) = self.people.parts_mut();
+            let dead1 = status1.dead().is_some();
+            let dead2 = status2.dead().is_some();
+            if dead1 || dead2 {
+                // corpses don't collide, they can only pass on the infection
+                if dead1 != dead2 && params.corpse_infectious {
+                    let (living, corpse, corpse_id) = if dead1 {
+                        (index2, status1, id1)
+                    } else {
+                        (index1, status2, id2)
+                    };
+                    let source = statuses[living].contact(
+                        time,
+                        params,
+                        &self.strains,
+                        &corpse,
+                        Setting::Corpse,
+                        exposure,
+                        rng,
+                    );
+                    if let Some(source) = source {
+                        let person = if dead1 { id2 } else { id1 };
+                        events.push(SimEvent::Infected {
+                            person,
+                            infector: Some(corpse_id),
+                            source,
+                        });
+                    }
+                }
+                continue;
+            }
+            events.push(SimEvent::CollisionPairTested {
+                person1: id1,
+                person2: id2,
+            });
+            let pair = (id1.min(id2), id1.max(id2));
+            if !self.touching.contains(&pair) {
+                started.push(groups);
+            }
+            touching.insert(pair);
+            let normal =
+                clamp_vec2(positions[index2] - positions[index1], self.box_size).normalize();
+            let vel_norm = (vel1 - vel2).dot(&normal);
+            // in the event-driven mode, the velocities have already been updated
+            if vel_norm > 0.0 && !params.event_driven {
+                velocities[index1] = vel1 - vel_norm * normal;
+                velocities[index2] = vel2 + vel_norm * normal;
+            }
+            if params.contact_duration {
+                *self.contacts.entry(pair).or_insert(0.0) += dt * exposure;
+                ongoing.insert(pair);
+                continue;
+            }
+            for &(index, other, person, infector) in
+                &[(index1, status2, id1, id2), (index2, status1, id2, id1)]
+            {
+                let source = statuses[index].contact(
+                    time,
+                    params,
+                    &self.strains,
+                    &other,
+                    Setting::Contact,
+                    exposure,
+                    rng,
+                );
+                if let Some(source) = source {
+                    events.push(SimEvent::Infected {
+                        person,
+                        infector: Some(infector),
+                        source,
+                    });
+                }
+            }
         }
+
+        let ended: Vec<_> = self
+            .contacts
+            .iter()
+            .filter(|(pair, _)| !ongoing.contains(pair))
+            .map(|(&pair, &duration)| (pair, duration))
+            .collect();
+        for (pair, duration) in ended {
+            self.contacts.remove(&pair);
+            self.end_contact(pair, duration, rng, &mut events);
+        }
+
+        for event in events {
+            self.emit(event);
+        }
+
+        self.mixing
+            .record(self.time, self.communities.len() + 1, &started);
+        self.touching = touching;
+        started.len()
+    }
+
+    // the community of the person at `index`, or the group after the communities for the
+    // people outside of them
+    fn group(&self, index: usize) -> usize {
+        self.people.communities()[index].unwrap_or(self.communities.len())
+    }
+
+    // How much more exposed people are in a crowd than at the average density, with
+    // `crowding_exponent`.
+    fn crowding(&self, pos: Vector2<f64>) -> f64 {
+        let exponent = self.params.crowding_exponent;
+        if exponent == 0.0 {
+            return 1.0;
+        }
+        let r = self.params.crowding_radius;
+        let area = self.box_size.0 * self.box_size.1;
+        let expected = self.living() as f64 * PI * r * r / area;
+        let statuses = self.people.statuses();
+        let neighbors = self
+            .neighbors_within(pos, r)
+            .into_iter()
+            .filter(|&index| statuses[index].dead().is_none())
+            .count();
+        (neighbors as f64 / expected).powf(exponent)
+    }
+
+    // the same for a cell of the region grid, from the number of people in it
+    pub fn region_crowding(&self, cell: (usize, usize)) -> f64 {
+        let exponent = self.params.crowding_exponent;
+        if exponent == 0.0 {
+            return 1.0;
+        }
+        let (cells_x, cells_y) = self.regions.size();
+        let expected = self.living() as f64 / (cells_x * cells_y) as f64;
+        (self.regions.stats(cell).population as f64 / expected).powf(exponent)
+    }
+
+    // both people get a chance to be infected, once for the whole contact
+    fn end_contact<R: Rng>(
+        &mut self,
+        (id1, id2): (usize, usize),
+        duration: f64,
+        rng: &mut R,
+        events: &mut Vec<SimEvent>,
+    ) {
+        let (index1, index2) = match (self.index_of(id1), self.index_of(id2)) {
+            (Some(index1), Some(index2)) => (index1, index2),
+            // the dead are removed eventually
+            _ => return,
+        };
+        let (time, params) = (self.time, self.infection_params());
+        let exposure = duration / params.reference_contact_duration;
+        let statuses = self.people.statuses_mut();
+        let (status1, status2) = (statuses[index1], statuses[index2]);
+        for &(index, other, person, infector) in
+            &[(index1, status2, id1, id2), (index2, status1, id2, id1)]
+        {
+            let source = statuses[index].contact(
+                time,
+                params,
+                &self.strains,
+                &other,
+                Setting::Contact,
+                exposure,
+                rng,
+            );
+            if let Some(source) = source {
+                events.push(SimEvent::Infected {
+                    person,
+                    infector: Some(infector),
+                    source,
+                });
+            }
+        }
+    }
+
+    // everyone who took part so far, less those who died of other causes
+    fn total(&self) -> usize {
+        (self.params.num_people + self.turnover.births).saturating_sub(self.turnover.natural_deaths)
+    }
+
+    fn living(&self) -> usize {
+        self.total().saturating_sub(self.totals.deaths).max(1)
     }
 
     pub fn stats(&self) -> Statistics {
+        let mut stats = self.stats_of(self.people.statuses().iter(), self.total());
+        if self.hospital.is_some() {
+            stats.hospitalized = self
+                .admitted()
+                .into_iter()
+                .filter(|&admitted| admitted)
+                .count();
+        }
+        stats
+    }
+
+    pub fn community_stats(&self, community: usize) -> Statistics {
+        let statuses = self
+            .people
+            .statuses()
+            .iter()
+            .zip(self.people.communities())
+            .filter(|(_, &c)| c == Some(community))
+            .map(|(status, _)| status);
+        self.stats_of(statuses, self.community_sizes[community])
+    }
+
+    // `total` is the number of people in the group at the start, so that the dead who have
+    // been removed are still counted
+    fn stats_of<'a, I>(&self, statuses: I, total: usize) -> Statistics
+    where
+        I: Iterator<Item = &'a Status> + Clone,
+    {
         let mut result: Statistics = Default::default();
 
-        result.population = self.people.len();
-        result.dead = self.params.num_people - self.people.len();
+        let living = statuses.filter(|status| status.dead().is_none());
 
-        for person in &self.people {
-            if person.status().infected().is_some() {
+        result.population = living.clone().count();
+        result.dead = total.saturating_sub(result.population);
+
+        for status in living {
+            if status.infected().is_some() {
                 result.infected += 1;
             }
-            if person.status().vaccinated() {
+            if status.vaccinated() {
                 result.vaccinated += 1;
             }
-            if person.status().vaccinated() && person.status().infected().is_some() {
+            if status.hybrid_immune() {
+                result.hybrid_immune += 1;
+            }
+            match status.doses() {
+                1 => result.one_dose += 1,
+                2 => result.two_doses += 1,
+                _ => (),
+            }
+            if status.vaccinated() && status.infected().is_some() {
                 result.vaccinated_infected += 1;
             }
-            if person.status().past_infected()
-                && !person.status().vaccinated()
-                && person.status().infected().is_none()
-            {
+            if status.past_infected() && !status.vaccinated() && status.infected().is_none() {
                 result.healed += 1;
             }
+            match status.severity() {
+                Some(Severity::Severe) => result.severe += 1,
+                Some(Severity::Critical) => result.critical += 1,
+                _ => (),
+            }
         }
 
         result
@@ -266,4 +1977,156 @@ pub struct Statistics {
     pub healed: usize,
     pub vaccinated: usize,
     pub vaccinated_infected: usize,
+    // dose coverage among the living
+    pub one_dose: usize,
+    pub two_doses: usize,
+    pub hybrid_immune: usize,
+    pub severe: usize,
+    pub critical: usize,
+    // the severe and critical cases in the hospital, if there is one
+    pub hospitalized: usize,
+}
+
+impl Statistics {
+    // demand for hospital resources, in the same units as `hospital_capacity`
+    pub fn hospital_load(&self, params: Params) -> f64 {
+        self.severe as f64 * params.severe_cost + self.critical as f64 * params.critical_cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::simulation::setup::Setup;
+
+    fn history(params: Params, seed: u64) -> String {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sim = Setup::new(params).create(&mut rng);
+        while sim.time() < 10.0 {
+            sim.step(0.0, &mut rng, &HEADLESS);
+        }
+        format!("{:?}", sim.history())
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_run() {
+        let params = Params {
+            num_people: 300,
+            init_infected: 10,
+            ..Default::default()
+        };
+        assert_eq!(history(params, 7), history(params, 7));
+        let params = Params {
+            contact_duration: true,
+            ..params
+        };
+        assert_eq!(history(params, 7), history(params, 7));
+    }
+
+    #[test]
+    fn the_reporting_leaves_the_run_as_it_was() {
+        let params = Params {
+            num_people: 300,
+            init_infected: 10,
+            case_detection_probability: 0.5,
+            ..Default::default()
+        };
+        let reported = Params {
+            reporting: true,
+            ..params
+        };
+        assert_eq!(history(params, 7), history(reported, 7));
+    }
+
+    #[test]
+    fn the_natural_deaths_are_counted_and_the_newborns_placed() {
+        let params = Params {
+            num_people: 300,
+            init_infected: 10,
+            vital_dynamics: true,
+            natural_mortality: [0.5; 4],
+            birth_rate: 2.0,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut sim = Setup::new(params).create(&mut rng);
+        while sim.time() < 30.0 {
+            sim.step(0.0, &mut rng, &HEADLESS);
+        }
+        let turnover = sim.turnover();
+        assert!(turnover.natural_deaths > 0 && turnover.births > 0);
+        // everyone is still there or in the tally
+        let removed: usize = sim.removed().values().sum();
+        assert_eq!(sim.statuses().len() + removed, 300 + turnover.births);
+        let newborns = sim.ages().iter().filter(|&&age| age < Some(0.1)).count();
+        assert!(newborns > 0);
+    }
+
+    // everyone infected and severe, in a box of 20 by 20 with the hospital in its left half
+    fn severe_cases_with_a_hospital(hospital_capacity: f64) -> Simulation {
+        let params = Params {
+            num_people: 20,
+            size_x: 20.0,
+            size_y: 20.0,
+            severe_fraction: 1.0,
+            critical_fraction: 0.0,
+            severe_cost: 1.0,
+            hospital_capacity,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut sim = Setup::new(params).create(&mut rng);
+        let hospital = Hospital {
+            x: 0.0,
+            y: 0.0,
+            width: 10.0,
+            height: 20.0,
+            staff: 0,
+            tint: [1.0; 3],
+        };
+        sim.set_hospital(hospital, &mut rng);
+        for status in sim.people.statuses_mut() {
+            status.infect(0.0, params, &mut rng);
+        }
+        sim
+    }
+
+    #[test]
+    fn only_as_many_are_admitted_as_the_hospital_has_room_for() {
+        let sim = severe_cases_with_a_hospital(2.0);
+        let inside = sim
+            .people
+            .positions()
+            .iter()
+            .filter(|pos| pos.x < 10.0)
+            .count();
+        assert!(inside > 2);
+        assert_eq!(sim.stats().hospitalized, 2);
+        let mobilities = sim.mobilities();
+        assert_eq!(
+            mobilities
+                .iter()
+                .filter(|&&mobility| mobility == 0.0)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn the_isolated_severe_cases_are_taken_to_the_hospital() {
+        let mut sim = severe_cases_with_a_hospital(20.0);
+        sim.restrictions.quarantine = true;
+        let outside: Vec<_> = (0..sim.people.len())
+            .filter(|&index| sim.people.positions()[index].x >= 10.0)
+            .collect();
+        assert!(!outside.is_empty());
+        let destinations = sim.destinations();
+        let mobilities = sim.mobilities();
+        for index in outside {
+            assert!(destinations.contains(&(index, Destination::Hospital)));
+            assert!(mobilities[index] > 0.0);
+        }
+    }
 }
@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+
+use nalgebra::Vector2;
+
+use super::{clamp_vec2, person::RADIUS};
+
+// Returns the overlapping pairs as (smaller index, larger index), in order, so that a seeded
+// run going through them doesn't depend on the hashing.
+pub fn find_pairs(positions: &[Vector2<f64>], box_size: (f64, f64)) -> BTreeSet<(usize, usize)> {
+    find_pairs_within(positions, box_size, RADIUS * 2.0)
+}
+
+// Sweep and prune along the x axis, taking the periodic boundary into account.
+pub fn find_pairs_within(
+    positions: &[Vector2<f64>],
+    box_size: (f64, f64),
+    distance: f64,
+) -> BTreeSet<(usize, usize)> {
+    crate::trace_span!("broad_phase");
+    let mut sorted: Vec<usize> = (0..positions.len()).collect();
+    sorted.sort_by(|index1, index2| {
+        positions[*index1]
+            .x
+            .partial_cmp(&positions[*index2].x)
+            .unwrap()
+    });
+
+    let mut pairs = BTreeSet::new();
+
+    let len = sorted.len();
+    for (i, &index1) in sorted.iter().enumerate() {
+        let pos1 = positions[index1];
+        for j in i + 1..i + len {
+            let index2 = sorted[j % len];
+            let pos2 = positions[index2];
+            // distance going forward along x, wrapping around the box
+            if (pos2.x - pos1.x).rem_euclid(box_size.0) >= distance {
+                break;
+            }
+            if clamp_vec2(pos2 - pos1, box_size).norm() < distance {
+                pairs.insert((index1.min(index2), index1.max(index2)));
+            }
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::person::overlaps;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn brute_force(positions: &[Vector2<f64>], box_size: (f64, f64)) -> BTreeSet<(usize, usize)> {
+        let mut pairs = BTreeSet::new();
+        for i in 0..positions.len() {
+            for j in i + 1..positions.len() {
+                if overlaps(positions[i], positions[j], box_size) {
+                    pairs.insert((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn random_positions<R: Rng>(rng: &mut R, n: usize, box_size: (f64, f64)) -> Vec<Vector2<f64>> {
+        (0..n)
+            .map(|_| {
+                Vector2::new(
+                    rng.gen_range(0.0, box_size.0),
+                    rng.gen_range(0.0, box_size.1),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for &(n, box_size) in &[
+            (10, (5.0, 5.0)),
+            (100, (10.0, 10.0)),
+            (300, (20.0, 10.0)),
+            (500, (50.0, 50.0)),
+        ] {
+            for _ in 0..20 {
+                let positions = random_positions(&mut rng, n, box_size);
+                assert_eq!(
+                    find_pairs(&positions, box_size),
+                    brute_force(&positions, box_size)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn finds_pairs_across_the_boundary() {
+        let box_size = (10.0, 10.0);
+        let positions = vec![
+            Vector2::new(0.2, 5.0),
+            Vector2::new(9.7, 5.0),
+            Vector2::new(5.0, 0.1),
+            Vector2::new(5.0, 9.5),
+        ];
+        let expected: BTreeSet<_> = vec![(0, 1), (2, 3)].into_iter().collect();
+        assert_eq!(find_pairs(&positions, box_size), expected);
+    }
+
+    #[test]
+    fn prunes_at_the_full_diameter() {
+        // further apart than one radius, but still overlapping
+        let box_size = (10.0, 10.0);
+        let positions = vec![Vector2::new(2.0, 2.0), Vector2::new(2.8, 2.1)];
+        let expected: BTreeSet<_> = vec![(0, 1)].into_iter().collect();
+        assert_eq!(find_pairs(&positions, box_size), expected);
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+};
+
+use nalgebra::Vector2;
+use rand::{
+    distributions::{Distribution, Uniform},
+    Rng,
+};
+
+use super::{
+    person::{Person, RADIUS},
+    placement::{self, Constraints},
+    population::Population,
+    Simulation,
+};
+
+pub const HEADER: &str = "id,x,y,age,vaccinated,infected,recovered,home_x,home_y";
+// the files from before the recovery times were kept
+const OLD_HEADER: &str = "id,x,y,age,vaccinated,infected,home_x,home_y";
+// an optional first line, with the simulation time the census was taken at
+const TIME_PREFIX: &str = "# time ";
+// tries at moving a person who breaks a placement rule, each a bit further away
+const PLACEMENT_ATTEMPTS: usize = 1000;
+
+// A person as given in a census file, with the time they last recovered at if they did. The
+// model has no homes, so these are only checked, and left empty when exporting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CensusRow {
+    pub id: usize,
+    pub position: Vector2<f64>,
+    pub age: Option<f64>,
+    pub vaccinated: bool,
+    pub infected: bool,
+    pub recovered: Option<f64>,
+    pub home: Option<Vector2<f64>>,
+}
+
+// The rows sorted by id, and the time the run starts at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Census {
+    pub time: f64,
+    pub rows: Vec<CensusRow>,
+}
+
+fn parse_bool(field: &str) -> Option<bool> {
+    match field {
+        "true" | "1" => Some(true),
+        "false" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_optional(field: &str) -> Result<Option<f64>, ()> {
+    if field.is_empty() {
+        return Ok(None);
+    }
+    match field.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(Some(value)),
+        _ => Err(()),
+    }
+}
+
+// without the recovery times in the old format
+fn parse_row(fields: &[&str], has_recovered: bool) -> Result<CensusRow, String> {
+    let expected = if has_recovered { 9 } else { 8 };
+    if fields.len() != expected {
+        return Err(format!(
+            "expected {} fields, found {}",
+            expected,
+            fields.len()
+        ));
+    }
+    let id = fields[0]
+        .parse()
+        .map_err(|_| format!("invalid id: {}", fields[0]))?;
+    let coordinate = |field: &str| match parse_optional(field) {
+        Ok(Some(value)) => Ok(value),
+        _ => Err(format!("invalid coordinate: {}", field)),
+    };
+    let position = Vector2::new(coordinate(fields[1])?, coordinate(fields[2])?);
+    let age = match parse_optional(fields[3]) {
+        Ok(Some(age)) if age < 0.0 => return Err(format!("negative age: {}", age)),
+        Ok(age) => age,
+        Err(()) => return Err(format!("invalid age: {}", fields[3])),
+    };
+    let flag = |field: &str| parse_bool(field).ok_or(format!("invalid flag: {}", field));
+    let vaccinated = flag(fields[4])?;
+    let infected = flag(fields[5])?;
+    let (recovered, home_fields) = if has_recovered {
+        let recovered = parse_optional(fields[6])
+            .map_err(|()| format!("invalid recovery time: {}", fields[6]))?;
+        (recovered, &fields[7..])
+    } else {
+        (None, &fields[6..])
+    };
+    let home = match (
+        parse_optional(home_fields[0]),
+        parse_optional(home_fields[1]),
+    ) {
+        (Ok(Some(x)), Ok(Some(y))) => Some(Vector2::new(x, y)),
+        (Ok(None), Ok(None)) => None,
+        _ => {
+            return Err(format!(
+                "invalid home: {}, {} (both or neither have to be given)",
+                home_fields[0], home_fields[1]
+            ))
+        }
+    };
+    Ok(CensusRow {
+        id,
+        position,
+        age,
+        vaccinated,
+        infected,
+        recovered,
+        home,
+    })
+}
+
+pub fn read(path: &str) -> io::Result<Census> {
+    parse(BufReader::new(File::open(path)?), path)
+}
+
+// `name` is what the errors call the file
+fn parse<R: BufRead>(file: R, name: &str) -> io::Result<Census> {
+    let mut time = 0.0;
+    let mut has_recovered = None;
+    let mut rows = vec![];
+    let mut ids = HashSet::new();
+
+    for (line_no, line) in file.lines().enumerate() {
+        let line = line?;
+        let invalid = |reason: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: {}", name, line_no + 1, reason),
+            )
+        };
+        if has_recovered.is_none() {
+            if let Some(value) = line.trim().strip_prefix(TIME_PREFIX) {
+                if line_no > 0 {
+                    return Err(invalid("the time has to be on the first line".to_owned()));
+                }
+                time = match value.trim().parse::<f64>() {
+                    Ok(value) if value.is_finite() && value >= 0.0 => value,
+                    _ => return Err(invalid(format!("invalid time: {}", value))),
+                };
+                continue;
+            }
+            has_recovered = match line.trim() {
+                HEADER => Some(true),
+                OLD_HEADER => Some(false),
+                _ => return Err(invalid(format!("the header should be {}", HEADER))),
+            };
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        let row = parse_row(&fields, has_recovered == Some(true)).map_err(invalid)?;
+        if !ids.insert(row.id) {
+            return Err(invalid(format!("duplicate id: {}", row.id)));
+        }
+        rows.push(row);
+    }
+
+    rows.sort_by_key(|row| row.id);
+    Ok(Census { time, rows })
+}
+
+// a free spot near `pos`, searching further away with every attempt
+fn free_spot<R: Rng>(
+    rng: &mut R,
+    pos: Vector2<f64>,
+    placed: &[Vector2<f64>],
+    constraints: &Constraints,
+) -> Option<Vector2<f64>> {
+    let box_size = constraints.box_size;
+    let angle = Uniform::new(0.0, 2.0 * std::f64::consts::PI);
+    (1..=PLACEMENT_ATTEMPTS).find_map(|attempt| {
+        let distance = 2.0 * RADIUS * (1.0 + attempt as f64 / 20.0);
+        let angle = angle.sample(rng);
+        let candidate = pos + Vector2::new(angle.cos(), angle.sin()) * distance;
+        let candidate = Vector2::new(
+            candidate.x.rem_euclid(box_size.0),
+            candidate.y.rem_euclid(box_size.1),
+        );
+        if constraints.allows(candidate, placed) {
+            Some(candidate)
+        } else {
+            None
+        }
+    })
+}
+
+// People breaking a placement rule, like overlapping someone with a smaller id, are moved to a
+// free spot nearby. Panics if someone is outside of the box or there is no room left.
+pub fn populate<R: Rng>(
+    rng: &mut R,
+    rows: &[CensusRow],
+    constraints: &Constraints,
+    speed_stdev: f64,
+) -> Population {
+    let box_size = constraints.box_size;
+    for row in rows {
+        let pos = row.position;
+        if pos.x < 0.0 || pos.x >= box_size.0 || pos.y < 0.0 || pos.y >= box_size.1 {
+            panic!(
+                "person {} at ({}, {}) is outside of the {} x {} box",
+                row.id, pos.x, pos.y, box_size.0, box_size.1
+            );
+        }
+    }
+
+    let mut positions: Vec<_> = rows.iter().map(|row| row.position).collect();
+    let violations = placement::validate(&positions, constraints);
+    let moved: Vec<usize> = violations.iter().map(|&(index, _)| index).collect();
+    let mut placed: Vec<_> = (0..positions.len())
+        .filter(|index| moved.binary_search(index).is_err())
+        .map(|index| positions[index])
+        .collect();
+    for &index in &moved {
+        let pos = free_spot(rng, positions[index], &placed, constraints).unwrap_or_else(|| {
+            panic!(
+                "no room for person {} near ({}, {})",
+                rows[index].id, positions[index].x, positions[index].y
+            )
+        });
+        positions[index] = pos;
+        placed.push(pos);
+    }
+    if !moved.is_empty() {
+        println!(
+            "Moved {} people from the census: {}.",
+            moved.len(),
+            placement::summarize(&violations)
+        );
+    }
+
+    let mut population = Population::new();
+    for (index, (row, &pos)) in rows.iter().zip(&positions).enumerate() {
+        population.push_with_id(row.id, Person::random_at(rng, pos, speed_stdev));
+        population.ages_mut()[index] = row.age;
+    }
+    population
+}
+
+// The living people in the format read above, so that a run can be continued from its current
+// state and time. The rest of the history is lost: everyone infected starts their infection
+// anew.
+pub fn write(path: &str, sim: &Simulation) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_to(&mut file, sim)?;
+    file.flush()
+}
+
+fn write_to<W: Write>(file: &mut W, sim: &Simulation) -> io::Result<()> {
+    writeln!(file, "{}{}", TIME_PREFIX, sim.time())?;
+    writeln!(file, "{}", HEADER)?;
+    let people = sim.ids().iter().zip(sim.people()).zip(sim.ages());
+    for ((&id, person), age) in people {
+        let status = person.status();
+        if status.dead().is_some() {
+            continue;
+        }
+        let optional =
+            |value: Option<f64>| value.map_or_else(String::new, |value| value.to_string());
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},,",
+            id,
+            person.pos().x,
+            person.pos().y,
+            optional(*age),
+            status.vaccinated(),
+            status.severity().is_some(),
+            optional(status.recovered())
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_text(text: &str) -> io::Result<Census> {
+        parse(text.as_bytes(), "census.csv")
+    }
+
+    fn error(text: &str) -> String {
+        parse_text(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn a_census_is_read_sorted_by_id() {
+        let census = parse_text(
+            "# time 12.5
+id,x,y,age,vaccinated,infected,recovered,home_x,home_y
+3,1.5,2,40,true,false,7.25,,
+1,4,5,,0,1,,10,20
+
+",
+        )
+        .unwrap();
+        assert_eq!(census.time, 12.5);
+        assert_eq!(
+            census.rows,
+            vec![
+                CensusRow {
+                    id: 1,
+                    position: Vector2::new(4.0, 5.0),
+                    age: None,
+                    vaccinated: false,
+                    infected: true,
+                    recovered: None,
+                    home: Some(Vector2::new(10.0, 20.0)),
+                },
+                CensusRow {
+                    id: 3,
+                    position: Vector2::new(1.5, 2.0),
+                    age: Some(40.0),
+                    vaccinated: true,
+                    infected: false,
+                    recovered: Some(7.25),
+                    home: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn the_old_header_has_no_recovery_times() {
+        let census = parse_text(
+            "id,x,y,age,vaccinated,infected,home_x,home_y
+1,4,5,30,false,true,,",
+        )
+        .unwrap();
+        assert_eq!(census.time, 0.0);
+        assert_eq!(census.rows.len(), 1);
+        assert_eq!(census.rows[0].recovered, None);
+        assert!(census.rows[0].infected);
+        assert!(error(&format!("{}\n1,4,5,30,false,true,2,,", OLD_HEADER))
+            .contains("expected 8 fields, found 9"));
+    }
+
+    #[test]
+    fn invalid_files_are_rejected_with_the_line() {
+        let row = |fields: &str| error(&format!("{}\n{}", HEADER, fields));
+        assert_eq!(
+            error("id,x,y"),
+            format!("census.csv:1: the header should be {}", HEADER)
+        );
+        assert!(error("# time 1\n# time 2").contains(":2: the time has to be on the first line"));
+        assert!(error(&format!("# time -1\n{}", HEADER)).contains(":1: invalid time: -1"));
+        assert!(error(&format!("# time NaN\n{}", HEADER)).contains("invalid time: NaN"));
+        assert!(row("1,4,5,30,false,true,,").contains(":2: expected 9 fields, found 8"));
+        assert!(row("a,4,5,30,false,true,,,").contains("invalid id: a"));
+        assert!(row("1,,5,30,false,true,,,").contains("invalid coordinate: "));
+        assert!(row("1,inf,5,30,false,true,,,").contains("invalid coordinate: inf"));
+        assert!(row("1,4,5,-3,false,true,,,").contains("negative age: -3"));
+        assert!(row("1,4,5,old,false,true,,,").contains("invalid age: old"));
+        assert!(row("1,4,5,30,yes,true,,,").contains("invalid flag: yes"));
+        assert!(row("1,4,5,30,false,true,soon,,").contains("invalid recovery time: soon"));
+        assert!(row("1,4,5,30,false,true,,10,").contains("both or neither have to be given"));
+        assert!(error(&format!(
+            "{}\n1,4,5,30,false,true,,,\n1,6,7,30,false,true,,,",
+            HEADER
+        ))
+        .contains(":3: duplicate id: 1"));
+    }
+
+    #[test]
+    fn a_written_census_reads_back_as_the_state_it_was_taken_in() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        use crate::simulation::{params::Params, setup::Setup};
+
+        let params = Params {
+            num_people: 20,
+            init_infected: 5,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut sim = Setup::new(params).create(&mut rng);
+        sim.start_at(4.5);
+        sim.people.statuses_mut()[6].mark_recovered(2.25);
+        sim.people.statuses_mut()[7].vaccinate(1.0, 0);
+        sim.people.statuses_mut()[8].die(3.0);
+        sim.people.ages_mut()[9] = Some(30.5);
+
+        let mut file = vec![];
+        write_to(&mut file, &sim).unwrap();
+        let census = parse(&file[..], "census.csv").unwrap();
+        assert_eq!(census.time, 4.5);
+        let living: Vec<_> = sim
+            .ids()
+            .iter()
+            .zip(sim.people())
+            .zip(sim.ages())
+            .filter(|((_, person), _)| person.status().dead().is_none())
+            .map(|((&id, person), &age)| CensusRow {
+                id,
+                position: person.pos(),
+                age,
+                vaccinated: person.status().vaccinated(),
+                infected: person.status().severity().is_some(),
+                recovered: person.status().recovered(),
+                home: None,
+            })
+            .collect();
+        assert_eq!(living.len(), 19);
+        assert_eq!(census.rows, living);
+        assert!(census.rows.iter().any(|row| row.infected));
+        assert!(census.rows.iter().any(|row| row.recovered == Some(2.25)));
+        assert!(census.rows.iter().any(|row| row.age == Some(30.5)));
+    }
+}
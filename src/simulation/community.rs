@@ -0,0 +1,30 @@
+use nalgebra::Vector2;
+use serde_derive::Deserialize;
+
+use super::geometry::Rect;
+
+// A rectangular area, given as a `[[communities]]` table in the config. People belong to
+// the community they are placed in at the start.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Community {
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Community {
+    pub fn rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn contains(&self, pos: Vector2<f64>) -> bool {
+        self.rect().contains(pos)
+    }
+}
@@ -0,0 +1,33 @@
+// simulated days over which the rate is averaged
+const SAMPLE_PERIOD: f64 = 1.0;
+
+// How many others a living person meets per day, averaged over the last full sample period.
+// Only the start of a contact counts, not every step of it.
+#[derive(Debug, Clone, Default)]
+pub struct ContactRate {
+    sample_start: f64,
+    contacts: u64,
+    person_days: f64,
+    pub per_person_day: Option<f64>,
+}
+
+impl ContactRate {
+    // `contacts` started in the step ending at `time`, which the living lived through for
+    // `person_days` in total
+    pub fn record(&mut self, time: f64, contacts: usize, person_days: f64) {
+        self.contacts += contacts as u64;
+        self.person_days += person_days;
+        if time - self.sample_start < SAMPLE_PERIOD {
+            return;
+        }
+        // each contact is one met by both of the people
+        self.per_person_day = if self.person_days > 0.0 {
+            Some(2.0 * self.contacts as f64 / self.person_days)
+        } else {
+            None
+        };
+        self.sample_start = time;
+        self.contacts = 0;
+        self.person_days = 0.0;
+    }
+}
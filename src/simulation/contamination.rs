@@ -0,0 +1,196 @@
+use nalgebra::Vector2;
+
+// the explicit steps get split so that none moves more than this fraction of a cell's
+// contamination out of it, which keeps them stable
+const MAX_OUTFLOW: f64 = 0.5;
+
+// How much infection the infected have left on the surfaces, over a grid of cells covering
+// the box, along with the strain left there last.
+#[derive(Debug, Clone)]
+pub struct ContaminationGrid {
+    box_size: (f64, f64),
+    cells_x: usize,
+    cells_y: usize,
+    levels: Vec<f64>,
+    strains: Vec<usize>,
+}
+
+impl ContaminationGrid {
+    pub fn new(box_size: (f64, f64), cells_per_side: usize) -> ContaminationGrid {
+        let cells_per_side = cells_per_side.max(1);
+        ContaminationGrid {
+            box_size,
+            cells_x: cells_per_side,
+            cells_y: cells_per_side,
+            levels: vec![0.0; cells_per_side * cells_per_side],
+            strains: vec![0; cells_per_side * cells_per_side],
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.cells_x, self.cells_y)
+    }
+
+    fn cell_index(&self, pos: Vector2<f64>) -> Option<usize> {
+        let x = pos.x / self.box_size.0 * self.cells_x as f64;
+        let y = pos.y / self.box_size.1 * self.cells_y as f64;
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if x >= self.cells_x || y >= self.cells_y {
+            return None;
+        }
+        Some(y * self.cells_x + x)
+    }
+
+    pub fn deposit(&mut self, pos: Vector2<f64>, amount: f64, strain: usize) {
+        if let Some(index) = self.cell_index(pos) {
+            self.levels[index] += amount;
+            self.strains[index] = strain;
+        }
+    }
+
+    // halves the contamination every `half_life` days
+    pub fn decay(&mut self, dt: f64, half_life: f64) {
+        let factor = 0.5f64.powf(dt / half_life);
+        for level in &mut self.levels {
+            *level *= factor;
+        }
+    }
+
+    // Carries the contamination with the `wind`, in units of length a day, and spreads it
+    // with the `diffusion` rate, in units of area a day. The box wraps around like for the
+    // people. Each cell takes the strain of whichever of it and its neighbours had the most.
+    pub fn advect(&mut self, dt: f64, wind: Vector2<f64>, diffusion: f64) {
+        let dx = self.box_size.0 / self.cells_x as f64;
+        let dy = self.box_size.1 / self.cells_y as f64;
+        let outflow = wind.x.abs() / dx
+            + wind.y.abs() / dy
+            + 2.0 * diffusion * (1.0 / (dx * dx) + 1.0 / (dy * dy));
+        let substeps = (dt * outflow / MAX_OUTFLOW).ceil().max(1.0) as usize;
+        let h = dt / substeps as f64;
+        for _ in 0..substeps {
+            self.advect_step(h, wind, diffusion, (dx, dy));
+        }
+    }
+
+    fn advect_step(&mut self, h: f64, wind: Vector2<f64>, diffusion: f64, (dx, dy): (f64, f64)) {
+        let (cells_x, cells_y) = (self.cells_x, self.cells_y);
+        let old = self.levels.clone();
+        let old_strains = self.strains.clone();
+        let at = |x: usize, y: usize| y * cells_x + x;
+        for y in 0..cells_y {
+            for x in 0..cells_x {
+                let left = at((x + cells_x - 1) % cells_x, y);
+                let right = at((x + 1) % cells_x, y);
+                let down = at(x, (y + cells_y - 1) % cells_y);
+                let up = at(x, (y + 1) % cells_y);
+                let here = at(x, y);
+                let c = old[here];
+                // upwind differences
+                let flow_x = if wind.x > 0.0 {
+                    wind.x * (c - old[left]) / dx
+                } else {
+                    wind.x * (old[right] - c) / dx
+                };
+                let flow_y = if wind.y > 0.0 {
+                    wind.y * (c - old[down]) / dy
+                } else {
+                    wind.y * (old[up] - c) / dy
+                };
+                let laplacian = (old[left] + old[right] - 2.0 * c) / (dx * dx)
+                    + (old[down] + old[up] - 2.0 * c) / (dy * dy);
+                self.levels[here] = (c + h * (diffusion * laplacian - flow_x - flow_y)).max(0.0);
+                let strongest = [here, left, right, down, up]
+                    .iter()
+                    .copied()
+                    .max_by(|&a, &b| old[a].partial_cmp(&old[b]).unwrap())
+                    .unwrap();
+                self.strains[here] = old_strains[strongest];
+            }
+        }
+    }
+
+    // the level and the strain of the cell containing `pos`
+    pub fn at(&self, pos: Vector2<f64>) -> (f64, usize) {
+        self.cell_index(pos)
+            .map_or((0.0, 0), |index| (self.levels[index], self.strains[index]))
+    }
+
+    pub fn level(&self, cell: (usize, usize)) -> f64 {
+        self.levels[cell.1 * self.cells_x + cell.0]
+    }
+
+    pub fn max_level(&self) -> f64 {
+        self.levels.iter().copied().fold(0.0, f64::max)
+    }
+
+    // lower left and upper right corner of the cell
+    pub fn bounds(&self, cell: (usize, usize)) -> (Vector2<f64>, Vector2<f64>) {
+        let width = self.box_size.0 / self.cells_x as f64;
+        let height = self.box_size.1 / self.cells_y as f64;
+        let min = Vector2::new(cell.0 as f64 * width, cell.1 as f64 * height);
+        (min, min + Vector2::new(width, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> ContaminationGrid {
+        ContaminationGrid::new((100.0, 100.0), 10)
+    }
+
+    #[test]
+    fn deposits_land_in_the_cell_of_the_position() {
+        let mut grid = grid();
+        grid.deposit(Vector2::new(15.0, 25.0), 2.0, 1);
+        grid.deposit(Vector2::new(19.0, 21.0), 1.0, 3);
+        assert_eq!(grid.at(Vector2::new(11.0, 29.0)), (3.0, 3));
+        assert_eq!(grid.level((1, 2)), 3.0);
+        assert_eq!(grid.at(Vector2::new(25.0, 25.0)), (0.0, 0));
+        // outside of the box
+        grid.deposit(Vector2::new(-1.0, 50.0), 5.0, 0);
+        assert_eq!(grid.max_level(), 3.0);
+    }
+
+    #[test]
+    fn contamination_halves_with_the_half_life() {
+        let mut grid = grid();
+        grid.deposit(Vector2::new(50.0, 50.0), 8.0, 0);
+        for _ in 0..4 {
+            grid.decay(0.25, 0.5);
+        }
+        assert!((grid.level((5, 5)) - 2.0).abs() < 1e-9);
+    }
+
+    fn total(grid: &ContaminationGrid) -> f64 {
+        grid.levels.iter().sum()
+    }
+
+    #[test]
+    fn the_wind_carries_the_contamination_downwind() {
+        let mut grid = grid();
+        grid.deposit(Vector2::new(55.0, 55.0), 1.0, 2);
+        // a cell a day to the right
+        grid.advect(3.0, Vector2::new(10.0, 0.0), 0.0);
+        assert!((total(&grid) - 1.0).abs() < 1e-9);
+        assert!(grid.level((8, 5)) > grid.level((2, 5)));
+        assert_eq!(grid.level((5, 4)), 0.0);
+        assert_eq!(grid.at(Vector2::new(85.0, 55.0)).1, 2);
+    }
+
+    #[test]
+    fn diffusion_spreads_evenly_and_wraps_around() {
+        let mut grid = grid();
+        grid.deposit(Vector2::new(5.0, 5.0), 1.0, 0);
+        grid.advect(1.0, Vector2::new(0.0, 0.0), 20.0);
+        assert!((total(&grid) - 1.0).abs() < 1e-9);
+        assert!(grid.level((0, 0)) < 1.0);
+        assert!((grid.level((1, 0)) - grid.level((9, 0))).abs() < 1e-12);
+        assert!((grid.level((0, 1)) - grid.level((0, 9))).abs() < 1e-12);
+        assert!(grid.level((9, 0)) > 0.0);
+    }
+}
@@ -0,0 +1,46 @@
+use super::{
+    events::{SimEvent, Subscriber},
+    params::Params,
+};
+
+// Running totals of what the interventions used up, as a crude economic proxy. The weights
+// of the components are parameters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Costs {
+    // living people times the days spent in lockdown
+    pub lockdown_person_days: f64,
+    pub vaccines: usize,
+    // under quarantine, every infected person is tested once
+    pub tests: usize,
+    pub isolation_days: f64,
+}
+
+impl Costs {
+    pub fn total(&self, params: Params) -> f64 {
+        self.lockdown_person_days * params.lockdown_day_cost
+            + self.vaccines as f64 * params.vaccine_cost
+            + self.tests as f64 * params.test_cost
+            + self.isolation_days * params.isolation_day_cost
+    }
+
+    pub fn describe(&self, params: Params) -> String {
+        format!(
+            "cost {:.1} (lockdown person-days {:.1}, vaccines {}, tests {}, isolation days {:.1})",
+            self.total(params),
+            self.lockdown_person_days,
+            self.vaccines,
+            self.tests,
+            self.isolation_days
+        )
+    }
+}
+
+impl Subscriber for Costs {
+    fn notify(&mut self, _time: f64, event: &SimEvent) {
+        match *event {
+            SimEvent::Vaccinated { .. } => self.vaccines += 1,
+            SimEvent::Tested { people } => self.tests += people,
+            _ => (),
+        }
+    }
+}
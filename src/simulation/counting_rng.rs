@@ -0,0 +1,47 @@
+use rand::{Error, RngCore, SeedableRng};
+
+// A generator counting the values taken from it, so that a run can say how far into its seed
+// it was when something happened, eg. in the header of the event log.
+#[derive(Debug, Clone)]
+pub struct CountingRng<R> {
+    inner: R,
+    draws: u64,
+}
+
+impl<R: SeedableRng> CountingRng<R> {
+    pub fn seed_from_u64(seed: u64) -> CountingRng<R> {
+        CountingRng {
+            inner: R::seed_from_u64(seed),
+            draws: 0,
+        }
+    }
+}
+
+impl<R> CountingRng<R> {
+    // the 32- and 64-bit values taken so far, with the bytes counted in 64-bit words
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+}
+
+impl<R: RngCore> RngCore for CountingRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.draws += 1;
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.draws += (dest.len() as u64).div_ceil(8);
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.draws += (dest.len() as u64).div_ceil(8);
+        self.inner.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,155 @@
+use std::{fs, io};
+
+use nalgebra::Vector2;
+use rand::{
+    distributions::{Distribution, Uniform},
+    Rng,
+};
+
+use super::person::RADIUS;
+
+// Relative population density over a grid of cells stretched over the whole box. The first
+// row is the top of the box, like in an image.
+#[derive(Debug, Clone)]
+pub struct DensityMap {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+    max: f64,
+    // running totals of the values, for sampling the cells
+    cumulative: Vec<f64>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl DensityMap {
+    pub fn new(rows: Vec<Vec<f64>>) -> DensityMap {
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            panic!("the density grid should have rows of equal, non-zero length");
+        }
+        if rows.iter().flatten().any(|&value| value < 0.0) {
+            panic!("densities can't be negative");
+        }
+        let values: Vec<f64> = rows.into_iter().flatten().collect();
+        let cumulative: Vec<f64> = values
+            .iter()
+            .scan(0.0, |total, value| {
+                *total += value;
+                Some(*total)
+            })
+            .collect();
+        if cumulative.last() == Some(&0.0) {
+            panic!("the density map is empty");
+        }
+        let max = values.iter().cloned().fold(0.0, f64::max);
+        DensityMap {
+            width,
+            height,
+            values,
+            max,
+            cumulative,
+        }
+    }
+
+    // a grayscale image in the PGM format, brighter meaning denser
+    pub fn load_pgm(path: &str) -> io::Result<DensityMap> {
+        let data = fs::read(path)?;
+
+        // the header is whitespace-separated, with comments starting with #
+        let mut fields = vec![];
+        let mut pos = 0;
+        while fields.len() < 4 {
+            while pos < data.len() && data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos < data.len() && data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid("truncated PGM header"));
+            }
+            fields.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+        }
+        let number = |field: &str| {
+            field
+                .parse::<usize>()
+                .map_err(|_| invalid("invalid number in PGM header"))
+        };
+        let (width, height, max) = (
+            number(&fields[1])?,
+            number(&fields[2])?,
+            number(&fields[3])?,
+        );
+
+        let pixels: Vec<f64> = match fields[0].as_str() {
+            "P2" => String::from_utf8_lossy(&data[pos..])
+                .split_ascii_whitespace()
+                .map(|value| number(value).map(|value| value as f64))
+                .collect::<io::Result<_>>()?,
+            "P5" if max < 256 => data[pos + 1..].iter().map(|&value| value as f64).collect(),
+            "P5" => data[pos + 1..]
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as f64)
+                .collect(),
+            _ => return Err(invalid("not a PGM file")),
+        };
+        if pixels.len() < width * height {
+            return Err(invalid("truncated PGM data"));
+        }
+
+        let rows = pixels[..width * height]
+            .chunks(width)
+            .map(|row| row.iter().map(|value| value / max as f64).collect())
+            .collect();
+        Ok(DensityMap::new(rows))
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    // relative to the densest cell
+    pub fn value(&self, x: usize, y: usize) -> f64 {
+        self.values[y * self.width + x] / self.max
+    }
+
+    // relative to the densest cell, at a position in the box
+    pub fn at(&self, pos: Vector2<f64>, box_size: (f64, f64)) -> f64 {
+        let cell_x = (pos.x / box_size.0 * self.width as f64) as usize;
+        let cell_y = (pos.y / box_size.1 * self.height as f64) as usize;
+        // the rows go from the top
+        let row = self.height - 1 - cell_y.min(self.height - 1);
+        self.value(cell_x.min(self.width - 1), row)
+    }
+
+    // a random position in the box, with the probability proportional to the density
+    pub fn sample<R: Rng>(&self, rng: &mut R, box_size: (f64, f64)) -> Vector2<f64> {
+        let total = *self.cumulative.last().unwrap();
+        let draw = Uniform::new(0.0, total).sample(rng);
+        let index = self
+            .cumulative
+            .partition_point(|&sum| sum <= draw)
+            .min(self.values.len() - 1);
+        let (cell_x, cell_y) = (index % self.width, index / self.width);
+
+        let cell_w = box_size.0 / self.width as f64;
+        let cell_h = box_size.1 / self.height as f64;
+        let x = (cell_x as f64 + rng.gen::<f64>()) * cell_w;
+        let y = (self.height - 1 - cell_y) as f64 * cell_h + rng.gen::<f64>() * cell_h;
+        Vector2::new(
+            x.max(RADIUS).min(box_size.0 - RADIUS),
+            y.max(RADIUS).min(box_size.1 - RADIUS),
+        )
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, BinaryHeap},
+};
+
+use nalgebra::Vector2;
+
+use super::{broad_phase, clamp_f64, clamp_vec2, person::RADIUS, Simulation};
+
+// A predicted collision between two people. `count1` and `count2` are the numbers of
+// collisions the people had been through when the event was predicted - if either of them
+// collided with someone else in the meantime, the event is no longer valid.
+#[derive(Debug, Clone, Copy)]
+struct Event {
+    time: f64,
+    index1: usize,
+    index2: usize,
+    count1: usize,
+    count2: usize,
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Event) -> bool {
+        self.time == other.time
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Event) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    // reversed, so that the earliest event is on top of the heap
+    fn cmp(&self, other: &Event) -> Ordering {
+        other.time.partial_cmp(&self.time).unwrap()
+    }
+}
+
+struct State {
+    box_size: (f64, f64),
+    // position at time 0 of the step, extrapolated along the current velocity
+    origins: Vec<Vector2<f64>>,
+    velocities: Vec<Vector2<f64>>,
+    counts: Vec<usize>,
+    neighbors: Vec<Vec<usize>>,
+    events: BinaryHeap<Event>,
+}
+
+impl State {
+    fn pos(&self, index: usize, time: f64) -> Vector2<f64> {
+        self.origins[index] + self.velocities[index] * time
+    }
+
+    // time at which the two people touch, if they do before the end of the step
+    fn collision_time(&self, index1: usize, index2: usize, now: f64, dt: f64) -> Option<f64> {
+        let dr = clamp_vec2(self.pos(index2, now) - self.pos(index1, now), self.box_size);
+        let dv = self.velocities[index2] - self.velocities[index1];
+        let b = dr.dot(&dv);
+        if b >= 0.0 {
+            // moving apart
+            return None;
+        }
+        let a = dv.dot(&dv);
+        let c = dr.dot(&dr) - 4.0 * RADIUS * RADIUS;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let time = now + ((-b - discriminant.sqrt()) / a).max(0.0);
+        if time < dt {
+            Some(time)
+        } else {
+            None
+        }
+    }
+
+    fn predict(&mut self, index: usize, now: f64, dt: f64) {
+        for &other in &self.neighbors[index] {
+            if let Some(time) = self.collision_time(index, other, now, dt) {
+                self.events.push(Event {
+                    time,
+                    index1: index,
+                    index2: other,
+                    count1: self.counts[index],
+                    count2: self.counts[other],
+                });
+            }
+        }
+    }
+
+    fn collide(&mut self, event: Event) {
+        let Event {
+            time,
+            index1,
+            index2,
+            ..
+        } = event;
+        let normal = clamp_vec2(
+            self.pos(index2, time) - self.pos(index1, time),
+            self.box_size,
+        )
+        .normalize();
+        let vel_norm = (self.velocities[index1] - self.velocities[index2]).dot(&normal);
+        for &(index, vel) in &[(index1, -vel_norm * normal), (index2, vel_norm * normal)] {
+            // keep the position at `time` unchanged
+            self.origins[index] -= vel * time;
+            self.velocities[index] += vel;
+            self.counts[index] += 1;
+        }
+    }
+}
+
+// Moves the people as hard spheres over `dt`, processing the collisions in the order
+// in which they happen. Returns the pairs that touched during the step, along with
+// the pairs overlapping at its end.
+pub fn advance(sim: &mut Simulation, dt: f64) -> BTreeSet<(usize, usize)> {
+    let mobility = sim.mobilities();
+    let box_size = sim.box_size;
+    let (positions, velocities, _) = sim.people.parts_mut();
+
+    // people who can't move don't take part in collisions
+    let moving: Vec<usize> = (0..mobility.len())
+        .filter(|&index| mobility[index] > 0.0)
+        .collect();
+    let velocities_now: Vec<Vector2<f64>> = moving
+        .iter()
+        .map(|&index| velocities[index] * mobility[index])
+        .collect();
+
+    // after collisions, nobody will be faster than sqrt(2) times the fastest person,
+    // so anyone who can be reached during the step is within this distance
+    let max_speed = velocities_now
+        .iter()
+        .map(|vel| vel.norm())
+        .fold(0.0, f64::max);
+    let reach = RADIUS * 2.0 + 4.0 * max_speed * dt;
+    let moving_positions: Vec<Vector2<f64>> =
+        moving.iter().map(|&index| positions[index]).collect();
+    let mut neighbors = vec![vec![]; moving.len()];
+    for (i, j) in broad_phase::find_pairs_within(&moving_positions, box_size, reach) {
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+
+    let mut state = State {
+        box_size,
+        origins: moving_positions,
+        velocities: velocities_now,
+        counts: vec![0; moving.len()],
+        neighbors,
+        events: BinaryHeap::new(),
+    };
+    for index in 0..moving.len() {
+        state.predict(index, 0.0, dt);
+    }
+
+    let mut pairs = BTreeSet::new();
+    while let Some(event) = state.events.pop() {
+        if event.count1 != state.counts[event.index1] || event.count2 != state.counts[event.index2]
+        {
+            continue;
+        }
+        state.collide(event);
+        let (index1, index2) = (moving[event.index1], moving[event.index2]);
+        pairs.insert((index1.min(index2), index1.max(index2)));
+        state.predict(event.index1, event.time, dt);
+        state.predict(event.index2, event.time, dt);
+    }
+
+    for (i, &index) in moving.iter().enumerate() {
+        let pos = state.pos(i, dt);
+        positions[index] = Vector2::new(clamp_f64(pos.x, box_size.0), clamp_f64(pos.y, box_size.1));
+        velocities[index] = state.velocities[i] / mobility[index];
+    }
+
+    pairs.extend(broad_phase::find_pairs(positions, box_size));
+    pairs
+}
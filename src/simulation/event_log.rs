@@ -0,0 +1,201 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use super::{
+    intervention::Intervention,
+    params::Params,
+    person::{Outcome, Severity, Status},
+};
+
+// where an infection happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    // from outside of the simulated population
+    External,
+    // two living people meeting
+    Contact,
+    Corpse,
+    // passed on by one of the vectors
+    Vector,
+    // from the contaminated surfaces
+    Surface,
+}
+
+impl Setting {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::External => "external",
+            Setting::Contact => "contact",
+            Setting::Corpse => "corpse",
+            Setting::Vector => "vector",
+            Setting::Surface => "surface",
+        }
+    }
+}
+
+// what is known about the circumstances of an infection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Source {
+    pub setting: Setting,
+    pub infector_vaccinated: bool,
+    pub infector_severity: Option<Severity>,
+}
+
+impl Source {
+    pub const EXTERNAL: Source = Source {
+        setting: Setting::External,
+        infector_vaccinated: false,
+        infector_severity: None,
+    };
+
+    pub fn new(setting: Setting, infector: &Status) -> Source {
+        Source {
+            setting,
+            infector_vaccinated: infector.vaccinated(),
+            infector_severity: infector.severity(),
+        }
+    }
+}
+
+// people are referred to by their ids
+#[derive(Debug, Clone, Copy)]
+pub enum LogEvent {
+    Intervention(Intervention),
+    // `infector` is `None` for people infected from outside, eg. by an intervention
+    Infection {
+        person: usize,
+        infector: Option<usize>,
+        source: Source,
+    },
+    Outcome {
+        person: usize,
+        outcome: Outcome,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LogEntry {
+    pub time: f64,
+    pub event: LogEvent,
+}
+
+// What the run started from, so that the seed and the parameters along with the times of the
+// interventions in the log are enough to repeat it: the generator was `draws` values into the
+// `seed` when the log began.
+#[derive(Debug, Clone, Copy)]
+pub struct LogHeader {
+    pub seed: u64,
+    pub draws: u64,
+    pub params: Params,
+}
+
+impl LogHeader {
+    // as comment lines, the parameters in the TOML of the config
+    pub fn write<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "# seed\t{}", self.seed)?;
+        writeln!(out, "# rng draws\t{}", self.draws)?;
+        writeln!(out, "# params")?;
+        let params = toml::to_string(&self.params)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        for line in params.lines() {
+            writeln!(out, "# {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+// With a `window`, between `window` and twice as many of the latest entries are kept.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    header: Option<LogHeader>,
+    entries: Vec<LogEntry>,
+    window: Option<usize>,
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        Default::default()
+    }
+
+    pub fn with_window(window: usize) -> EventLog {
+        EventLog {
+            header: None,
+            entries: vec![],
+            window: Some(window.max(1)),
+        }
+    }
+
+    pub fn set_header(&mut self, header: LogHeader) {
+        self.header = Some(header);
+    }
+
+    pub fn header(&self) -> Option<&LogHeader> {
+        self.header.as_ref()
+    }
+
+    pub fn record(&mut self, time: f64, event: LogEvent) {
+        self.entries.push(LogEntry { time, event });
+        if let Some(window) = self.window {
+            if self.entries.len() >= 2 * window {
+                self.entries.drain(..self.entries.len() - window);
+            }
+        }
+    }
+
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        if let Some(header) = &self.header {
+            header.write(&mut file)?;
+        }
+        for entry in &self.entries {
+            entry.write(&mut file)?;
+        }
+        file.flush()
+    }
+}
+
+impl LogEntry {
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match self.event {
+            LogEvent::Intervention(intervention) => {
+                writeln!(out, "{:.4}\tintervention\t{}", self.time, intervention)
+            }
+            LogEvent::Infection {
+                person,
+                infector,
+                source,
+            } => writeln!(
+                out,
+                "{:.4}\tinfection\t{}\t{}\t{}\t{}\t{}",
+                self.time,
+                person,
+                infector.map_or("-".to_owned(), |infector| infector.to_string()),
+                source.setting.name(),
+                if source.infector_vaccinated {
+                    "vaccinated"
+                } else {
+                    "unvaccinated"
+                },
+                source
+                    .infector_severity
+                    .map_or("-", |severity| severity.name())
+            ),
+            LogEvent::Outcome { person, outcome } => writeln!(
+                out,
+                "{:.4}\t{}\t{}",
+                self.time,
+                match outcome {
+                    Outcome::Healed => "healed",
+                    Outcome::Died => "died",
+                },
+                person
+            ),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    sync::{Arc, Mutex},
+};
+
+use super::{
+    event_log::{EventLog, LogEvent, Source},
+    intervention::Intervention,
+    person::Outcome,
+};
+
+// people are referred to by their ids
+#[derive(Debug, Clone, Copy)]
+pub enum SimEvent {
+    Intervention(Intervention),
+    Infected {
+        person: usize,
+        infector: Option<usize>,
+        source: Source,
+    },
+    Recovered {
+        person: usize,
+    },
+    Died {
+        person: usize,
+    },
+    // of other causes, with `vital_dynamics`, whether infected or not
+    DiedNaturally {
+        person: usize,
+    },
+    Vaccinated {
+        person: usize,
+        dose: usize,
+    },
+    // two living people met, whether or not anything was passed on
+    CollisionPairTested {
+        person1: usize,
+        person2: usize,
+    },
+    // the last infected person recovered or died
+    Extinct,
+    HospitalCapacity {
+        exceeded: bool,
+    },
+    // under quarantine, the infected get tested
+    Tested {
+        people: usize,
+    },
+}
+
+pub trait Subscriber: Send {
+    fn notify(&mut self, time: f64, event: &SimEvent);
+}
+
+pub type SharedSubscriber = Arc<Mutex<dyn Subscriber>>;
+
+// The counts since the start, which the window of the event log doesn't keep.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Totals {
+    pub cases: usize,
+    pub deaths: usize,
+}
+
+impl Subscriber for Totals {
+    fn notify(&mut self, _time: f64, event: &SimEvent) {
+        match event {
+            SimEvent::Infected { .. } => self.cases += 1,
+            SimEvent::Died { .. } => self.deaths += 1,
+            _ => (),
+        }
+    }
+}
+
+// The subscribers are shared between the clones of a simulation, so that the copy kept for
+// rendering doesn't lose them.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Vec<SharedSubscriber>,
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventBus({} subscribers)", self.subscribers.len())
+    }
+}
+
+impl EventBus {
+    pub fn subscribe(&mut self, subscriber: SharedSubscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    // to the subscribers owned by the simulation first, then to the shared ones
+    pub fn publish<'a, I>(&self, time: f64, event: &SimEvent, local: I)
+    where
+        I: IntoIterator<Item = &'a mut dyn Subscriber>,
+    {
+        for subscriber in local {
+            subscriber.notify(time, event);
+        }
+        for subscriber in &self.subscribers {
+            subscriber.lock().unwrap().notify(time, event);
+        }
+    }
+}
+
+impl SimEvent {
+    // the events kept in the event log
+    pub fn log_event(&self) -> Option<LogEvent> {
+        let event = match *self {
+            SimEvent::Intervention(intervention) => LogEvent::Intervention(intervention),
+            SimEvent::Infected {
+                person,
+                infector,
+                source,
+            } => LogEvent::Infection {
+                person,
+                infector,
+                source,
+            },
+            SimEvent::Recovered { person } => LogEvent::Outcome {
+                person,
+                outcome: Outcome::Healed,
+            },
+            SimEvent::Died { person } => LogEvent::Outcome {
+                person,
+                outcome: Outcome::Died,
+            },
+            SimEvent::DiedNaturally { .. }
+            | SimEvent::Vaccinated { .. }
+            | SimEvent::CollisionPairTested { .. }
+            | SimEvent::Extinct
+            | SimEvent::HospitalCapacity { .. }
+            | SimEvent::Tested { .. } => return None,
+        };
+        Some(event)
+    }
+}
+
+impl Subscriber for EventLog {
+    fn notify(&mut self, time: f64, event: &SimEvent) {
+        if let Some(event) = event.log_event() {
+            self.record(time, event);
+        }
+    }
+}
+
+// Writes every event as a tab-separated line. The contacts make up most of the file.
+pub struct EventWriter {
+    file: BufWriter<File>,
+}
+
+impl EventWriter {
+    pub fn create(path: &str) -> io::Result<EventWriter> {
+        Ok(EventWriter {
+            file: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    fn write(&mut self, time: f64, event: &SimEvent) -> io::Result<()> {
+        let file = &mut self.file;
+        match *event {
+            SimEvent::Intervention(intervention) => {
+                writeln!(file, "{:.4}\tintervention\t{}", time, intervention)
+            }
+            SimEvent::Infected {
+                person,
+                infector,
+                source,
+            } => writeln!(
+                file,
+                "{:.4}\tinfected\t{}\t{}\t{}",
+                time,
+                person,
+                infector.map_or("-".to_owned(), |infector| infector.to_string()),
+                source.setting.name()
+            ),
+            SimEvent::Recovered { person } => writeln!(file, "{:.4}\trecovered\t{}", time, person),
+            SimEvent::Died { person } => writeln!(file, "{:.4}\tdied\t{}", time, person),
+            SimEvent::DiedNaturally { person } => {
+                writeln!(file, "{:.4}\tdied naturally\t{}", time, person)
+            }
+            SimEvent::Vaccinated { person, dose } => {
+                writeln!(file, "{:.4}\tvaccinated\t{}\t{}", time, person, dose)
+            }
+            SimEvent::CollisionPairTested { person1, person2 } => {
+                writeln!(file, "{:.4}\tcontact\t{}\t{}", time, person1, person2)
+            }
+            SimEvent::Extinct => writeln!(file, "{:.4}\textinct", time),
+            SimEvent::HospitalCapacity { exceeded } => writeln!(
+                file,
+                "{:.4}\thospital capacity\t{}",
+                time,
+                if exceeded { "exceeded" } else { "restored" }
+            ),
+            SimEvent::Tested { people } => writeln!(file, "{:.4}\ttested\t{}", time, people),
+        }
+    }
+}
+
+impl Subscriber for EventWriter {
+    fn notify(&mut self, time: f64, event: &SimEvent) {
+        if let Err(err) = self.write(time, event) {
+            eprintln!("Couldn't write the event: {}", err);
+        }
+    }
+}
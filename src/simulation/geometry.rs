@@ -0,0 +1,283 @@
+use nalgebra::Vector2;
+
+// A straight piece of a wall, from `a` to `b`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub a: Vector2<f64>,
+    pub b: Vector2<f64>,
+}
+
+// Where a circle touches a segment: the unit normal pointing from the segment towards the
+// center of the circle, and how far the circle reaches past the segment along it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub normal: Vector2<f64>,
+    pub depth: f64,
+}
+
+// the sign tells which side of the line through `a` and `b` the point `p` is on
+fn orientation(a: Vector2<f64>, b: Vector2<f64>, p: Vector2<f64>) -> f64 {
+    (b - a).perp(&(p - a))
+}
+
+impl Segment {
+    pub fn new(a: Vector2<f64>, b: Vector2<f64>) -> Segment {
+        Segment { a, b }
+    }
+
+    // one of the ends, if the perpendicular from `p` misses the segment
+    pub fn closest_point(&self, p: Vector2<f64>) -> Vector2<f64> {
+        let ab = self.b - self.a;
+        let length_squared = ab.dot(&ab);
+        if length_squared == 0.0 {
+            return self.a;
+        }
+        let t = ((p - self.a).dot(&ab) / length_squared).clamp(0.0, 1.0);
+        self.a + ab * t
+    }
+
+    pub fn distance(&self, p: Vector2<f64>) -> f64 {
+        (p - self.closest_point(p)).norm()
+    }
+
+    // Past the ends, the normal points away from the closer end, so that a circle rolls around
+    // a corner instead of catching on it. None if the circle doesn't touch the segment.
+    pub fn contact(&self, center: Vector2<f64>, radius: f64) -> Option<Contact> {
+        let offset = center - self.closest_point(center);
+        let distance = offset.norm();
+        if distance >= radius {
+            return None;
+        }
+        let normal = if distance > 0.0 {
+            offset / distance
+        } else {
+            // the center is right on the segment, so either side will do
+            let ab = self.b - self.a;
+            if ab.norm() > 0.0 {
+                Vector2::new(-ab.y, ab.x) / ab.norm()
+            } else {
+                Vector2::new(1.0, 0.0)
+            }
+        };
+        Some(Contact {
+            normal,
+            depth: radius - distance,
+        })
+    }
+
+    // whether the segments cross, not counting just touching
+    pub fn crosses(&self, other: &Segment) -> bool {
+        let d1 = orientation(self.a, self.b, other.a);
+        let d2 = orientation(self.a, self.b, other.b);
+        let d3 = orientation(other.a, other.b, self.a);
+        let d4 = orientation(other.a, other.b, self.b);
+        d1 * d2 < 0.0 && d3 * d4 < 0.0
+    }
+}
+
+// An axis-aligned rectangle from (`x`, `y`), including its lower edges but not the upper ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn contains(&self, pos: Vector2<f64>) -> bool {
+        pos.x >= self.x
+            && pos.x < self.x + self.width
+            && pos.y >= self.y
+            && pos.y < self.y + self.height
+    }
+
+    // larger by `margin` on every side, or smaller if it's negative
+    pub fn grown(&self, margin: f64) -> Rect {
+        Rect {
+            x: self.x - margin,
+            y: self.y - margin,
+            width: self.width + 2.0 * margin,
+            height: self.height + 2.0 * margin,
+        }
+    }
+}
+
+// `velocity` mirrored about a surface with the unit `normal`, unless it's already moving away
+pub fn reflect(velocity: Vector2<f64>, normal: Vector2<f64>) -> Vector2<f64> {
+    let towards = velocity.dot(&normal);
+    if towards < 0.0 {
+        velocity - normal * (2.0 * towards)
+    } else {
+        velocity
+    }
+}
+
+// Bounces a circle that moved from `previous` to `pos` off the segment: pushes it out to the
+// side it came from and reflects its velocity. Returns whether it hit.
+pub fn collide(
+    previous: Vector2<f64>,
+    pos: &mut Vector2<f64>,
+    velocity: &mut Vector2<f64>,
+    radius: f64,
+    segment: &Segment,
+) -> bool {
+    // too fast to have touched it on the way through
+    if Segment::new(previous, *pos).crosses(segment) {
+        let normal = segment.contact(previous, f64::INFINITY).unwrap().normal;
+        *pos = previous;
+        *velocity = reflect(*velocity, normal);
+        return true;
+    }
+    match segment.contact(*pos, radius) {
+        Some(contact) => {
+            *pos += contact.normal * contact.depth;
+            *velocity = reflect(*velocity, contact.normal);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn v(x: f64, y: f64) -> Vector2<f64> {
+        Vector2::new(x, y)
+    }
+
+    fn assert_close(actual: Vector2<f64>, expected: Vector2<f64>) {
+        assert!(
+            (actual - expected).norm() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn wall() -> Segment {
+        Segment::new(v(0.0, 0.0), v(10.0, 0.0))
+    }
+
+    #[test]
+    fn rects_contain_their_lower_edges_only() {
+        let rect = Rect {
+            x: 1.0,
+            y: 2.0,
+            width: 3.0,
+            height: 4.0,
+        };
+        assert!(rect.contains(v(1.0, 2.0)));
+        assert!(!rect.contains(v(4.0, 3.0)));
+        assert!(!rect.contains(v(2.0, 6.0)));
+        assert!(rect.grown(0.5).contains(v(4.2, 6.2)));
+        assert!(!rect.grown(-0.5).contains(v(1.2, 3.0)));
+    }
+
+    #[test]
+    fn closest_point_inside_and_past_the_ends() {
+        assert_close(wall().closest_point(v(3.0, 2.0)), v(3.0, 0.0));
+        assert_close(wall().closest_point(v(-2.0, 1.0)), v(0.0, 0.0));
+        assert_close(wall().closest_point(v(12.0, -1.0)), v(10.0, 0.0));
+        assert!((wall().distance(v(13.0, 4.0)) - 5.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn degenerate_segment_is_a_point() {
+        let point = Segment::new(v(1.0, 1.0), v(1.0, 1.0));
+        assert_close(point.closest_point(v(4.0, 5.0)), v(1.0, 1.0));
+        let contact = point.contact(v(1.0, 1.5), 1.0).unwrap();
+        assert_close(contact.normal, v(0.0, 1.0));
+    }
+
+    #[test]
+    fn contact_normal_and_depth() {
+        assert_eq!(wall().contact(v(5.0, 2.0), 1.0), None);
+        let contact = wall().contact(v(5.0, 0.25), 1.0).unwrap();
+        assert_close(contact.normal, v(0.0, 1.0));
+        assert!((contact.depth - 0.75).abs() < EPSILON);
+        let below = wall().contact(v(5.0, -0.5), 1.0).unwrap();
+        assert_close(below.normal, v(0.0, -1.0));
+    }
+
+    #[test]
+    fn contact_at_a_corner_points_away_from_the_end() {
+        let contact = wall().contact(v(10.3, 0.4), 1.0).unwrap();
+        assert_close(contact.normal, v(0.6, 0.8));
+        assert!((contact.depth - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn reflect_about_the_normal() {
+        assert_close(reflect(v(0.0, -2.0), v(0.0, 1.0)), v(0.0, 2.0));
+        assert_close(reflect(v(3.0, -1.0), v(0.0, 1.0)), v(3.0, 1.0));
+        // moving away already
+        assert_close(reflect(v(3.0, 1.0), v(0.0, 1.0)), v(3.0, 1.0));
+        // a diagonal wall
+        let normal = v(1.0, 1.0) / 2f64.sqrt();
+        assert_close(reflect(v(-1.0, 0.0), normal), v(0.0, 1.0));
+    }
+
+    #[test]
+    fn reflection_keeps_the_speed() {
+        let normal = v(0.6, 0.8);
+        let velocity = v(-1.5, -0.25);
+        let reflected = reflect(velocity, normal);
+        assert!((reflected.norm() - velocity.norm()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn crossing_segments() {
+        let vertical = Segment::new(v(5.0, -1.0), v(5.0, 1.0));
+        assert!(wall().crosses(&vertical));
+        let touching = Segment::new(v(5.0, 0.0), v(5.0, 1.0));
+        assert!(!wall().crosses(&touching));
+        let parallel = Segment::new(v(0.0, 1.0), v(10.0, 1.0));
+        assert!(!wall().crosses(&parallel));
+        let past_the_end = Segment::new(v(11.0, -1.0), v(11.0, 1.0));
+        assert!(!wall().crosses(&past_the_end));
+    }
+
+    #[test]
+    fn collide_pushes_out_and_bounces() {
+        let mut pos = v(5.0, 0.5);
+        let mut velocity = v(1.0, -1.0);
+        assert!(collide(v(4.9, 0.6), &mut pos, &mut velocity, 1.0, &wall()));
+        assert_close(pos, v(5.0, 1.0));
+        assert_close(velocity, v(1.0, 1.0));
+    }
+
+    #[test]
+    fn collide_stops_tunnelling() {
+        let mut pos = v(5.0, -3.0);
+        let mut velocity = v(0.0, -10.0);
+        assert!(collide(v(5.0, 3.0), &mut pos, &mut velocity, 1.0, &wall()));
+        assert_close(pos, v(5.0, 3.0));
+        assert_close(velocity, v(0.0, 10.0));
+    }
+
+    #[test]
+    fn collide_misses() {
+        let mut pos = v(5.0, 2.0);
+        let mut velocity = v(0.0, -1.0);
+        assert!(!collide(v(5.0, 2.1), &mut pos, &mut velocity, 1.0, &wall()));
+        assert_close(pos, v(5.0, 2.0));
+        assert_close(velocity, v(0.0, -1.0));
+    }
+
+    #[test]
+    fn concave_corner_reflects_both_components() {
+        let floor = wall();
+        let side = Segment::new(v(0.0, 0.0), v(0.0, 10.0));
+        let mut pos = v(0.5, 0.5);
+        let mut velocity = v(-1.0, -2.0);
+        for segment in &[floor, side] {
+            collide(v(0.6, 0.7), &mut pos, &mut velocity, 1.0, segment);
+        }
+        assert_close(pos, v(1.0, 1.0));
+        assert_close(velocity, v(1.0, 2.0));
+    }
+}
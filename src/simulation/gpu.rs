@@ -0,0 +1,550 @@
+use std::{collections::BTreeSet, mem, sync::mpsc};
+
+use bytemuck::{Pod, Zeroable};
+use nalgebra::Vector2;
+use rand::Rng;
+use wgpu::util::DeviceExt;
+
+use super::{person::RADIUS, Simulation, SimulationParameters};
+
+const WORKGROUP_SIZE: u32 = 64;
+// room for this many colliding pairs per person at first, doubled whenever it runs out
+const PAIRS_PER_PERSON: usize = 8;
+// changed entries closer than this are uploaded together, rather than with a write each
+const MERGE_GAP: usize = 16;
+
+const SHADER_SRC: &str = r#"
+struct Params {
+    box_x: f32,
+    box_y: f32,
+    dt: f32,
+    radius: f32,
+    cells_x: u32,
+    cells_y: u32,
+    count: u32,
+    max_pairs: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> positions: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read> velocities: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read_write> heads: array<atomic<u32>>;
+@group(0) @binding(4) var<storage, read_write> next: array<u32>;
+// of the pairs and of the people who moved
+@group(0) @binding(5) var<storage, read_write> counts: array<atomic<u32>, 2>;
+@group(0) @binding(6) var<storage, read_write> pairs: array<vec2<u32>>;
+@group(0) @binding(7) var<storage, read_write> moved: array<u32>;
+@group(0) @binding(8) var<storage, read_write> moved_positions: array<vec2<f32>>;
+
+fn wrap(x: f32, limit: f32) -> f32 {
+    return x - floor(x / limit) * limit;
+}
+
+fn wrap_half(x: f32, limit: f32) -> f32 {
+    return x - round(x / limit) * limit;
+}
+
+fn cell_of(pos: vec2<f32>) -> vec2<u32> {
+    let x = min(u32(pos.x / params.box_x * f32(params.cells_x)), params.cells_x - 1u);
+    let y = min(u32(pos.y / params.box_y * f32(params.cells_y)), params.cells_y - 1u);
+    return vec2<u32>(x, y);
+}
+
+@compute @workgroup_size(64)
+fn clear(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x < arrayLength(&heads)) {
+        atomicStore(&heads[id.x], 0xffffffffu);
+    }
+    if (id.x == 0u) {
+        atomicStore(&counts[0], 0u);
+        atomicStore(&counts[1], 0u);
+    }
+}
+
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    let shifted = positions[i] + velocities[i] * params.dt;
+    let pos = vec2<f32>(wrap(shifted.x, params.box_x), wrap(shifted.y, params.box_y));
+    positions[i] = pos;
+    if (velocities[i].x != 0.0 || velocities[i].y != 0.0) {
+        let slot = atomicAdd(&counts[1], 1u);
+        moved[slot] = i;
+        moved_positions[slot] = pos;
+    }
+    let cell = cell_of(pos);
+    next[i] = atomicExchange(&heads[cell.y * params.cells_x + cell.x], i);
+}
+
+@compute @workgroup_size(64)
+fn find_pairs(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    let pos = positions[i];
+    let cell = cell_of(pos);
+    let limit = 4.0 * params.radius * params.radius;
+    let cells_x = i32(params.cells_x);
+    let cells_y = i32(params.cells_y);
+    for (var dy = -1; dy <= 1; dy = dy + 1) {
+        for (var dx = -1; dx <= 1; dx = dx + 1) {
+            let x = u32((i32(cell.x) + dx + cells_x) % cells_x);
+            let y = u32((i32(cell.y) + dy + cells_y) % cells_y);
+            var j = atomicLoad(&heads[y * params.cells_x + x]);
+            loop {
+                if (j == 0xffffffffu) {
+                    break;
+                }
+                if (j > i) {
+                    let diff = positions[j] - pos;
+                    let d = vec2<f32>(wrap_half(diff.x, params.box_x), wrap_half(diff.y, params.box_y));
+                    if (dot(d, d) < limit) {
+                        let slot = atomicAdd(&counts[0], 1u);
+                        if (slot < params.max_pairs) {
+                            pairs[slot] = vec2<u32>(i, j);
+                        }
+                    }
+                }
+                j = next[j];
+            }
+        }
+    }
+}
+"#;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct GpuParams {
+    box_x: f32,
+    box_y: f32,
+    dt: f32,
+    radius: f32,
+    cells_x: u32,
+    cells_y: u32,
+    count: u32,
+    max_pairs: u32,
+}
+
+struct Buffers {
+    capacity: usize,
+    num_cells: usize,
+    max_pairs: usize,
+    params: wgpu::Buffer,
+    positions: wgpu::Buffer,
+    velocities: wgpu::Buffer,
+    // only used by the shaders, but they must live as long as the bind group
+    _heads: wgpu::Buffer,
+    _next: wgpu::Buffer,
+    counts: wgpu::Buffer,
+    pairs: wgpu::Buffer,
+    moved: wgpu::Buffer,
+    moved_positions: wgpu::Buffer,
+    positions_staging: wgpu::Buffer,
+    counts_staging: wgpu::Buffer,
+    pairs_staging: wgpu::Buffer,
+    moved_staging: wgpu::Buffer,
+    moved_positions_staging: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // what the buffers on the device hold, so that only the changes are uploaded
+    synced_positions: Vec<[f32; 2]>,
+    synced_velocities: Vec<[f32; 2]>,
+}
+
+// Writes the entries of `values` differing from `synced`, the copy of what's in the buffer.
+fn upload_changes(
+    queue: &wgpu::Queue,
+    buffer: &wgpu::Buffer,
+    synced: &mut Vec<[f32; 2]>,
+    values: &[[f32; 2]],
+) {
+    // the new entries differ from anything, even their own values being NaN
+    synced.resize(values.len(), [f32::NAN; 2]);
+    let mut index = 0;
+    while index < values.len() {
+        if values[index] == synced[index] {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        let mut end = index + 1;
+        let mut scan = end;
+        while scan < values.len() && scan - end < MERGE_GAP {
+            if values[scan] != synced[scan] {
+                end = scan + 1;
+            }
+            scan += 1;
+        }
+        let offset = (start * mem::size_of::<[f32; 2]>()) as u64;
+        queue.write_buffer(buffer, offset, bytemuck::cast_slice(&values[start..end]));
+        synced[start..end].copy_from_slice(&values[start..end]);
+        index = end;
+    }
+}
+
+// Moves the people and finds the colliding pairs in compute shaders. Lives in the simulation
+// thread, as the device can't be cloned along with the `Simulation`.
+pub struct GpuPhysics {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    clear: wgpu::ComputePipeline,
+    integrate: wgpu::ComputePipeline,
+    find_pairs: wgpu::ComputePipeline,
+    buffers: Option<Buffers>,
+    pairs_per_person: usize,
+}
+
+impl GpuPhysics {
+    pub fn new() -> Option<GpuPhysics> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: Default::default(),
+            },
+            None,
+        ))
+        .ok()?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("physics"),
+            source: wgpu::ShaderSource::Wgsl(SHADER_SRC.into()),
+        });
+
+        let entries: Vec<_> = (0..9)
+            .map(|binding| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: match binding {
+                        0 => wgpu::BufferBindingType::Uniform,
+                        2 => wgpu::BufferBindingType::Storage { read_only: true },
+                        _ => wgpu::BufferBindingType::Storage { read_only: false },
+                    },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            })
+            .collect();
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &entries,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+        let clear = pipeline("clear");
+        let integrate = pipeline("integrate");
+        let find_pairs = pipeline("find_pairs");
+
+        Some(GpuPhysics {
+            device,
+            queue,
+            layout,
+            clear,
+            integrate,
+            find_pairs,
+            buffers: None,
+            pairs_per_person: PAIRS_PER_PERSON,
+        })
+    }
+
+    fn storage_buffer(&self, size: usize, usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size.max(4) as u64,
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_buffers(&self, capacity: usize, num_cells: usize, max_pairs: usize) -> Buffers {
+        use wgpu::BufferUsages as Usages;
+
+        let params = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::bytes_of(&GpuParams::zeroed()),
+                usage: Usages::UNIFORM | Usages::COPY_DST,
+            });
+        let vec2_size = 8;
+        let positions = self.storage_buffer(
+            capacity * vec2_size,
+            Usages::STORAGE | Usages::COPY_DST | Usages::COPY_SRC,
+        );
+        let velocities =
+            self.storage_buffer(capacity * vec2_size, Usages::STORAGE | Usages::COPY_DST);
+        let heads = self.storage_buffer(num_cells * 4, Usages::STORAGE);
+        let next = self.storage_buffer(capacity * 4, Usages::STORAGE);
+        let counts = self.storage_buffer(8, Usages::STORAGE | Usages::COPY_SRC);
+        let pairs = self.storage_buffer(max_pairs * vec2_size, Usages::STORAGE | Usages::COPY_SRC);
+        let moved = self.storage_buffer(capacity * 4, Usages::STORAGE | Usages::COPY_SRC);
+        let moved_positions =
+            self.storage_buffer(capacity * vec2_size, Usages::STORAGE | Usages::COPY_SRC);
+        let staging = |size| self.storage_buffer(size, Usages::MAP_READ | Usages::COPY_DST);
+        let positions_staging = staging(capacity * vec2_size);
+        let counts_staging = staging(8);
+        let pairs_staging = staging(max_pairs * vec2_size);
+        let moved_staging = staging(capacity * 4);
+        let moved_positions_staging = staging(capacity * vec2_size);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.layout,
+            entries: &[
+                &params,
+                &positions,
+                &velocities,
+                &heads,
+                &next,
+                &counts,
+                &pairs,
+                &moved,
+                &moved_positions,
+            ]
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>(),
+        });
+
+        Buffers {
+            capacity,
+            num_cells,
+            max_pairs,
+            params,
+            positions,
+            velocities,
+            _heads: heads,
+            _next: next,
+            counts,
+            pairs,
+            moved,
+            moved_positions,
+            positions_staging,
+            counts_staging,
+            pairs_staging,
+            moved_staging,
+            moved_positions_staging,
+            bind_group,
+            synced_positions: vec![],
+            synced_velocities: vec![],
+        }
+    }
+
+    fn read<T: Pod>(&self, buffer: &wgpu::Buffer, size: u64) -> Vec<T> {
+        let slice = buffer.slice(..size);
+        let (tx, rx) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv().unwrap().unwrap();
+        let data = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        buffer.unmap();
+        data
+    }
+
+    // The positions stay on the device between the steps, with only the ones changed on the
+    // CPU, eg. by the walls, uploaded again. Back come the colliding pairs and the positions of
+    // the people who moved, or all of them if that's less to copy.
+    fn advance(&mut self, sim: &mut Simulation, dt: f64) -> BTreeSet<(usize, usize)> {
+        let count = sim.people.len();
+        if count == 0 {
+            return BTreeSet::new();
+        }
+
+        // cells have to be at least as large as a person's diameter
+        let cells_x = ((sim.box_size.0 / (2.0 * RADIUS)).floor() as usize).max(1);
+        let cells_y = ((sim.box_size.1 / (2.0 * RADIUS)).floor() as usize).max(1);
+        let num_cells = cells_x * cells_y;
+
+        let pairs_per_person = self.pairs_per_person;
+        let needs_buffers = self.buffers.as_ref().map_or(true, |buffers| {
+            buffers.capacity < count
+                || buffers.num_cells != num_cells
+                || buffers.max_pairs < buffers.capacity * pairs_per_person
+        });
+        if needs_buffers {
+            let capacity = count.next_power_of_two();
+            self.buffers =
+                Some(self.create_buffers(capacity, num_cells, capacity * pairs_per_person));
+        }
+        let mut buffers = self.buffers.take().unwrap();
+
+        let positions: Vec<[f32; 2]> = sim
+            .people
+            .positions()
+            .iter()
+            .map(|pos| [pos.x as f32, pos.y as f32])
+            .collect();
+        let mobility = sim.mobilities();
+        let velocities: Vec<[f32; 2]> = sim
+            .people
+            .velocities()
+            .iter()
+            .zip(mobility)
+            .map(|(vel, mobility)| [(vel.x * mobility) as f32, (vel.y * mobility) as f32])
+            .collect();
+        let params = GpuParams {
+            box_x: sim.box_size.0 as f32,
+            box_y: sim.box_size.1 as f32,
+            dt: dt as f32,
+            radius: RADIUS as f32,
+            cells_x: cells_x as u32,
+            cells_y: cells_y as u32,
+            count: count as u32,
+            max_pairs: buffers.max_pairs as u32,
+        };
+        self.queue
+            .write_buffer(&buffers.params, 0, bytemuck::bytes_of(&params));
+        upload_changes(
+            &self.queue,
+            &buffers.positions,
+            &mut buffers.synced_positions,
+            &positions,
+        );
+        upload_changes(
+            &self.queue,
+            &buffers.velocities,
+            &mut buffers.synced_velocities,
+            &velocities,
+        );
+
+        let workgroups = |n: usize| ((n as u32 + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE).max(1);
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            pass.set_bind_group(0, &buffers.bind_group, &[]);
+            pass.set_pipeline(&self.clear);
+            pass.dispatch_workgroups(workgroups(num_cells), 1, 1);
+            pass.set_pipeline(&self.integrate);
+            pass.dispatch_workgroups(workgroups(count), 1, 1);
+            pass.set_pipeline(&self.find_pairs);
+            pass.dispatch_workgroups(workgroups(count), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&buffers.counts, 0, &buffers.counts_staging, 0, 8);
+        self.queue.submit(Some(encoder.finish()));
+        let counts: Vec<u32> = self.read(&buffers.counts_staging, 8);
+        let (num_pairs, num_moved) = (counts[0] as usize, counts[1] as usize);
+        let overflow = num_pairs > buffers.max_pairs;
+
+        // an index and a position for each of the moved, or just the positions of everyone
+        let compact = num_moved * 12 < count * 8;
+        let positions_size = (count * 8) as u64;
+        let (moved_size, moved_positions_size) = ((num_moved * 4) as u64, (num_moved * 8) as u64);
+        let pairs_size = (num_pairs * 8) as u64;
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        if compact && num_moved > 0 {
+            encoder.copy_buffer_to_buffer(&buffers.moved, 0, &buffers.moved_staging, 0, moved_size);
+            encoder.copy_buffer_to_buffer(
+                &buffers.moved_positions,
+                0,
+                &buffers.moved_positions_staging,
+                0,
+                moved_positions_size,
+            );
+        } else if !compact {
+            encoder.copy_buffer_to_buffer(
+                &buffers.positions,
+                0,
+                &buffers.positions_staging,
+                0,
+                positions_size,
+            );
+        }
+        if num_pairs > 0 && !overflow {
+            encoder.copy_buffer_to_buffer(&buffers.pairs, 0, &buffers.pairs_staging, 0, pairs_size);
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        let sim_positions = sim.people.parts_mut().0;
+        if compact {
+            if num_moved > 0 {
+                let moved: Vec<u32> = self.read(&buffers.moved_staging, moved_size);
+                let moved_positions: Vec<[f32; 2]> =
+                    self.read(&buffers.moved_positions_staging, moved_positions_size);
+                for (index, new_pos) in moved.into_iter().zip(moved_positions) {
+                    let index = index as usize;
+                    sim_positions[index] = Vector2::new(new_pos[0] as f64, new_pos[1] as f64);
+                    buffers.synced_positions[index] = new_pos;
+                }
+            }
+        } else {
+            let positions: Vec<[f32; 2]> = self.read(&buffers.positions_staging, positions_size);
+            for (pos, &new_pos) in sim_positions.iter_mut().zip(&positions) {
+                *pos = Vector2::new(new_pos[0] as f64, new_pos[1] as f64);
+            }
+            buffers.synced_positions = positions;
+        }
+
+        let pairs = if overflow {
+            // the pairs that didn't fit are lost, so this step they're found on the CPU, and
+            // the next ones have more room
+            while buffers.capacity * self.pairs_per_person < num_pairs {
+                self.pairs_per_person *= 2;
+            }
+            eprintln!(
+                "{} colliding pairs didn't fit on the GPU, making room for {} per person.",
+                num_pairs, self.pairs_per_person
+            );
+            sim.find_collisions()
+        } else if num_pairs == 0 {
+            BTreeSet::new()
+        } else {
+            let pairs: Vec<[u32; 2]> = self.read(&buffers.pairs_staging, pairs_size);
+            pairs
+                .into_iter()
+                .map(|[i, j]| (i as usize, j as usize))
+                .collect()
+        };
+        self.buffers = Some(buffers);
+        pairs
+    }
+}
+
+impl Simulation {
+    pub fn step_gpu<R: Rng>(
+        &mut self,
+        dt: f64,
+        rng: &mut R,
+        params: &SimulationParameters,
+        gpu: &mut GpuPhysics,
+    ) {
+        // exact collision scheduling is only implemented on the CPU
+        if self.params.event_driven {
+            self.step(dt, rng, params);
+        } else {
+            self.step_with(dt, rng, params, |sim, dt| gpu.advance(sim, dt));
+        }
+    }
+}
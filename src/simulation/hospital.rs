@@ -0,0 +1,62 @@
+use nalgebra::Vector2;
+use serde_derive::Deserialize;
+
+use super::{
+    geometry::Rect,
+    params::Params,
+    person::{Severity, Status},
+};
+
+fn default_tint() -> [f32; 3] {
+    [0.85, 0.9, 1.0]
+}
+
+// The rectangle the severe and critical cases go to, given as a `[hospital]` table in the
+// config. They stay there without moving until they recover or die, tended to by the `staff`
+// people who keep coming back, and who can catch the infection from them. Only as many are
+// admitted as `hospital_capacity` has room for, the others stay where they are.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Hospital {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub staff: usize,
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 3],
+}
+
+impl Hospital {
+    pub fn rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn contains(&self, pos: Vector2<f64>) -> bool {
+        self.rect().contains(pos)
+    }
+}
+
+// of the hospital resources a case takes up, in the units of `hospital_capacity`
+pub fn cost(params: &Params, severity: Option<Severity>) -> f64 {
+    match severity {
+        Some(Severity::Severe) => params.severe_cost,
+        Some(Severity::Critical) => params.critical_cost,
+        _ => 0.0,
+    }
+}
+
+// the living severe and critical cases
+pub fn needs_hospital(status: &Status) -> bool {
+    status.dead().is_none()
+        && status.infected().is_some()
+        && matches!(
+            status.severity(),
+            Some(Severity::Severe) | Some(Severity::Critical)
+        )
+}
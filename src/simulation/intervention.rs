@@ -0,0 +1,187 @@
+use std::{fmt, str::FromStr};
+
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Intervention {
+    VaccinateFraction(f64),
+    InfectRandom(usize),
+    InfectArea { x: f64, y: f64, radius: f64 },
+    ToggleQuarantine,
+    ToggleLockdown,
+    SetQuarantine(bool),
+    SetLockdown(bool),
+    // start recording the timeline of the person with the given id
+    Track(usize),
+    // multiplies all the infection probabilities from the parameters
+    SetTransmissibility(f64),
+    // the new doses are of the vaccine with this index, 0 being the original one
+    RollOutVaccine(usize),
+}
+
+impl fmt::Display for Intervention {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Intervention::VaccinateFraction(fraction) => write!(f, "vaccinate {}", fraction),
+            Intervention::InfectRandom(n) => write!(f, "infect {}", n),
+            Intervention::InfectArea { x, y, radius } => {
+                write!(f, "infect_area {} {} {}", x, y, radius)
+            }
+            Intervention::ToggleQuarantine => write!(f, "toggle_quarantine"),
+            Intervention::ToggleLockdown => write!(f, "toggle_lockdown"),
+            Intervention::SetQuarantine(on) => write!(f, "quarantine {}", on_off(*on)),
+            Intervention::SetLockdown(on) => write!(f, "lockdown {}", on_off(*on)),
+            Intervention::Track(id) => write!(f, "track {}", id),
+            Intervention::SetTransmissibility(factor) => write!(f, "transmissibility {}", factor),
+            Intervention::RollOutVaccine(vaccine) => write!(f, "roll_out_vaccine {}", vaccine),
+        }
+    }
+}
+
+// the format written above
+impl FromStr for Intervention {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Intervention, String> {
+        let invalid = || format!("invalid intervention: {}", text);
+        let number = |word: &str| word.parse::<f64>().map_err(|_| invalid());
+        let count = |word: &str| word.parse::<usize>().map_err(|_| invalid());
+        let flag = |word: &str| match word {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(invalid()),
+        };
+        let words: Vec<_> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["vaccinate", fraction] => Ok(Intervention::VaccinateFraction(number(fraction)?)),
+            ["infect", n] => Ok(Intervention::InfectRandom(count(n)?)),
+            ["infect_area", x, y, radius] => Ok(Intervention::InfectArea {
+                x: number(x)?,
+                y: number(y)?,
+                radius: number(radius)?,
+            }),
+            ["toggle_quarantine"] => Ok(Intervention::ToggleQuarantine),
+            ["toggle_lockdown"] => Ok(Intervention::ToggleLockdown),
+            ["quarantine", on] => Ok(Intervention::SetQuarantine(flag(on)?)),
+            ["lockdown", on] => Ok(Intervention::SetLockdown(flag(on)?)),
+            ["track", id] => Ok(Intervention::Track(count(id)?)),
+            ["transmissibility", factor] => Ok(Intervention::SetTransmissibility(number(factor)?)),
+            ["roll_out_vaccine", vaccine] => Ok(Intervention::RollOutVaccine(count(vaccine)?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+// The interventions setting what `interventions` left in force from the start of a run, at
+// the time of the last of them, for the ones dropped from a capped list.
+pub fn fold(interventions: &[(f64, Intervention)]) -> Vec<(f64, Intervention)> {
+    let (mut quarantine, mut lockdown, mut transmissibility, mut vaccine) = (false, false, 1.0, 0);
+    for &(_, intervention) in interventions {
+        match intervention {
+            Intervention::ToggleQuarantine => quarantine = !quarantine,
+            Intervention::ToggleLockdown => lockdown = !lockdown,
+            Intervention::SetQuarantine(on) => quarantine = on,
+            Intervention::SetLockdown(on) => lockdown = on,
+            Intervention::SetTransmissibility(factor) => transmissibility = factor,
+            Intervention::RollOutVaccine(index) => vaccine = index,
+            _ => (),
+        }
+    }
+    let time = interventions.last().map_or(0.0, |&(time, _)| time);
+    let in_force = [
+        (quarantine, Intervention::SetQuarantine(true)),
+        (lockdown, Intervention::SetLockdown(true)),
+        (
+            transmissibility != 1.0,
+            Intervention::SetTransmissibility(transmissibility),
+        ),
+        (vaccine != 0, Intervention::RollOutVaccine(vaccine)),
+    ];
+    in_force
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|&(_, intervention)| (time, intervention))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Restrictions {
+    pub quarantine: bool,
+    pub lockdown: bool,
+}
+
+// What is in force at a moment, sampled along with the stats for the lanes under the graph.
+#[derive(Debug, Clone, Copy)]
+pub struct InForce {
+    pub restrictions: Restrictions,
+    pub transmissibility: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interventions_parse_back_from_their_text() {
+        let interventions = [
+            Intervention::VaccinateFraction(0.25),
+            Intervention::InfectRandom(3),
+            Intervention::InfectArea {
+                x: 10.0,
+                y: 20.5,
+                radius: 4.0,
+            },
+            Intervention::ToggleQuarantine,
+            Intervention::ToggleLockdown,
+            Intervention::SetQuarantine(true),
+            Intervention::SetLockdown(false),
+            Intervention::Track(7),
+            Intervention::SetTransmissibility(0.5),
+            Intervention::RollOutVaccine(1),
+        ];
+        for intervention in &interventions {
+            assert_eq!(intervention.to_string().parse(), Ok(*intervention));
+        }
+    }
+
+    #[test]
+    fn folding_keeps_what_is_in_force() {
+        let interventions = [
+            (1.0, Intervention::ToggleLockdown),
+            (2.0, Intervention::SetQuarantine(true)),
+            (3.0, Intervention::VaccinateFraction(0.5)),
+            (4.0, Intervention::SetTransmissibility(0.5)),
+            (5.0, Intervention::SetQuarantine(false)),
+        ];
+        assert_eq!(
+            fold(&interventions),
+            vec![
+                (5.0, Intervention::SetLockdown(true)),
+                (5.0, Intervention::SetTransmissibility(0.5)),
+            ]
+        );
+        assert_eq!(fold(&interventions[2..3]), vec![]);
+    }
+
+    #[test]
+    fn invalid_interventions_are_rejected() {
+        for text in &[
+            "",
+            "lockdown",
+            "lockdown maybe",
+            "infect -1",
+            "vaccinate x",
+            "fly 1",
+        ] {
+            assert!(text.parse::<Intervention>().is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use super::{
+    event_log::{LogEntry, LogHeader},
+    events::{SimEvent, Subscriber},
+};
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+#[cfg(feature = "zstd")]
+const COMPRESSION_LEVEL: i32 = 3;
+
+enum Output {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "zstd")]
+    Compressed(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl Output {
+    fn create(path: &str, compress: bool) -> io::Result<Output> {
+        let file = BufWriter::new(File::create(path)?);
+        if compress {
+            Output::compressed(file)
+        } else {
+            Ok(Output::Plain(file))
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    fn compressed(file: BufWriter<File>) -> io::Result<Output> {
+        Ok(Output::Compressed(zstd::Encoder::new(
+            file,
+            COMPRESSION_LEVEL,
+        )?))
+    }
+
+    // `Params::validate` doesn't let it come to this
+    #[cfg(not(feature = "zstd"))]
+    fn compressed(_file: BufWriter<File>) -> io::Result<Output> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "event_log_compression requires building with the `zstd` feature",
+        ))
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Output::Plain(file) => file,
+            #[cfg(feature = "zstd")]
+            Output::Compressed(encoder) => encoder,
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Plain(mut file) => file.flush(),
+            #[cfg(feature = "zstd")]
+            Output::Compressed(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+// Streams the event log to numbered files next to `path`, eg. event_log.1.txt, starting the
+// next one once a file has `max_size` megabytes of events in it (before compression). Each
+// file starts with the `header`, if there is one.
+pub struct RotatingLog {
+    header: Option<LogHeader>,
+    stem: String,
+    extension: String,
+    compress: bool,
+    max_size: usize,
+    index: usize,
+    size: usize,
+    output: Option<Output>,
+    line: Vec<u8>,
+}
+
+impl RotatingLog {
+    pub fn create(
+        path: &str,
+        max_size: f64,
+        compress: bool,
+        header: Option<LogHeader>,
+    ) -> io::Result<RotatingLog> {
+        let path = Path::new(path);
+        let extension = path
+            .extension()
+            .map_or(String::new(), |ext| format!(".{}", ext.to_string_lossy()));
+        let stem = path.with_extension("").to_string_lossy().into_owned();
+        let mut log = RotatingLog {
+            header,
+            stem,
+            extension,
+            compress,
+            max_size: ((max_size * BYTES_PER_MB) as usize).max(1),
+            index: 0,
+            size: 0,
+            output: None,
+            line: vec![],
+        };
+        log.rotate()?;
+        Ok(log)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(output) = self.output.take() {
+            output.finish()?;
+        }
+        self.index += 1;
+        self.size = 0;
+        let suffix = if self.compress { ".zst" } else { "" };
+        let path = format!("{}.{}{}{}", self.stem, self.index, self.extension, suffix);
+        let mut output = Output::create(&path, self.compress)?;
+        if let Some(header) = &self.header {
+            header.write(output.writer())?;
+        }
+        self.output = Some(output);
+        Ok(())
+    }
+
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        self.line.clear();
+        entry.write(&mut self.line)?;
+        if self.size > 0 && self.size + self.line.len() > self.max_size {
+            self.rotate()?;
+        }
+        self.size += self.line.len();
+        self.output.as_mut().unwrap().writer().write_all(&self.line)
+    }
+
+    // completes the current file; nothing is written afterwards
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.output.take() {
+            Some(output) => output.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Subscriber for RotatingLog {
+    fn notify(&mut self, time: f64, event: &SimEvent) {
+        let event = match event.log_event() {
+            Some(event) => event,
+            None => return,
+        };
+        if self.output.is_none() {
+            return;
+        }
+        if let Err(err) = self.write(&LogEntry { time, event }) {
+            eprintln!("Couldn't write the event log: {}", err);
+        }
+    }
+}
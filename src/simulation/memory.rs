@@ -0,0 +1,146 @@
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+// what is dropped from a series of samples that reached its cap
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Eviction {
+    // every other sample, so that the whole run stays visible at a coarser resolution
+    #[default]
+    Decimate,
+    // the older half
+    DropOldest,
+}
+
+// Brings a series that grew past `cap` back under it, doing nothing for a cap of 0. The
+// latest sample is always kept.
+pub fn evict<T>(series: &mut Vec<T>, cap: usize, eviction: Eviction) {
+    if cap == 0 || series.len() <= cap {
+        return;
+    }
+    match eviction {
+        Eviction::Decimate => {
+            let last = series.len() - 1;
+            let mut index = 0;
+            series.retain(|_| {
+                let keep = index % 2 == 0 || index == last;
+                index += 1;
+                keep
+            });
+            // a cap of 1 or 2 wouldn't go down by halving
+            if series.len() > cap {
+                series.drain(..series.len() - cap);
+            }
+        }
+        Eviction::DropOldest => {
+            series.drain(..series.len() - (cap / 2).max(1));
+        }
+    }
+}
+
+// Drops the older half of a series that grew past `cap` like DropOldest, putting back in
+// front what `fold` makes of the dropped samples, eg. a state they built up.
+pub fn evict_folding<T, F>(series: &mut Vec<T>, cap: usize, fold: F)
+where
+    F: FnOnce(&[T]) -> Vec<T>,
+{
+    if cap == 0 || series.len() <= cap {
+        return;
+    }
+    let dropped: Vec<_> = series.drain(..series.len() - (cap / 2).max(1)).collect();
+    series.splice(..0, fold(&dropped));
+}
+
+// Roughly how many bytes are held by the parts of a run that grow the longer it goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    // the sampled statistics of the simulation, including the communities, the weather and
+    // the reports
+    pub history: usize,
+    pub event_log: usize,
+    pub timelines: usize,
+    // the daily counts behind the reproduction numbers, the reports still due and the dead
+    // no longer displayed
+    pub counts: usize,
+    // the copies of the simulation kept for travelling back
+    pub rewind: usize,
+    // the series copied for drawing the charts
+    pub charts: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.history + self.event_log + self.timelines + self.counts + self.rewind + self.charts
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mb = |bytes: usize| bytes as f64 / BYTES_PER_MB;
+        write!(
+            f,
+            "{:.1} MB: history {:.1}, event log {:.1}, timelines {:.1}, counts {:.1}, \
+             rewind {:.1}, charts {:.1}",
+            mb(self.total()),
+            mb(self.history),
+            mb(self.event_log),
+            mb(self.timelines),
+            mb(self.counts),
+            mb(self.rewind),
+            mb(self.charts)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_is_evicted_under_the_cap_or_without_one() {
+        let mut series: Vec<usize> = (0..10).collect();
+        evict(&mut series, 10, Eviction::Decimate);
+        evict(&mut series, 0, Eviction::DropOldest);
+        assert_eq!(series, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn decimation_keeps_the_first_and_the_latest() {
+        let mut series: Vec<usize> = (0..10).collect();
+        evict(&mut series, 9, Eviction::Decimate);
+        assert_eq!(series, vec![0, 2, 4, 6, 8, 9]);
+    }
+
+    #[test]
+    fn dropping_the_oldest_keeps_half_the_cap() {
+        let mut series: Vec<usize> = (0..11).collect();
+        evict(&mut series, 10, Eviction::DropOldest);
+        assert_eq!(series, vec![6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn the_dropped_samples_are_folded_in_front() {
+        let mut series: Vec<usize> = (1..=11).collect();
+        evict_folding(&mut series, 10, |dropped| vec![dropped.iter().sum()]);
+        assert_eq!(series, vec![21, 7, 8, 9, 10, 11]);
+        evict_folding(&mut series, 10, |_| unreachable!());
+    }
+
+    #[test]
+    fn the_series_stays_bounded() {
+        for &eviction in &[Eviction::Decimate, Eviction::DropOldest] {
+            for &cap in &[1, 2, 3, 100] {
+                let mut series = vec![];
+                for sample in 0..1000 {
+                    series.push(sample);
+                    evict(&mut series, cap, eviction);
+                    assert!(series.len() <= cap);
+                    assert_eq!(series.last(), Some(&sample));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::mem;
+
+// simulated days over which the contacts are counted
+const SAMPLE_PERIOD: f64 = 1.0;
+
+// The contacts started between the groups of people over the last full sample period, as a
+// symmetric matrix. The groups are the communities, followed by the people outside of all of
+// them.
+#[derive(Debug, Clone, Default)]
+pub struct Mixing {
+    sample_start: f64,
+    counting: Vec<Vec<u64>>,
+    pub matrix: Option<Vec<Vec<u64>>>,
+}
+
+impl Mixing {
+    // `pairs` are the groups of the people in each contact started in the step ending at `time`
+    pub fn record(&mut self, time: f64, groups: usize, pairs: &[(usize, usize)]) {
+        if self.counting.len() != groups {
+            self.counting = vec![vec![0; groups]; groups];
+        }
+        for &(group1, group2) in pairs {
+            self.counting[group1][group2] += 1;
+            if group1 != group2 {
+                self.counting[group2][group1] += 1;
+            }
+        }
+        if time - self.sample_start < SAMPLE_PERIOD {
+            return;
+        }
+        let counted = mem::replace(&mut self.counting, vec![vec![0; groups]; groups]);
+        self.matrix = Some(counted);
+        self.sample_start = time;
+    }
+}
+
+// How many of the `values` fall into each of `bins` equal bins from 0 to `max`, with the ones
+// past `max` counted in the last bin.
+pub fn histogram<I: Iterator<Item = f64>>(values: I, bins: usize, max: f64) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    if bins == 0 {
+        return counts;
+    }
+    for value in values {
+        let bin = if max > 0.0 {
+            (value / max * bins as f64).max(0.0) as usize
+        } else {
+            0
+        };
+        counts[bin.min(bins - 1)] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_bins_and_overflow() {
+        let values = vec![0.0, 0.4, 0.5, 0.99, 1.0, 3.0];
+        assert_eq!(histogram(values.into_iter(), 2, 1.0), vec![2, 4]);
+        assert_eq!(histogram(vec![0.0, 0.0].into_iter(), 3, 0.0), vec![2, 0, 0]);
+    }
+
+    #[test]
+    fn mixing_is_symmetric_and_published_after_a_day() {
+        let mut mixing = Mixing::default();
+        mixing.record(0.5, 2, &[(0, 1), (1, 1)]);
+        assert_eq!(mixing.matrix, None);
+        mixing.record(1.0, 2, &[(0, 0)]);
+        assert_eq!(mixing.matrix, Some(vec![vec![1, 1], vec![1, 1]]));
+        mixing.record(1.5, 2, &[]);
+        assert_eq!(mixing.matrix, Some(vec![vec![1, 1], vec![1, 1]]));
+    }
+}
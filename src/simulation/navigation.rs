@@ -0,0 +1,252 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use nalgebra::Vector2;
+
+use super::geometry::Segment;
+
+// Where someone is headed, with the people steered along a flow field towards it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    // inside the obstacle with this index
+    Room(usize),
+    // and out of it
+    OutOf(usize),
+    Hospital,
+}
+
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+// a cell still to be visited, at the distance it was reached with
+#[derive(Debug, Clone, Copy)]
+struct Visit {
+    distance: f64,
+    cell: usize,
+}
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Visit) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl Eq for Visit {}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Visit) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Visit {
+    // reversed, so that the nearest cell is on top of the heap
+    fn cmp(&self, other: &Visit) -> Ordering {
+        other.distance.partial_cmp(&self.distance).unwrap()
+    }
+}
+
+// The way to the goal from each cell of a grid over the box: the neighbouring cell to go to
+// next, along the shortest path between the centers of the cells that doesn't cross a wall.
+// The box doesn't wrap around for the paths.
+#[derive(Debug, Clone)]
+pub struct FlowField {
+    box_size: (f64, f64),
+    cells_x: usize,
+    cells_y: usize,
+    distances: Vec<f64>,
+    next: Vec<Option<usize>>,
+}
+
+impl FlowField {
+    pub fn new<F: Fn(Vector2<f64>) -> bool>(
+        box_size: (f64, f64),
+        cells_per_side: usize,
+        walls: &[Segment],
+        is_goal: F,
+    ) -> FlowField {
+        let cells_per_side = cells_per_side.max(1);
+        let mut field = FlowField {
+            box_size,
+            cells_x: cells_per_side,
+            cells_y: cells_per_side,
+            distances: vec![f64::INFINITY; cells_per_side * cells_per_side],
+            next: vec![None; cells_per_side * cells_per_side],
+        };
+        let mut queue = BinaryHeap::new();
+        for cell in 0..field.distances.len() {
+            if is_goal(field.center(cell)) {
+                field.distances[cell] = 0.0;
+                queue.push(Visit {
+                    distance: 0.0,
+                    cell,
+                });
+            }
+        }
+        // Dijkstra's outwards from the goal, so that each cell points back along the way
+        // it was reached
+        while let Some(Visit { distance, cell }) = queue.pop() {
+            if distance > field.distances[cell] {
+                continue;
+            }
+            for neighbour in field.neighbours(cell) {
+                let step = Segment::new(field.center(cell), field.center(neighbour));
+                if walls.iter().any(|wall| wall.crosses(&step)) {
+                    continue;
+                }
+                let through = distance + (step.b - step.a).norm();
+                if through < field.distances[neighbour] {
+                    field.distances[neighbour] = through;
+                    field.next[neighbour] = Some(cell);
+                    queue.push(Visit {
+                        distance: through,
+                        cell: neighbour,
+                    });
+                }
+            }
+        }
+        field
+    }
+
+    fn center(&self, cell: usize) -> Vector2<f64> {
+        let (x, y) = (cell % self.cells_x, cell / self.cells_x);
+        Vector2::new(
+            (x as f64 + 0.5) * self.box_size.0 / self.cells_x as f64,
+            (y as f64 + 0.5) * self.box_size.1 / self.cells_y as f64,
+        )
+    }
+
+    fn neighbours(&self, cell: usize) -> impl Iterator<Item = usize> {
+        let (cells_x, cells_y) = (self.cells_x as isize, self.cells_y as isize);
+        let (x, y) = (cell as isize % cells_x, cell as isize / cells_x);
+        NEIGHBOURS.iter().filter_map(move |&(dx, dy)| {
+            let (x, y) = (x + dx, y + dy);
+            let inside = x >= 0 && y >= 0 && x < cells_x && y < cells_y;
+            if inside {
+                Some((y * cells_x + x) as usize)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn cell_index(&self, pos: Vector2<f64>) -> usize {
+        let x = (pos.x / self.box_size.0 * self.cells_x as f64) as usize;
+        let y = (pos.y / self.box_size.1 * self.cells_y as f64) as usize;
+        y.min(self.cells_y - 1) * self.cells_x + x.min(self.cells_x - 1)
+    }
+
+    // along the path, the shortest one from a cell
+    pub fn distance(&self, pos: Vector2<f64>) -> f64 {
+        self.distances[self.cell_index(pos)]
+    }
+
+    // the unit vector towards the center of the next cell on the way, None once in a goal
+    // cell or where the goal can't be reached
+    pub fn direction(&self, pos: Vector2<f64>) -> Option<Vector2<f64>> {
+        let next = self.next[self.cell_index(pos)]?;
+        let offset = self.center(next) - pos;
+        if offset.norm() > 0.0 {
+            Some(offset.normalize())
+        } else {
+            None
+        }
+    }
+}
+
+// The flow fields for the destinations in use, built when they're first needed and again
+// whenever the walls change, eg. when a door opens.
+#[derive(Debug, Clone, Default)]
+pub struct Navigation {
+    cells_per_side: usize,
+    walls: Vec<Segment>,
+    fields: Vec<(Destination, FlowField)>,
+}
+
+impl Navigation {
+    pub fn field<F: Fn(Vector2<f64>) -> bool>(
+        &mut self,
+        destination: Destination,
+        box_size: (f64, f64),
+        cells_per_side: usize,
+        walls: &[Segment],
+        is_goal: F,
+    ) -> &FlowField {
+        if self.walls != walls || self.cells_per_side != cells_per_side {
+            self.walls = walls.to_vec();
+            self.cells_per_side = cells_per_side;
+            self.fields.clear();
+        }
+        let index = match self
+            .fields
+            .iter()
+            .position(|(existing, _)| *existing == destination)
+        {
+            Some(index) => index,
+            None => {
+                let field = FlowField::new(box_size, cells_per_side, walls, is_goal);
+                self.fields.push((destination, field));
+                self.fields.len() - 1
+            }
+        };
+        &self.fields[index].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> Vector2<f64> {
+        Vector2::new(x, y)
+    }
+
+    // a wall across the middle of the box, with a gap at the top
+    fn walls() -> Vec<Segment> {
+        vec![Segment::new(v(50.0, 0.0), v(50.0, 80.0))]
+    }
+
+    fn to_the_right(pos: Vector2<f64>) -> bool {
+        pos.x > 90.0
+    }
+
+    #[test]
+    fn the_path_goes_around_the_wall() {
+        let field = FlowField::new((100.0, 100.0), 20, &walls(), to_the_right);
+        // headed up towards the gap rather than into the wall
+        let direction = field.direction(v(40.0, 20.0)).unwrap();
+        assert!(direction.y > 0.5);
+        assert!(field.distance(v(40.0, 20.0)) > 100.0);
+        assert_eq!(field.direction(v(95.0, 20.0)), None);
+        let open = FlowField::new((100.0, 100.0), 20, &[], to_the_right);
+        assert!(open.direction(v(40.0, 20.0)).unwrap().x > 0.9);
+    }
+
+    #[test]
+    fn unreachable_goals_give_no_direction() {
+        let closed = vec![Segment::new(v(50.0, -1.0), v(50.0, 101.0))];
+        let field = FlowField::new((100.0, 100.0), 20, &closed, to_the_right);
+        assert_eq!(field.direction(v(40.0, 20.0)), None);
+        assert_eq!(field.distance(v(40.0, 20.0)), f64::INFINITY);
+    }
+
+    #[test]
+    fn fields_are_rebuilt_when_the_walls_change() {
+        let mut navigation = Navigation::default();
+        let destination = Destination::Room(0);
+        let blocked = navigation
+            .field(destination, (100.0, 100.0), 20, &walls(), to_the_right)
+            .distance(v(40.0, 20.0));
+        let open = navigation
+            .field(destination, (100.0, 100.0), 20, &[], to_the_right)
+            .distance(v(40.0, 20.0));
+        assert!(open < blocked);
+    }
+}
@@ -0,0 +1,254 @@
+use nalgebra::Vector2;
+use serde_derive::Deserialize;
+
+use super::geometry::Segment;
+
+pub const HOURS_PER_DAY: f64 = 24.0;
+
+fn default_door_at() -> f64 {
+    0.5
+}
+
+fn default_closes() -> f64 {
+    HOURS_PER_DAY
+}
+
+// A gap in a wall, given as an `[[obstacles.doors]]` table: `width` wide, centered `at` the
+// fraction of the way along the `edge`-th segment of the obstacle. Between the `opens` and
+// `closes` hours of each day it can be walked through, otherwise it's shut like the rest of
+// the wall; opening later than closing keeps it open over midnight.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Door {
+    pub edge: usize,
+    #[serde(default = "default_door_at")]
+    pub at: f64,
+    pub width: f64,
+    #[serde(default)]
+    pub opens: f64,
+    #[serde(default = "default_closes")]
+    pub closes: f64,
+}
+
+pub fn hour_of_day(time: f64) -> f64 {
+    time.rem_euclid(1.0) * HOURS_PER_DAY
+}
+
+impl Door {
+    fn always_open(&self) -> bool {
+        self.closes - self.opens >= HOURS_PER_DAY
+    }
+
+    pub fn has_hours(&self) -> bool {
+        !self.always_open()
+    }
+
+    pub fn is_open(&self, time: f64) -> bool {
+        self.hours_until_closed(time) > 0.0
+    }
+
+    // 0 while it's shut, infinite if it never is
+    pub fn hours_until_closed(&self, time: f64) -> f64 {
+        if self.always_open() {
+            return f64::INFINITY;
+        }
+        let since_opening = (hour_of_day(time) - self.opens).rem_euclid(HOURS_PER_DAY);
+        let open_for = (self.closes - self.opens).rem_euclid(HOURS_PER_DAY);
+        (open_for - since_opening).max(0.0)
+    }
+}
+
+// A wall nobody can walk through, given as an `[[obstacles]]` table in the config: a polyline
+// through `points`, joined back to the first point if `closed`. A closed one with doors is a
+// room: while it's open, the `visitors` fraction of the people find their way in through the
+// open doors, and before the doors close everyone inside finds their way out.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Obstacle {
+    pub points: Vec<[f64; 2]>,
+    #[serde(default)]
+    pub closed: bool,
+    #[serde(default)]
+    pub doors: Vec<Door>,
+    #[serde(default)]
+    pub visitors: f64,
+}
+
+// the same for a person and a room each time, but unrelated between the rooms
+fn hash(id: usize, room: usize) -> f64 {
+    let mut x = (id as u64) ^ (room as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl Obstacle {
+    fn points(&self) -> Vec<Vector2<f64>> {
+        self.points
+            .iter()
+            .map(|&[x, y]| Vector2::new(x, y))
+            .collect()
+    }
+
+    fn edges(&self) -> Vec<Segment> {
+        let points = self.points();
+        let mut edges: Vec<_> = points
+            .windows(2)
+            .map(|pair| Segment::new(pair[0], pair[1]))
+            .collect();
+        if self.closed && points.len() > 2 {
+            edges.push(Segment::new(points[points.len() - 1], points[0]));
+        }
+        edges
+    }
+
+    // the walls, leaving gaps for the doors
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut segments = vec![];
+        for (index, edge) in self.edges().into_iter().enumerate() {
+            let mut gaps: Vec<_> = self
+                .doors
+                .iter()
+                .filter(|door| door.edge == index)
+                .map(|door| Obstacle::door_span(&edge, door))
+                .collect();
+            gaps.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            let mut from = 0.0;
+            for (start, end) in gaps {
+                if start > from {
+                    segments.push(part_of(&edge, from, start));
+                }
+                from = end.max(from);
+            }
+            if from < 1.0 {
+                segments.push(part_of(&edge, from, 1.0));
+            }
+        }
+        segments
+    }
+
+    // where along the edge the door is, as fractions of its length
+    fn door_span(edge: &Segment, door: &Door) -> (f64, f64) {
+        let length = (edge.b - edge.a).norm();
+        let half_width = if length > 0.0 {
+            door.width / length / 2.0
+        } else {
+            0.5
+        };
+        (
+            (door.at - half_width).max(0.0),
+            (door.at + half_width).min(1.0),
+        )
+    }
+
+    // the gap left for the door, None if it's on an edge the obstacle doesn't have
+    pub fn door_segment(&self, door: &Door) -> Option<Segment> {
+        let edge = *self.edges().get(door.edge)?;
+        let (start, end) = Obstacle::door_span(&edge, door);
+        Some(part_of(&edge, start, end))
+    }
+
+    pub fn is_room(&self) -> bool {
+        self.closed && self.points.len() > 2 && !self.doors.is_empty()
+    }
+
+    // by the even-odd rule
+    pub fn contains(&self, pos: Vector2<f64>) -> bool {
+        let mut inside = false;
+        for edge in self.edges() {
+            let (a, b) = (edge.a, edge.b);
+            if (a.y > pos.y) != (b.y > pos.y) {
+                let x = a.x + (pos.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                if pos.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    pub fn center(&self) -> Vector2<f64> {
+        let points = self.points();
+        points
+            .iter()
+            .fold(Vector2::new(0.0, 0.0), |sum, &point| sum + point)
+            / points.len().max(1) as f64
+    }
+
+    // whether the person with `id` is one of the visitors of the room with `index`
+    pub fn is_visited_by(&self, index: usize, id: usize) -> bool {
+        hash(id, index) < self.visitors
+    }
+}
+
+fn part_of(edge: &Segment, from: f64, to: f64) -> Segment {
+    let ab = edge.b - edge.a;
+    Segment::new(edge.a + ab * from, edge.a + ab * to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room() -> Obstacle {
+        Obstacle {
+            points: vec![[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]],
+            closed: true,
+            doors: vec![Door {
+                edge: 0,
+                at: 0.5,
+                width: 2.0,
+                opens: 8.0,
+                closes: 20.0,
+            }],
+            visitors: 0.5,
+        }
+    }
+
+    #[test]
+    fn doors_leave_gaps_in_their_edge() {
+        let room = room();
+        let segments = room.segments();
+        assert_eq!(segments.len(), 5);
+        assert_eq!(
+            segments[0],
+            Segment::new(Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0))
+        );
+        assert_eq!(
+            segments[1],
+            Segment::new(Vector2::new(6.0, 0.0), Vector2::new(10.0, 0.0))
+        );
+        assert_eq!(
+            room.door_segment(&room.doors[0]),
+            Some(Segment::new(Vector2::new(4.0, 0.0), Vector2::new(6.0, 0.0)))
+        );
+    }
+
+    #[test]
+    fn doors_open_for_their_hours() {
+        let door = &room().doors[0];
+        assert!(!door.is_open(3.25));
+        assert!(door.is_open(3.5));
+        assert!((door.hours_until_closed(3.5) - 8.0).abs() < 1e-9);
+        assert!(!door.is_open(3.9));
+        let night = Door {
+            opens: 22.0,
+            closes: 2.0,
+            ..door.clone()
+        };
+        assert!(night.is_open(0.0) && night.is_open(0.95) && !night.is_open(0.5));
+        let always = Door {
+            opens: 0.0,
+            closes: 24.0,
+            ..door.clone()
+        };
+        assert_eq!(always.hours_until_closed(0.3), f64::INFINITY);
+    }
+
+    #[test]
+    fn rooms_contain_their_inside() {
+        let room = room();
+        assert!(room.contains(Vector2::new(5.0, 5.0)));
+        assert!(!room.contains(Vector2::new(5.0, -1.0)));
+        assert!(!room.contains(Vector2::new(11.0, 5.0)));
+    }
+}
@@ -1,5 +1,16 @@
 use serde_derive::{Deserialize, Serialize};
 
+use super::{intervention::Intervention, memory::Eviction};
+
+// initially infected people around a point, given as `[[seed_clusters]]` tables in the config
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SeedCluster {
+    pub count: usize,
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Params {
     #[serde(default = "default_num_people")]
@@ -30,6 +41,336 @@ pub struct Params {
     pub infection_avg_duration: f64,
     #[serde(default = "default_death_rate")]
     pub death_rate: f64,
+    #[serde(default = "default_corpse_display_time")]
+    pub corpse_display_time: f64,
+    #[serde(default)]
+    pub corpse_infectious: bool,
+    #[serde(default = "default_lockdown_mobility")]
+    pub lockdown_mobility: f64,
+    #[serde(default = "default_brush_radius")]
+    pub brush_radius: f64,
+    #[serde(default = "default_region_grid_size")]
+    pub region_grid_size: usize,
+    #[serde(default)]
+    pub event_driven: bool,
+    #[serde(default = "default_stats_sample_interval")]
+    pub stats_sample_interval: f64,
+    #[serde(default = "default_severe_fraction")]
+    pub severe_fraction: f64,
+    #[serde(default = "default_critical_fraction")]
+    pub critical_fraction: f64,
+    #[serde(default = "default_severe_cost")]
+    pub severe_cost: f64,
+    #[serde(default = "default_critical_cost")]
+    pub critical_cost: f64,
+    #[serde(default = "default_hospital_capacity")]
+    pub hospital_capacity: f64,
+    #[serde(default)]
+    pub auto_lockdown: bool,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default = "default_lockdown_day_cost")]
+    pub lockdown_day_cost: f64,
+    #[serde(default = "default_vaccine_cost")]
+    pub vaccine_cost: f64,
+    #[serde(default = "default_test_cost")]
+    pub test_cost: f64,
+    #[serde(default = "default_isolation_day_cost")]
+    pub isolation_day_cost: f64,
+    #[serde(default)]
+    pub two_dose: bool,
+    #[serde(default = "default_dose_interval")]
+    pub dose_interval: f64,
+    #[serde(default = "default_dose_delay")]
+    pub dose_delay: f64,
+    #[serde(default = "default_first_dose_efficacy")]
+    pub first_dose_efficacy: f64,
+    #[serde(default = "default_hybrid_immunity_bonus")]
+    pub hybrid_immunity_bonus: f64,
+    #[serde(default = "default_timeline_sample")]
+    pub timeline_sample: usize,
+    #[serde(default = "default_audio_tick_infections")]
+    pub audio_tick_infections: usize,
+    #[serde(default = "default_rewind_memory")]
+    pub rewind_memory: f64,
+    #[serde(default = "default_rewind_interval")]
+    pub rewind_interval: f64,
+    #[serde(default = "default_branch_intervention")]
+    pub branch_intervention: Intervention,
+    #[serde(default)]
+    pub contact_duration: bool,
+    #[serde(default = "default_reference_contact_duration")]
+    pub reference_contact_duration: f64,
+    #[serde(default = "default_crowding_exponent")]
+    pub crowding_exponent: f64,
+    #[serde(default = "default_crowding_radius")]
+    pub crowding_radius: f64,
+    #[serde(default)]
+    pub weather: bool,
+    #[serde(default = "default_weather_timescale")]
+    pub weather_timescale: f64,
+    #[serde(default = "default_weather_mobility_effect")]
+    pub weather_mobility_effect: f64,
+    #[serde(default = "default_weather_transmission_effect")]
+    pub weather_transmission_effect: f64,
+    #[serde(default = "default_sim_hz")]
+    pub sim_hz: f64,
+    #[serde(default = "default_render_fps")]
+    pub render_fps: f64,
+    #[serde(default = "default_event_log_window")]
+    pub event_log_window: usize,
+    #[serde(default = "default_event_log_max_size")]
+    pub event_log_max_size: f64,
+    #[serde(default)]
+    pub event_log_compression: bool,
+    #[serde(default = "default_immunity_display_time")]
+    pub immunity_display_time: f64,
+    #[serde(default = "default_lod_threshold")]
+    pub lod_threshold: usize,
+    #[serde(default)]
+    pub deaths_axis: bool,
+    #[serde(default = "default_transmissibility_presets")]
+    pub transmissibility_presets: [f64; 5],
+    #[serde(default = "default_pressure_iterations")]
+    pub pressure_iterations: usize,
+    #[serde(default = "default_target_contact_rate")]
+    pub target_contact_rate: f64,
+    #[serde(default)]
+    pub calibrate_contacts_by: ContactCalibration,
+    #[serde(default)]
+    pub reporting: bool,
+    #[serde(default = "default_case_detection_probability")]
+    pub case_detection_probability: f64,
+    #[serde(default = "default_case_reporting_delay")]
+    pub case_reporting_delay: f64,
+    #[serde(default = "default_death_detection_probability")]
+    pub death_detection_probability: f64,
+    #[serde(default = "default_death_reporting_delay")]
+    pub death_reporting_delay: f64,
+    #[serde(default = "default_generation_interval_mean")]
+    pub generation_interval_mean: f64,
+    #[serde(default = "default_generation_interval_stdev")]
+    pub generation_interval_stdev: f64,
+    #[serde(default = "default_history_cap")]
+    pub history_cap: usize,
+    #[serde(default)]
+    pub history_eviction: Eviction,
+    #[serde(default = "default_timeline_cap")]
+    pub timeline_cap: usize,
+    #[serde(default = "default_init_past_infected")]
+    pub init_past_infected: f64,
+    #[serde(default = "default_init_past_infected_max_days")]
+    pub init_past_infected_max_days: f64,
+    #[serde(default)]
+    pub dose_response: bool,
+    #[serde(default = "default_dose_half_life")]
+    pub dose_half_life: f64,
+    #[serde(default = "default_dose_threshold_mean")]
+    pub dose_threshold_mean: f64,
+    #[serde(default)]
+    pub vital_dynamics: bool,
+    #[serde(default = "default_natural_mortality")]
+    pub natural_mortality: [f64; 4],
+    #[serde(default = "default_birth_rate")]
+    pub birth_rate: f64,
+    #[serde(default = "default_vectors")]
+    pub vectors: usize,
+    #[serde(default = "default_vector_speed_stdev")]
+    pub vector_speed_stdev: f64,
+    #[serde(default = "default_vector_contact_radius")]
+    pub vector_contact_radius: f64,
+    #[serde(default = "default_exposure_to_vector")]
+    pub exposure_to_vector: f64,
+    #[serde(default = "default_exposure_from_vector")]
+    pub exposure_from_vector: f64,
+    #[serde(default = "default_vector_infection_duration")]
+    pub vector_infection_duration: f64,
+    #[serde(default)]
+    pub fomites: bool,
+    #[serde(default = "default_fomite_grid_size")]
+    pub fomite_grid_size: usize,
+    #[serde(default = "default_fomite_shedding")]
+    pub fomite_shedding: f64,
+    #[serde(default = "default_fomite_half_life")]
+    pub fomite_half_life: f64,
+    #[serde(default = "default_fomite_exposure")]
+    pub fomite_exposure: f64,
+    #[serde(default = "default_wind_x")]
+    pub wind_x: f64,
+    #[serde(default = "default_wind_y")]
+    pub wind_y: f64,
+    #[serde(default = "default_fomite_diffusion")]
+    pub fomite_diffusion: f64,
+    #[serde(default = "default_navigation_grid_size")]
+    pub navigation_grid_size: usize,
+}
+
+impl Default for Params {
+    fn default() -> Params {
+        Params {
+            num_people: default_num_people(),
+            size_x: default_size(),
+            size_y: default_size(),
+            speed_stdev: default_speed_stdev(),
+            init_infected: default_infected(),
+            init_vaccinated: default_vaccinated(),
+            infection_prob_infected_to_general: default_inf_to_gen(),
+            infection_prob_infected_to_healed: default_inf_to_healed(),
+            infection_prob_infected_to_vaccinated: default_inf_to_vacc(),
+            infection_prob_vaccinated_to_general: default_vacc_to_gen(),
+            infection_prob_vaccinated_to_healed: default_vacc_to_healed(),
+            infection_prob_vaccinated_to_vaccinated: default_vacc_to_vacc(),
+            infection_avg_duration: default_duration(),
+            death_rate: default_death_rate(),
+            corpse_display_time: default_corpse_display_time(),
+            corpse_infectious: false,
+            lockdown_mobility: default_lockdown_mobility(),
+            brush_radius: default_brush_radius(),
+            region_grid_size: default_region_grid_size(),
+            event_driven: false,
+            stats_sample_interval: default_stats_sample_interval(),
+            severe_fraction: default_severe_fraction(),
+            critical_fraction: default_critical_fraction(),
+            severe_cost: default_severe_cost(),
+            critical_cost: default_critical_cost(),
+            hospital_capacity: default_hospital_capacity(),
+            auto_lockdown: false,
+            language: Default::default(),
+            lockdown_day_cost: default_lockdown_day_cost(),
+            vaccine_cost: default_vaccine_cost(),
+            test_cost: default_test_cost(),
+            isolation_day_cost: default_isolation_day_cost(),
+            two_dose: false,
+            dose_interval: default_dose_interval(),
+            dose_delay: default_dose_delay(),
+            first_dose_efficacy: default_first_dose_efficacy(),
+            hybrid_immunity_bonus: default_hybrid_immunity_bonus(),
+            timeline_sample: default_timeline_sample(),
+            audio_tick_infections: default_audio_tick_infections(),
+            rewind_memory: default_rewind_memory(),
+            rewind_interval: default_rewind_interval(),
+            branch_intervention: default_branch_intervention(),
+            contact_duration: false,
+            reference_contact_duration: default_reference_contact_duration(),
+            crowding_exponent: default_crowding_exponent(),
+            crowding_radius: default_crowding_radius(),
+            weather: false,
+            weather_timescale: default_weather_timescale(),
+            weather_mobility_effect: default_weather_mobility_effect(),
+            weather_transmission_effect: default_weather_transmission_effect(),
+            sim_hz: default_sim_hz(),
+            render_fps: default_render_fps(),
+            event_log_window: default_event_log_window(),
+            event_log_max_size: default_event_log_max_size(),
+            event_log_compression: false,
+            immunity_display_time: default_immunity_display_time(),
+            lod_threshold: default_lod_threshold(),
+            deaths_axis: false,
+            transmissibility_presets: default_transmissibility_presets(),
+            pressure_iterations: default_pressure_iterations(),
+            target_contact_rate: default_target_contact_rate(),
+            calibrate_contacts_by: Default::default(),
+            reporting: false,
+            case_detection_probability: default_case_detection_probability(),
+            case_reporting_delay: default_case_reporting_delay(),
+            death_detection_probability: default_death_detection_probability(),
+            death_reporting_delay: default_death_reporting_delay(),
+            generation_interval_mean: default_generation_interval_mean(),
+            generation_interval_stdev: default_generation_interval_stdev(),
+            history_cap: default_history_cap(),
+            history_eviction: Default::default(),
+            timeline_cap: default_timeline_cap(),
+            init_past_infected: default_init_past_infected(),
+            init_past_infected_max_days: default_init_past_infected_max_days(),
+            dose_response: false,
+            dose_half_life: default_dose_half_life(),
+            dose_threshold_mean: default_dose_threshold_mean(),
+            vital_dynamics: false,
+            natural_mortality: default_natural_mortality(),
+            birth_rate: default_birth_rate(),
+            vectors: default_vectors(),
+            vector_speed_stdev: default_vector_speed_stdev(),
+            vector_contact_radius: default_vector_contact_radius(),
+            exposure_to_vector: default_exposure_to_vector(),
+            exposure_from_vector: default_exposure_from_vector(),
+            vector_infection_duration: default_vector_infection_duration(),
+            fomites: false,
+            fomite_grid_size: default_fomite_grid_size(),
+            fomite_shedding: default_fomite_shedding(),
+            fomite_half_life: default_fomite_half_life(),
+            fomite_exposure: default_fomite_exposure(),
+            wind_x: default_wind_x(),
+            wind_y: default_wind_y(),
+            fomite_diffusion: default_fomite_diffusion(),
+            navigation_grid_size: default_navigation_grid_size(),
+        }
+    }
+}
+
+impl Params {
+    // all the infection probabilities multiplied by `factor`
+    pub fn with_transmissibility(self, factor: f64) -> Params {
+        let scale = |prob: f64| (prob * factor).min(1.0);
+        Params {
+            infection_prob_infected_to_general: scale(self.infection_prob_infected_to_general),
+            infection_prob_infected_to_healed: scale(self.infection_prob_infected_to_healed),
+            infection_prob_infected_to_vaccinated: scale(
+                self.infection_prob_infected_to_vaccinated,
+            ),
+            infection_prob_vaccinated_to_general: scale(self.infection_prob_vaccinated_to_general),
+            infection_prob_vaccinated_to_healed: scale(self.infection_prob_vaccinated_to_healed),
+            infection_prob_vaccinated_to_vaccinated: scale(
+                self.infection_prob_vaccinated_to_vaccinated,
+            ),
+            ..self
+        }
+    }
+
+    // the values the simulation can't run with, which deserializing doesn't catch
+    pub fn validate(&self) -> Result<(), String> {
+        if self.event_log_compression && !cfg!(feature = "zstd") {
+            return Err(
+                "event_log_compression requires building with the `zstd` feature".to_owned(),
+            );
+        }
+        // NaN as well as infinity would never let a sample be taken
+        let interval = self.stats_sample_interval;
+        if !(interval.is_finite() && interval > 0.0) {
+            return Err(format!(
+                "stats_sample_interval should be positive and finite, not {}",
+                interval
+            ));
+        }
+        let (mean, stdev) = (
+            self.generation_interval_mean,
+            self.generation_interval_stdev,
+        );
+        if !(mean.is_finite() && stdev.is_finite()) {
+            return Err(format!(
+                "the generation interval should be finite, not {} with a deviation of {}",
+                mean, stdev
+            ));
+        }
+        Ok(())
+    }
+
+    // `factor` of the people in a box of `factor` of the area, so that the density stays the
+    // same, with the initially infected and vaccinated, the vectors and the hospital beds
+    // scaled alike; there's at least one initially infected if there was any
+    pub fn scaled(self, factor: f64) -> Params {
+        let scale =
+            |n: usize, at_least: usize| ((n as f64 * factor).round() as usize).max(at_least);
+        Params {
+            num_people: scale(self.num_people, 1),
+            size_x: self.size_x * factor.sqrt(),
+            size_y: self.size_y * factor.sqrt(),
+            init_infected: scale(self.init_infected, self.init_infected.min(1)),
+            init_vaccinated: scale(self.init_vaccinated, 0),
+            hospital_capacity: self.hospital_capacity * factor,
+            vectors: scale(self.vectors, 0),
+            ..self
+        }
+    }
 }
 
 fn default_num_people() -> usize {
@@ -83,3 +424,336 @@ fn default_duration() -> f64 {
 fn default_death_rate() -> f64 {
     0.02
 }
+
+fn default_corpse_display_time() -> f64 {
+    5.0
+}
+
+fn default_lockdown_mobility() -> f64 {
+    0.2
+}
+
+fn default_brush_radius() -> f64 {
+    2.0
+}
+
+fn default_region_grid_size() -> usize {
+    10
+}
+
+fn default_stats_sample_interval() -> f64 {
+    1.0
+}
+
+fn default_severe_fraction() -> f64 {
+    0.1
+}
+
+fn default_critical_fraction() -> f64 {
+    0.02
+}
+
+fn default_severe_cost() -> f64 {
+    1.0
+}
+
+fn default_critical_cost() -> f64 {
+    3.0
+}
+
+fn default_hospital_capacity() -> f64 {
+    20.0
+}
+
+fn default_lockdown_day_cost() -> f64 {
+    1.0
+}
+
+fn default_vaccine_cost() -> f64 {
+    0.1
+}
+
+fn default_test_cost() -> f64 {
+    0.05
+}
+
+fn default_isolation_day_cost() -> f64 {
+    1.0
+}
+
+fn default_dose_interval() -> f64 {
+    21.0
+}
+
+fn default_dose_delay() -> f64 {
+    14.0
+}
+
+fn default_first_dose_efficacy() -> f64 {
+    0.5
+}
+
+fn default_hybrid_immunity_bonus() -> f64 {
+    0.0
+}
+
+fn default_timeline_sample() -> usize {
+    0
+}
+
+fn default_audio_tick_infections() -> usize {
+    10
+}
+
+fn default_rewind_memory() -> f64 {
+    256.0
+}
+
+fn default_rewind_interval() -> f64 {
+    1.0
+}
+
+fn default_branch_intervention() -> Intervention {
+    Intervention::ToggleLockdown
+}
+
+fn default_reference_contact_duration() -> f64 {
+    0.05
+}
+
+fn default_crowding_exponent() -> f64 {
+    0.0
+}
+
+fn default_crowding_radius() -> f64 {
+    5.0
+}
+
+fn default_weather_timescale() -> f64 {
+    5.0
+}
+
+fn default_weather_mobility_effect() -> f64 {
+    0.5
+}
+
+fn default_weather_transmission_effect() -> f64 {
+    0.5
+}
+
+fn default_sim_hz() -> f64 {
+    60.0
+}
+
+fn default_render_fps() -> f64 {
+    60.0
+}
+
+fn default_event_log_window() -> usize {
+    0
+}
+
+fn default_event_log_max_size() -> f64 {
+    64.0
+}
+
+fn default_immunity_display_time() -> f64 {
+    90.0
+}
+
+fn default_lod_threshold() -> usize {
+    5000
+}
+
+fn default_transmissibility_presets() -> [f64; 5] {
+    [0.5, 1.0, 1.5, 2.0, 3.0]
+}
+
+fn default_pressure_iterations() -> usize {
+    0
+}
+
+fn default_target_contact_rate() -> f64 {
+    0.0
+}
+
+fn default_case_detection_probability() -> f64 {
+    0.3
+}
+
+fn default_case_reporting_delay() -> f64 {
+    5.0
+}
+
+fn default_death_detection_probability() -> f64 {
+    0.9
+}
+
+fn default_death_reporting_delay() -> f64 {
+    14.0
+}
+
+fn default_generation_interval_mean() -> f64 {
+    15.0
+}
+
+fn default_generation_interval_stdev() -> f64 {
+    8.0
+}
+
+fn default_history_cap() -> usize {
+    0
+}
+
+fn default_timeline_cap() -> usize {
+    0
+}
+
+fn default_init_past_infected() -> f64 {
+    0.0
+}
+
+fn default_init_past_infected_max_days() -> f64 {
+    0.0
+}
+
+fn default_dose_half_life() -> f64 {
+    1.0
+}
+
+fn default_dose_threshold_mean() -> f64 {
+    1.0
+}
+
+fn default_natural_mortality() -> [f64; 4] {
+    [0.0005, 0.001, 0.005, 0.04]
+}
+
+fn default_birth_rate() -> f64 {
+    0.0
+}
+
+fn default_vectors() -> usize {
+    0
+}
+
+fn default_vector_speed_stdev() -> f64 {
+    10.0
+}
+
+fn default_vector_contact_radius() -> f64 {
+    1.0
+}
+
+fn default_exposure_to_vector() -> f64 {
+    0.5
+}
+
+fn default_exposure_from_vector() -> f64 {
+    0.5
+}
+
+fn default_vector_infection_duration() -> f64 {
+    10.0
+}
+
+fn default_fomite_grid_size() -> usize {
+    20
+}
+
+fn default_fomite_shedding() -> f64 {
+    1.0
+}
+
+fn default_fomite_half_life() -> f64 {
+    0.5
+}
+
+fn default_fomite_exposure() -> f64 {
+    1.0
+}
+
+fn default_wind_x() -> f64 {
+    0.0
+}
+
+fn default_wind_y() -> f64 {
+    0.0
+}
+
+fn default_fomite_diffusion() -> f64 {
+    0.0
+}
+
+fn default_navigation_grid_size() -> usize {
+    50
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Pl,
+    De,
+}
+
+// what is adjusted to reach the `target_contact_rate`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactCalibration {
+    // the spread of the speeds
+    #[default]
+    Speed,
+    // the number of people in the box
+    Density,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaling_keeps_the_density() {
+        let params = Params {
+            num_people: 1000,
+            init_infected: 20,
+            init_vaccinated: 100,
+            hospital_capacity: 10.0,
+            ..Default::default()
+        };
+        let scaled = params.scaled(0.25);
+        assert_eq!(scaled.num_people, 250);
+        assert_eq!(scaled.init_infected, 5);
+        assert_eq!(scaled.init_vaccinated, 25);
+        assert!((scaled.hospital_capacity - 2.5).abs() < 1e-9);
+        let density = |params: &Params| params.num_people as f64 / (params.size_x * params.size_y);
+        assert!((density(&scaled) - density(&params)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scaling_leaves_an_infection_if_there_was_one() {
+        let params = Params {
+            init_infected: 1,
+            init_vaccinated: 0,
+            ..Default::default()
+        };
+        let scaled = params.scaled(0.1);
+        assert_eq!(scaled.init_infected, 1);
+        assert_eq!(scaled.init_vaccinated, 0);
+        let none = Params {
+            init_infected: 0,
+            ..params
+        };
+        assert_eq!(none.scaled(0.1).init_infected, 0);
+    }
+
+    #[test]
+    fn compressing_the_log_needs_zstd() {
+        let params = Params {
+            event_log_compression: true,
+            ..Default::default()
+        };
+        assert_eq!(params.validate().is_ok(), cfg!(feature = "zstd"));
+    }
+}
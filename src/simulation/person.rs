@@ -1,18 +1,85 @@
 use nalgebra::Vector2;
 use rand::{
-    distributions::{Distribution, Normal, Uniform},
+    distributions::{Distribution, Exp, Normal},
     Rng,
 };
 
-use super::{clamp_f64, clamp_vec2, params::Params};
+use super::{
+    clamp_vec2,
+    event_log::{Setting, Source},
+    params::Params,
+    strain::Strains,
+};
 
 pub const RADIUS: f64 = 0.5;
 
+pub fn overlaps(pos1: Vector2<f64>, pos2: Vector2<f64>, box_size: (f64, f64)) -> bool {
+    let pos_diff = clamp_vec2(pos1 - pos2, box_size);
+    pos_diff.dot(&pos_diff).sqrt() < RADIUS * 2.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Mild,
+    Severe,
+    Critical,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Mild => "mild",
+            Severity::Severe => "severe",
+            Severity::Critical => "critical",
+        }
+    }
+
+    fn random<R: Rng>(params: Params, rng: &mut R) -> Severity {
+        let draw = rng.gen::<f64>();
+        if draw < params.critical_fraction {
+            Severity::Critical
+        } else if draw < params.critical_fraction + params.severe_fraction {
+            Severity::Severe
+        } else {
+            Severity::Mild
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Healed,
+    Died,
+}
+
+// how the epidemic went for someone, so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Course {
+    NeverInfected,
+    // and still alive
+    Infected,
+    Died,
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Status {
     infected: Option<f64>, // simulation time when infected
-    past_infected: bool,
-    vaccinated: bool,
+    severity: Option<Severity>,
+    recovered: Option<f64>, // simulation time of the last recovery
+    // of the current infection
+    strain: u8,
+    // a bit for each strain the person recovered from
+    recovered_from: u32,
+    // simulation times of the vaccine doses
+    first_dose: Option<f64>,
+    second_dose: Option<f64>,
+    // the version of the vaccine given with the last dose
+    vaccine: u8,
+    dead: Option<f64>, // simulation time of death
+    // the exposure dose accumulated in the dose-response mode, infecting the person once it
+    // reaches the threshold, which is drawn when they're first exposed
+    dose: f64,
+    dose_threshold: Option<f64>,
 }
 
 impl Status {
@@ -20,117 +87,292 @@ impl Status {
         self.infected
     }
 
+    // of the current infection
+    pub fn strain(&self) -> Option<usize> {
+        self.infected.map(|_| self.strain as usize)
+    }
+
+    pub fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
     pub fn past_infected(&self) -> bool {
-        self.past_infected
+        self.recovered.is_some()
     }
 
-    pub fn vaccinated(&self) -> bool {
-        self.vaccinated
+    pub fn course(&self) -> Course {
+        if self.dead.is_some() {
+            Course::Died
+        } else if self.infected.is_some() || self.past_infected() {
+            Course::Infected
+        } else {
+            Course::NeverInfected
+        }
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-pub struct Person {
-    position: Vector2<f64>,
-    velocity: Vector2<f64>,
-    status: Status,
-}
+    // the time of the last recovery
+    pub fn recovered(&self) -> Option<f64> {
+        self.recovered
+    }
 
-impl Person {
-    pub fn random<R: Rng>(rng: &mut R, space_size: (f64, f64), speed_stdev: f64) -> Person {
-        let (size_x, size_y) = space_size;
-        let position = Vector2::new(
-            Uniform::new(RADIUS, size_x - RADIUS).sample(rng),
-            Uniform::new(RADIUS, size_y - RADIUS).sample(rng),
-        );
-        let velocity = Vector2::new(
-            Normal::new(0.0, speed_stdev).sample(rng),
-            Normal::new(0.0, speed_stdev).sample(rng),
-        );
+    // the time of the last recovery or vaccine dose
+    pub fn immune_since(&self) -> Option<f64> {
+        [self.recovered, self.first_dose, self.second_dose]
+            .iter()
+            .flatten()
+            .copied()
+            .max_by(|t1, t2| t1.partial_cmp(t2).unwrap())
+    }
 
-        Person {
-            position,
-            velocity,
-            status: Default::default(),
+    // at least one dose
+    pub fn vaccinated(&self) -> bool {
+        self.first_dose.is_some()
+    }
+
+    pub fn doses(&self) -> usize {
+        self.first_dose.iter().chain(&self.second_dose).count()
+    }
+
+    // how much of the full vaccine protection the person has, from 0 to 1
+    pub fn protection(&self, time: f64, params: Params) -> f64 {
+        if !params.two_dose {
+            return if self.vaccinated() { 1.0 } else { 0.0 };
+        }
+        let effective = |dose: Option<f64>| dose.is_some_and(|t| time - t >= params.dose_delay);
+        if effective(self.second_dose) {
+            1.0
+        } else if effective(self.first_dose) {
+            params.first_dose_efficacy
+        } else {
+            0.0
         }
     }
 
-    pub fn overlaps(&self, other: &Person, box_size: (f64, f64)) -> bool {
-        let pos_diff = clamp_vec2(self.position - other.position, box_size);
-        pos_diff.dot(&pos_diff).sqrt() < RADIUS * 2.0
+    // both recovered and vaccinated
+    pub fn hybrid_immune(&self) -> bool {
+        self.past_infected() && self.vaccinated() && self.infected.is_none()
     }
 
-    pub fn pos(&self) -> Vector2<f64> {
-        self.position
+    pub fn second_dose_due(&self, time: f64, params: Params) -> bool {
+        params.two_dose
+            && self.dead.is_none()
+            && self.second_dose.is_none()
+            && self
+                .first_dose
+                .is_some_and(|t| time - t >= params.dose_interval)
     }
 
-    pub fn vel(&self) -> Vector2<f64> {
-        self.velocity
+    pub fn dead(&self) -> Option<f64> {
+        self.dead
     }
 
-    pub fn status(&self) -> &Status {
-        &self.status
+    pub fn susceptible(&self) -> bool {
+        self.dead.is_none() && self.infected.is_none() && !self.vaccinated()
+    }
+
+    pub fn infect<R: Rng>(&mut self, time: f64, params: Params, rng: &mut R) {
+        self.infect_with(0, time, params, rng);
+    }
+
+    pub fn infect_with<R: Rng>(&mut self, strain: usize, time: f64, params: Params, rng: &mut R) {
+        self.dose = 0.0;
+        self.dose_threshold = None;
+        self.infected = Some(time);
+        self.strain = strain as u8;
+        self.severity = Some(Severity::random(params, rng));
+    }
+
+    // gives the next dose of the given version of the vaccine, if any is left
+    pub fn vaccinate(&mut self, time: f64, vaccine: usize) {
+        if self.first_dose.is_none() {
+            self.first_dose = Some(time);
+        } else if self.second_dose.is_none() {
+            self.second_dose = Some(time);
+        } else {
+            return;
+        }
+        self.vaccine = vaccine as u8;
+    }
+
+    // stands in for what passes the infection on without being a person, eg. the surfaces
+    pub fn carrier(strain: usize, time: f64) -> Status {
+        Status {
+            infected: Some(time),
+            strain: strain as u8,
+            ..Default::default()
+        }
     }
 
-    pub fn infect(&mut self, time: f64) {
-        self.status.infected = Some(time);
+    // ends the infection without any immunity, as for the vectors
+    pub fn clear_infection(&mut self) {
+        self.infected = None;
+        self.severity = None;
     }
 
-    pub fn vaccinate(&mut self) {
-        self.status.vaccinated = true;
+    // as if the person recovered from the original strain at `time`
+    pub fn mark_recovered(&mut self, time: f64) {
+        self.recovered = Some(time);
+        self.recovered_from |= 1;
     }
 
-    pub fn shift(&mut self, dt: f64, box_size: (f64, f64)) {
-        self.position += self.velocity * dt;
-        self.position.x = clamp_f64(self.position.x, box_size.0);
-        self.position.y = clamp_f64(self.position.y, box_size.1);
+    pub fn die(&mut self, time: f64) {
+        self.dead = Some(time);
     }
 
-    pub fn set_vel(&mut self, vel: Vector2<f64>) {
-        self.velocity = vel;
+    // Returns the source of the infection if the person got infected. `exposure` is the
+    // number of independent chances the other person had to pass the infection on, which
+    // can be fractional - eg. for a short contact in a sparse area.
+    #[allow(clippy::too_many_arguments)]
+    pub fn contact<R: Rng>(
+        &mut self,
+        time: f64,
+        params: Params,
+        strains: &Strains,
+        other: &Status,
+        setting: Setting,
+        exposure: f64,
+        rng: &mut R,
+    ) -> Option<Source> {
+        if self.dead.is_some() {
+            return None;
+        }
+        if other.dead.is_some() && !params.corpse_infectious {
+            return None;
+        }
+        if other.infected.is_some() {
+            let strain = other.strain as usize;
+            let threshold = self.infection_prob(time, params, strains, other);
+            let infected = if params.dose_response {
+                // with an exponential threshold and no decay, this is the same as rolling for
+                // every contact
+                self.dose += -(1.0 - threshold.min(1.0)).ln() * exposure;
+                let limit = *self
+                    .dose_threshold
+                    .get_or_insert_with(|| Exp::new(1.0 / params.dose_threshold_mean).sample(rng));
+                self.dose >= limit
+            } else {
+                let threshold = 1.0 - (1.0 - threshold.min(1.0)).powf(exposure);
+                rng.gen::<f64>() < threshold
+            };
+            if infected {
+                self.infect_with(strain, time, params, rng);
+                return Some(Source::new(setting, other));
+            }
+        }
+        None
     }
 
-    pub fn contact<R: Rng>(&mut self, time: f64, params: Params, other: Person, rng: &mut R) {
-        if other.status.infected.is_some() {
-            let draw = rng.gen::<f64>();
-            let threshold = match (
-                self.status.past_infected,
-                self.status.vaccinated,
-                other.status.vaccinated,
-            ) {
+    // of getting infected by `other` in a contact of the reference length
+    fn infection_prob(&self, time: f64, params: Params, strains: &Strains, other: &Status) -> f64 {
+        let threshold =
+            |healed, vaccinated, other_vaccinated| match (healed, vaccinated, other_vaccinated) {
                 (false, false, false) => params.infection_prob_infected_to_general,
                 (true, false, false) => params.infection_prob_infected_to_healed,
-                (_, true, false) => params.infection_prob_infected_to_vaccinated,
+                (false, true, false) => params.infection_prob_infected_to_vaccinated,
+                (true, true, false) => {
+                    params.infection_prob_infected_to_vaccinated
+                        * (1.0 - params.hybrid_immunity_bonus)
+                }
                 (false, false, true) => params.infection_prob_vaccinated_to_general,
                 (true, false, true) => params.infection_prob_vaccinated_to_healed,
-                (_, true, true) => params.infection_prob_vaccinated_to_vaccinated,
+                (false, true, true) => params.infection_prob_vaccinated_to_vaccinated,
+                (true, true, true) => {
+                    params.infection_prob_vaccinated_to_vaccinated
+                        * (1.0 - params.hybrid_immunity_bonus)
+                }
             };
-            if draw < threshold {
-                self.status.infected = Some(time);
-            }
-        }
+        // partial protection interpolates between the unvaccinated and vaccinated cases
+        let strain = other.strain as usize;
+        let own =
+            self.protection(time, params) * strains.vaccine_efficacy(self.vaccine as usize, strain);
+        let others = other.protection(time, params)
+            * strains.vaccine_efficacy(other.vaccine as usize, strain);
+        let vaccination = |healed| {
+            (1.0 - own) * (1.0 - others) * threshold(healed, false, false)
+                + own * (1.0 - others) * threshold(healed, true, false)
+                + (1.0 - own) * others * threshold(healed, false, true)
+                + own * others * threshold(healed, true, true)
+        };
+        // and so does a past infection with a different strain
+        let immunity = strains.protection(self.recovered_from, strain);
+        ((1.0 - immunity) * vaccination(false) + immunity * vaccination(true))
+            * strains.transmissibility(strain)
     }
 
-    pub fn update_status<R: Rng>(
+    // returns the outcome of the infection, if it ended during this step
+    pub fn update<R: Rng>(
         &mut self,
         time: f64,
         params: Params,
         dt: f64,
         rng: &mut R,
-    ) -> bool {
-        match self.status.infected {
-            Some(infected) => {
-                if rng.gen::<f64>() < params.death_rate * dt / params.infection_avg_duration {
-                    return true;
-                }
-                let heal_prob = (time - infected) / params.infection_avg_duration - 0.7;
-                if rng.gen::<f64>() < heal_prob {
-                    self.status.infected = None;
-                    self.status.past_infected = true;
-                }
+    ) -> Option<Outcome> {
+        if self.dead.is_some() {
+            return None;
+        }
+        if params.dose_response && self.dose > 0.0 {
+            self.dose *= 0.5f64.powf(dt / params.dose_half_life);
+        }
+        if let Some(infected) = self.infected {
+            if rng.gen::<f64>() < params.death_rate * dt / params.infection_avg_duration {
+                return Some(Outcome::Died);
+            }
+            let heal_prob = (time - infected) / params.infection_avg_duration - 0.7;
+            if rng.gen::<f64>() < heal_prob {
+                self.infected = None;
+                self.severity = None;
+                self.recovered = Some(time);
+                self.recovered_from |= 1 << self.strain;
+                return Some(Outcome::Healed);
             }
-            _ => (),
         }
-        false
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Person {
+    position: Vector2<f64>,
+    velocity: Vector2<f64>,
+    status: Status,
+}
+
+impl Person {
+    pub fn new(position: Vector2<f64>, velocity: Vector2<f64>, status: Status) -> Person {
+        Person {
+            position,
+            velocity,
+            status,
+        }
+    }
+
+    // a person at the given position, with a random velocity
+    pub fn random_at<R: Rng>(rng: &mut R, position: Vector2<f64>, speed_stdev: f64) -> Person {
+        let velocity = Vector2::new(
+            Normal::new(0.0, speed_stdev).sample(rng),
+            Normal::new(0.0, speed_stdev).sample(rng),
+        );
+
+        Person {
+            position,
+            velocity,
+            status: Default::default(),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Person, box_size: (f64, f64)) -> bool {
+        overlaps(self.position, other.position, box_size)
+    }
+
+    pub fn pos(&self) -> Vector2<f64> {
+        self.position
+    }
+
+    pub fn vel(&self) -> Vector2<f64> {
+        self.velocity
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
     }
 }
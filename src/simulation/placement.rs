@@ -0,0 +1,159 @@
+use std::fmt;
+
+use nalgebra::Vector2;
+use rand::{
+    distributions::{Distribution, Uniform},
+    Rng,
+};
+
+use super::{
+    broad_phase,
+    community::Community,
+    density::DensityMap,
+    geometry::Segment,
+    person::{overlaps, RADIUS},
+};
+
+// tries at finding a random spot for someone before giving up
+const PLACEMENT_ATTEMPTS: usize = 100_000;
+
+// A rule broken by someone's initial position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    OutsideBox,
+    // where the density map is zero
+    Uninhabited,
+    // partly inside the community with this index and partly outside of it
+    CommunityBoundary(usize),
+    // on top of someone placed earlier
+    Overlap,
+    // touching an obstacle
+    Wall,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::OutsideBox => write!(f, "outside of the box"),
+            Violation::Uninhabited => write!(f, "where the density map is zero"),
+            Violation::CommunityBoundary(community) => {
+                write!(f, "on the boundary of community {}", community)
+            }
+            Violation::Overlap => write!(f, "overlapping someone else"),
+            Violation::Wall => write!(f, "touching a wall"),
+        }
+    }
+}
+
+// What the initial positions have to respect.
+#[derive(Debug, Clone, Copy)]
+pub struct Constraints<'a> {
+    pub box_size: (f64, f64),
+    pub density: Option<&'a DensityMap>,
+    pub communities: &'a [Community],
+    pub walls: &'a [Segment],
+}
+
+impl<'a> Constraints<'a> {
+    // the first rule broken by someone at `pos`, not counting the overlaps
+    pub fn check(&self, pos: Vector2<f64>) -> Option<Violation> {
+        let (size_x, size_y) = self.box_size;
+        if pos.x < 0.0 || pos.x >= size_x || pos.y < 0.0 || pos.y >= size_y {
+            return Some(Violation::OutsideBox);
+        }
+        if let Some(density) = self.density {
+            if density.at(pos, self.box_size) == 0.0 {
+                return Some(Violation::Uninhabited);
+            }
+        }
+        if self.walls.iter().any(|wall| wall.distance(pos) < RADIUS) {
+            return Some(Violation::Wall);
+        }
+        self.communities
+            .iter()
+            .position(|community| straddles(community, pos))
+            .map(Violation::CommunityBoundary)
+    }
+
+    // free of the others in `placed` too
+    pub fn allows(&self, pos: Vector2<f64>, placed: &[Vector2<f64>]) -> bool {
+        self.check(pos).is_none()
+            && placed
+                .iter()
+                .all(|&other| !overlaps(pos, other, self.box_size))
+    }
+
+    // Uniformly random, unless there is a density map. Panics if there seems to be no room
+    // left.
+    pub fn random_spot<R: Rng>(&self, rng: &mut R, placed: &[Vector2<f64>]) -> Vector2<f64> {
+        self.try_random_spot(rng, placed, PLACEMENT_ATTEMPTS)
+            .unwrap_or_else(|| {
+                panic!(
+                    "no room for person {} after {} attempts",
+                    placed.len(),
+                    PLACEMENT_ATTEMPTS
+                )
+            })
+    }
+
+    // the same, None if there's no room found in `attempts` tries
+    pub fn try_random_spot<R: Rng>(
+        &self,
+        rng: &mut R,
+        placed: &[Vector2<f64>],
+        attempts: usize,
+    ) -> Option<Vector2<f64>> {
+        let (size_x, size_y) = self.box_size;
+        let x = Uniform::new(RADIUS, size_x - RADIUS);
+        let y = Uniform::new(RADIUS, size_y - RADIUS);
+        (0..attempts)
+            .map(|_| match self.density {
+                Some(density) => density.sample(rng, self.box_size),
+                None => Vector2::new(x.sample(rng), y.sample(rng)),
+            })
+            .find(|&pos| self.allows(pos, placed))
+    }
+}
+
+fn straddles(community: &Community, pos: Vector2<f64>) -> bool {
+    let rect = community.rect();
+    rect.grown(RADIUS).contains(pos) && !rect.grown(-RADIUS).contains(pos)
+}
+
+// Everyone breaking a rule, in the order of the indices. Of two overlapping people, the one
+// with the larger index is reported.
+pub fn validate(positions: &[Vector2<f64>], constraints: &Constraints) -> Vec<(usize, Violation)> {
+    let mut violations: Vec<_> = positions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, &pos)| constraints.check(pos).map(|violation| (index, violation)))
+        .collect();
+    let mut overlapping: Vec<usize> = broad_phase::find_pairs(positions, constraints.box_size)
+        .into_iter()
+        .map(|(_, index)| index)
+        .collect();
+    overlapping.sort_unstable();
+    overlapping.dedup();
+    for index in overlapping {
+        if let Err(insert_at) = violations.binary_search_by_key(&index, |&(index, _)| index) {
+            violations.insert(insert_at, (index, Violation::Overlap));
+        }
+    }
+    violations
+}
+
+// eg. "2 overlapping someone else, 1 where the density map is zero"
+pub fn summarize(violations: &[(usize, Violation)]) -> String {
+    let mut counts: Vec<(Violation, usize)> = vec![];
+    for &(_, violation) in violations {
+        match counts.iter_mut().find(|(kind, _)| *kind == violation) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((violation, 1)),
+        }
+    }
+    counts
+        .iter()
+        .map(|(violation, count)| format!("{} {}", count, violation))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use serde_derive::Deserialize;
+use toml::Value;
+
+use super::{intervention::Intervention, Statistics};
+
+// An automated control strategy, consulted once per simulated day.
+pub trait Policy: Send {
+    fn decide(&mut self, stats: &Statistics, day: u64) -> Vec<Intervention>;
+}
+
+// Creates a policy from its `[policy]` table in the config.
+pub type PolicyFactory = fn(&Value) -> Box<dyn Policy>;
+
+#[derive(Default)]
+pub struct PolicyRegistry {
+    factories: HashMap<String, PolicyFactory>,
+}
+
+impl PolicyRegistry {
+    pub fn new() -> PolicyRegistry {
+        Default::default()
+    }
+
+    pub fn with_builtins() -> PolicyRegistry {
+        let mut registry = PolicyRegistry::new();
+        registry.register("threshold_lockdown", |config| {
+            Box::new(config.clone().try_into::<ThresholdLockdown>().unwrap())
+        });
+        registry.register("circuit_breaker", |config| {
+            let breaker = config.clone().try_into::<CircuitBreaker>().unwrap();
+            assert!(
+                breaker.period > 0,
+                "the circuit breaker period should be positive"
+            );
+            Box::new(breaker)
+        });
+        registry.register("test_and_isolate", |config| {
+            Box::new(config.clone().try_into::<TestAndIsolate>().unwrap())
+        });
+        #[cfg(feature = "scripting")]
+        registry.register("script", |config| {
+            let path = config
+                .get("path")
+                .and_then(Value::as_str)
+                .expect("policy script path not given");
+            Box::new(super::script_policy::ScriptPolicy::load(path))
+        });
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, factory: PolicyFactory) {
+        self.factories.insert(name.to_owned(), factory);
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.factories.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    pub fn create(&self, name: &str, config: &Value) -> Option<Box<dyn Policy>> {
+        self.factories.get(name).map(|factory| factory(config))
+    }
+}
+
+// Locks down when the number of infected exceeds `lockdown_above`, lifts the lockdown when
+// it drops below `lift_below`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThresholdLockdown {
+    #[serde(default = "default_lockdown_above")]
+    lockdown_above: usize,
+    #[serde(default = "default_lift_below")]
+    lift_below: usize,
+    #[serde(skip)]
+    active: bool,
+}
+
+impl Policy for ThresholdLockdown {
+    fn decide(&mut self, stats: &Statistics, _day: u64) -> Vec<Intervention> {
+        if !self.active && stats.infected > self.lockdown_above {
+            self.active = true;
+            vec![Intervention::SetLockdown(true)]
+        } else if self.active && stats.infected < self.lift_below {
+            self.active = false;
+            vec![Intervention::SetLockdown(false)]
+        } else {
+            vec![]
+        }
+    }
+}
+
+// Locks down for the first `duration` days of every `period` days, regardless of the numbers.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CircuitBreaker {
+    #[serde(default = "default_period")]
+    period: u64,
+    #[serde(default = "default_duration")]
+    duration: u64,
+    #[serde(skip)]
+    active: Option<bool>,
+}
+
+impl Policy for CircuitBreaker {
+    fn decide(&mut self, _stats: &Statistics, day: u64) -> Vec<Intervention> {
+        let active = day % self.period < self.duration;
+        if self.active == Some(active) {
+            return vec![];
+        }
+        self.active = Some(active);
+        vec![Intervention::SetLockdown(active)]
+    }
+}
+
+// Once there are more than `start_above` infected, starts the quarantine, which tests everyone
+// infected and isolates them. There's no contact tracing in the model, so nobody else is.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestAndIsolate {
+    #[serde(default = "default_start_above")]
+    start_above: usize,
+    #[serde(skip)]
+    started: bool,
+}
+
+impl Policy for TestAndIsolate {
+    fn decide(&mut self, stats: &Statistics, _day: u64) -> Vec<Intervention> {
+        if self.started || stats.infected <= self.start_above {
+            return vec![];
+        }
+        self.started = true;
+        vec![Intervention::SetQuarantine(true)]
+    }
+}
+
+fn default_lockdown_above() -> usize {
+    100
+}
+
+fn default_lift_below() -> usize {
+    20
+}
+
+fn default_period() -> u64 {
+    28
+}
+
+fn default_duration() -> u64 {
+    14
+}
+
+fn default_start_above() -> usize {
+    10
+}
@@ -0,0 +1,235 @@
+use std::mem;
+
+use nalgebra::Vector2;
+
+use super::{
+    clamp_f64,
+    person::{Person, Status},
+};
+
+// People are stored as parallel arrays, so that the hot loops (movement, collision detection)
+// only touch the data they need.
+#[derive(Debug, Clone, Default)]
+pub struct Population {
+    positions: Vec<Vector2<f64>>,
+    velocities: Vec<Vector2<f64>>,
+    statuses: Vec<Status>,
+    // identifiers stay the same when people are removed, unlike the indices
+    ids: Vec<usize>,
+    next_id: usize,
+    communities: Vec<Option<usize>>,
+    // in years, where known
+    ages: Vec<Option<f64>>,
+}
+
+// the youngest age in each of the age groups, in years
+pub const AGE_GROUPS: [f64; 4] = [0.0, 18.0, 40.0, 65.0];
+
+pub fn age_group(age: f64) -> usize {
+    AGE_GROUPS
+        .iter()
+        .rposition(|&youngest| age >= youngest)
+        .unwrap_or(0)
+}
+
+fn retain_by_mask<T>(values: &mut Vec<T>, keep: &[bool]) {
+    let mut index = 0;
+    values.retain(|_| {
+        index += 1;
+        keep[index - 1]
+    });
+}
+
+impl Population {
+    pub fn new() -> Population {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.statuses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+    }
+
+    pub fn approx_size(&self) -> usize {
+        let per_person = 2 * mem::size_of::<Vector2<f64>>()
+            + mem::size_of::<Status>()
+            + mem::size_of::<usize>()
+            + mem::size_of::<Option<usize>>()
+            + mem::size_of::<Option<f64>>();
+        self.len() * per_person
+    }
+
+    pub fn push(&mut self, person: Person) {
+        self.positions.push(person.pos());
+        self.velocities.push(person.vel());
+        self.statuses.push(*person.status());
+        self.ids.push(self.next_id);
+        self.next_id += 1;
+        self.communities.push(None);
+        self.ages.push(None);
+    }
+
+    // the ids have to be pushed in increasing order
+    pub fn push_with_id(&mut self, id: usize, person: Person) {
+        self.next_id = id;
+        self.push(person);
+    }
+
+    pub fn get(&self, index: usize) -> Person {
+        Person::new(
+            self.positions[index],
+            self.velocities[index],
+            self.statuses[index],
+        )
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Person> + '_ {
+        (0..self.len()).map(move |index| self.get(index))
+    }
+
+    pub fn positions(&self) -> &[Vector2<f64>] {
+        &self.positions
+    }
+
+    pub fn velocities(&self) -> &[Vector2<f64>] {
+        &self.velocities
+    }
+
+    pub fn statuses(&self) -> &[Status] {
+        &self.statuses
+    }
+
+    pub fn ids(&self) -> &[usize] {
+        &self.ids
+    }
+
+    pub fn communities(&self) -> &[Option<usize>] {
+        &self.communities
+    }
+
+    pub fn communities_mut(&mut self) -> &mut [Option<usize>] {
+        &mut self.communities
+    }
+
+    pub fn ages(&self) -> &[Option<f64>] {
+        &self.ages
+    }
+
+    pub fn ages_mut(&mut self) -> &mut [Option<f64>] {
+        &mut self.ages
+    }
+
+    pub fn statuses_mut(&mut self) -> &mut [Status] {
+        &mut self.statuses
+    }
+
+    pub fn parts_mut(&mut self) -> (&mut [Vector2<f64>], &mut [Vector2<f64>], &mut [Status]) {
+        (
+            &mut self.positions,
+            &mut self.velocities,
+            &mut self.statuses,
+        )
+    }
+
+    // returns the statuses of the people who were removed, with their ages
+    pub fn retain<F: FnMut(&Status) -> bool>(&mut self, f: F) -> Vec<(Status, Option<f64>)> {
+        let keep: Vec<bool> = self.statuses.iter().map(f).collect();
+        let removed = self
+            .statuses
+            .iter()
+            .zip(&self.ages)
+            .zip(&keep)
+            .filter(|(_, &keep)| !keep)
+            .map(|((status, &age), _)| (*status, age))
+            .collect();
+        retain_by_mask(&mut self.positions, &keep);
+        retain_by_mask(&mut self.velocities, &keep);
+        retain_by_mask(&mut self.statuses, &keep);
+        retain_by_mask(&mut self.ids, &keep);
+        retain_by_mask(&mut self.communities, &keep);
+        retain_by_mask(&mut self.ages, &keep);
+        removed
+    }
+
+    // `mobility` is the fraction of its velocity with which each person moves
+    #[cfg(not(feature = "simd"))]
+    pub fn shift(&mut self, dt: f64, mobility: &[f64], box_size: (f64, f64)) {
+        let people = self
+            .positions
+            .iter_mut()
+            .zip(&self.velocities)
+            .zip(mobility);
+        for ((pos, vel), mobility) in people {
+            *pos += *vel * (dt * mobility);
+            pos.x = clamp_f64(pos.x, box_size.0);
+            pos.y = clamp_f64(pos.y, box_size.1);
+        }
+    }
+
+    // Four people at a time, with their x and y coordinates in the lanes of a vector each,
+    // and the ones left over one by one.
+    #[cfg(feature = "simd")]
+    pub fn shift(&mut self, dt: f64, mobility: &[f64], box_size: (f64, f64)) {
+        use wide::{f64x4, CmpGt, CmpLt};
+
+        // around the box, like `clamp_f64`
+        let wrap = |coords: f64x4, limit: f64| {
+            let coords = coords.cmp_gt(limit).blend(coords - limit, coords);
+            coords.cmp_lt(0.0).blend(coords + limit, coords)
+        };
+        let len = self.positions.len().min(mobility.len());
+        let lanes = len / 4 * 4;
+        let people = self.positions[..lanes]
+            .chunks_exact_mut(4)
+            .zip(self.velocities[..lanes].chunks_exact(4))
+            .zip(mobility[..lanes].chunks_exact(4));
+        for ((pos, vel), mobility) in people {
+            let step = f64x4::from([mobility[0], mobility[1], mobility[2], mobility[3]]) * dt;
+            let x = f64x4::from([pos[0].x, pos[1].x, pos[2].x, pos[3].x])
+                + f64x4::from([vel[0].x, vel[1].x, vel[2].x, vel[3].x]) * step;
+            let y = f64x4::from([pos[0].y, pos[1].y, pos[2].y, pos[3].y])
+                + f64x4::from([vel[0].y, vel[1].y, vel[2].y, vel[3].y]) * step;
+            let (x, y) = (
+                wrap(x, box_size.0).to_array(),
+                wrap(y, box_size.1).to_array(),
+            );
+            for (lane, pos) in pos.iter_mut().enumerate() {
+                *pos = Vector2::new(x[lane], y[lane]);
+            }
+        }
+        let rest = self.positions[lanes..len]
+            .iter_mut()
+            .zip(&self.velocities[lanes..len])
+            .zip(&mobility[lanes..len]);
+        for ((pos, vel), mobility) in rest {
+            *pos += *vel * (dt * mobility);
+            pos.x = clamp_f64(pos.x, box_size.0);
+            pos.y = clamp_f64(pos.y, box_size.1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifting_wraps_everyone_around_the_box() {
+        let mut population = Population::new();
+        // more than a whole number of lanes, going out of the box on either side
+        for index in 0..7 {
+            let velocity = Vector2::new(if index % 2 == 0 { 3.0 } else { -3.0 }, 1.0);
+            let position = Vector2::new(1.0 + index as f64, 5.0);
+            population.push(Person::new(position, velocity, Status::default()));
+        }
+        let mobility = [1.0, 1.0, 0.0, 1.0, 1.0, 0.5, 1.0];
+        population.shift(1.0, &mobility, (8.0, 10.0));
+        let xs: Vec<_> = population.positions().iter().map(|pos| pos.x).collect();
+        assert_eq!(xs, vec![4.0, 7.0, 3.0, 1.0, 8.0, 4.5, 2.0]);
+        let ys: Vec<_> = population.positions().iter().map(|pos| pos.y).collect();
+        assert_eq!(ys, vec![6.0, 6.0, 5.0, 6.0, 6.0, 5.5, 6.0]);
+    }
+}
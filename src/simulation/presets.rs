@@ -0,0 +1,75 @@
+use super::params::Params;
+
+pub const PRESET_NAMES: &[&str] = &[
+    "flu",
+    "measles",
+    "covid",
+    "covid-delta",
+    "covid-omicron",
+    "ebola",
+];
+
+pub fn preset(name: &str) -> Option<Params> {
+    let base = Params::default();
+    let params = match name {
+        "flu" => Params {
+            infection_prob_infected_to_general: 0.03,
+            infection_prob_infected_to_healed: 0.01,
+            infection_prob_infected_to_vaccinated: 0.012,
+            infection_prob_vaccinated_to_general: 0.02,
+            infection_prob_vaccinated_to_healed: 0.007,
+            infection_prob_vaccinated_to_vaccinated: 0.008,
+            infection_avg_duration: 7.0,
+            death_rate: 0.001,
+            ..base
+        },
+        "measles" => Params {
+            infection_prob_infected_to_general: 0.2,
+            infection_prob_infected_to_healed: 0.002,
+            infection_prob_infected_to_vaccinated: 0.006,
+            infection_prob_vaccinated_to_general: 0.2,
+            infection_prob_vaccinated_to_healed: 0.002,
+            infection_prob_vaccinated_to_vaccinated: 0.006,
+            infection_avg_duration: 14.0,
+            death_rate: 0.002,
+            ..base
+        },
+        "covid" => base,
+        "covid-delta" => Params {
+            infection_prob_infected_to_general: 0.04,
+            infection_prob_infected_to_healed: 0.01,
+            infection_prob_infected_to_vaccinated: 0.006,
+            infection_prob_vaccinated_to_general: 0.028,
+            infection_prob_vaccinated_to_healed: 0.007,
+            infection_prob_vaccinated_to_vaccinated: 0.004,
+            death_rate: 0.03,
+            ..base
+        },
+        "covid-omicron" => Params {
+            infection_prob_infected_to_general: 0.06,
+            infection_prob_infected_to_healed: 0.03,
+            infection_prob_infected_to_vaccinated: 0.024,
+            infection_prob_vaccinated_to_general: 0.045,
+            infection_prob_vaccinated_to_healed: 0.022,
+            infection_prob_vaccinated_to_vaccinated: 0.018,
+            infection_avg_duration: 20.0,
+            death_rate: 0.005,
+            ..base
+        },
+        "ebola" => Params {
+            infection_prob_infected_to_general: 0.05,
+            infection_prob_infected_to_healed: 0.005,
+            infection_prob_infected_to_vaccinated: 0.01,
+            infection_prob_vaccinated_to_general: 0.05,
+            infection_prob_vaccinated_to_healed: 0.005,
+            infection_prob_vaccinated_to_vaccinated: 0.01,
+            infection_avg_duration: 20.0,
+            death_rate: 0.5,
+            corpse_display_time: 10.0,
+            corpse_infectious: true,
+            ..base
+        },
+        _ => return None,
+    };
+    Some(params)
+}
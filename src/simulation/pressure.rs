@@ -0,0 +1,45 @@
+use nalgebra::Vector2;
+
+use super::{broad_phase, clamp_f64, clamp_vec2, person::RADIUS};
+
+// Position-based relaxation of the overlaps: every pass pushes each overlapping pair apart
+// along the line between them, until they just touch, and the passes repeat since resolving
+// one overlap in a crowd can create others. Only the `solid` people take part - the others
+// pass through everyone anyway.
+pub fn relax(
+    positions: &mut [Vector2<f64>],
+    solid: &[bool],
+    box_size: (f64, f64),
+    iterations: usize,
+) {
+    crate::trace_span!("pressure");
+    for _ in 0..iterations {
+        let pairs: Vec<_> = broad_phase::find_pairs(positions, box_size)
+            .into_iter()
+            .filter(|&(index1, index2)| solid[index1] && solid[index2])
+            .collect();
+        if pairs.is_empty() {
+            break;
+        }
+        for (index1, index2) in pairs {
+            let offset = clamp_vec2(positions[index2] - positions[index1], box_size);
+            let distance = offset.norm();
+            let depth = 2.0 * RADIUS - distance;
+            if depth <= 0.0 {
+                continue;
+            }
+            // two people in the same spot are separated along an arbitrary axis
+            let normal = if distance > 0.0 {
+                offset / distance
+            } else {
+                Vector2::new(1.0, 0.0)
+            };
+            let correction = normal * (depth / 2.0);
+            for &(index, sign) in &[(index1, -1.0), (index2, 1.0)] {
+                let pos = positions[index] + correction * sign;
+                positions[index] =
+                    Vector2::new(clamp_f64(pos.x, box_size.0), clamp_f64(pos.y, box_size.1));
+            }
+        }
+    }
+}
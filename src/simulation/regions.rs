@@ -0,0 +1,86 @@
+use nalgebra::Vector2;
+
+use super::population::Population;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegionStats {
+    pub population: usize,
+    pub infected: usize,
+    pub susceptible: usize,
+    pub vaccinated: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegionGrid {
+    box_size: (f64, f64),
+    cells_x: usize,
+    cells_y: usize,
+    cells: Vec<RegionStats>,
+}
+
+impl RegionGrid {
+    pub fn new(box_size: (f64, f64), cells_per_side: usize) -> RegionGrid {
+        let cells_per_side = cells_per_side.max(1);
+        RegionGrid {
+            box_size,
+            cells_x: cells_per_side,
+            cells_y: cells_per_side,
+            cells: vec![Default::default(); cells_per_side * cells_per_side],
+        }
+    }
+
+    pub fn rebuild(&mut self, people: &Population) {
+        for cell in &mut self.cells {
+            *cell = Default::default();
+        }
+        for (pos, status) in people.positions().iter().zip(people.statuses()) {
+            if status.dead().is_some() {
+                continue;
+            }
+            let (x, y) = match self.cell_at(*pos) {
+                Some(cell) => cell,
+                None => continue,
+            };
+            let cell = &mut self.cells[y * self.cells_x + x];
+            cell.population += 1;
+            if status.infected().is_some() {
+                cell.infected += 1;
+            }
+            if status.vaccinated() {
+                cell.vaccinated += 1;
+            }
+            if status.susceptible() {
+                cell.susceptible += 1;
+            }
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.cells_x, self.cells_y)
+    }
+
+    pub fn cell_at(&self, pos: Vector2<f64>) -> Option<(usize, usize)> {
+        let x = pos.x / self.box_size.0 * self.cells_x as f64;
+        let y = pos.y / self.box_size.1 * self.cells_y as f64;
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if x >= self.cells_x || y >= self.cells_y {
+            return None;
+        }
+        Some((x, y))
+    }
+
+    pub fn stats(&self, cell: (usize, usize)) -> RegionStats {
+        self.cells[cell.1 * self.cells_x + cell.0]
+    }
+
+    // lower left and upper right corner of the cell
+    pub fn bounds(&self, cell: (usize, usize)) -> (Vector2<f64>, Vector2<f64>) {
+        let width = self.box_size.0 / self.cells_x as f64;
+        let height = self.box_size.1 / self.cells_y as f64;
+        let min = Vector2::new(cell.0 as f64 * width, cell.1 as f64 * height);
+        (min, min + Vector2::new(width, height))
+    }
+}
@@ -0,0 +1,116 @@
+use std::mem;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    events::{SimEvent, Subscriber},
+    params::Params,
+    reproduction::DailyCounts,
+};
+
+// The cumulative counts, both as they really are and as known from the reports.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reported {
+    pub cases: usize,
+    pub deaths: usize,
+    pub true_cases: usize,
+    pub true_deaths: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Report {
+    Case,
+    Death,
+}
+
+// Infections are confirmed with `case_detection_probability` and deaths registered with
+// `death_detection_probability`, each appearing in the reports `case_reporting_delay` or
+// `death_reporting_delay` days after it happened.
+#[derive(Debug, Clone)]
+pub struct Reporting {
+    // apart from the one moving the people, so that the same seed gives the same epidemic
+    // with the reporting on or off
+    rng: StdRng,
+    // the events since the last update, not yet rolled for detection
+    happened: Vec<(f64, Report)>,
+    // the detected ones, with the times they are due to be reported
+    pending: Vec<(f64, Report)>,
+    counts: Reported,
+    // the cases reported on each day
+    daily_cases: DailyCounts,
+}
+
+impl Subscriber for Reporting {
+    fn notify(&mut self, time: f64, event: &SimEvent) {
+        let report = match event {
+            SimEvent::Infected { .. } => Report::Case,
+            SimEvent::Died { .. } => Report::Death,
+            _ => return,
+        };
+        self.happened.push((time, report));
+    }
+}
+
+impl Reporting {
+    pub fn new(seed: u64) -> Reporting {
+        Reporting {
+            rng: StdRng::seed_from_u64(seed),
+            happened: vec![],
+            pending: vec![],
+            counts: Default::default(),
+            daily_cases: Default::default(),
+        }
+    }
+
+    pub fn update(&mut self, time: f64, params: Params) {
+        for (happened, report) in self.happened.drain(..) {
+            let (probability, delay) = match report {
+                Report::Case => {
+                    self.counts.true_cases += 1;
+                    (
+                        params.case_detection_probability,
+                        params.case_reporting_delay,
+                    )
+                }
+                Report::Death => {
+                    self.counts.true_deaths += 1;
+                    (
+                        params.death_detection_probability,
+                        params.death_reporting_delay,
+                    )
+                }
+            };
+            if self.rng.gen::<f64>() < probability {
+                self.pending.push((happened + delay, report));
+            }
+        }
+        let (counts, daily_cases) = (&mut self.counts, &mut self.daily_cases);
+        self.pending.retain(|&(due, report)| {
+            if due > time {
+                return true;
+            }
+            match report {
+                Report::Case => {
+                    counts.cases += 1;
+                    daily_cases.add(due.floor() as usize);
+                }
+                Report::Death => counts.deaths += 1,
+            }
+            false
+        });
+    }
+
+    pub fn daily_cases(&self) -> &DailyCounts {
+        &self.daily_cases
+    }
+
+    pub fn approx_size(&self) -> usize {
+        mem::size_of_val(&self.happened[..])
+            + mem::size_of_val(&self.pending[..])
+            + self.daily_cases.approx_size()
+    }
+
+    pub fn counts(&self) -> Reported {
+        self.counts
+    }
+}
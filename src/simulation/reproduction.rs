@@ -0,0 +1,175 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    mem,
+};
+
+use super::events::{SimEvent, Subscriber};
+
+// days of infections averaged over by both of the estimates
+const WINDOW: usize = 7;
+// the generation interval is cut off after this many means
+const MAX_INTERVAL_MEANS: f64 = 3.0;
+// the days of infection counted for, up to the latest one; the number is shown for the last
+// full day before the reporting delay
+const KEPT_DAYS: usize = 120;
+
+// Counts by the day, forgetting the days more than KEPT_DAYS before the latest one.
+#[derive(Debug, Clone, Default)]
+pub struct DailyCounts {
+    first_day: usize,
+    counts: VecDeque<usize>,
+}
+
+impl DailyCounts {
+    pub fn add(&mut self, day: usize) {
+        if day < self.first_day {
+            return;
+        }
+        let index = day - self.first_day;
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        let forgotten = self.counts.len().saturating_sub(KEPT_DAYS);
+        self.counts.drain(..forgotten);
+        self.first_day += forgotten;
+    }
+
+    // None for the days forgotten
+    pub fn get(&self, day: usize) -> Option<usize> {
+        let index = day.checked_sub(self.first_day)?;
+        Some(self.counts.get(index).copied().unwrap_or(0))
+    }
+
+    // the day after the latest one counted
+    pub fn end(&self) -> usize {
+        self.first_day + self.counts.len()
+    }
+
+    pub fn approx_size(&self) -> usize {
+        self.counts.len() * mem::size_of::<usize>()
+    }
+
+    // over the WINDOW days up to `day`, None if some of them are forgotten
+    fn window_sum(&self, day: usize) -> Option<usize> {
+        let start = (day + 1).saturating_sub(WINDOW);
+        if start < self.first_day {
+            return None;
+        }
+        Some(
+            self.counts
+                .iter()
+                .skip(start - self.first_day)
+                .take(day + 1 - start)
+                .sum(),
+        )
+    }
+}
+
+// The true reproduction number from who infected whom: the secondary cases of the people
+// infected on each day, over the number of those people. For the recent days it's still
+// growing, as the people infected then haven't infected everyone they will yet.
+#[derive(Debug, Clone, Default)]
+pub struct Reproduction {
+    // the day each of the infected was infected on, forgotten once they're not
+    infected_on: HashMap<usize, usize>,
+    // by the day of infection
+    cases: DailyCounts,
+    // by the day of infection of the infector
+    secondary: DailyCounts,
+}
+
+impl Subscriber for Reproduction {
+    fn notify(&mut self, time: f64, event: &SimEvent) {
+        match *event {
+            SimEvent::Infected {
+                person, infector, ..
+            } => {
+                let day = time.floor() as usize;
+                self.infected_on.insert(person, day);
+                self.cases.add(day);
+                if let Some(&infector_day) = infector.and_then(|id| self.infected_on.get(&id)) {
+                    self.secondary.add(infector_day);
+                }
+            }
+            SimEvent::Recovered { person }
+            | SimEvent::Died { person }
+            | SimEvent::DiedNaturally { person } => {
+                self.infected_on.remove(&person);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Reproduction {
+    // over the WINDOW days of infection up to `day`, None for the days already forgotten
+    pub fn true_rt(&self, day: usize) -> Option<f64> {
+        let cases = self.cases.window_sum(day)?;
+        if cases == 0 {
+            return None;
+        }
+        Some(self.secondary.window_sum(day)? as f64 / cases as f64)
+    }
+
+    pub fn approx_size(&self) -> usize {
+        self.infected_on.len() * mem::size_of::<(usize, usize)>()
+            + self.cases.approx_size()
+            + self.secondary.approx_size()
+    }
+}
+
+// The weights of the days after an infection at which the infected pass it on, discretized
+// from a gamma distribution with the given mean and standard deviation. `weights[k]` is for
+// `k` days later, there being none on the same day.
+pub fn generation_interval(mean: f64, stdev: f64) -> Vec<f64> {
+    // without a spread, or passing it on right away, it's all on a single day
+    if !(mean > 0.0 && stdev > 0.0) {
+        let day = mean.round().max(1.0) as usize;
+        let mut weights = vec![0.0; day + 1];
+        weights[day] = 1.0;
+        return weights;
+    }
+    let shape = (mean / stdev).powi(2);
+    let scale = stdev * stdev / mean;
+    let days = (mean * MAX_INTERVAL_MEANS).ceil().max(1.0) as usize;
+    // the density up to a constant, at the middle of each day
+    let mut weights: Vec<f64> = (0..=days)
+        .map(|day| match day {
+            0 => 0.0,
+            _ => {
+                let x = day as f64 - 0.5;
+                x.powf(shape - 1.0) * (-x / scale).exp()
+            }
+        })
+        .collect();
+    let total: f64 = weights.iter().sum();
+    for weight in &mut weights {
+        *weight /= total;
+    }
+    weights
+}
+
+// The reproduction number by the method of Cori et al. from the daily `incidence`: the cases
+// over the WINDOW days up to `day`, over the infectiousness of the earlier cases weighted by
+// the generation interval. Missing a constant fraction of the cases doesn't change it, but
+// a delay moves it back in time.
+pub fn estimate_rt(incidence: &DailyCounts, weights: &[f64], day: usize) -> Option<f64> {
+    let start = (day + 1).saturating_sub(WINDOW);
+    let mut cases = 0.0;
+    let mut infectiousness = 0.0;
+    for t in start..=day.min(incidence.end().checked_sub(1)?) {
+        cases += incidence.get(t)? as f64;
+        for (k, weight) in weights.iter().enumerate().skip(1) {
+            if k > t {
+                break;
+            }
+            infectiousness += weight * incidence.get(t - k)? as f64;
+        }
+    }
+    if infectiousness > 0.0 {
+        Some(cases / infectiousness)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+use super::{Params, Simulation};
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Travel {
+    Back,
+    Forward,
+}
+
+// Copies of the whole simulation taken every `rewind_interval` days, oldest first. The oldest
+// ones are dropped to stay within `rewind_memory` megabytes.
+#[derive(Debug, Clone)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<(usize, Simulation)>,
+    size: usize,
+    budget: usize,
+    interval: f64,
+}
+
+impl RewindBuffer {
+    pub fn new(params: &Params) -> RewindBuffer {
+        RewindBuffer {
+            snapshots: VecDeque::new(),
+            size: 0,
+            budget: (params.rewind_memory * BYTES_PER_MB) as usize,
+            interval: params.rewind_interval,
+        }
+    }
+
+    pub fn record(&mut self, sim: &Simulation) {
+        if self.budget == 0 {
+            return;
+        }
+        if let Some((_, last)) = self.snapshots.back() {
+            if sim.time() - last.time() < self.interval {
+                return;
+            }
+        }
+
+        let size = sim.approx_size();
+        self.snapshots.push_back((size, sim.clone()));
+        self.size += size;
+        while self.size > self.budget && self.snapshots.len() > 1 {
+            let (size, _) = self.snapshots.pop_front().unwrap();
+            self.size -= size;
+        }
+    }
+
+    // after travelling back, the run that goes on replaces the future it left
+    pub fn discard_after(&mut self, time: f64) {
+        while let Some((size, snapshot)) = self.snapshots.back() {
+            if snapshot.time() <= time {
+                break;
+            }
+            self.size -= size;
+            self.snapshots.pop_back();
+        }
+    }
+
+    // in bytes, roughly
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // the latest snapshot before `time` or the earliest one after it
+    pub fn travel(&self, direction: Travel, time: f64) -> Option<Simulation> {
+        let mut snapshots = self.snapshots.iter().map(|(_, snapshot)| snapshot);
+        let snapshot = match direction {
+            Travel::Back => snapshots.rev().find(|snapshot| snapshot.time() < time),
+            Travel::Forward => snapshots.find(|snapshot| snapshot.time() > time),
+        };
+        snapshot.cloned()
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+
+use super::{intervention::Intervention, policy::Policy, Statistics};
+
+// A policy written in rhai. The script has to define `fn decide(stats, day)`, which can call
+// the intervention functions registered below; `stats` is a map with the same fields as
+// `Statistics`.
+pub struct ScriptPolicy {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    decided: Arc<Mutex<Vec<Intervention>>>,
+}
+
+impl ScriptPolicy {
+    pub fn load(path: &str) -> ScriptPolicy {
+        let script = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("couldn't read the policy script {}: {}", path, err));
+        ScriptPolicy::new(&script, path)
+    }
+
+    // `name` is what the errors call the script
+    fn new(script: &str, name: &str) -> ScriptPolicy {
+        let decided = Arc::new(Mutex::new(vec![]));
+        let mut engine = Engine::new();
+
+        let queue = decided.clone();
+        engine.register_fn("vaccinate", move |fraction: f64| {
+            queue
+                .lock()
+                .unwrap()
+                .push(Intervention::VaccinateFraction(fraction));
+        });
+        let queue = decided.clone();
+        engine.register_fn("infect", move |n: i64| {
+            queue
+                .lock()
+                .unwrap()
+                .push(Intervention::InfectRandom(n.max(0) as usize));
+        });
+        let queue = decided.clone();
+        engine.register_fn("infect_area", move |x: f64, y: f64, radius: f64| {
+            queue
+                .lock()
+                .unwrap()
+                .push(Intervention::InfectArea { x, y, radius });
+        });
+        let queue = decided.clone();
+        engine.register_fn("quarantine", move |on: bool| {
+            queue.lock().unwrap().push(Intervention::SetQuarantine(on));
+        });
+        let queue = decided.clone();
+        engine.register_fn("lockdown", move |on: bool| {
+            queue.lock().unwrap().push(Intervention::SetLockdown(on));
+        });
+        let queue = decided.clone();
+        engine.register_fn("roll_out_vaccine", move |vaccine: i64| {
+            queue
+                .lock()
+                .unwrap()
+                .push(Intervention::RollOutVaccine(vaccine.max(0) as usize));
+        });
+
+        let ast = engine
+            .compile(script)
+            .unwrap_or_else(|err| panic!("couldn't compile the policy script {}: {}", name, err));
+
+        // top-level statements run once, here, so that the script can set up its state - the
+        // calls to `decide` don't run them again
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .unwrap_or_else(|err| panic!("policy script failed: {}", err));
+
+        ScriptPolicy {
+            engine,
+            ast,
+            scope,
+            decided,
+        }
+    }
+}
+
+fn stats_map(stats: &Statistics) -> Map {
+    let mut map = Map::new();
+    for (name, value) in &[
+        ("population", stats.population),
+        ("dead", stats.dead),
+        ("infected", stats.infected),
+        ("healed", stats.healed),
+        ("vaccinated", stats.vaccinated),
+        ("vaccinated_infected", stats.vaccinated_infected),
+        ("severe", stats.severe),
+        ("critical", stats.critical),
+        ("hospitalized", stats.hospitalized),
+    ] {
+        map.insert((*name).into(), (*value as i64).into());
+    }
+    map
+}
+
+impl Policy for ScriptPolicy {
+    fn decide(&mut self, stats: &Statistics, day: u64) -> Vec<Intervention> {
+        // whatever `decide` returns is ignored, only the interventions it calls count
+        let options = CallFnOptions::new().eval_ast(false).rewind_scope(false);
+        let _: Dynamic = self
+            .engine
+            .call_fn_with_options(
+                options,
+                &mut self.scope,
+                &self.ast,
+                "decide",
+                (stats_map(stats), day as i64),
+            )
+            .unwrap_or_else(|err| panic!("policy script failed: {}", err));
+        self.decided.lock().unwrap().drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_top_level_statements_run_once() {
+        let script = "
+            vaccinate(0.05);
+            fn decide(stats, day) {
+                if day == 2 {
+                    lockdown(true);
+                }
+            }
+        ";
+        let mut policy = ScriptPolicy::new(script, "test");
+        let stats = Statistics::default();
+        let decided: Vec<_> = (0..4).map(|day| policy.decide(&stats, day)).collect();
+        assert_eq!(
+            decided,
+            vec![
+                vec![Intervention::VaccinateFraction(0.05)],
+                vec![],
+                vec![Intervention::SetLockdown(true)],
+                vec![],
+            ]
+        );
+    }
+}
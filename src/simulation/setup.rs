@@ -0,0 +1,159 @@
+use rand::Rng;
+
+use super::{
+    census::{self, Census},
+    community::Community,
+    density::DensityMap,
+    geometry::Segment,
+    hospital::Hospital,
+    obstacle::Obstacle,
+    placement::Constraints,
+    strain::Strains,
+    zone::Zone,
+    Params, SeedCluster, Simulation,
+};
+
+// The parts of the config that determine the initial state of a simulation, loaded from
+// config.toml by the binary. Everything creating a simulation goes through here, so that the
+// initial infections and vaccinations are the same for the same seed.
+pub struct Setup {
+    pub params: Params,
+    pub density: Option<DensityMap>,
+    pub communities: Vec<Community>,
+    pub zones: Vec<Zone>,
+    pub obstacles: Vec<Obstacle>,
+    // the obstacles without their doors
+    pub walls: Vec<Segment>,
+    pub hospital: Option<Hospital>,
+    pub strains: Strains,
+    pub seed_clusters: Vec<SeedCluster>,
+    pub census: Option<Census>,
+}
+
+impl Setup {
+    // only the parameters, with everyone placed at random
+    pub fn new(params: Params) -> Setup {
+        Setup {
+            params,
+            density: None,
+            communities: vec![],
+            zones: vec![],
+            obstacles: vec![],
+            walls: vec![],
+            hospital: None,
+            strains: Default::default(),
+            seed_clusters: vec![],
+            census: None,
+        }
+    }
+
+    pub fn has_census(&self) -> bool {
+        self.census.is_some()
+    }
+
+    // whether anything is at fixed coordinates, which wouldn't fit a smaller box
+    pub fn has_layout(&self) -> bool {
+        self.census.is_some()
+            || !self.communities.is_empty()
+            || !self.zones.is_empty()
+            || !self.walls.is_empty()
+            || self.hospital.is_some()
+            || !self.seed_clusters.is_empty()
+    }
+
+    // with the parameters and the cases of the strains scaled by `factor`, for a config
+    // without a layout
+    pub fn scaled(self, factor: f64) -> Setup {
+        Setup {
+            params: self.params.scaled(factor),
+            strains: self.strains.scaled(factor),
+            ..self
+        }
+    }
+
+    // no one starts where the density map is zero, on the boundary of a community or in a wall
+    fn constraints(&self) -> Constraints<'_> {
+        Constraints {
+            box_size: (self.params.size_x, self.params.size_y),
+            density: self.density.as_ref(),
+            communities: &self.communities,
+            walls: &self.walls,
+        }
+    }
+
+    pub fn create<R: Rng>(&self, rng: &mut R) -> Simulation {
+        let params = self.params;
+        if let Some(census) = &self.census {
+            return self.create_from_census(census, rng);
+        }
+        let mut sim = Simulation::placed(rng, params, &self.constraints());
+        sim.set_communities(self.communities.clone());
+        sim.set_zones(self.zones.clone());
+        sim.set_obstacles(self.obstacles.clone());
+        if let Some(hospital) = &self.hospital {
+            sim.set_hospital(hospital.clone(), rng);
+        }
+        sim.set_strains(self.strains.clone());
+        // seed clusters replace the uniformly random initial infections
+        if self.seed_clusters.is_empty() {
+            sim.infect(params.init_infected, rng);
+        }
+        for &cluster in &self.seed_clusters {
+            sim.seed_cluster(cluster, rng);
+        }
+        sim.vaccinate(params.init_vaccinated, rng);
+        sim.past_infect(
+            params.init_past_infected,
+            params.init_past_infected_max_days,
+            rng,
+        );
+        sim
+    }
+
+    // the census gives the initial infections, recoveries and vaccinations, along with the
+    // positions and the time to start at
+    fn create_from_census<R: Rng>(&self, census: &Census, rng: &mut R) -> Simulation {
+        let rows = &census.rows;
+        let params = Params {
+            num_people: rows.len(),
+            ..self.params
+        };
+        let population = census::populate(rng, rows, &self.constraints(), params.speed_stdev);
+        let mut sim = Simulation::with_population(rng, params, population);
+        sim.start_at(census.time);
+        sim.set_density(self.density.as_ref());
+        sim.set_communities(self.communities.clone());
+        sim.set_zones(self.zones.clone());
+        sim.set_obstacles(self.obstacles.clone());
+        if let Some(hospital) = &self.hospital {
+            sim.set_hospital(hospital.clone(), rng);
+        }
+        sim.set_strains(self.strains.clone());
+        for &cluster in &self.seed_clusters {
+            sim.seed_cluster(cluster, rng);
+        }
+        let recovered: Vec<_> = rows
+            .iter()
+            .filter_map(|row| row.recovered.map(|time| (row.id, time)))
+            .collect();
+        let infected: Vec<_> = rows
+            .iter()
+            .filter(|row| row.infected)
+            .map(|row| row.id)
+            .collect();
+        let vaccinated: Vec<_> = rows
+            .iter()
+            .filter(|row| row.vaccinated)
+            .map(|row| row.id)
+            .collect();
+        sim.mark_recovered_ids(&recovered);
+        sim.infect_ids(&infected, rng);
+        sim.vaccinate_ids(&vaccinated);
+        sim.past_infect(
+            params.init_past_infected,
+            params.init_past_infected_max_days,
+            rng,
+        );
+        sim
+    }
+}
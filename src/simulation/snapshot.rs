@@ -0,0 +1,71 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use super::{person::Status, Statistics};
+
+// bumped whenever the layout of RenderSnapshot changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+pub const STATUS_HEALTHY: u8 = 0;
+pub const STATUS_INFECTED: u8 = 1;
+pub const STATUS_VACCINATED_INFECTED: u8 = 2;
+pub const STATUS_HEALED: u8 = 3;
+pub const STATUS_VACCINATED: u8 = 4;
+pub const STATUS_DEAD: u8 = 5;
+
+// What a frontend needs to draw a frame: the positions and status codes of the people to show,
+// including the dead while their corpses are displayed.
+#[derive(Debug, Clone, Default)]
+pub struct RenderSnapshot {
+    pub version: u32,
+    pub time: f64,
+    pub people: Vec<(f32, f32, u8)>,
+    pub stats: Statistics,
+}
+
+pub fn status_code(status: &Status) -> u8 {
+    if status.dead().is_some() {
+        STATUS_DEAD
+    } else if status.infected().is_some() {
+        if status.vaccinated() {
+            STATUS_VACCINATED_INFECTED
+        } else {
+            STATUS_INFECTED
+        }
+    } else if status.vaccinated() {
+        STATUS_VACCINATED
+    } else if status.past_infected() {
+        STATUS_HEALED
+    } else {
+        STATUS_HEALTHY
+    }
+}
+
+// The simulation thread fills one slot while the readers take the other, so that neither waits
+// for the other for longer than swapping a pointer.
+#[derive(Debug, Default)]
+pub struct SnapshotBuffer {
+    slots: [Mutex<Arc<RenderSnapshot>>; 2],
+    front: AtomicUsize,
+}
+
+impl SnapshotBuffer {
+    pub fn new() -> SnapshotBuffer {
+        Default::default()
+    }
+
+    pub fn publish(&self, snapshot: RenderSnapshot) {
+        let back = 1 - self.front.load(Ordering::Acquire);
+        *self.slots[back].lock().unwrap() = Arc::new(snapshot);
+        self.front.store(back, Ordering::Release);
+    }
+
+    pub fn latest(&self) -> Arc<RenderSnapshot> {
+        self.slots[self.front.load(Ordering::Acquire)]
+            .lock()
+            .unwrap()
+            .clone()
+    }
+}
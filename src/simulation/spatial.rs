@@ -0,0 +1,108 @@
+use nalgebra::Vector2;
+
+use super::{clamp_vec2, person::RADIUS};
+
+// Uniform grid over the (periodic) box, stored as person indices sorted by cell.
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    box_size: (f64, f64),
+    cells_x: usize,
+    cells_y: usize,
+    // indices into `entries` where each cell starts; has one extra element at the end
+    cell_start: Vec<usize>,
+    entries: Vec<usize>,
+}
+
+impl SpatialGrid {
+    pub fn new(box_size: (f64, f64)) -> SpatialGrid {
+        // cells at least as large as a person's diameter
+        let cells_x = ((box_size.0 / (2.0 * RADIUS)).floor() as usize).max(1);
+        let cells_y = ((box_size.1 / (2.0 * RADIUS)).floor() as usize).max(1);
+        SpatialGrid {
+            box_size,
+            cells_x,
+            cells_y,
+            cell_start: vec![0; cells_x * cells_y + 1],
+            entries: vec![],
+        }
+    }
+
+    fn cell_coord(&self, x: f64, y: f64) -> (isize, isize) {
+        (
+            (x / self.box_size.0 * self.cells_x as f64).floor() as isize,
+            (y / self.box_size.1 * self.cells_y as f64).floor() as isize,
+        )
+    }
+
+    fn cell_index(&self, x: isize, y: isize) -> usize {
+        let x = x.rem_euclid(self.cells_x as isize) as usize;
+        let y = y.rem_euclid(self.cells_y as isize) as usize;
+        y * self.cells_x + x
+    }
+
+    pub fn rebuild(&mut self, positions: &[Vector2<f64>]) {
+        let cells: Vec<usize> = positions
+            .iter()
+            .map(|pos| {
+                let (x, y) = self.cell_coord(pos.x, pos.y);
+                self.cell_index(x, y)
+            })
+            .collect();
+
+        // counting sort of the people by their cells
+        for start in &mut self.cell_start {
+            *start = 0;
+        }
+        for &cell in &cells {
+            self.cell_start[cell + 1] += 1;
+        }
+        for i in 1..self.cell_start.len() {
+            self.cell_start[i] += self.cell_start[i - 1];
+        }
+        let mut fill = self.cell_start.clone();
+        self.entries = vec![0; positions.len()];
+        for (index, &cell) in cells.iter().enumerate() {
+            self.entries[fill[cell]] = index;
+            fill[cell] += 1;
+        }
+    }
+
+    pub fn cell(&self, index: usize) -> &[usize] {
+        &self.entries[self.cell_start[index]..self.cell_start[index + 1]]
+    }
+
+    // indices of the cells covering the square of half-side `r` around `pos`, each at most once
+    fn cells_around(&self, pos: Vector2<f64>, r: f64) -> Vec<usize> {
+        let (min_x, min_y) = self.cell_coord(pos.x - r, pos.y - r);
+        let (max_x, max_y) = self.cell_coord(pos.x + r, pos.y + r);
+        let range = |min: isize, max: isize, cells: usize| {
+            if (max - min + 1) as usize >= cells {
+                (0, cells as isize - 1)
+            } else {
+                (min, max)
+            }
+        };
+        let (min_x, max_x) = range(min_x, max_x, self.cells_x);
+        let (min_y, max_y) = range(min_y, max_y, self.cells_y);
+
+        let mut result = vec![];
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                result.push(self.cell_index(x, y));
+            }
+        }
+        result
+    }
+
+    pub fn query(&self, positions: &[Vector2<f64>], pos: Vector2<f64>, r: f64) -> Vec<usize> {
+        let mut result = vec![];
+        for cell in self.cells_around(pos, r) {
+            for &index in self.cell(cell) {
+                if clamp_vec2(positions[index] - pos, self.box_size).norm() <= r {
+                    result.push(index);
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+
+use toml::Value;
+
+use super::Simulation;
+
+// A condition for stopping the simulation, given as `run_until = { day = 365 }` in the
+// config or `--run-until day=365` on the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopCondition {
+    Day(f64),
+    // at most this many people infected
+    Infected(usize),
+    DeadFraction(f64),
+}
+
+impl StopCondition {
+    fn new(name: &str, value: f64) -> StopCondition {
+        match name {
+            "day" => StopCondition::Day(value),
+            "infected" => StopCondition::Infected(value as usize),
+            "dead_fraction" => StopCondition::DeadFraction(value),
+            _ => panic!(
+                "unknown run_until condition: {} (available: day, infected, dead_fraction)",
+                name
+            ),
+        }
+    }
+
+    // `name=value`
+    pub fn parse(s: &str) -> StopCondition {
+        let (name, value) = s
+            .split_once('=')
+            .unwrap_or_else(|| panic!("invalid run_until condition: {}", s));
+        let value = value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid value in run_until condition: {}", s));
+        StopCondition::new(name, value)
+    }
+
+    // every entry of the table is a separate condition, any of them stops the simulation
+    pub fn from_table(table: &Value) -> Vec<StopCondition> {
+        let table = table.as_table().expect("run_until should be a table");
+        table
+            .iter()
+            .map(|(name, value)| {
+                let value = value
+                    .as_float()
+                    .or_else(|| value.as_integer().map(|value| value as f64))
+                    .unwrap_or_else(|| panic!("invalid value for run_until.{}", name));
+                StopCondition::new(name, value)
+            })
+            .collect()
+    }
+
+    pub fn is_met(&self, sim: &Simulation) -> bool {
+        match *self {
+            StopCondition::Day(day) => sim.time() >= day,
+            StopCondition::Infected(infected) => sim.stats().infected <= infected,
+            StopCondition::DeadFraction(fraction) => {
+                sim.stats().dead as f64 >= fraction * sim.params().num_people as f64
+            }
+        }
+    }
+}
+
+impl fmt::Display for StopCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopCondition::Day(day) => write!(f, "day = {}", day),
+            StopCondition::Infected(infected) => write!(f, "infected = {}", infected),
+            StopCondition::DeadFraction(fraction) => write!(f, "dead_fraction = {}", fraction),
+        }
+    }
+}
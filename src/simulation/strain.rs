@@ -0,0 +1,157 @@
+use serde_derive::Deserialize;
+
+// the past infections of a person are kept as a bit per strain
+pub const MAX_STRAINS: usize = 32;
+
+fn default_transmissibility() -> f64 {
+    1.0
+}
+
+fn default_cases() -> usize {
+    1
+}
+
+// A variant of the pathogen, given as a `[[strains]]` table in the config. It appears `day` days
+// in, in `cases` random people who aren't infected at the time, and its infection probabilities
+// are those of the parameters multiplied by `transmissibility`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Strain {
+    pub name: String,
+    #[serde(default = "default_transmissibility")]
+    pub transmissibility: f64,
+    #[serde(default)]
+    pub day: f64,
+    #[serde(default = "default_cases")]
+    pub cases: usize,
+}
+
+// An updated vaccine, given as a `[[vaccines]]` table in the config, replacing the previous
+// one for the doses given from `day` on - or from whenever an intervention rolls it out.
+// `efficacy` is the fraction of the full vaccine protection it gives against each strain.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Vaccine {
+    pub name: String,
+    #[serde(default)]
+    pub day: Option<f64>,
+    pub efficacy: Vec<f64>,
+}
+
+// The strain from the parameters, present from the start, followed by the ones from the config.
+// `cross_immunity[a][b]` is the fraction of the protection of the recovered that an infection
+// with strain a gives against strain b - 1 everywhere, unless given. Likewise, the vaccines
+// protect against the strains to a degree - the one given from the start by
+// `vaccine_efficacy`, followed by the updated ones.
+#[derive(Debug, Clone)]
+pub struct Strains {
+    introduced: Vec<Strain>,
+    cross_immunity: Vec<Vec<f64>>,
+    vaccine_efficacy: Vec<Vec<f64>>,
+    updated_vaccines: Vec<Vaccine>,
+}
+
+impl Default for Strains {
+    fn default() -> Strains {
+        Strains::new(vec![], None)
+    }
+}
+
+impl Strains {
+    // panics unless the matrix has a row and a column for each of the strains
+    pub fn new(introduced: Vec<Strain>, cross_immunity: Option<Vec<Vec<f64>>>) -> Strains {
+        let len = introduced.len() + 1;
+        if len > MAX_STRAINS {
+            panic!("at most {} strains are supported", MAX_STRAINS);
+        }
+        let cross_immunity = cross_immunity.unwrap_or_else(|| vec![vec![1.0; len]; len]);
+        if cross_immunity.len() != len || cross_immunity.iter().any(|row| row.len() != len) {
+            panic!(
+                "cross_immunity should be a {0}x{0} matrix, a row and a column for each strain",
+                len
+            );
+        }
+        Strains {
+            introduced,
+            cross_immunity,
+            vaccine_efficacy: vec![vec![1.0; len]],
+            updated_vaccines: vec![],
+        }
+    }
+
+    // panics unless the efficacies are given for each of the strains
+    pub fn with_vaccines(self, efficacy: Option<Vec<f64>>, updated: Vec<Vaccine>) -> Strains {
+        let len = self.count();
+        let efficacy = efficacy.unwrap_or_else(|| vec![1.0; len]);
+        let vaccine_efficacy: Vec<_> = Some(efficacy)
+            .into_iter()
+            .chain(updated.iter().map(|vaccine| vaccine.efficacy.clone()))
+            .collect();
+        if vaccine_efficacy
+            .iter()
+            .any(|efficacy| efficacy.len() != len)
+        {
+            panic!(
+                "the vaccine efficacies should be given for each of the {} strains",
+                len
+            );
+        }
+        Strains {
+            vaccine_efficacy,
+            updated_vaccines: updated,
+            ..self
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.introduced.len() + 1
+    }
+
+    // with `factor` of the cases of each strain, but at least one if there were any, as in
+    // `Params::scaled`
+    pub fn scaled(mut self, factor: f64) -> Strains {
+        for strain in &mut self.introduced {
+            strain.cases =
+                ((strain.cases as f64 * factor).round() as usize).max(strain.cases.min(1));
+        }
+        self
+    }
+
+    pub fn transmissibility(&self, strain: usize) -> f64 {
+        match strain {
+            0 => 1.0,
+            _ => self.introduced[strain - 1].transmissibility,
+        }
+    }
+
+    // the strains introduced later, with their indices
+    pub fn introduced(&self) -> impl Iterator<Item = (usize, &Strain)> {
+        self.introduced
+            .iter()
+            .enumerate()
+            .map(|(index, strain)| (index + 1, strain))
+    }
+
+    // the original vaccine and the updated ones
+    pub fn vaccines(&self) -> usize {
+        self.vaccine_efficacy.len()
+    }
+
+    // the updated vaccines, with their indices
+    pub fn updated_vaccines(&self) -> impl Iterator<Item = (usize, &Vaccine)> {
+        self.updated_vaccines
+            .iter()
+            .enumerate()
+            .map(|(index, vaccine)| (index + 1, vaccine))
+    }
+
+    pub fn vaccine_efficacy(&self, vaccine: usize, strain: usize) -> f64 {
+        self.vaccine_efficacy[vaccine][strain]
+    }
+
+    // the best of the protections given by the strains in `recovered_from` against `strain`
+    pub fn protection(&self, recovered_from: u32, strain: usize) -> f64 {
+        (0..self.count())
+            .filter(|&past| recovered_from & (1 << past) != 0)
+            .map(|past| self.cross_immunity[past][strain])
+            .fold(0.0, f64::max)
+    }
+}
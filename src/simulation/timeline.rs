@@ -0,0 +1,177 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    mem,
+};
+
+use super::{
+    events::{SimEvent, Subscriber},
+    memory::{self, Eviction},
+    person::{Severity, Status},
+};
+
+// The model has no incubation period, so being infected and having symptoms is the same
+// phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Susceptible,
+    Infected(Severity),
+    Isolated(Severity),
+    Recovered,
+    Dead,
+}
+
+impl Phase {
+    pub fn of(status: &Status, isolated: bool) -> Phase {
+        if status.dead().is_some() {
+            return Phase::Dead;
+        }
+        match status.severity() {
+            Some(severity) if isolated => Phase::Isolated(severity),
+            Some(severity) => Phase::Infected(severity),
+            None if status.past_infected() => Phase::Recovered,
+            None => Phase::Susceptible,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Susceptible => "susceptible",
+            Phase::Infected(_) => "infected",
+            Phase::Isolated(_) => "isolated",
+            Phase::Recovered => "recovered",
+            Phase::Dead => "dead",
+        }
+    }
+}
+
+// the contacts further apart than this are counted separately, in days
+const CONTACT_GAP: f64 = 0.1;
+// how far back a contact that is still going on is looked for
+const CONTACT_LOOKBACK: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    pub start: f64,
+    pub end: f64,
+    pub other: usize,
+}
+
+// The history of a single person: the phases with their start times, the times of the
+// vaccine doses and the people they met.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    pub id: usize,
+    pub segments: Vec<(f64, Phase)>,
+    pub doses: Vec<f64>,
+    pub contacts: Vec<Contact>,
+}
+
+impl Timeline {
+    pub fn new(id: usize) -> Timeline {
+        Timeline {
+            id,
+            segments: vec![],
+            doses: vec![],
+            contacts: vec![],
+        }
+    }
+
+    // called on every step of a contact
+    fn record_contact(&mut self, time: f64, other: usize) {
+        let ongoing = self
+            .contacts
+            .iter_mut()
+            .rev()
+            .take(CONTACT_LOOKBACK)
+            .find(|contact| contact.other == other && time - contact.end <= CONTACT_GAP);
+        match ongoing {
+            Some(contact) => contact.end = time,
+            None => self.contacts.push(Contact {
+                start: time,
+                end: time,
+                other,
+            }),
+        }
+    }
+
+    pub fn record(&mut self, time: f64, phase: Phase, doses: usize) {
+        if self.segments.last().map(|&(_, last)| last) != Some(phase) {
+            self.segments.push((time, phase));
+        }
+        while self.doses.len() < doses {
+            self.doses.push(time);
+        }
+    }
+
+    // keeps only the latest `cap` phases and contacts, unless the cap is 0
+    pub fn limit(&mut self, cap: usize) {
+        memory::evict(&mut self.segments, cap, Eviction::DropOldest);
+        memory::evict(&mut self.contacts, cap, Eviction::DropOldest);
+    }
+
+    pub fn approx_size(&self) -> usize {
+        mem::size_of_val(&self.segments[..])
+            + mem::size_of_val(&self.doses[..])
+            + mem::size_of_val(&self.contacts[..])
+    }
+
+    fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(out, "  {{\"id\": {}, \"segments\": [", self.id)?;
+        for (i, (start, phase)) in self.segments.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(
+                out,
+                "{}{{\"start\": {:.4}, \"phase\": \"{}\"",
+                separator,
+                start,
+                phase.name()
+            )?;
+            if let Phase::Infected(severity) | Phase::Isolated(severity) = phase {
+                write!(out, ", \"severity\": \"{}\"", severity.name())?;
+            }
+            write!(out, "}}")?;
+        }
+        let doses: Vec<String> = self.doses.iter().map(|t| format!("{:.4}", t)).collect();
+        let contacts: Vec<String> = self
+            .contacts
+            .iter()
+            .map(|contact| {
+                format!(
+                    "{{\"other\": {}, \"start\": {:.4}, \"end\": {:.4}}}",
+                    contact.other, contact.start, contact.end
+                )
+            })
+            .collect();
+        write!(
+            out,
+            "], \"doses\": [{}], \"contacts\": [{}]}}",
+            doses.join(", "),
+            contacts.join(", ")
+        )
+    }
+}
+
+// the contacts with the person followed
+impl Subscriber for Timeline {
+    fn notify(&mut self, time: f64, event: &SimEvent) {
+        if let SimEvent::CollisionPairTested { person1, person2 } = *event {
+            if person1 == self.id {
+                self.record_contact(time, person2);
+            } else if person2 == self.id {
+                self.record_contact(time, person1);
+            }
+        }
+    }
+}
+
+pub fn write_json(path: &str, timelines: &[Timeline]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "[")?;
+    for (i, timeline) in timelines.iter().enumerate() {
+        timeline.write_json(&mut file)?;
+        writeln!(file, "{}", if i + 1 < timelines.len() { "," } else { "" })?;
+    }
+    writeln!(file, "]")?;
+    file.flush()
+}
@@ -0,0 +1,91 @@
+use super::Params;
+
+// A parameter which can be changed while the simulation is running, and takes effect right
+// away.
+pub struct Tunable {
+    pub name: &'static str,
+    pub step: f64,
+    pub min: f64,
+    pub max: f64,
+    pub get: fn(&Params) -> f64,
+    pub set: fn(&mut Params, f64),
+}
+
+pub const TUNABLES: &[Tunable] = &[
+    Tunable {
+        name: "infection_prob_infected_to_general",
+        step: 0.005,
+        min: 0.0,
+        max: 1.0,
+        get: |params| params.infection_prob_infected_to_general,
+        set: |params, value| params.infection_prob_infected_to_general = value,
+    },
+    Tunable {
+        name: "infection_prob_infected_to_healed",
+        step: 0.001,
+        min: 0.0,
+        max: 1.0,
+        get: |params| params.infection_prob_infected_to_healed,
+        set: |params, value| params.infection_prob_infected_to_healed = value,
+    },
+    Tunable {
+        name: "infection_prob_infected_to_vaccinated",
+        step: 0.001,
+        min: 0.0,
+        max: 1.0,
+        get: |params| params.infection_prob_infected_to_vaccinated,
+        set: |params, value| params.infection_prob_infected_to_vaccinated = value,
+    },
+    Tunable {
+        name: "death_rate",
+        step: 0.005,
+        min: 0.0,
+        max: 1.0,
+        get: |params| params.death_rate,
+        set: |params, value| params.death_rate = value,
+    },
+    Tunable {
+        name: "lockdown_mobility",
+        step: 0.05,
+        min: 0.0,
+        max: 1.0,
+        get: |params| params.lockdown_mobility,
+        set: |params, value| params.lockdown_mobility = value,
+    },
+    Tunable {
+        name: "hospital_capacity",
+        step: 1.0,
+        min: 0.0,
+        max: f64::INFINITY,
+        get: |params| params.hospital_capacity,
+        set: |params, value| params.hospital_capacity = value,
+    },
+    Tunable {
+        name: "weather_transmission_effect",
+        step: 0.1,
+        min: 0.0,
+        max: f64::INFINITY,
+        get: |params| params.weather_transmission_effect,
+        set: |params, value| params.weather_transmission_effect = value,
+    },
+];
+
+// the new value of one of the TUNABLES
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamUpdate {
+    pub index: usize,
+    pub value: f64,
+}
+
+impl Tunable {
+    // `steps` up or down from `value`, rounded to the step to avoid accumulating errors
+    pub fn nudge(&self, value: f64, steps: f64) -> f64 {
+        let value = ((value / self.step).round() + steps) * self.step;
+        value.clamp(self.min, self.max)
+    }
+
+    // the number of decimal places of the step
+    pub fn precision(&self) -> usize {
+        (-self.step.log10()).ceil().max(0.0) as usize
+    }
+}
@@ -0,0 +1,57 @@
+use rand::Rng;
+
+use super::{params::Params, population::age_group};
+
+pub const DAYS_PER_YEAR: f64 = 365.25;
+// the people whose ages aren't known get one up to this, uniformly at random
+pub const MAX_INITIAL_AGE: f64 = 90.0;
+// the newborns for whom no room is found in this many tries aren't born
+pub const BIRTH_PLACEMENT_ATTEMPTS: usize = 1000;
+
+// The people who joined and left the population other than through the epidemic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Turnover {
+    pub births: usize,
+    pub natural_deaths: usize,
+}
+
+// of dying of other causes during `dt` days at `age` years
+pub fn death_prob(params: &Params, age: f64, dt: f64) -> f64 {
+    let yearly = params.natural_mortality[age_group(age)].min(1.0);
+    // the yearly probability compounds over the year
+    1.0 - (1.0 - yearly).powf(dt / DAYS_PER_YEAR)
+}
+
+// how many are born in `dt` days to `living` people, the fractions being rounded at random
+pub fn births<R: Rng>(params: &Params, living: usize, dt: f64, rng: &mut R) -> usize {
+    let expected = params.birth_rate * living as f64 * dt / DAYS_PER_YEAR;
+    let whole = expected.floor();
+    whole as usize + (rng.gen::<f64>() < expected - whole) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn a_year_of_steps_adds_up_to_the_yearly_mortality() {
+        let params = Params::default();
+        let survival = (1.0 - death_prob(&params, 70.0, 0.5)).powf(2.0 * DAYS_PER_YEAR);
+        let yearly = params.natural_mortality[age_group(70.0)];
+        assert!((1.0 - survival - yearly).abs() < 1e-9);
+        assert!(death_prob(&params, 70.0, 1.0) > death_prob(&params, 30.0, 1.0));
+    }
+
+    #[test]
+    fn whole_births_are_not_rounded() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let params = Params {
+            birth_rate: 0.01,
+            ..Default::default()
+        };
+        assert_eq!(births(&params, 1000, DAYS_PER_YEAR, &mut rng), 10);
+        assert_eq!(births(&params, 0, DAYS_PER_YEAR, &mut rng), 0);
+    }
+}
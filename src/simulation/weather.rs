@@ -0,0 +1,23 @@
+use rand::{
+    distributions::{Distribution, Normal},
+    Rng,
+};
+
+// A slowly varying weather signal, the same everywhere: an Ornstein-Uhlenbeck process with
+// unit variance, correlated over `weather_timescale` days.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Weather {
+    state: f64,
+}
+
+impl Weather {
+    pub fn step<R: Rng>(&mut self, dt: f64, timescale: f64, rng: &mut R) {
+        let noise = Normal::new(0.0, 1.0).sample(rng);
+        self.state += -self.state * dt / timescale + (2.0 * dt / timescale).sqrt() * noise;
+    }
+
+    // from 0 for fine weather to 1 for bad weather
+    pub fn badness(&self) -> f64 {
+        (self.state.tanh() + 1.0) / 2.0
+    }
+}
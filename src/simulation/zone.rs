@@ -0,0 +1,37 @@
+use nalgebra::Vector2;
+use serde_derive::Deserialize;
+
+use super::geometry::Rect;
+
+fn default_tint() -> [f32; 3] {
+    [0.85, 1.0, 0.85]
+}
+
+// A rectangular area, given as a `[[zones]]` table in the config, where the infection
+// probabilities are multiplied by `factor` - eg. 0.1 for a well ventilated place or outdoors.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Zone {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub factor: f64,
+    // the background color
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 3],
+}
+
+impl Zone {
+    pub fn rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn contains(&self, pos: Vector2<f64>) -> bool {
+        self.rect().contains(pos)
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+};
+
+use crate::simulation::Statistics;
+
+const HEADER: &str = "t,population,dead,infected,healed,vaccinated,vaccinated_infected";
+
+pub fn write(path: &str, data: &[(f64, Statistics)]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "{}", HEADER)?;
+    for (t, stats) in data {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{}",
+            t,
+            stats.population,
+            stats.dead,
+            stats.infected,
+            stats.healed,
+            stats.vaccinated,
+            stats.vaccinated_infected
+        )?;
+    }
+    file.flush()
+}
+
+pub fn read(path: &str) -> io::Result<Vec<(f64, Statistics)>> {
+    let file = BufReader::new(File::open(path)?);
+    let mut result = vec![];
+
+    for (line_no, line) in file.lines().enumerate() {
+        let line = line?;
+        if line_no == 0 || line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: malformed row: {}", path, line_no + 1, line),
+            )
+        };
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        if fields.len() != 7 {
+            return Err(invalid());
+        }
+        let t = fields[0].parse().map_err(|_| invalid())?;
+        let mut counts = [0usize; 6];
+        for (count, field) in counts.iter_mut().zip(&fields[1..]) {
+            *count = field.parse().map_err(|_| invalid())?;
+        }
+        result.push((
+            t,
+            Statistics {
+                population: counts[0],
+                dead: counts[1],
+                infected: counts[2],
+                healed: counts[3],
+                vaccinated: counts[4],
+                vaccinated_infected: counts[5],
+                ..Default::default()
+            },
+        ));
+    }
+
+    Ok(result)
+}
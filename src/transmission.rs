@@ -0,0 +1,340 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::simulation::{
+    event_log::{EventLog, LogEvent, Setting, Source},
+    person::{Outcome, Severity},
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    // time of the first infection
+    infection_day: Option<f64>,
+    outcome: Option<Outcome>,
+}
+
+impl Node {
+    fn outcome_name(&self) -> &'static str {
+        match self.outcome {
+            Some(Outcome::Healed) => "healed",
+            Some(Outcome::Died) => "died",
+            None if self.infection_day.is_some() => "infected",
+            None => "none",
+        }
+    }
+}
+
+// (infector, infected, time)
+type Edge = (usize, usize, f64);
+
+// The who-infected-whom network recorded in the event log. People infected more than once
+// have an incoming edge for every infection.
+fn build(log: &EventLog) -> (BTreeMap<usize, Node>, Vec<Edge>) {
+    let mut nodes: BTreeMap<usize, Node> = BTreeMap::new();
+    let mut edges = vec![];
+    for entry in log.entries() {
+        match entry.event {
+            LogEvent::Infection {
+                person, infector, ..
+            } => {
+                let node = nodes.entry(person).or_default();
+                node.infection_day.get_or_insert(entry.time);
+                node.outcome = None;
+                if let Some(infector) = infector {
+                    nodes.entry(infector).or_default();
+                    edges.push((infector, person, entry.time));
+                }
+            }
+            LogEvent::Outcome { person, outcome } => {
+                nodes.entry(person).or_default().outcome = Some(outcome);
+            }
+            LogEvent::Intervention(_) => (),
+        }
+    }
+    (nodes, edges)
+}
+
+pub fn write_dot(path: &str, log: &EventLog) -> io::Result<()> {
+    let (nodes, edges) = build(log);
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "digraph transmission {{")?;
+    for (id, node) in &nodes {
+        match node.infection_day {
+            Some(day) => writeln!(
+                file,
+                "    {} [infection_day={:.4}, outcome=\"{}\"];",
+                id,
+                day,
+                node.outcome_name()
+            )?,
+            None => writeln!(file, "    {} [outcome=\"{}\"];", id, node.outcome_name())?,
+        }
+    }
+    for (infector, person, day) in edges {
+        writeln!(file, "    {} -> {} [day={:.4}];", infector, person, day)?;
+    }
+    writeln!(file, "}}")?;
+    file.flush()
+}
+
+pub fn write_graphml(path: &str, log: &EventLog) -> io::Result<()> {
+    let (nodes, edges) = build(log);
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        file,
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+    )?;
+    writeln!(
+        file,
+        "  <key id=\"infection_day\" for=\"node\" attr.name=\"infection_day\" attr.type=\"double\"/>"
+    )?;
+    writeln!(
+        file,
+        "  <key id=\"outcome\" for=\"node\" attr.name=\"outcome\" attr.type=\"string\"/>"
+    )?;
+    writeln!(
+        file,
+        "  <key id=\"day\" for=\"edge\" attr.name=\"day\" attr.type=\"double\"/>"
+    )?;
+    writeln!(
+        file,
+        "  <graph id=\"transmission\" edgedefault=\"directed\">"
+    )?;
+    for (id, node) in &nodes {
+        writeln!(file, "    <node id=\"n{}\">", id)?;
+        if let Some(day) = node.infection_day {
+            writeln!(file, "      <data key=\"infection_day\">{:.4}</data>", day)?;
+        }
+        writeln!(
+            file,
+            "      <data key=\"outcome\">{}</data>",
+            node.outcome_name()
+        )?;
+        writeln!(file, "    </node>")?;
+    }
+    for (infector, person, day) in edges {
+        writeln!(
+            file,
+            "    <edge source=\"n{}\" target=\"n{}\"><data key=\"day\">{:.4}</data></edge>",
+            infector, person, day
+        )?;
+    }
+    writeln!(file, "  </graph>")?;
+    writeln!(file, "</graphml>")?;
+    file.flush()
+}
+
+// the format is chosen by the extension, DOT being the default
+pub fn write(path: &str, log: &EventLog) -> io::Result<()> {
+    if path.ends_with(".graphml") {
+        write_graphml(path, log)
+    } else {
+        write_dot(path, log)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Episode {
+    start: f64,
+    ended: bool,
+    secondary_cases: usize,
+}
+
+fn mean_variance(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+// histogram with bins of one day
+fn write_histogram<W: Write>(out: &mut W, values: &[f64]) -> io::Result<()> {
+    let mut bins: BTreeMap<u64, usize> = BTreeMap::new();
+    for value in values {
+        *bins.entry(value.floor() as u64).or_default() += 1;
+    }
+    for (bin, count) in bins {
+        writeln!(out, "  {:>4}-{:<4} {}", bin, bin + 1, count)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SourceCounts {
+    external: usize,
+    contact: usize,
+    corpse: usize,
+    vector: usize,
+    surface: usize,
+    // the rest only count infections passed on by someone
+    vaccinated: usize,
+    mild: usize,
+    severe: usize,
+    critical: usize,
+}
+
+impl SourceCounts {
+    fn add(&mut self, source: Source) {
+        match source.setting {
+            Setting::External => self.external += 1,
+            Setting::Contact => self.contact += 1,
+            Setting::Corpse => self.corpse += 1,
+            Setting::Vector => self.vector += 1,
+            Setting::Surface => self.surface += 1,
+        }
+        if source.setting == Setting::External {
+            return;
+        }
+        if source.infector_vaccinated {
+            self.vaccinated += 1;
+        }
+        match source.infector_severity {
+            Some(Severity::Mild) => self.mild += 1,
+            Some(Severity::Severe) => self.severe += 1,
+            Some(Severity::Critical) => self.critical += 1,
+            None => (),
+        }
+    }
+}
+
+const DAYS_PER_SOURCE_BIN: f64 = 7.0;
+
+fn write_sources<W: Write>(out: &mut W, log: &EventLog) -> io::Result<()> {
+    let mut bins: BTreeMap<u64, SourceCounts> = BTreeMap::new();
+    for entry in log.entries() {
+        if let LogEvent::Infection { source, .. } = entry.event {
+            let bin = (entry.time / DAYS_PER_SOURCE_BIN).floor() as u64;
+            bins.entry(bin).or_default().add(source);
+        }
+    }
+
+    writeln!(out, "Infections by source, per week:")?;
+    writeln!(
+        out,
+        "  {:>4} {:>8} {:>8} {:>8} {:>8} {:>8} | infector: {:>10} {:>6} {:>6} {:>8}",
+        "week",
+        "external",
+        "contact",
+        "corpse",
+        "vector",
+        "surface",
+        "vaccinated",
+        "mild",
+        "severe",
+        "critical"
+    )?;
+    for (bin, counts) in bins {
+        writeln!(
+            out,
+            "  {:>4} {:>8} {:>8} {:>8} {:>8} {:>8} |           {:>10} {:>6} {:>6} {:>8}",
+            bin,
+            counts.external,
+            counts.contact,
+            counts.corpse,
+            counts.vector,
+            counts.surface,
+            counts.vaccinated,
+            counts.mild,
+            counts.severe,
+            counts.critical
+        )?;
+    }
+    Ok(())
+}
+
+// Generation intervals, the offspring distribution and the sources of the infections,
+// derived from the infection events.
+// The model has no incubation period - symptoms coincide with the infection, so the serial
+// intervals are the same as the generation intervals.
+pub fn write_report(path: &str, log: &EventLog) -> io::Result<()> {
+    let mut episodes: Vec<Episode> = vec![];
+    // the latest infection of each person
+    let mut current: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut intervals = vec![];
+
+    for entry in log.entries() {
+        match entry.event {
+            LogEvent::Infection {
+                person, infector, ..
+            } => {
+                if let Some(&episode) = infector.and_then(|infector| current.get(&infector)) {
+                    intervals.push(entry.time - episodes[episode].start);
+                    episodes[episode].secondary_cases += 1;
+                }
+                current.insert(person, episodes.len());
+                episodes.push(Episode {
+                    start: entry.time,
+                    ended: false,
+                    secondary_cases: 0,
+                });
+            }
+            LogEvent::Outcome { person, .. } => {
+                if let Some(&episode) = current.get(&person) {
+                    episodes[episode].ended = true;
+                }
+            }
+            LogEvent::Intervention(_) => (),
+        }
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+
+    writeln!(
+        file,
+        "Generation intervals (days): {} samples",
+        intervals.len()
+    )?;
+    if intervals.len() > 1 {
+        let (mean, variance) = mean_variance(&intervals);
+        writeln!(file, "  mean {:.3}, sd {:.3}", mean, variance.sqrt())?;
+        write_histogram(&mut file, &intervals)?;
+    }
+    writeln!(file)?;
+    writeln!(
+        file,
+        "Serial intervals: same as the generation intervals (no incubation period)"
+    )?;
+    writeln!(file)?;
+
+    // infections still going on at the end would bias the numbers down
+    let secondary: Vec<f64> = episodes
+        .iter()
+        .filter(|episode| episode.ended)
+        .map(|episode| episode.secondary_cases as f64)
+        .collect();
+    writeln!(
+        file,
+        "Secondary cases per infector: {} finished infections",
+        secondary.len()
+    )?;
+    if secondary.len() > 1 {
+        let (mean, variance) = mean_variance(&secondary);
+        writeln!(file, "  mean (R) {:.3}, variance {:.3}", mean, variance)?;
+        // method of moments estimate of the negative binomial dispersion parameter
+        if variance > mean {
+            writeln!(
+                file,
+                "  dispersion k {:.3}",
+                mean * mean / (variance - mean)
+            )?;
+        } else {
+            writeln!(file, "  dispersion k: infinite (no overdispersion)")?;
+        }
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for &cases in &secondary {
+            *counts.entry(cases as usize).or_default() += 1;
+        }
+        for (cases, count) in counts {
+            writeln!(file, "  {:>4} {}", cases, count)?;
+        }
+    }
+    writeln!(file)?;
+
+    write_sources(&mut file, log)?;
+
+    file.flush()
+}
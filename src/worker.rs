@@ -0,0 +1,186 @@
+use std::{
+    any::Any,
+    env,
+    ffi::OsStr,
+    fs,
+    io::{self, Write},
+    panic,
+    path::{Path, PathBuf},
+    process,
+};
+
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use toml::Value;
+
+use crate::{
+    announce, calibration, config,
+    simulation::{policy::PolicyRegistry, stop_condition::StopCondition},
+    stats_csv,
+};
+
+// a run without any stop conditions ends once nobody is infected, or after this many days
+const MAX_DAYS: f64 = 3650.0;
+
+// The queue is a directory shared between the machines, eg. over NFS:
+//   pending/<job>.toml  the jobs waiting for a worker, each a table of the parameters that
+//                       replace the ones from config.toml, with an optional `seed` and
+//                       `run_until` table
+//   running/            the jobs claimed by a worker, renamed to <job>.toml.<worker>, with
+//                       their statistics in <job>.csv.<worker> until they're complete
+//   done/               the finished jobs, with their statistics in <job>.csv next to them
+//   failed/             the jobs that couldn't be run, with the reason in <job>.txt
+// Claiming a job is a rename, so that only one worker gets it even when many try at once.
+struct Queue {
+    root: PathBuf,
+    worker: String,
+}
+
+impl Queue {
+    fn open(root: &str) -> io::Result<Queue> {
+        let root = PathBuf::from(root);
+        for dir in &["pending", "running", "done", "failed"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        let host = env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_owned());
+        Ok(Queue {
+            root,
+            worker: format!("{}-{}", host, process::id()),
+        })
+    }
+
+    // the name and the claimed file of the next job, in the order of the names
+    fn claim(&self) -> io::Result<Option<(String, PathBuf)>> {
+        let mut pending: Vec<_> = fs::read_dir(self.root.join("pending"))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension() == Some(OsStr::new("toml")))
+            .collect();
+        pending.sort();
+        for path in pending {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let claimed = self
+                .root
+                .join("running")
+                .join(format!("{}.toml.{}", name, self.worker));
+            // someone else was faster
+            if fs::rename(&path, &claimed).is_ok() {
+                return Ok(Some((name, claimed)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn finish(&self, name: &str, claimed: &Path, result: Result<(), String>) -> io::Result<()> {
+        let dir = match &result {
+            Ok(()) => self.root.join("done"),
+            Err(_) => self.root.join("failed"),
+        };
+        if let Err(reason) = result {
+            let mut file = fs::File::create(dir.join(format!("{}.txt", name)))?;
+            writeln!(file, "{}: {}", self.worker, reason)?;
+        }
+        fs::rename(claimed, dir.join(format!("{}.toml", name)))
+    }
+
+    // where the statistics are written, and where they're moved to once complete
+    fn results_paths(&self, name: &str) -> (PathBuf, PathBuf) {
+        (
+            self.root
+                .join("running")
+                .join(format!("{}.csv.{}", name, self.worker)),
+            self.root.join("done").join(format!("{}.csv", name)),
+        )
+    }
+}
+
+// the keys of a job that aren't parameters
+const JOB_KEYS: [&str; 2] = ["seed", "run_until"];
+
+fn run_job(
+    config_path: &str,
+    preset: Option<&str>,
+    job: &Path,
+    outputs: (&Path, &Path),
+) -> Result<(), String> {
+    let text = fs::read_to_string(job).map_err(|err| err.to_string())?;
+    let job: Value = text
+        .parse()
+        .map_err(|err| format!("invalid job: {}", err))?;
+    let mut overrides = job.clone();
+    if let Some(table) = overrides.as_table_mut() {
+        for key in &JOB_KEYS {
+            table.remove(*key);
+        }
+    }
+    let mut setup = config::load_setup(config_path, preset);
+    setup.params = config::override_params(setup.params, &overrides)
+        .map_err(|err| format!("invalid parameters: {}", err))?;
+    calibration::calibrate(&mut setup);
+    let seed = match job.get("seed") {
+        Some(seed) => seed.as_integer().ok_or("the seed should be an integer")? as u64,
+        None => thread_rng().gen(),
+    };
+    let run_until = match job.get("run_until") {
+        Some(table) => StopCondition::from_table(table),
+        None => config::load_run_until(config_path),
+    };
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sim = setup.create(&mut rng);
+    let mut policy = config::load_policy(config_path, &PolicyRegistry::with_builtins());
+    sim.run_headless(&mut rng, &mut policy, |sim| {
+        let finished = if run_until.is_empty() {
+            sim.stats().infected == 0
+        } else {
+            run_until.iter().any(|condition| condition.is_met(sim))
+        };
+        finished || sim.time() >= MAX_DAYS
+    });
+
+    let (partial, output) = outputs;
+    stats_csv::write(&partial.to_string_lossy(), sim.history())
+        .and_then(|()| fs::rename(partial, output))
+        .map_err(|err| err.to_string())?;
+    println!(
+        "Finished {} (seed {}): {}",
+        output.display(),
+        seed,
+        announce::describe(sim.time(), &sim.stats())
+    );
+    Ok(())
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match panic.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "panicked".to_owned(),
+        },
+    }
+}
+
+// Runs the jobs from the queue in `root` one after another, until none are left.
+pub fn run(root: &str, config_path: &str, preset: Option<&str>) {
+    let queue = Queue::open(root)
+        .unwrap_or_else(|err| panic!("couldn't open the job queue {}: {}", root, err));
+    println!("Worker {} taking jobs from {}.", queue.worker, root);
+
+    let mut finished = 0;
+    while let Some((name, claimed)) = queue.claim().unwrap() {
+        let (partial, output) = queue.results_paths(&name);
+        // a config that can't be loaded panics, which only fails the job
+        let result =
+            panic::catch_unwind(|| run_job(config_path, preset, &claimed, (&partial, &output)))
+                .unwrap_or_else(|panic| Err(panic_message(&*panic)));
+        // whatever was written of a failed run
+        let _ = fs::remove_file(&partial);
+        if let Err(reason) = &result {
+            eprintln!("Job {} failed: {}", name, reason);
+        }
+        if let Err(err) = queue.finish(&name, &claimed, result) {
+            eprintln!("Couldn't move job {} out of the queue: {}", name, err);
+        }
+        finished += 1;
+    }
+    println!("No jobs left, {} finished by this worker.", finished);
+}